@@ -0,0 +1,10 @@
+CREATE TABLE `online_characters` (
+  `character_id` int NOT NULL,
+  `account_id` int NOT NULL,
+  `name` varchar(13) NOT NULL,
+  `world_id` int NOT NULL,
+  `channel_id` int NOT NULL,
+  `map_id` int NOT NULL,
+  PRIMARY KEY (`character_id`),
+  KEY (`world_id`, `name`)
+) ENGINE=InnoDB;
\ No newline at end of file
@@ -0,0 +1,11 @@
+CREATE TABLE `world_messages` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `world_id` int NOT NULL,
+  `channel_id` int NOT NULL,
+  `recipient_id` int NOT NULL,
+  `kind` varchar(32) NOT NULL,
+  `value` int,
+  `packet` blob NOT NULL,
+  PRIMARY KEY (`id`),
+  KEY (`world_id`, `channel_id`)
+) ENGINE=InnoDB;
\ No newline at end of file
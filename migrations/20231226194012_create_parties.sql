@@ -0,0 +1,6 @@
+CREATE TABLE `parties` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `world_id` int NOT NULL,
+  `leader_id` int NOT NULL,
+  PRIMARY KEY (`id`)
+) ENGINE=InnoDB;
\ No newline at end of file
@@ -0,0 +1,7 @@
+CREATE TABLE `quest_progress` (
+  `character_id` int NOT NULL,
+  `quest_id` int NOT NULL,
+  `mob_id` int NOT NULL,
+  `count` int NOT NULL,
+  PRIMARY KEY (`character_id`, `quest_id`, `mob_id`)
+) ENGINE=InnoDB;
\ No newline at end of file
@@ -0,0 +1,7 @@
+CREATE TABLE `buddies` (
+  `character_id` int NOT NULL,
+  `buddy_id` int NOT NULL,
+  `group_name` varchar(16) NOT NULL DEFAULT 'Default Group',
+  `pending` tinyint(1) NOT NULL DEFAULT 0,
+  PRIMARY KEY (`character_id`, `buddy_id`)
+) ENGINE=InnoDB;
\ No newline at end of file
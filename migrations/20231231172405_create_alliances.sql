@@ -0,0 +1,14 @@
+CREATE TABLE `alliances` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `world_id` int NOT NULL,
+  `name` varchar(12) NOT NULL,
+  `rank1_title` varchar(12) NOT NULL DEFAULT 'Master',
+  `rank2_title` varchar(12) NOT NULL DEFAULT 'Jr. Master',
+  `rank3_title` varchar(12) NOT NULL DEFAULT 'Member',
+  `rank4_title` varchar(12) NOT NULL DEFAULT 'Member',
+  `rank5_title` varchar(12) NOT NULL DEFAULT 'Member',
+  `capacity` int NOT NULL DEFAULT 2,
+  `notice` varchar(100) NOT NULL DEFAULT '',
+  PRIMARY KEY (`id`),
+  UNIQUE KEY (`world_id`, `name`)
+) ENGINE=InnoDB;
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE `guilds` ADD COLUMN `alliance_id` int;
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE `characters` ADD COLUMN `alliance_rank` int NOT NULL DEFAULT 5;
\ No newline at end of file
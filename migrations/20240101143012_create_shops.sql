@@ -0,0 +1,6 @@
+CREATE TABLE `shops` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `npc_id` int NOT NULL,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY (`npc_id`)
+) ENGINE=InnoDB;
\ No newline at end of file
@@ -0,0 +1,9 @@
+CREATE TABLE `shop_items` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `shop_id` int NOT NULL,
+  `item_id` int NOT NULL,
+  `price` int NOT NULL,
+  `position` int NOT NULL,
+  PRIMARY KEY (`id`),
+  KEY (`shop_id`)
+) ENGINE=InnoDB;
\ No newline at end of file
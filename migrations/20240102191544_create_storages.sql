@@ -0,0 +1,9 @@
+CREATE TABLE `storages` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `account_id` int NOT NULL,
+  `world_id` int NOT NULL,
+  `slots` int NOT NULL DEFAULT 4,
+  `mesos` int NOT NULL DEFAULT 0,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY (`account_id`, `world_id`)
+) ENGINE=InnoDB;
\ No newline at end of file
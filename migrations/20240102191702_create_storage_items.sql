@@ -0,0 +1,12 @@
+CREATE TABLE `storage_items` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `storage_id` int NOT NULL,
+  `item_id` int NOT NULL,
+  `inventory_type` enum('Equip','Use','Setup','Etc','Cash') NOT NULL,
+  `position` int NOT NULL,
+  `amount` int NOT NULL,
+  `owner` varchar(13) NOT NULL DEFAULT '',
+  `flag` int NOT NULL DEFAULT 0,
+  PRIMARY KEY (`id`),
+  KEY (`storage_id`)
+) ENGINE=InnoDB;
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE `characters` ADD COLUMN `merchant_mesos` int NOT NULL DEFAULT 0;
\ No newline at end of file
@@ -0,0 +1,14 @@
+CREATE TABLE `player_shop_items` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `character_id` int NOT NULL,
+  `item_id` int NOT NULL,
+  `inventory_type` enum('Equip','Use','Setup','Etc','Cash') NOT NULL,
+  `position` int NOT NULL,
+  `amount` int NOT NULL,
+  `bundles` int NOT NULL,
+  `price` int NOT NULL,
+  `owner` varchar(13) NOT NULL DEFAULT '',
+  `flag` int NOT NULL DEFAULT 0,
+  PRIMARY KEY (`id`),
+  KEY (`character_id`)
+) ENGINE=InnoDB;
\ No newline at end of file
@@ -0,0 +1,7 @@
+CREATE TABLE `hired_merchants` (
+  `character_id` int NOT NULL,
+  `world_id` int NOT NULL,
+  `channel_id` int NOT NULL,
+  `map_id` int NOT NULL,
+  PRIMARY KEY (`character_id`)
+) ENGINE=InnoDB;
\ No newline at end of file
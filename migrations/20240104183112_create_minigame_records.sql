@@ -0,0 +1,8 @@
+CREATE TABLE `minigame_records` (
+  `character_id` int NOT NULL,
+  `game_type` enum('Omok','MatchCards') NOT NULL,
+  `wins` int NOT NULL DEFAULT 0,
+  `ties` int NOT NULL DEFAULT 0,
+  `losses` int NOT NULL DEFAULT 0,
+  PRIMARY KEY (`character_id`, `game_type`)
+) ENGINE=InnoDB;
\ No newline at end of file
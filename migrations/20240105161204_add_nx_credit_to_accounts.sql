@@ -0,0 +1 @@
+ALTER TABLE `accounts` ADD COLUMN `nx_credit` int NOT NULL DEFAULT 0;
\ No newline at end of file
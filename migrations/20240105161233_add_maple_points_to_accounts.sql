@@ -0,0 +1 @@
+ALTER TABLE `accounts` ADD COLUMN `maple_points` int NOT NULL DEFAULT 0;
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE `items` ADD COLUMN `cash_id` bigint DEFAULT NULL;
\ No newline at end of file
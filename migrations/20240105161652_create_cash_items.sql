@@ -0,0 +1,11 @@
+CREATE TABLE `cash_items` (
+  `id` bigint NOT NULL AUTO_INCREMENT,
+  `account_id` int NOT NULL,
+  `world_id` int NOT NULL,
+  `item_id` int NOT NULL,
+  `commodity_id` int NOT NULL,
+  `amount` int NOT NULL,
+  `gift_from` varchar(13) NOT NULL DEFAULT '',
+  PRIMARY KEY (`id`),
+  KEY (`account_id`, `world_id`)
+) ENGINE=InnoDB;
\ No newline at end of file
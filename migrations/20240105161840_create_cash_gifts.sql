@@ -0,0 +1,11 @@
+CREATE TABLE `cash_gifts` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `character_id` int NOT NULL,
+  `item_id` int NOT NULL,
+  `commodity_id` int NOT NULL,
+  `amount` int NOT NULL,
+  `sender` varchar(13) NOT NULL,
+  `message` varchar(73) NOT NULL DEFAULT '',
+  PRIMARY KEY (`id`),
+  KEY (`character_id`)
+) ENGINE=InnoDB;
\ No newline at end of file
@@ -0,0 +1,6 @@
+CREATE TABLE `wishlists` (
+  `character_id` int NOT NULL,
+  `position` int NOT NULL,
+  `commodity_id` int NOT NULL,
+  PRIMARY KEY (`character_id`, `position`)
+) ENGINE=InnoDB;
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE `items` ADD COLUMN `expires_at` bigint DEFAULT NULL;
\ No newline at end of file
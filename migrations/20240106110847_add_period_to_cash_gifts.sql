@@ -0,0 +1 @@
+ALTER TABLE `cash_gifts` ADD COLUMN `period` int NOT NULL DEFAULT 0;
\ No newline at end of file
@@ -0,0 +1,19 @@
+CREATE TABLE `mts_listings` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `world_id` int NOT NULL,
+  `character_id` int NOT NULL,
+  `seller_name` varchar(13) NOT NULL,
+  `item_id` int NOT NULL,
+  `inventory_type` enum('Equip','Use','Setup','Etc','Cash') NOT NULL,
+  `amount` int NOT NULL,
+  `owner` varchar(13) NOT NULL DEFAULT '',
+  `flag` int NOT NULL DEFAULT 0,
+  `cash_id` bigint DEFAULT NULL,
+  `expires_at` bigint DEFAULT NULL,
+  `price` int NOT NULL,
+  `ends_at` bigint NOT NULL,
+  `in_transfer` tinyint(1) NOT NULL DEFAULT 0,
+  PRIMARY KEY (`id`),
+  KEY (`world_id`, `in_transfer`),
+  KEY (`character_id`)
+) ENGINE=InnoDB;
\ No newline at end of file
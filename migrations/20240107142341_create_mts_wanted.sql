@@ -0,0 +1,5 @@
+CREATE TABLE `mts_wanted` (
+  `character_id` int NOT NULL,
+  `listing_id` int NOT NULL,
+  PRIMARY KEY (`character_id`, `listing_id`)
+) ENGINE=InnoDB;
\ No newline at end of file
@@ -0,0 +1,11 @@
+CREATE TABLE `pets` (
+  `id` bigint NOT NULL,
+  `item_id` int NOT NULL,
+  `name` varchar(13) NOT NULL,
+  `level` int NOT NULL DEFAULT 1,
+  `tameness` int NOT NULL DEFAULT 0,
+  `fullness` int NOT NULL DEFAULT 100,
+  `flag` int NOT NULL DEFAULT 0,
+  `slot` int DEFAULT NULL,
+  PRIMARY KEY (`id`)
+) ENGINE=InnoDB;
\ No newline at end of file
@@ -0,0 +1,7 @@
+CREATE TABLE `mounts` (
+  `character_id` int NOT NULL,
+  `level` int NOT NULL DEFAULT 1,
+  `exp` int NOT NULL DEFAULT 0,
+  `tiredness` int NOT NULL DEFAULT 0,
+  PRIMARY KEY (`character_id`)
+) ENGINE=InnoDB;
\ No newline at end of file
@@ -0,0 +1,9 @@
+CREATE TABLE `reactor_drops` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `reactor_id` int NOT NULL,
+  `item_id` int NOT NULL,
+  `chance` int NOT NULL DEFAULT 1,
+  `quest_id` int,
+  PRIMARY KEY (`id`),
+  KEY (`reactor_id`)
+) ENGINE=InnoDB;
\ No newline at end of file
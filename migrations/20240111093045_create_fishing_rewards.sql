@@ -0,0 +1,9 @@
+CREATE TABLE `fishing_rewards` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `item_id` int,
+  `amount` int NOT NULL DEFAULT 1,
+  `mesos` int NOT NULL DEFAULT 0,
+  `exp` int NOT NULL DEFAULT 0,
+  `weight` int NOT NULL DEFAULT 1,
+  PRIMARY KEY (`id`)
+) ENGINE=InnoDB;
\ No newline at end of file
@@ -0,0 +1,8 @@
+CREATE TABLE `boss_entries` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `character_id` int NOT NULL,
+  `boss` varchar(20) NOT NULL,
+  `entered_at` datetime NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  PRIMARY KEY (`id`),
+  KEY (`character_id`, `boss`)
+) ENGINE=InnoDB;
\ No newline at end of file
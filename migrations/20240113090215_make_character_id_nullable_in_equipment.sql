@@ -0,0 +1 @@
+ALTER TABLE `equipment` MODIFY COLUMN `character_id` int DEFAULT NULL;
\ No newline at end of file
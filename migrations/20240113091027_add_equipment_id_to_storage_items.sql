@@ -0,0 +1 @@
+ALTER TABLE `storage_items` ADD COLUMN `equipment_id` int DEFAULT NULL;
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE `player_shop_items` ADD COLUMN `equipment_id` int DEFAULT NULL;
\ No newline at end of file
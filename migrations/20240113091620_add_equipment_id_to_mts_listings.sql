@@ -0,0 +1 @@
+ALTER TABLE `mts_listings` ADD COLUMN `equipment_id` int DEFAULT NULL;
\ No newline at end of file
@@ -0,0 +1,13 @@
+INSERT INTO `fishing_rewards` (`item_id`, `amount`, `mesos`, `exp`, `weight`) VALUES
+  (NULL, 0, 0, 0, 40),
+  (NULL, 0, 500, 0, 15),
+  (NULL, 0, 1000, 0, 6),
+  (NULL, 0, 0, 200, 10),
+  (NULL, 0, 0, 500, 4),
+  (4031627, 1, 0, 0, 8),
+  (4031628, 1, 0, 0, 4),
+  (4031630, 1, 0, 0, 4),
+  (4031631, 1, 0, 0, 3),
+  (4031633, 1, 0, 0, 3),
+  (4031634, 1, 0, 0, 2),
+  (4031635, 1, 0, 0, 1);
\ No newline at end of file
@@ -0,0 +1,306 @@
+use crate::{guild, session::ChannelSession, world};
+use slate_data::{
+    packet::{self, NpcDialog, Stat},
+    sql::{
+        self,
+        alliance::{ALLIANCE_LEADER_RANK, MAX_ALLIANCE_CAPACITY},
+    },
+};
+use slate_net::Packet;
+
+/// Lenario, who creates and manages guild alliances
+pub const ALLIANCE_NPC: i32 = 2010009;
+
+/// The mesos it costs to create an alliance
+pub const CREATE_COST: i32 = 2000000;
+
+/// The mesos it costs to let one more guild join an alliance
+const CAPACITY_COST: i32 = 1000000;
+
+/// Loads the alliance the character's guild is in, if it's in one
+pub async fn load_alliance(session: &ChannelSession) -> anyhow::Result<Option<sql::Alliance>> {
+    let character = session.character.as_ref().unwrap();
+
+    match character.guild.as_ref().and_then(|guild| guild.alliance_id) {
+        Some(alliance_id) => sql::Alliance::load_optional(alliance_id, &session.db).await,
+        None => Ok(None),
+    }
+}
+
+/// Sends the character their alliance's info, along with the info of each guild in it
+pub async fn send_info(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let alliance = match load_alliance(session).await? {
+        Some(alliance) => alliance,
+        None => return Ok(()),
+    };
+
+    let guilds = sql::Guild::load_all_by_alliance(alliance.id, &session.db).await?;
+    let mut guild_members = Vec::new();
+
+    for guild in guilds.iter() {
+        let members = sql::GuildMember::load_all(guild.id, &session.db).await?;
+        guild_members.push((guild.clone(), members));
+    }
+
+    session
+        .stream
+        .write_packet(packet::alliance_info(Some(&alliance), &guilds))
+        .await?;
+    session
+        .stream
+        .write_packet(packet::alliance_guilds(&guild_members))
+        .await
+}
+
+/// Sends a packet to every online member of an alliance
+pub async fn send_to_members(
+    session: &ChannelSession,
+    alliance_id: i32,
+    packet: Packet,
+) -> anyhow::Result<()> {
+    let member_ids = sql::Character::load_alliance_member_ids(alliance_id, &session.db).await?;
+    world::send_packet_to_all(session, &member_ids, packet).await?;
+    Ok(())
+}
+
+/// Tells every member of an alliance that it changed, so they reload their guild and alliance
+pub async fn notify_changed(session: &ChannelSession, alliance_id: i32) -> anyhow::Result<()> {
+    let member_ids = sql::Character::load_alliance_member_ids(alliance_id, &session.db).await?;
+    guild::notify_changed(session, &member_ids).await
+}
+
+/// Removes a guild from its alliance, the guild's members see the alliance as disbanded
+pub async fn remove_guild(
+    session: &ChannelSession,
+    alliance: &sql::Alliance,
+    guild_id: i32,
+) -> anyhow::Result<()> {
+    alliance.remove_guild(guild_id, &session.db).await?;
+
+    let member_ids = sql::Character::load_guild_member_ids(guild_id, &session.db).await?;
+    let packet = packet::alliance_disbanded(alliance.id);
+    world::send_packet_to_all(session, &member_ids, packet).await?;
+    guild::notify_changed(session, &member_ids).await?;
+
+    notify_changed(session, alliance.id).await
+}
+
+/// Shows the character as online or offline to their alliance
+/// Called when the character logs in or out
+pub async fn update_member_online(session: &ChannelSession, online: bool) -> anyhow::Result<()> {
+    let character = session.character.as_ref().unwrap();
+
+    let (guild_id, alliance_id) = match &character.guild {
+        Some(sql::Guild {
+            id,
+            alliance_id: Some(alliance_id),
+            ..
+        }) => (*id, *alliance_id),
+        _ => return Ok(()),
+    };
+
+    let packet =
+        packet::update_alliance_member_online(alliance_id, guild_id, character.data.id, online);
+    send_to_members(session, alliance_id, packet).await
+}
+
+/// Disbands an alliance, removing all of its guilds
+pub async fn disband(session: &ChannelSession, alliance: &sql::Alliance) -> anyhow::Result<()> {
+    let member_ids = sql::Character::load_alliance_member_ids(alliance.id, &session.db).await?;
+    alliance.disband(&session.db).await?;
+
+    let packet = packet::alliance_disbanded(alliance.id);
+    world::send_packet_to_all(session, &member_ids, packet).await?;
+    guild::notify_changed(session, &member_ids).await
+}
+
+/// Shows the alliance npc's options
+pub async fn talk(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let text = "What would you like to do?\r\n\
+        #L0#Create an alliance#l\r\n\
+        #L1#Increase my alliance's capacity#l\r\n\
+        #L2#Disband my alliance#l";
+
+    session
+        .stream
+        .write_packet(packet::npc_talk(ALLIANCE_NPC, NpcDialog::Selection, text))
+        .await
+}
+
+/// Handles the option the character picked from the alliance npc
+pub async fn respond(session: &mut ChannelSession, selection: i32) -> anyhow::Result<()> {
+    let character = session.character.as_ref().unwrap();
+    let alliance_id = character.guild.as_ref().and_then(|guild| guild.alliance_id);
+    let is_leader = alliance_id.is_some() && character.data.alliance_rank == ALLIANCE_LEADER_RANK;
+
+    match selection {
+        0 if alliance_id.is_some() => say(session, "Your guild is already in an alliance.").await,
+        0 if character.data.guild_rank != Some(1) => {
+            say(session, "Only guild masters can create an alliance.").await
+        }
+        0 => {
+            let text = format!(
+                "Creating an alliance costs {} mesos. The guild masters of the other guilds \
+                have to be in your party and in this map.\r\nWhat will the alliance be called?",
+                CREATE_COST
+            );
+            session
+                .stream
+                .write_packet(packet::npc_talk(ALLIANCE_NPC, NpcDialog::GetText, &text))
+                .await?;
+
+            // The conversation continues once the character enters a name
+            session.npc_conversation = Some(ALLIANCE_NPC);
+            Ok(())
+        }
+        1 | 2 if !is_leader => say(session, "Only the alliance leader can do that.").await,
+        1 => increase_capacity(session).await,
+        2 => match load_alliance(session).await? {
+            Some(alliance) => disband(session, &alliance).await,
+            None => Ok(()),
+        },
+        _ => Ok(()),
+    }
+}
+
+/// Creates an alliance named after the text the character entered
+/// The guilds of the guild masters in the character's party join it
+pub async fn respond_text(session: &mut ChannelSession, name: &str) -> anyhow::Result<()> {
+    let character = session.character.as_ref().unwrap();
+    let character_id = character.data.id;
+
+    let guild = match (&character.guild, character.data.guild_rank) {
+        (Some(guild), Some(1)) if guild.alliance_id.is_none() => guild.clone(),
+        _ => return Ok(()),
+    };
+
+    if !(3..=12).contains(&name.len()) || !name.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return say(session, "That name can't be used for an alliance.").await;
+    }
+
+    if character.data.mesos < CREATE_COST {
+        let text = format!("You need {} mesos to create an alliance.", CREATE_COST);
+        return say(session, &text).await;
+    }
+
+    let party = match character.data.party {
+        Some(party_id) => sql::Party::load_optional(party_id, &session.db).await?,
+        None => None,
+    };
+
+    let party = match party {
+        Some(party) if party.leader_id == character_id => party,
+        _ => {
+            return say(
+                session,
+                "You must be the leader of a party of guild masters.",
+            )
+            .await
+        }
+    };
+
+    // Every other party member has to be a guild master in the same map
+    let map_characters = session.state.get_map_characters(character.field_id);
+    let members = sql::PartyMember::load_all(party.id, &session.db).await?;
+    let mut guild_ids = vec![guild.id];
+
+    for member in members.iter().filter(|member| member.id != character_id) {
+        if !map_characters.contains_key(&member.id) {
+            let text = "Every party member must be in this map to create an alliance.";
+            return say(session, text).await;
+        }
+
+        let member_data = sql::Character::load(member.id, &session.db).await?;
+
+        let member_guild = match (member_data.guild, member_data.guild_rank) {
+            (Some(guild_id), Some(1)) => sql::Guild::load_optional(guild_id, &session.db).await?,
+            _ => None,
+        };
+
+        match member_guild {
+            Some(member_guild) if member_guild.alliance_id.is_none() => {
+                guild_ids.push(member_guild.id)
+            }
+            Some(_) => {
+                let text = format!("{}'s guild is already in an alliance.", member.name);
+                return say(session, &text).await;
+            }
+            None => {
+                let text = format!("{} isn't a guild master.", member.name);
+                return say(session, &text).await;
+            }
+        }
+    }
+
+    if !(2..=MAX_ALLIANCE_CAPACITY as usize).contains(&guild_ids.len()) {
+        let text = format!(
+            "An alliance needs between 2 and {} guilds.",
+            MAX_ALLIANCE_CAPACITY
+        );
+        return say(session, &text).await;
+    }
+
+    if sql::Alliance::name_exists(name, session.world_id, &session.db).await? {
+        return say(session, "An alliance with that name already exists.").await;
+    }
+
+    let alliance_id = sql::Alliance::create(
+        session.world_id,
+        name,
+        character_id,
+        &guild_ids,
+        &session.db,
+    )
+    .await?;
+
+    let character = session.character.as_mut().unwrap();
+    character.data.mesos -= CREATE_COST;
+    let mesos = character.data.mesos;
+    session
+        .stream
+        .write_packet(packet::update_stats(&[(Stat::Mesos, mesos)]))
+        .await?;
+
+    notify_changed(session, alliance_id).await
+}
+
+/// Lets one more guild join the character's alliance
+async fn increase_capacity(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let mut alliance = match load_alliance(session).await? {
+        Some(alliance) => alliance,
+        None => return Ok(()),
+    };
+
+    if alliance.capacity >= MAX_ALLIANCE_CAPACITY {
+        return say(session, "Your alliance can't get any bigger.").await;
+    }
+
+    let character = session.character.as_mut().unwrap();
+
+    if character.data.mesos < CAPACITY_COST {
+        let text = format!(
+            "You need {} mesos to increase your alliance's capacity.",
+            CAPACITY_COST
+        );
+        return say(session, &text).await;
+    }
+
+    character.data.mesos -= CAPACITY_COST;
+    let mesos = character.data.mesos;
+    session
+        .stream
+        .write_packet(packet::update_stats(&[(Stat::Mesos, mesos)]))
+        .await?;
+
+    alliance.capacity += 1;
+    alliance.update_capacity(&session.db).await?;
+    notify_changed(session, alliance.id).await
+}
+
+/// Shows a message from the alliance npc
+async fn say(session: &mut ChannelSession, text: &str) -> anyhow::Result<()> {
+    session
+        .stream
+        .write_packet(packet::npc_talk(ALLIANCE_NPC, NpcDialog::Ok, text))
+        .await
+}
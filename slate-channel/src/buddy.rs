@@ -0,0 +1,42 @@
+use crate::{session::ChannelSession, world};
+use slate_data::{packet, sql};
+
+/// Sends the character their buddy list
+pub async fn send_list(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let character_id = session.character.as_ref().unwrap().data.id;
+    let buddies = sql::BuddyEntry::load_all(character_id, &session.db).await?;
+
+    session
+        .stream
+        .write_packet(packet::update_buddy_list(&buddies))
+        .await
+}
+
+/// Shows the character the next buddy request they haven't answered, if there is one
+pub async fn send_next_request(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let character_id = session.character.as_ref().unwrap().data.id;
+
+    if let Some(request) = sql::BuddyRequest::load_next(character_id, &session.db).await? {
+        session
+            .stream
+            .write_packet(packet::buddy_request(
+                request.id,
+                &request.name,
+                character_id,
+            ))
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Shows the character's channel to their buddies, or -1 when the character goes offline
+/// Called when the character logs in or out
+pub async fn update_buddies(session: &ChannelSession, channel_id: i32) -> anyhow::Result<()> {
+    let character_id = session.character.as_ref().unwrap().data.id;
+    let buddy_ids = sql::Buddy::load_mutual_ids(character_id, &session.db).await?;
+
+    let packet = packet::update_buddy_channel(character_id, channel_id);
+    world::send_packet_to_all(session, &buddy_ids, packet).await?;
+    Ok(())
+}
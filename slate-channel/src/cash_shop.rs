@@ -0,0 +1,398 @@
+use crate::{packet_handler::connect, session::ChannelSession, world};
+use anyhow::anyhow;
+use slate_data::{
+    nx,
+    packet::{self, CashShopError, NoticeType},
+    sql::{self, cash_shop::CashCurrency, item::InventoryType},
+};
+use sqlx::types::chrono::Utc;
+
+/// The most commodities a character can have in their wishlist
+const WISHLIST_SIZE: usize = 10;
+
+/// The cash shop a character is in, along with their account's locker in the character's world
+#[derive(Debug)]
+pub struct CashShop {
+    pub items: Vec<sql::CashItem>,
+    pub wishlist: sql::Wishlist,
+}
+
+/// Moves the character out of their map into the cash shop
+/// Gifts sent to the character are put in their locker
+pub async fn enter(session: &mut ChannelSession) -> anyhow::Result<()> {
+    if session.cash_shop.is_some() || session.mts.is_some() {
+        return Ok(());
+    }
+
+    let account_id = session.account_id.unwrap();
+    let account = sql::Account::load_optional_by_id(account_id, &session.db)
+        .await?
+        .ok_or_else(|| anyhow!("Account {} not found", account_id))?;
+
+    session.leave_map().await?;
+
+    let character = session.character.as_ref().unwrap();
+    let character_id = character.data.id;
+    let world_id = session.world_id;
+
+    let now = Utc::now().timestamp_millis();
+    let minigame_records = sql::MinigameRecord::load_all(character_id, &session.db).await?;
+    let storage = sql::Storage::load_or_create(account_id, world_id, &session.db).await?;
+    sql::CashItem::delete_expired(account_id, world_id, now, &session.db).await?;
+    let gifts =
+        sql::CashGift::claim_all(character_id, account_id, world_id, now, &session.db).await?;
+    let items = sql::CashItem::load_all(account_id, world_id, &session.db).await?;
+    let wishlist = sql::Wishlist::load(character_id, &session.db).await?;
+
+    let packet = connect::open_cash_shop(character, &minigame_records, &account.name);
+    session.stream.write_packet(packet).await?;
+    session
+        .stream
+        .write_packet(packet::cash_inventory(&items, storage.slots))
+        .await?;
+    session
+        .stream
+        .write_packet(packet::cash_gifts(&gifts))
+        .await?;
+    session
+        .stream
+        .write_packet(packet::wishlist(&wishlist, false))
+        .await?;
+    session
+        .stream
+        .write_packet(packet::cash_balance(
+            account.nx_credit,
+            account.maple_points,
+        ))
+        .await?;
+
+    session.cash_shop = Some(CashShop { items, wishlist });
+    Ok(())
+}
+
+/// Leaves the cash shop, the client reconnects to the channel and the character enters their map again
+pub async fn leave(session: &mut ChannelSession) -> anyhow::Result<()> {
+    if session.cash_shop.take().is_none() {
+        return Ok(());
+    }
+
+    session.migrate(session.channel_id).await
+}
+
+/// Shows the character their account's current NX Credit and Maple Points
+pub async fn send_balance(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let account_id = session.account_id.unwrap();
+    let account = sql::Account::load_optional_by_id(account_id, &session.db)
+        .await?
+        .ok_or_else(|| anyhow!("Account {} not found", account_id))?;
+
+    session
+        .stream
+        .write_packet(packet::cash_balance(
+            account.nx_credit,
+            account.maple_points,
+        ))
+        .await
+}
+
+/// Buys a commodity into the character's locker, paid for with the given balance
+pub async fn buy(
+    session: &mut ChannelSession,
+    currency: i32,
+    serial_number: i32,
+) -> anyhow::Result<()> {
+    let cash_shop = match session.cash_shop.as_mut() {
+        Some(cash_shop) => cash_shop,
+        None => return Ok(()),
+    };
+
+    let (currency, commodity) = match (
+        CashCurrency::from_i32(currency),
+        nx::Commodity::load(serial_number),
+    ) {
+        (Some(currency), Ok(commodity)) if commodity.on_sale => (currency, commodity),
+        _ => return send_error(session, CashShopError::Unknown).await,
+    };
+
+    let character = session.character.as_ref().unwrap();
+
+    if !commodity.is_for_gender(character.data.gender) {
+        return send_error(session, CashShopError::WrongGender).await;
+    }
+
+    let contents = match get_contents(&commodity) {
+        Some(contents) => contents,
+        None => return send_error(session, CashShopError::Unknown).await,
+    };
+
+    let account_id = session.account_id.unwrap();
+    let now = Utc::now().timestamp_millis();
+
+    let items = contents
+        .iter()
+        .map(|content| sql::CashItem {
+            id: 0,
+            account_id,
+            world_id: session.world_id,
+            item_id: content.item_id,
+            commodity_id: content.serial_number,
+            amount: content.count,
+            gift_from: String::new(),
+            expires_at: content.get_expiration(now),
+            equipment_id: None,
+            equip: None,
+        })
+        .collect();
+
+    let items = match sql::CashItem::buy(items, account_id, currency, commodity.price, &session.db)
+        .await?
+    {
+        Some(items) => items,
+        None => return send_error(session, CashShopError::NotEnoughCash).await,
+    };
+
+    cash_shop.items.extend(items.iter().cloned());
+
+    let packet = if commodity.is_package() {
+        packet::cash_package_bought(&items)
+    } else {
+        packet::cash_item_bought(&items[0])
+    };
+    session.stream.write_packet(packet).await?;
+    send_balance(session).await
+}
+
+/// Buys a commodity as a gift for another character in the world, paid for with NX Credit
+pub async fn gift(
+    session: &mut ChannelSession,
+    serial_number: i32,
+    recipient: &str,
+    message: &str,
+) -> anyhow::Result<()> {
+    if session.cash_shop.is_none() {
+        return Ok(());
+    }
+
+    let commodity = match nx::Commodity::load(serial_number) {
+        Ok(commodity) if commodity.on_sale && (1..=73).contains(&message.len()) => commodity,
+        _ => return send_error(session, CashShopError::Unknown).await,
+    };
+
+    let contents = match get_contents(&commodity) {
+        Some(contents) => contents,
+        None => return send_error(session, CashShopError::Unknown).await,
+    };
+
+    let recipient =
+        match sql::Character::load_optional_by_name(recipient, session.world_id, &session.db)
+            .await?
+        {
+            Some(recipient) => recipient,
+            None => return send_error(session, CashShopError::WrongCharacterName).await,
+        };
+
+    if Some(recipient.account_id) == session.account_id {
+        return send_error(session, CashShopError::GiftToOwnAccount).await;
+    }
+
+    if !commodity.is_for_gender(recipient.gender) {
+        return send_error(session, CashShopError::WrongGender).await;
+    }
+
+    let sender = session.character.as_ref().unwrap().data.name.clone();
+
+    let gifts: Vec<_> = contents
+        .iter()
+        .map(|content| sql::CashGift {
+            id: 0,
+            character_id: recipient.id,
+            item_id: content.item_id,
+            commodity_id: content.serial_number,
+            amount: content.count,
+            sender: sender.clone(),
+            message: message.to_string(),
+            period: content.period,
+        })
+        .collect();
+
+    if !sql::CashGift::send(
+        &gifts,
+        session.account_id.unwrap(),
+        commodity.price,
+        &session.db,
+    )
+    .await?
+    {
+        return send_error(session, CashShopError::NotEnoughCash).await;
+    }
+
+    session
+        .stream
+        .write_packet(packet::cash_gift_sent(&recipient.name, &commodity))
+        .await?;
+    send_balance(session).await?;
+
+    let text = format!(
+        "You have received a gift from {}. Check the Cash Shop to claim it.",
+        sender
+    );
+    let packet = packet::server_notice(NoticeType::PinkText, &text);
+    world::send_packet(session, recipient.id, packet).await?;
+    Ok(())
+}
+
+/// Replaces the character's wishlist, skipping any commodities that aren't on sale
+pub async fn update_wishlist(
+    session: &mut ChannelSession,
+    serial_numbers: &[i32],
+) -> anyhow::Result<()> {
+    let cash_shop = match session.cash_shop.as_mut() {
+        Some(cash_shop) => cash_shop,
+        None => return Ok(()),
+    };
+
+    cash_shop.wishlist.commodity_ids = serial_numbers
+        .iter()
+        .copied()
+        .filter(|serial_number| {
+            nx::Commodity::load(*serial_number)
+                .map(|commodity| commodity.on_sale)
+                .unwrap_or(false)
+        })
+        .take(WISHLIST_SIZE)
+        .collect();
+    cash_shop.wishlist.save(&session.db).await?;
+
+    let packet = packet::wishlist(&cash_shop.wishlist, true);
+    session.stream.write_packet(packet).await
+}
+
+/// Moves an item from the character's locker into their inventory
+pub async fn take_item(session: &mut ChannelSession, cash_id: i64) -> anyhow::Result<()> {
+    let cash_shop = match session.cash_shop.as_mut() {
+        Some(cash_shop) => cash_shop,
+        None => return Ok(()),
+    };
+
+    let item = match cash_shop.items.iter().find(|item| item.id == cash_id) {
+        Some(item) => item.clone(),
+        None => return Ok(()),
+    };
+
+    let inventory_type = match InventoryType::from_item_id(item.item_id) {
+        Some(inventory_type) => inventory_type,
+        None => return send_error(session, CashShopError::Unknown).await,
+    };
+
+    let character = session.character.as_mut().unwrap();
+
+    let position = match character.get_free_position(inventory_type) {
+        Some(position) => position,
+        None => return send_error(session, CashShopError::InventoryFull).await,
+    };
+
+    let taken = match item.take(character.data.id, position, &session.db).await? {
+        Some(taken) => taken,
+        None => return Ok(()),
+    };
+
+    cash_shop.items.retain(|stored| stored.id != item.id);
+    character.items.push(taken.clone());
+    character.add_pet(&taken, &session.db).await?;
+
+    let pet = taken
+        .cash_id
+        .and_then(|pet_id| character.get_pet_mut(pet_id))
+        .map(|pet| pet.data.clone());
+
+    session
+        .stream
+        .write_packet(packet::cash_item_taken(&taken, pet.as_ref()))
+        .await
+}
+
+/// Moves an item bought from the cash shop out of the character's inventory into their locker
+pub async fn store_item(
+    session: &mut ChannelSession,
+    cash_id: i64,
+    inventory_type: InventoryType,
+) -> anyhow::Result<()> {
+    let cash_shop = match session.cash_shop.as_mut() {
+        Some(cash_shop) => cash_shop,
+        None => return Ok(()),
+    };
+
+    let character = session.character.as_mut().unwrap();
+
+    let index = match character
+        .items
+        .iter()
+        .position(|item| item.inventory_type == inventory_type && item.cash_id == Some(cash_id))
+    {
+        Some(index) => index,
+        None => return Ok(()),
+    };
+
+    let item = &character.items[index];
+
+    // The commodity isn't kept with items in inventories
+    let commodity_id = nx::Commodity::find_by_item_id(item.item_id)
+        .map(|commodity| commodity.serial_number)
+        .unwrap_or_default();
+
+    let stored = match sql::CashItem::store(
+        item,
+        session.account_id.unwrap(),
+        session.world_id,
+        commodity_id,
+        &session.db,
+    )
+    .await?
+    {
+        Some(stored) => stored,
+        None => {
+            log::warn!(
+                "Item {} is no longer in character {}'s inventory",
+                item.id,
+                item.character_id
+            );
+            return Ok(());
+        }
+    };
+
+    // The client removes the item from its inventory by itself
+    let item = character.items.remove(index);
+    character.remove_pet(&item, &session.db).await?;
+    cash_shop.items.push(stored.clone());
+
+    session
+        .stream
+        .write_packet(packet::cash_item_stored(&stored))
+        .await
+}
+
+/// Gets the commodities bought as the given commodity, its contents if it's a package
+/// Returns None if any of them can't be put into an inventory
+fn get_contents(commodity: &nx::Commodity) -> Option<Vec<nx::Commodity>> {
+    let contents = commodity
+        .get_package_contents()
+        .unwrap_or_else(|| vec![commodity.clone()]);
+
+    let valid = !contents.is_empty()
+        && contents.iter().all(
+            |content| match InventoryType::from_item_id(content.item_id) {
+                Some(InventoryType::Equip) => nx::Equipment::load_by_id(content.item_id).is_some(),
+                Some(_) => true,
+                None => false,
+            },
+        );
+
+    valid.then_some(contents)
+}
+
+async fn send_error(session: &mut ChannelSession, error: CashShopError) -> anyhow::Result<()> {
+    session
+        .stream
+        .write_packet(packet::cash_shop_error(error))
+        .await
+}
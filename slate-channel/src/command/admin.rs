@@ -0,0 +1,133 @@
+use super::{reply, Args, Command, CommandFuture};
+use crate::{
+    session::{ChannelSession, SessionMessage},
+    world,
+};
+use slate_data::sql;
+use std::collections::BTreeMap;
+
+/// Bans a character's account and disconnects them if they're online
+pub struct Ban;
+
+impl Command for Ban {
+    fn name(&self) -> &'static str {
+        "ban"
+    }
+
+    fn gm_level(&self) -> i32 {
+        2
+    }
+
+    fn usage(&self) -> &'static str {
+        "<character name>"
+    }
+
+    fn execute<'a>(
+        &'a self,
+        mut args: Args<'a>,
+        session: &'a mut ChannelSession,
+    ) -> CommandFuture<'a> {
+        Box::pin(async move {
+            let name = args.next_str()?;
+
+            let target =
+                match sql::Character::load_optional_by_name(name, session.world_id, &session.db)
+                    .await?
+                {
+                    Some(target) => target,
+                    None => return reply(session, &format!("{} doesn't exist", name)).await,
+                };
+
+            // GMs can only ban characters ranked below them, which also keeps them from banning themselves
+            if target.gm >= session.character.as_ref().unwrap().data.gm {
+                return reply(session, &format!("You can't ban {}", target.name)).await;
+            }
+
+            sql::Account::update_banned(target.account_id, true, &session.db).await?;
+            world::send(session, target.id, SessionMessage::Disconnect).await?;
+
+            reply(session, &format!("Banned {}", target.name)).await
+        })
+    }
+}
+
+/// Lists the characters online in the world, by channel
+pub struct Online;
+
+impl Command for Online {
+    fn name(&self) -> &'static str {
+        "online"
+    }
+
+    fn gm_level(&self) -> i32 {
+        1
+    }
+
+    fn usage(&self) -> &'static str {
+        ""
+    }
+
+    fn execute<'a>(
+        &'a self,
+        _args: Args<'a>,
+        session: &'a mut ChannelSession,
+    ) -> CommandFuture<'a> {
+        Box::pin(async move {
+            let online_characters =
+                sql::OnlineCharacter::load_all(session.world_id, &session.db).await?;
+            let mut channels: BTreeMap<i32, Vec<String>> = BTreeMap::new();
+
+            for online_character in online_characters {
+                channels
+                    .entry(online_character.channel_id)
+                    .or_default()
+                    .push(online_character.name);
+            }
+
+            reply(
+                session,
+                &format!(
+                    "{} characters online",
+                    channels.values().map(Vec::len).sum::<usize>()
+                ),
+            )
+            .await?;
+
+            for (channel_id, names) in channels.iter() {
+                // Channel ids are 0-indexed
+                let message = format!("Channel {}: {}", channel_id + 1, names.join(", "));
+                reply(session, &message).await?;
+            }
+
+            Ok(())
+        })
+    }
+}
+
+/// Reloads npc, portal, and quest scripts
+pub struct ReloadScripts;
+
+impl Command for ReloadScripts {
+    fn name(&self) -> &'static str {
+        "reloadscripts"
+    }
+
+    fn gm_level(&self) -> i32 {
+        3
+    }
+
+    fn usage(&self) -> &'static str {
+        ""
+    }
+
+    fn execute<'a>(
+        &'a self,
+        _args: Args<'a>,
+        session: &'a mut ChannelSession,
+    ) -> CommandFuture<'a> {
+        Box::pin(async move {
+            // TODO clear the script cache once npc/portal/quest scripts are supported
+            reply(session, "Reloading scripts isn't supported yet").await
+        })
+    }
+}
@@ -0,0 +1,260 @@
+use super::{reply, Args, Command, CommandFuture, UsageError};
+use crate::{guild, session::ChannelSession};
+use slate_data::{
+    maple::{exp, job},
+    nx,
+    packet::{self, SpecialEffect, Stat},
+    sql::item::InventoryType,
+};
+
+/// Gives the character an item
+pub struct Item;
+
+impl Command for Item {
+    fn name(&self) -> &'static str {
+        "item"
+    }
+
+    fn gm_level(&self) -> i32 {
+        1
+    }
+
+    fn usage(&self) -> &'static str {
+        "<item id> [amount]"
+    }
+
+    fn execute<'a>(
+        &'a self,
+        mut args: Args<'a>,
+        session: &'a mut ChannelSession,
+    ) -> CommandFuture<'a> {
+        Box::pin(async move {
+            let item_id: i32 = args.next()?;
+            let amount: i32 = args.next_or(1)?;
+
+            if amount <= 0 {
+                return Err(UsageError.into());
+            }
+
+            let exists = match InventoryType::from_item_id(item_id) {
+                Some(InventoryType::Equip) => nx::Equipment::load_by_id(item_id).is_some(),
+                _ => nx::Item::load(item_id).is_ok(),
+            };
+
+            if !exists {
+                return reply(session, &format!("Item {} doesn't exist", item_id)).await;
+            }
+
+            let character = session.character.as_mut().unwrap();
+
+            match character.add_item(item_id, amount, &session.db).await? {
+                Some(changes) => {
+                    session
+                        .stream
+                        .write_packet(packet::update_inventory(&changes))
+                        .await
+                }
+                None => reply(session, "Not enough inventory space").await,
+            }
+        })
+    }
+}
+
+/// Sets the character's level
+pub struct Level;
+
+impl Command for Level {
+    fn name(&self) -> &'static str {
+        "level"
+    }
+
+    fn gm_level(&self) -> i32 {
+        1
+    }
+
+    fn usage(&self) -> &'static str {
+        "<level>"
+    }
+
+    fn execute<'a>(
+        &'a self,
+        mut args: Args<'a>,
+        session: &'a mut ChannelSession,
+    ) -> CommandFuture<'a> {
+        Box::pin(async move {
+            let level: i32 = args.next()?;
+
+            if !(1..=exp::MAX_LEVEL).contains(&level) {
+                return Err(UsageError.into());
+            }
+
+            let character = session.character.as_mut().unwrap();
+            character.data.level = level;
+            character.data.exp = 0;
+            let character_id = character.data.id;
+
+            session
+                .stream
+                .write_packet(packet::update_stats(&[
+                    (Stat::Level, level),
+                    (Stat::Exp, 0),
+                ]))
+                .await?;
+            session.update_map_character();
+            session.broadcast_packet(
+                packet::show_foreign_effect(character_id, SpecialEffect::LevelUp),
+                false,
+            )?;
+            guild::update_member_level(session).await
+        })
+    }
+}
+
+/// Sets the character's job
+pub struct Job;
+
+impl Command for Job {
+    fn name(&self) -> &'static str {
+        "job"
+    }
+
+    fn gm_level(&self) -> i32 {
+        1
+    }
+
+    fn usage(&self) -> &'static str {
+        "<job id>"
+    }
+
+    fn execute<'a>(
+        &'a self,
+        mut args: Args<'a>,
+        session: &'a mut ChannelSession,
+    ) -> CommandFuture<'a> {
+        Box::pin(async move {
+            let job: i32 = args.next()?;
+
+            if !job::is_valid(job) {
+                return reply(session, &format!("Job {} doesn't exist", job)).await;
+            }
+
+            let character = session.character.as_mut().unwrap();
+            character.data.job = job;
+            let character_id = character.data.id;
+
+            session
+                .stream
+                .write_packet(packet::update_stats(&[(Stat::Job, job)]))
+                .await?;
+            session.broadcast_packet(
+                packet::show_foreign_effect(character_id, SpecialEffect::JobChange),
+                false,
+            )?;
+            guild::update_member_level(session).await
+        })
+    }
+}
+
+/// Gives (or takes away) mesos from the character
+pub struct Mesos;
+
+impl Command for Mesos {
+    fn name(&self) -> &'static str {
+        "mesos"
+    }
+
+    fn gm_level(&self) -> i32 {
+        1
+    }
+
+    fn usage(&self) -> &'static str {
+        "<amount>"
+    }
+
+    fn execute<'a>(
+        &'a self,
+        mut args: Args<'a>,
+        session: &'a mut ChannelSession,
+    ) -> CommandFuture<'a> {
+        Box::pin(async move {
+            let amount: i32 = args.next()?;
+
+            let character = session.character.as_mut().unwrap();
+            let mesos = (character.data.mesos as i64 + amount as i64).clamp(0, i32::MAX as i64);
+            character.data.mesos = mesos as i32;
+
+            session
+                .stream
+                .write_packet(packet::update_stats(&[(Stat::Mesos, mesos as i32)]))
+                .await
+        })
+    }
+}
+
+/// Fully restores the character's hp and mp
+pub struct Heal;
+
+impl Command for Heal {
+    fn name(&self) -> &'static str {
+        "heal"
+    }
+
+    fn gm_level(&self) -> i32 {
+        1
+    }
+
+    fn usage(&self) -> &'static str {
+        ""
+    }
+
+    fn execute<'a>(
+        &'a self,
+        _args: Args<'a>,
+        session: &'a mut ChannelSession,
+    ) -> CommandFuture<'a> {
+        Box::pin(async move {
+            let character = session.character.as_mut().unwrap();
+            character.data.hp = character.data.max_hp;
+            character.data.mp = character.data.max_mp;
+
+            let stats = [(Stat::Hp, character.data.hp), (Stat::Mp, character.data.mp)];
+            session
+                .stream
+                .write_packet(packet::update_stats(&stats))
+                .await?;
+            session.update_party_hp()
+        })
+    }
+}
+
+/// Gives (or takes away) guild points from the character's guild
+pub struct GuildPoints;
+
+impl Command for GuildPoints {
+    fn name(&self) -> &'static str {
+        "gp"
+    }
+
+    fn gm_level(&self) -> i32 {
+        1
+    }
+
+    fn usage(&self) -> &'static str {
+        "<amount>"
+    }
+
+    fn execute<'a>(
+        &'a self,
+        mut args: Args<'a>,
+        session: &'a mut ChannelSession,
+    ) -> CommandFuture<'a> {
+        Box::pin(async move {
+            let amount: i32 = args.next()?;
+
+            match session.character.as_ref().unwrap().data.guild {
+                Some(guild_id) => guild::gain_points(session, guild_id, amount).await,
+                None => reply(session, "You aren't in a guild").await,
+            }
+        })
+    }
+}
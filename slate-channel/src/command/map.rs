@@ -0,0 +1,147 @@
+use super::{reply, Args, Command, CommandFuture};
+use crate::session::ChannelSession;
+use slate_data::{maple, nx, packet, sql};
+
+/// The most monsters that can be spawned with a single command
+const MAX_SPAWN: i32 = 100;
+
+/// Warps to a map, or to the map of a character on the same channel
+pub struct Warp;
+
+impl Command for Warp {
+    fn name(&self) -> &'static str {
+        "warp"
+    }
+
+    fn gm_level(&self) -> i32 {
+        1
+    }
+
+    fn usage(&self) -> &'static str {
+        "<map id | character name>"
+    }
+
+    fn execute<'a>(
+        &'a self,
+        mut args: Args<'a>,
+        session: &'a mut ChannelSession,
+    ) -> CommandFuture<'a> {
+        Box::pin(async move {
+            let target = args.next_str()?;
+
+            let map_id = match target.parse::<i32>() {
+                Ok(map_id) => map_id,
+                Err(_) => {
+                    match sql::OnlineCharacter::load_optional_by_name(
+                        target,
+                        session.world_id,
+                        &session.db,
+                    )
+                    .await?
+                    {
+                        Some(online_character)
+                            if online_character.channel_id == session.channel_id =>
+                        {
+                            online_character.map_id
+                        }
+                        Some(_) => {
+                            return reply(session, &format!("{} is on another channel", target))
+                                .await
+                        }
+                        None => return reply(session, &format!("{} isn't online", target)).await,
+                    }
+                }
+            };
+
+            if maple::Map::load(map_id).is_err() {
+                return reply(session, &format!("Map {} doesn't exist", map_id)).await;
+            }
+
+            session.change_map(map_id, 0).await
+        })
+    }
+}
+
+/// Spawns monsters at the character's position
+pub struct Spawn;
+
+impl Command for Spawn {
+    fn name(&self) -> &'static str {
+        "spawn"
+    }
+
+    fn gm_level(&self) -> i32 {
+        1
+    }
+
+    fn usage(&self) -> &'static str {
+        "<monster id> [amount]"
+    }
+
+    fn execute<'a>(
+        &'a self,
+        mut args: Args<'a>,
+        session: &'a mut ChannelSession,
+    ) -> CommandFuture<'a> {
+        Box::pin(async move {
+            let monster_id: i32 = args.next()?;
+            let amount = args.next_or(1)?.clamp(1, MAX_SPAWN);
+
+            let data = match nx::Mob::load(monster_id) {
+                Ok(data) => data,
+                Err(_) => {
+                    return reply(session, &format!("Monster {} doesn't exist", monster_id)).await
+                }
+            };
+
+            let character = session.character.as_ref().unwrap();
+            let (field_id, pos) = (character.field_id, character.pos);
+
+            for _ in 0..amount {
+                // TODO use the foothold below the character once footholds are tracked
+                let monster =
+                    maple::Monster::new(session.state.next_object_id(), data.clone(), pos, 0);
+                session.broadcast_packet(packet::spawn_monster(&monster, true), true)?;
+                session.state.add_monster(field_id, monster);
+            }
+
+            Ok(())
+        })
+    }
+}
+
+/// Kills every monster in the character's map
+pub struct KillAll;
+
+impl Command for KillAll {
+    fn name(&self) -> &'static str {
+        "killall"
+    }
+
+    fn gm_level(&self) -> i32 {
+        1
+    }
+
+    fn usage(&self) -> &'static str {
+        ""
+    }
+
+    fn execute<'a>(
+        &'a self,
+        _args: Args<'a>,
+        session: &'a mut ChannelSession,
+    ) -> CommandFuture<'a> {
+        Box::pin(async move {
+            let field_id = session.character.as_ref().unwrap().field_id;
+
+            // Monsters killed this way don't give exp or drop items
+            let monsters = session.state.remove_monsters(field_id);
+
+            for monster in monsters.iter() {
+                session.broadcast_packet(packet::kill_monster(monster.object_id, 1), true)?;
+            }
+
+            reply(session, &format!("Killed {} monsters", monsters.len())).await
+        })
+    }
+}
@@ -0,0 +1,137 @@
+use crate::session::ChannelSession;
+use slate_data::packet::{self, NoticeType};
+use std::{fmt, future::Future, pin::Pin, str::FromStr};
+
+mod admin;
+mod character;
+mod map;
+
+/// Every command that can be used in game
+static COMMANDS: &[&dyn Command] = &[
+    &map::Warp,
+    &map::Spawn,
+    &map::KillAll,
+    &character::Item,
+    &character::Level,
+    &character::Job,
+    &character::Mesos,
+    &character::Heal,
+    &character::GuildPoints,
+    &admin::Ban,
+    &admin::Online,
+    &admin::ReloadScripts,
+];
+
+pub type CommandFuture<'a> = Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>>;
+
+/// A chat command, ex. `!warp 100000000`
+pub trait Command: Sync {
+    /// The name the command is called by (without the prefix)
+    fn name(&self) -> &'static str;
+
+    /// The minimum gm level needed to use the command
+    fn gm_level(&self) -> i32;
+
+    /// The command's arguments, shown when it's used incorrectly
+    fn usage(&self) -> &'static str;
+
+    fn execute<'a>(&'a self, args: Args<'a>, session: &'a mut ChannelSession) -> CommandFuture<'a>;
+}
+
+/// Handles a chat message if it's a command, commands start with either `!` or `@`
+/// Returns false if the message isn't a command and should be sent as regular chat
+pub async fn handle(message: &str, session: &mut ChannelSession) -> anyhow::Result<bool> {
+    let prefix = match message.chars().next() {
+        Some(prefix @ ('!' | '@')) => prefix,
+        _ => return Ok(false),
+    };
+
+    let mut parts = message[1..].split_whitespace();
+
+    let name = match parts.next() {
+        Some(name) => name.to_lowercase(),
+        None => return Ok(false),
+    };
+
+    let gm_level = session.character.as_ref().unwrap().data.gm;
+
+    let command = match COMMANDS
+        .iter()
+        .find(|command| command.name() == name && command.gm_level() <= gm_level)
+    {
+        Some(command) => command,
+        // Regular players can use the prefixes in chat without getting errors
+        None if gm_level == 0 => return Ok(false),
+        None => {
+            reply(session, &format!("Unknown command: {}{}", prefix, name)).await?;
+            return Ok(true);
+        }
+    };
+
+    let args = Args::new(parts.collect());
+
+    if let Err(e) = command.execute(args, session).await {
+        if !e.is::<UsageError>() {
+            return Err(e);
+        }
+
+        let usage = format!("Usage: {}{} {}", prefix, command.name(), command.usage());
+        reply(session, usage.trim_end()).await?;
+    }
+
+    Ok(true)
+}
+
+/// Sends a command's response to the character using it
+async fn reply(session: &mut ChannelSession, message: &str) -> anyhow::Result<()> {
+    session
+        .stream
+        .write_packet(packet::server_notice(NoticeType::PinkText, message))
+        .await
+}
+
+/// The arguments passed to a command
+pub struct Args<'a> {
+    args: Vec<&'a str>,
+    index: usize,
+}
+
+impl<'a> Args<'a> {
+    fn new(args: Vec<&'a str>) -> Self {
+        Self { args, index: 0 }
+    }
+
+    /// Gets the next argument as a string
+    pub fn next_str(&mut self) -> anyhow::Result<&'a str> {
+        let arg = self.args.get(self.index).ok_or(UsageError)?;
+        self.index += 1;
+        Ok(arg)
+    }
+
+    /// Parses the next argument
+    pub fn next<T: FromStr>(&mut self) -> anyhow::Result<T> {
+        let arg = self.next_str()?;
+        Ok(arg.parse().map_err(|_| UsageError)?)
+    }
+
+    /// Parses the next argument if there is one, otherwise returns the default
+    pub fn next_or<T: FromStr>(&mut self, default: T) -> anyhow::Result<T> {
+        if self.index >= self.args.len() {
+            return Ok(default);
+        }
+
+        self.next()
+    }
+}
+
+/// Returned when a command is missing arguments or given invalid arguments
+#[derive(Debug)]
+pub struct UsageError;
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid command usage")
+    }
+}
+
+impl std::error::Error for UsageError {}
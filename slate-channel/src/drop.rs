@@ -0,0 +1,109 @@
+use crate::session::ChannelSession;
+use slate_data::{
+    maple::{
+        self,
+        map::{MapBroadcast, PacketBroadcast},
+    },
+    packet,
+};
+use std::time::Duration;
+use tokio::time;
+
+/// The horizontal space between items dropped at the same time
+const DROP_SPACING: i32 = 25;
+
+/// How long items stay on the ground before they disappear
+const DROP_DURATION: Duration = Duration::from_secs(180);
+
+/// Drops items in the character's map, spread out around the position of the object that dropped them
+pub fn spawn(
+    session: &mut ChannelSession,
+    source_id: i32,
+    pos: (i32, i32),
+    item_ids: &[i32],
+) -> anyhow::Result<()> {
+    let field_id = session.character.as_ref().unwrap().field_id;
+    let first_x = pos.0 - (item_ids.len() as i32 - 1) * DROP_SPACING / 2;
+
+    for (i, &item_id) in item_ids.iter().enumerate() {
+        let drop = maple::Drop {
+            object_id: session.state.next_object_id(),
+            item_id,
+            amount: 1,
+            pos: (first_x + i as i32 * DROP_SPACING, pos.1),
+            source_pos: pos,
+            source_id,
+        };
+
+        let object_id = drop.object_id;
+        session.broadcast_packet(packet::spawn_drop(&drop, true), true)?;
+        session.state.add_drop(field_id, drop);
+        schedule_expiry(session, field_id, object_id);
+    }
+
+    Ok(())
+}
+
+/// Removes a dropped item from the map once it's been on the ground for too long, unless it's picked up first
+fn schedule_expiry(session: &ChannelSession, field_id: i32, object_id: i32) {
+    let state = session.state.clone();
+
+    tokio::spawn(async move {
+        time::sleep(DROP_DURATION).await;
+
+        if state.take_drop(field_id, object_id).is_none() {
+            return;
+        }
+
+        // Nobody might be in the map to see it disappear
+        let _ = state
+            .get_map_broadcast_tx(field_id)
+            .send(MapBroadcast::Packet(PacketBroadcast {
+                packet: packet::expire_drop(object_id),
+                sender_id: 0,
+                send_to_sender: true,
+            }));
+    });
+}
+
+/// Picks up a dropped item in the character's map, putting it into their inventory
+/// `pet_slot` is the slot of the summoned pet picking it up for the character, if any
+pub async fn pick_up(
+    session: &mut ChannelSession,
+    object_id: i32,
+    pet_slot: Option<i32>,
+) -> anyhow::Result<()> {
+    let character = session.character.as_mut().unwrap();
+    let field_id = character.field_id;
+    let character_id = character.data.id;
+
+    let drop = match session.state.take_drop(field_id, object_id) {
+        Some(drop) => drop,
+        None => return session.stream.write_packet(packet::update_stats(&[])).await,
+    };
+
+    let changes = match character
+        .add_item(drop.item_id, drop.amount, &session.db)
+        .await?
+    {
+        Some(changes) => changes,
+        None => {
+            // Someone else can pick it up instead
+            session.state.add_drop(field_id, drop);
+            return session.stream.write_packet(packet::update_stats(&[])).await;
+        }
+    };
+
+    session
+        .stream
+        .write_packet(packet::update_inventory(&changes))
+        .await?;
+    session
+        .stream
+        .write_packet(packet::show_item_gain(drop.item_id, drop.amount))
+        .await?;
+    session.broadcast_packet(
+        packet::pick_up_drop(object_id, character_id, pet_slot),
+        true,
+    )
+}
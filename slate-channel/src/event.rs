@@ -0,0 +1,523 @@
+use crate::{
+    drop, monster,
+    session::{ChannelSession, SessionMessage},
+    state::State,
+};
+use slate_data::{maple, nx, packet, sql};
+use sqlx::types::chrono::Utc;
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::{sync::Mutex, time};
+
+/// A running event shared by the sessions of its characters
+pub type SharedEvent = Arc<Mutex<Event>>;
+
+/// An event a party plays through together in its own stage maps (ex. a party quest)
+pub struct EventInfo {
+    pub name: &'static str,
+    /// The map of each stage, in order
+    pub stage_map_ids: &'static [i32],
+    /// The map the party can be sent to once it clears the last stage
+    pub bonus_map_id: Option<i32>,
+    /// The map characters are sent to when they leave the event or its time runs out
+    pub exit_map_id: i32,
+    pub min_members: usize,
+    pub max_members: usize,
+    pub min_level: i32,
+    pub max_level: i32,
+    /// The seconds the party has to clear every stage
+    pub time_limit: i64,
+    /// The seconds the party gets to spend in the bonus map
+    pub bonus_time_limit: i64,
+    /// The exp the characters in a stage's map get when it's cleared
+    pub stage_exp: &'static [i32],
+    /// The item each stage's monsters drop (ex. coupons), if any
+    pub stage_drops: &'static [Option<i32>],
+    /// Items that only exist for the event, they're taken from characters when they leave it
+    pub event_items: &'static [i32],
+    /// Sets up the puzzle of the stage the party just reached
+    pub start_stage: fn(&mut Event) -> anyhow::Result<()>,
+}
+
+impl EventInfo {
+    /// Gets the ids of all of the event's maps
+    pub fn map_ids(&self) -> Vec<i32> {
+        let mut map_ids = self.stage_map_ids.to_vec();
+        map_ids.extend(self.bonus_map_id);
+        map_ids
+    }
+
+    /// Gets the stage of one of the event's maps, None for the bonus map
+    pub fn get_stage(&self, map_id: i32) -> Option<usize> {
+        self.stage_map_ids.iter().position(|id| *id == map_id)
+    }
+}
+
+/// A running instance of an event, played in its own private copies of the event's maps
+pub struct Event {
+    pub info: &'static EventInfo,
+    /// The field id of the event's copy of each of its maps, by map id
+    pub field_ids: HashMap<i32, i32>,
+    pub party_id: i32,
+    pub leader_id: i32,
+    /// The characters still in the event
+    pub character_ids: Vec<i32>,
+    /// The furthest stage the party has reached
+    pub stage: usize,
+    pub stage_cleared: bool,
+    /// When the event's time runs out, in milliseconds
+    pub ends_at: i64,
+    /// Each character's part of the current stage's puzzle (ex. the question they were asked)
+    pub answers: HashMap<i32, i32>,
+    /// The areas characters have to stand in to clear the current stage
+    pub combination: Vec<String>,
+    /// Set once the event ends and its characters are sent to the exit map
+    pub closed: bool,
+}
+
+impl Event {
+    /// Gets the seconds left before the event's time runs out
+    pub fn get_remaining_seconds(&self, now: i64) -> i32 {
+        ((self.ends_at - now) / 1000).max(0) as i32
+    }
+
+    pub fn is_last_stage(&self) -> bool {
+        self.stage + 1 == self.info.stage_map_ids.len()
+    }
+}
+
+/// Starts an event for the party led by the character in its own copies of the event's maps,
+/// the given characters must be in the character's map
+pub async fn start(
+    session: &mut ChannelSession,
+    info: &'static EventInfo,
+    party_id: i32,
+    character_ids: Vec<i32>,
+) -> anyhow::Result<()> {
+    let character_id = session.character.as_ref().unwrap().data.id;
+
+    let mut event = Event {
+        info,
+        field_ids: HashMap::new(),
+        party_id,
+        leader_id: character_id,
+        character_ids: character_ids.clone(),
+        stage: 0,
+        stage_cleared: false,
+        ends_at: Utc::now().timestamp_millis() + info.time_limit * 1000,
+        answers: HashMap::new(),
+        combination: Vec::new(),
+        closed: false,
+    };
+    (info.start_stage)(&mut event)?;
+
+    for map_id in info.map_ids() {
+        let field_id = session.state.create_instance(map_id);
+        event.field_ids.insert(map_id, field_id);
+    }
+
+    let field_ids: Vec<i32> = event.field_ids.values().copied().collect();
+
+    if let Err(e) = spawn_monsters(&session.state, &event.field_ids) {
+        for field_id in field_ids {
+            session.state.remove_instance(field_id);
+        }
+
+        return Err(e);
+    }
+
+    let map_id = info.stage_map_ids[0];
+    let message = SessionMessage::ChangeField {
+        map_id,
+        field_id: event.field_ids[&map_id],
+        portal_id: 0,
+    };
+
+    let event = Arc::new(Mutex::new(event));
+    session.state.add_event(&field_ids, event.clone());
+
+    log::info!("Started {} for party {}", info.name, party_id);
+    tokio::spawn(run_timer(session.state.clone(), event));
+
+    for character_id in character_ids {
+        monster::send(session, character_id, message.clone()).await?;
+    }
+
+    Ok(())
+}
+
+/// Gets the event the character is in, if they're in one of its maps
+pub async fn get(session: &ChannelSession) -> Option<SharedEvent> {
+    let character = session.character.as_ref().unwrap();
+    let event = session.state.get_event(character.field_id)?;
+    let is_member = event
+        .lock()
+        .await
+        .character_ids
+        .contains(&character.data.id);
+    is_member.then_some(event)
+}
+
+/// Gets the field id of the event's copy of a map, if the character is in an event with a copy of it
+pub async fn get_field_id(session: &ChannelSession, map_id: i32) -> Option<i32> {
+    let event = get(session).await?;
+    let field_id = event.lock().await.field_ids.get(&map_id).copied();
+    field_id
+}
+
+/// Shows the event's clock to a character entering one of its maps, moving the party on if it's a new stage
+pub async fn on_enter_map(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let event = match get(session).await {
+        Some(event) => event,
+        None => return Ok(()),
+    };
+
+    let map_id = session.character.as_ref().unwrap().data.map;
+
+    let seconds = {
+        let mut event = event.lock().await;
+
+        match event.info.get_stage(map_id) {
+            Some(stage) if stage > event.stage => {
+                event.stage = stage;
+                event.stage_cleared = false;
+                event.answers.clear();
+                event.combination.clear();
+
+                let start_stage = event.info.start_stage;
+                start_stage(&mut event)?;
+            }
+            _ => {}
+        }
+
+        event.get_remaining_seconds(Utc::now().timestamp_millis())
+    };
+
+    session.stream.write_packet(packet::clock(seconds)).await
+}
+
+/// Removes the character from the event they were in if they've left its maps
+pub async fn on_change_map(
+    session: &mut ChannelSession,
+    previous_field_id: i32,
+) -> anyhow::Result<()> {
+    let event = match session.state.get_event(previous_field_id) {
+        Some(event) => event,
+        None => return Ok(()),
+    };
+
+    let field_id = session.character.as_ref().unwrap().field_id;
+
+    if let Some(current) = session.state.get_event(field_id) {
+        if Arc::ptr_eq(&current, &event) {
+            return Ok(());
+        }
+    }
+
+    leave(session, &event).await?;
+
+    let info = event.lock().await.info;
+
+    for item in take_event_items(session, info).await? {
+        session
+            .stream
+            .write_packet(packet::update_item_amount(&item))
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Sends the character out of their event if they're no longer in its party
+pub async fn on_party_changed(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let event = match get(session).await {
+        Some(event) => event,
+        None => return Ok(()),
+    };
+
+    let (party_id, exit_map_id) = {
+        let event = event.lock().await;
+        (event.party_id, event.info.exit_map_id)
+    };
+
+    if session.character.as_ref().unwrap().data.party == Some(party_id) {
+        return Ok(());
+    }
+
+    session.change_map(exit_map_id, 0).await
+}
+
+/// Removes a disconnecting character from their event
+pub async fn on_disconnect(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let event = match get(session).await {
+        Some(event) => event,
+        None => return Ok(()),
+    };
+
+    leave(session, &event).await?;
+
+    // The character isn't in the event when they log back in, so they don't get to keep its items
+    let info = event.lock().await.info;
+    take_event_items(session, info).await?;
+
+    // They log back in at the exit map instead
+    let character = session.character.as_mut().unwrap();
+    session
+        .state
+        .remove_map_character(character.field_id, character.data.id);
+    character.data.map = info.exit_map_id;
+    character.field_id = info.exit_map_id;
+    character.data.spawn_point = 0;
+    Ok(())
+}
+
+/// Drops the current stage's item where a monster in the event died, and brings the monster back until the stage
+/// is cleared
+pub async fn on_monster_killed(
+    session: &mut ChannelSession,
+    monster: &maple::Monster,
+) -> anyhow::Result<()> {
+    let character = session.character.as_ref().unwrap();
+    let (map_id, field_id) = (character.data.map, character.field_id);
+
+    let event = match session.state.get_event(field_id) {
+        Some(event) => event,
+        None => return Ok(()),
+    };
+
+    let item_id = {
+        let event = event.lock().await;
+
+        match event.info.get_stage(map_id) {
+            Some(stage) if stage == event.stage && !event.stage_cleared => {
+                event.info.stage_drops.get(stage).copied().flatten()
+            }
+            _ => return Ok(()),
+        }
+    };
+
+    if let Some(item_id) = item_id {
+        drop::spawn(session, monster.object_id, monster.pos, &[item_id])?;
+    }
+
+    // Bosses only have to be killed once
+    if monster.data.is_boss {
+        return Ok(());
+    }
+
+    let respawned = maple::Monster::new(
+        session.state.next_object_id(),
+        monster.data.clone(),
+        monster.pos,
+        monster.fh,
+    );
+    session.broadcast_packet(packet::spawn_monster(&respawned, true), true)?;
+    session.state.add_monster(field_id, respawned);
+    Ok(())
+}
+
+/// Clears the stage the character's event is on, opening the way to the next stage
+/// The event's characters in the stage's map get its exp
+pub async fn clear_stage(session: &mut ChannelSession, event: &SharedEvent) -> anyhow::Result<()> {
+    let (exp, character_ids) = {
+        let mut event = event.lock().await;
+        event.stage_cleared = true;
+
+        let exp = event.info.stage_exp.get(event.stage).copied().unwrap_or(0);
+        (exp, event.character_ids.clone())
+    };
+
+    session.broadcast_packet(packet::show_map_effect("quest/party/clear"), true)?;
+    session.broadcast_packet(packet::play_map_sound("Party1/Clear"), true)?;
+    session.broadcast_packet(packet::trigger_map_object("gate"), true)?;
+
+    let field_id = session.character.as_ref().unwrap().field_id;
+    let map_characters = session.state.get_map_characters(field_id);
+    let exp = (exp as f64 * session.state.world.exp_rate as f64) as i32;
+
+    for character_id in character_ids {
+        if !map_characters.contains_key(&character_id) {
+            continue;
+        }
+
+        let message = SessionMessage::GainExp {
+            exp,
+            party_bonus: 0,
+        };
+        monster::send(session, character_id, message).await?;
+    }
+
+    Ok(())
+}
+
+/// Shows everyone in the character's map that they got the stage's puzzle wrong
+pub fn fail_stage(session: &ChannelSession) -> anyhow::Result<()> {
+    session.broadcast_packet(packet::show_map_effect("quest/party/wrong_kor"), true)?;
+    session.broadcast_packet(packet::play_map_sound("Party1/Failed"), true)
+}
+
+/// Sends the event's characters to its bonus map, restarting the clock with the bonus map's time limit
+/// Returns false if the event doesn't have a bonus map
+pub async fn start_bonus(
+    session: &mut ChannelSession,
+    event: &SharedEvent,
+) -> anyhow::Result<bool> {
+    let (map_id, field_id, character_ids) = {
+        let mut event = event.lock().await;
+
+        let map_id = match event.info.bonus_map_id {
+            Some(map_id) => map_id,
+            None => return Ok(false),
+        };
+
+        event.ends_at = Utc::now().timestamp_millis() + event.info.bonus_time_limit * 1000;
+        (
+            map_id,
+            event.field_ids[&map_id],
+            event.character_ids.clone(),
+        )
+    };
+
+    let message = SessionMessage::ChangeField {
+        map_id,
+        field_id,
+        portal_id: 0,
+    };
+
+    for character_id in character_ids {
+        monster::send(session, character_id, message.clone()).await?;
+    }
+
+    Ok(true)
+}
+
+/// Checks if the character can go through a portal into a map, an event's next stage only opens once the
+/// current one is cleared
+pub async fn can_enter(session: &ChannelSession, map_id: i32) -> bool {
+    let event = match get(session).await {
+        Some(event) => event,
+        None => return true,
+    };
+
+    let event = event.lock().await;
+
+    match event.info.get_stage(map_id) {
+        Some(stage) => stage <= event.stage || (stage == event.stage + 1 && event.stage_cleared),
+        None => event.info.bonus_map_id != Some(map_id),
+    }
+}
+
+/// Removes the character from an event, the event ends if its leader leaves or it doesn't have enough characters
+async fn leave(session: &ChannelSession, event: &SharedEvent) -> anyhow::Result<()> {
+    let character_id = session.character.as_ref().unwrap().data.id;
+
+    let ended = {
+        let mut event = event.lock().await;
+
+        if !event.character_ids.contains(&character_id) {
+            return Ok(());
+        }
+
+        event.character_ids.retain(|id| *id != character_id);
+        event.leader_id == character_id || event.character_ids.len() < event.info.min_members
+    };
+
+    if ended {
+        end(&session.state, event).await;
+    }
+
+    Ok(())
+}
+
+/// Takes the event's items from the character, returning the updated items
+async fn take_event_items(
+    session: &mut ChannelSession,
+    info: &EventInfo,
+) -> anyhow::Result<Vec<sql::Item>> {
+    let character = session.character.as_mut().unwrap();
+    let mut taken = Vec::new();
+
+    for item_id in info.event_items {
+        while let Some(amount) = character
+            .items
+            .iter()
+            .find(|item| item.item_id == *item_id)
+            .map(|item| item.amount)
+        {
+            let item = character.remove_item(*item_id, amount).unwrap();
+            item.update_amount(&session.db).await?;
+            taken.push(item);
+        }
+    }
+
+    Ok(taken)
+}
+
+/// Spawns the monsters of each of an event's maps in the event's copy of the map, by map id
+fn spawn_monsters(state: &State, field_ids: &HashMap<i32, i32>) -> anyhow::Result<()> {
+    for (map_id, field_id) in field_ids.iter() {
+        for life in nx::Map::load(*map_id)?.monsters.values() {
+            let pos = (life.position.0 as i32, life.position.1 as i32);
+            let data = nx::Mob::load(life.id)?;
+            let monster = maple::Monster::new(state.next_object_id(), data, pos, life.fh as i32);
+            state.add_monster(*field_id, monster);
+        }
+    }
+
+    Ok(())
+}
+
+/// Ends the event once its time runs out, the bonus map can push back when that is
+async fn run_timer(state: Arc<State>, event: SharedEvent) {
+    loop {
+        let ends_at = {
+            let event = event.lock().await;
+
+            if event.closed {
+                return;
+            }
+
+            event.ends_at
+        };
+
+        let now = Utc::now().timestamp_millis();
+
+        if ends_at <= now {
+            break;
+        }
+
+        time::sleep(Duration::from_millis((ends_at - now) as u64)).await;
+    }
+
+    end(&state, &event).await;
+}
+
+/// Ends an event, sending its characters to the exit map and removing its copies of the event's maps
+async fn end(state: &State, event: &SharedEvent) {
+    let (info, field_ids, character_ids) = {
+        let mut event = event.lock().await;
+
+        if event.closed {
+            return;
+        }
+
+        event.closed = true;
+        let field_ids: Vec<i32> = event.field_ids.values().copied().collect();
+        (event.info, field_ids, event.character_ids.clone())
+    };
+
+    state.remove_event(&field_ids);
+    log::info!("{} ended", info.name);
+
+    let message = SessionMessage::ChangeMap {
+        map_id: info.exit_map_id,
+        portal_id: 0,
+    };
+
+    for character_id in character_ids {
+        state.send_to_session(character_id, message.clone()).await;
+    }
+
+    for field_id in field_ids {
+        state.remove_instance(field_id);
+    }
+}
@@ -0,0 +1,825 @@
+use crate::{
+    monster,
+    session::{ChannelSession, SessionMessage},
+    state::State,
+};
+use slate_data::{
+    maple::{
+        self,
+        map::{MapBroadcast, PacketBroadcast},
+    },
+    nx,
+    packet::{self, NoticeType, NpcDialog},
+    sql,
+};
+use slate_net::Packet;
+use sqlx::types::chrono::Utc;
+use std::{sync::Arc, time::Duration};
+use tokio::{sync::Mutex, time};
+
+/// A boss expedition shared by the sessions of its characters
+pub type SharedExpedition = Arc<Mutex<Expedition>>;
+
+/// A boss fought by an expedition, a group of characters larger than a party
+pub struct ExpeditionInfo {
+    pub name: &'static str,
+    /// The key a character's entries into the boss's map are stored under
+    pub boss: &'static str,
+    /// The npc characters register with
+    pub npc_id: i32,
+    /// Where the expedition registers, and where it's sent back to once its time runs out
+    pub entry_map_id: i32,
+    pub boss_map_id: i32,
+    pub min_members: usize,
+    pub max_members: usize,
+    pub min_level: i32,
+    /// How many times a character can enter the boss's map each day
+    pub daily_entries: i64,
+    /// The seconds the expedition has to defeat the boss
+    pub time_limit: i64,
+    /// The monsters that make up the boss, they share one pool of hp shown on the first part's hp bar
+    pub parts: &'static [i32],
+    pub boss_pos: (i32, i32),
+}
+
+const EXPEDITIONS: [ExpeditionInfo; 3] = [
+    ExpeditionInfo {
+        name: "Zakum",
+        boss: "zakum",
+        npc_id: 2030013,
+        entry_map_id: 211042300,
+        boss_map_id: 280030000,
+        min_members: 6,
+        max_members: 30,
+        min_level: 50,
+        daily_entries: 2,
+        time_limit: 2 * 60 * 60,
+        // Zakum's body, then its eight arms
+        parts: &[
+            8800000, 8800003, 8800004, 8800005, 8800006, 8800007, 8800008, 8800009, 8800010,
+        ],
+        // TODO summon Zakum by dropping an Eye of Fire on its altar, once characters can drop items
+        boss_pos: (-10, -215),
+    },
+    ExpeditionInfo {
+        name: "Horntail",
+        boss: "horntail",
+        npc_id: 2083004,
+        entry_map_id: 240050400,
+        boss_map_id: 240060200,
+        min_members: 6,
+        max_members: 30,
+        min_level: 80,
+        daily_entries: 2,
+        time_limit: 2 * 60 * 60,
+        // Horntail's three heads, hands, wings, legs, and tail
+        parts: &[
+            8810002, 8810003, 8810004, 8810005, 8810006, 8810007, 8810008, 8810009,
+        ],
+        boss_pos: (71, 260),
+    },
+    ExpeditionInfo {
+        name: "Pink Bean",
+        boss: "pink_bean",
+        npc_id: 2141000,
+        entry_map_id: 270050000,
+        boss_map_id: 270050100,
+        min_members: 6,
+        max_members: 30,
+        min_level: 120,
+        daily_entries: 1,
+        time_limit: 60 * 60,
+        // TODO the goddess statues Pink Bean is protected by
+        parts: &[8820001],
+        boss_pos: (5, -42),
+    },
+];
+
+/// A character registered with an expedition
+#[derive(Debug, Clone)]
+pub struct ExpeditionMember {
+    pub character_id: i32,
+    pub name: String,
+}
+
+/// An expedition registered with a boss's npc, it fights the boss in its own copy of the boss map
+pub struct Expedition {
+    pub info: &'static ExpeditionInfo,
+    pub leader_id: i32,
+    /// The characters registered with the expedition, including the leader
+    pub members: Vec<ExpeditionMember>,
+    /// Set once the expedition has entered the boss map, characters can't register after that
+    pub started: bool,
+    /// The field id of the expedition's copy of the boss map, once it's started
+    pub field_id: i32,
+    /// The hp left in the pool shared by the boss's parts
+    pub boss_hp: i64,
+    pub boss_max_hp: i64,
+    /// The colors of the boss's hp bar
+    pub boss_hp_colors: (u8, u8),
+    pub boss_defeated: bool,
+    /// When the expedition's time runs out, in milliseconds
+    pub ends_at: i64,
+    /// Set once the expedition is disbanded or its time runs out
+    pub closed: bool,
+}
+
+impl Expedition {
+    pub fn is_member(&self, character_id: i32) -> bool {
+        self.members
+            .iter()
+            .any(|member| member.character_id == character_id)
+    }
+
+    /// Gets the boss's hp bar, scaled down for bosses with more hp than the bar can hold
+    fn boss_hp_bar(&self) -> Packet {
+        let scale = self.boss_max_hp / i32::MAX as i64 + 1;
+        let (tag_color, tag_bg_color) = self.boss_hp_colors;
+
+        packet::show_boss_hp(
+            self.info.parts[0],
+            (self.boss_hp / scale) as i32,
+            (self.boss_max_hp / scale) as i32,
+            tag_color,
+            tag_bg_color,
+        )
+    }
+}
+
+/// Checks if an npc registers expeditions
+pub fn is_expedition_npc(npc_id: i32) -> bool {
+    get_info_by_npc(npc_id).is_some()
+}
+
+/// Shows an expedition npc's options, returns false if the npc doesn't register expeditions
+pub async fn talk(session: &mut ChannelSession, npc_id: i32) -> anyhow::Result<bool> {
+    let info = match get_info_by_npc(npc_id) {
+        Some(info) => info,
+        None => return Ok(false),
+    };
+
+    let text = format!(
+        "Expeditions of {} to {} characters of level {} or higher can take on {}. \
+        Each of you can enter {} time(s) a day.\r\n\
+        #L0#Register a new expedition as its leader#l\r\n\
+        #L1#Join the expedition#l\r\n\
+        #L2#Leave the expedition#l\r\n\
+        #L3#See who's in the expedition#l\r\n\
+        #L4#Start the expedition#l",
+        info.min_members, info.max_members, info.min_level, info.name, info.daily_entries
+    );
+
+    session.npc_conversation = Some(npc_id);
+    session
+        .stream
+        .write_packet(packet::npc_talk(npc_id, NpcDialog::Selection, &text))
+        .await?;
+    Ok(true)
+}
+
+/// Handles the option the character picked from an expedition npc
+pub async fn respond(
+    session: &mut ChannelSession,
+    npc_id: i32,
+    selection: i32,
+) -> anyhow::Result<()> {
+    let info = match get_info_by_npc(npc_id) {
+        Some(info) => info,
+        None => return Ok(()),
+    };
+
+    let expedition = session.state.get_expedition(info.boss_map_id);
+
+    match (selection, expedition) {
+        (0, None) => register(session, info).await,
+        (0, Some(_)) => {
+            say(
+                session,
+                npc_id,
+                "An expedition has already been registered.",
+            )
+            .await
+        }
+        (_, None) => say(session, npc_id, "No expedition has been registered yet.").await,
+        (1, Some(expedition)) => join(session, &expedition).await,
+        (2, Some(expedition)) => {
+            let character_id = session.character.as_ref().unwrap().data.id;
+
+            if !expedition.lock().await.is_member(character_id) {
+                return say(session, npc_id, "You aren't in the expedition.").await;
+            }
+
+            leave(session, &expedition).await
+        }
+        (3, Some(expedition)) => show_members(session, &expedition).await,
+        (4, Some(expedition)) => start(session, &expedition).await,
+        _ => Ok(()),
+    }
+}
+
+/// Shows the expedition's clock and the boss's hp bar to a character entering its boss map
+pub async fn on_enter_map(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let expedition = match get(session).await {
+        Some(expedition) => expedition,
+        None => return Ok(()),
+    };
+
+    let (seconds, hp_bar) = {
+        let expedition = expedition.lock().await;
+        let now = Utc::now().timestamp_millis();
+        let seconds = ((expedition.ends_at - now) / 1000).max(0) as i32;
+        let hp_bar = (!expedition.boss_defeated).then(|| expedition.boss_hp_bar());
+        (seconds, hp_bar)
+    };
+
+    session.stream.write_packet(packet::clock(seconds)).await?;
+
+    if let Some(hp_bar) = hp_bar {
+        session.stream.write_packet(hp_bar).await?;
+    }
+
+    Ok(())
+}
+
+/// Gets the field id of the expedition's copy of its boss map, if the character is fighting in it
+pub async fn get_field_id(session: &ChannelSession, map_id: i32) -> Option<i32> {
+    let expedition = get(session).await?;
+    let expedition = expedition.lock().await;
+    (expedition.info.boss_map_id == map_id).then_some(expedition.field_id)
+}
+
+/// Removes the character from their expedition if they've left its entry map before it started, or its boss map
+pub async fn on_change_map(
+    session: &mut ChannelSession,
+    previous_field_id: i32,
+) -> anyhow::Result<()> {
+    let expedition = match get_by_field(session, previous_field_id).await {
+        Some(expedition) => expedition,
+        None => return Ok(()),
+    };
+
+    leave(session, &expedition).await
+}
+
+/// Removes a disconnecting character from their expedition, they log back in at the entry map
+pub async fn on_disconnect(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let field_id = session.character.as_ref().unwrap().field_id;
+
+    let expedition = match get_by_field(session, field_id).await {
+        Some(expedition) => expedition,
+        None => return Ok(()),
+    };
+
+    leave(session, &expedition).await?;
+
+    let (info, started) = {
+        let expedition = expedition.lock().await;
+        (expedition.info, expedition.started)
+    };
+
+    if started {
+        let character = session.character.as_mut().unwrap();
+        session
+            .state
+            .remove_map_character(field_id, character.data.id);
+        character.data.map = info.entry_map_id;
+        character.field_id = info.entry_map_id;
+        character.data.spawn_point = 0;
+    }
+
+    Ok(())
+}
+
+/// Checks if the character can go through a portal into a map, boss maps are only open to the expedition fighting
+/// in them
+pub async fn can_enter(session: &ChannelSession, map_id: i32) -> bool {
+    if !EXPEDITIONS.iter().any(|info| info.boss_map_id == map_id) {
+        return true;
+    }
+
+    get_field_id(session, map_id).await.is_some()
+}
+
+/// Takes the damage dealt to a part of an expedition's boss from the hp pool its parts share, killing every part
+/// once it runs out
+/// Returns false if the monster isn't part of an expedition's boss
+pub async fn on_boss_damaged(
+    session: &mut ChannelSession,
+    monster: &maple::Monster,
+    damage: i32,
+) -> anyhow::Result<bool> {
+    let field_id = session.character.as_ref().unwrap().field_id;
+
+    let expedition = match session.state.get_expedition(field_id) {
+        Some(expedition) => expedition,
+        None => return Ok(false),
+    };
+
+    let (parts, hp_bar, depleted) = {
+        let mut expedition = expedition.lock().await;
+
+        if !expedition.info.parts.contains(&monster.data.id) {
+            return Ok(false);
+        }
+
+        expedition.boss_hp = (expedition.boss_hp - damage as i64).max(0);
+        (
+            expedition.info.parts,
+            expedition.boss_hp_bar(),
+            expedition.boss_hp == 0,
+        )
+    };
+
+    session.broadcast_packet(hp_bar, true)?;
+
+    if depleted {
+        for part in session.state.get_monsters(field_id) {
+            if !parts.contains(&part.data.id) {
+                continue;
+            }
+
+            if let Some(part) = session.state.remove_monster(field_id, part.object_id) {
+                monster::kill(session, part).await?;
+            }
+        }
+    }
+
+    Ok(true)
+}
+
+/// Announces the expedition's victory once every part of its boss is dead
+pub async fn on_monster_killed(
+    session: &mut ChannelSession,
+    monster: &maple::Monster,
+) -> anyhow::Result<()> {
+    let field_id = session.character.as_ref().unwrap().field_id;
+
+    let expedition = match session.state.get_expedition(field_id) {
+        Some(expedition) => expedition,
+        None => return Ok(()),
+    };
+
+    let name = {
+        let mut expedition = expedition.lock().await;
+        let parts = expedition.info.parts;
+
+        if !parts.contains(&monster.data.id)
+            || expedition.boss_defeated
+            || session
+                .state
+                .get_monsters(field_id)
+                .iter()
+                .any(|part| parts.contains(&part.data.id))
+        {
+            return Ok(());
+        }
+
+        expedition.boss_defeated = true;
+        expedition.boss_hp = 0;
+        expedition.info.name
+    };
+
+    log::info!("An expedition defeated {}", name);
+
+    let text = format!("The expedition has defeated {}!", name);
+    session.broadcast_packet(packet::server_notice(NoticeType::Notice, &text), true)
+}
+
+/// Registers a new expedition with the character as its leader
+async fn register(
+    session: &mut ChannelSession,
+    info: &'static ExpeditionInfo,
+) -> anyhow::Result<()> {
+    if let Some(text) = check_eligible(session, info).await? {
+        return say(session, info.npc_id, &text).await;
+    }
+
+    let character = session.character.as_ref().unwrap();
+    let member = ExpeditionMember {
+        character_id: character.data.id,
+        name: character.data.name.clone(),
+    };
+
+    let expedition = Expedition {
+        info,
+        leader_id: member.character_id,
+        members: vec![member.clone()],
+        started: false,
+        field_id: 0,
+        boss_hp: 0,
+        boss_max_hp: 0,
+        boss_hp_colors: (0, 0),
+        boss_defeated: false,
+        ends_at: 0,
+        closed: false,
+    };
+
+    if !session
+        .state
+        .add_expedition(info.boss_map_id, Arc::new(Mutex::new(expedition)))
+    {
+        return say(
+            session,
+            info.npc_id,
+            "An expedition has already been registered.",
+        )
+        .await;
+    }
+
+    let text = format!(
+        "{} has registered an expedition to fight {}.",
+        member.name, info.name
+    );
+    session.broadcast_packet(packet::server_notice(NoticeType::Notice, &text), true)
+}
+
+/// Adds the character to an expedition that hasn't started yet
+async fn join(session: &mut ChannelSession, expedition: &SharedExpedition) -> anyhow::Result<()> {
+    let info = expedition.lock().await.info;
+
+    if let Some(text) = check_eligible(session, info).await? {
+        return say(session, info.npc_id, &text).await;
+    }
+
+    let character = session.character.as_ref().unwrap();
+    let member = ExpeditionMember {
+        character_id: character.data.id,
+        name: character.data.name.clone(),
+    };
+
+    let count = {
+        let mut expedition = expedition.lock().await;
+
+        if expedition.closed || expedition.started {
+            return say(session, info.npc_id, "The expedition has already left.").await;
+        }
+
+        if expedition.is_member(member.character_id) {
+            return say(session, info.npc_id, "You're already in the expedition.").await;
+        }
+
+        if expedition.members.len() >= info.max_members {
+            return say(session, info.npc_id, "The expedition is full.").await;
+        }
+
+        expedition.members.push(member.clone());
+        expedition.members.len()
+    };
+
+    let text = format!(
+        "{} has joined the expedition ({}/{}).",
+        member.name, count, info.max_members
+    );
+    session.broadcast_packet(packet::server_notice(NoticeType::Notice, &text), true)
+}
+
+/// Lists the characters registered with an expedition
+async fn show_members(
+    session: &mut ChannelSession,
+    expedition: &SharedExpedition,
+) -> anyhow::Result<()> {
+    let (npc_id, text) = {
+        let expedition = expedition.lock().await;
+        let names: Vec<String> = expedition
+            .members
+            .iter()
+            .map(|member| {
+                if member.character_id == expedition.leader_id {
+                    format!("{} (leader)", member.name)
+                } else {
+                    member.name.clone()
+                }
+            })
+            .collect();
+
+        let text = format!(
+            "The expedition has {}/{} members:\r\n{}",
+            names.len(),
+            expedition.info.max_members,
+            names.join("\r\n")
+        );
+        (expedition.info.npc_id, text)
+    };
+
+    say(session, npc_id, &text).await
+}
+
+/// Sends an expedition into its own copy of its boss map, each member uses up one of their daily entries
+async fn start(session: &mut ChannelSession, expedition: &SharedExpedition) -> anyhow::Result<()> {
+    let character_id = session.character.as_ref().unwrap().data.id;
+    let (info, character_ids) = {
+        let expedition = expedition.lock().await;
+        let character_ids: Vec<i32> = expedition
+            .members
+            .iter()
+            .map(|member| member.character_id)
+            .collect();
+        (expedition.info, character_ids)
+    };
+
+    if expedition.lock().await.leader_id != character_id {
+        return say(
+            session,
+            info.npc_id,
+            "Only the expedition's leader can start it.",
+        )
+        .await;
+    }
+
+    if character_ids.len() < info.min_members {
+        let text = format!(
+            "The expedition needs at least {} members to start.",
+            info.min_members
+        );
+        return say(session, info.npc_id, &text).await;
+    }
+
+    let map_characters = session.state.get_map_characters(info.entry_map_id);
+
+    if character_ids
+        .iter()
+        .any(|character_id| !map_characters.contains_key(character_id))
+    {
+        return say(
+            session,
+            info.npc_id,
+            "Everyone in the expedition has to be here before it can start.",
+        )
+        .await;
+    }
+
+    let field_id = {
+        let mut expedition = expedition.lock().await;
+
+        if expedition.closed || expedition.started {
+            return Ok(());
+        }
+
+        expedition.field_id = session.state.create_instance(info.boss_map_id);
+
+        if let Err(e) = spawn_boss(&session.state, &mut expedition) {
+            session.state.remove_instance(expedition.field_id);
+            return Err(e);
+        }
+
+        expedition.started = true;
+        expedition.ends_at = Utc::now().timestamp_millis() + info.time_limit * 1000;
+        expedition.field_id
+    };
+
+    // The npc can register the next expedition while this one fights in its own copy of the boss map
+    session.state.remove_expedition(info.boss_map_id);
+    session.state.add_expedition(field_id, expedition.clone());
+
+    for character_id in character_ids.iter() {
+        sql::BossEntry::insert(*character_id, info.boss, &session.db).await?;
+    }
+
+    log::info!("Started an expedition to fight {}", info.name);
+    tokio::spawn(run_timer(session.state.clone(), expedition.clone()));
+
+    let message = SessionMessage::ChangeField {
+        map_id: info.boss_map_id,
+        field_id,
+        portal_id: 0,
+    };
+
+    for character_id in character_ids {
+        monster::send(session, character_id, message.clone()).await?;
+    }
+
+    Ok(())
+}
+
+/// Removes the character from an expedition
+/// The expedition is disbanded if its leader leaves before it starts, and ends once everyone has left its boss map
+async fn leave(session: &mut ChannelSession, expedition: &SharedExpedition) -> anyhow::Result<()> {
+    let character_id = session.character.as_ref().unwrap().data.id;
+
+    let (started, disbanded, ended) = {
+        let mut expedition = expedition.lock().await;
+
+        if !expedition.is_member(character_id) {
+            return Ok(());
+        }
+
+        expedition
+            .members
+            .retain(|member| member.character_id != character_id);
+
+        let started = expedition.started;
+        let disbanded = !started && expedition.leader_id == character_id;
+        let ended = started && expedition.members.is_empty();
+        (started, disbanded, ended)
+    };
+
+    if disbanded || ended {
+        end(&session.state, expedition).await;
+    } else if !started {
+        let info = expedition.lock().await.info;
+        let name = session.character.as_ref().unwrap().data.name.clone();
+        let text = format!("{} has left the expedition.", name);
+        broadcast(
+            &session.state,
+            info.entry_map_id,
+            packet::server_notice(NoticeType::Notice, &text),
+        );
+    }
+
+    Ok(())
+}
+
+/// Checks if the character can join an expedition, returns the reason they can't if they can't
+async fn check_eligible(
+    session: &ChannelSession,
+    info: &ExpeditionInfo,
+) -> anyhow::Result<Option<String>> {
+    let character = session.character.as_ref().unwrap();
+
+    if character.data.level < info.min_level {
+        let text = format!(
+            "You need to be at least level {} to fight {}.",
+            info.min_level, info.name
+        );
+        return Ok(Some(text));
+    }
+
+    let entries =
+        sql::BossEntry::get_count_today(character.data.id, info.boss, &session.db).await?;
+
+    if entries >= info.daily_entries {
+        let text = format!(
+            "You've already fought {} {} time(s) today. Come back tomorrow.",
+            info.name, info.daily_entries
+        );
+        return Ok(Some(text));
+    }
+
+    Ok(None)
+}
+
+/// Spawns every part of the expedition's boss in its copy of the boss map, filling the hp pool they share
+fn spawn_boss(state: &State, expedition: &mut Expedition) -> anyhow::Result<()> {
+    let info = expedition.info;
+    let mut monsters = Vec::new();
+
+    for part_id in info.parts {
+        let data = nx::Mob::load(*part_id)?;
+
+        // TODO use the foothold below the boss once footholds are tracked
+        let monster = maple::Monster::new(state.next_object_id(), data, info.boss_pos, 0);
+        state.add_monster(expedition.field_id, monster.clone());
+        monsters.push(monster);
+    }
+
+    expedition.boss_max_hp = monsters
+        .iter()
+        .map(|monster| monster.data.max_hp.max(0) as i64)
+        .sum();
+    expedition.boss_hp = expedition.boss_max_hp;
+    expedition.boss_hp_colors = monsters
+        .first()
+        .map(|monster| (monster.data.hp_tag_color, monster.data.hp_tag_bg_color))
+        .unwrap_or_default();
+    Ok(())
+}
+
+/// Gets the expedition the character is fighting in, if they're in its copy of the boss map
+async fn get(session: &ChannelSession) -> Option<SharedExpedition> {
+    let character = session.character.as_ref().unwrap();
+    let expedition = session.state.get_expedition(character.field_id)?;
+
+    let is_member = {
+        let expedition = expedition.lock().await;
+        expedition.started && expedition.is_member(character.data.id)
+    };
+
+    is_member.then_some(expedition)
+}
+
+/// Gets the character's expedition if they're in the field it's in, its entry map before it starts or its copy of the
+/// boss map after
+async fn get_by_field(session: &ChannelSession, field_id: i32) -> Option<SharedExpedition> {
+    let character_id = session.character.as_ref().unwrap().data.id;
+
+    // Expeditions are kept under their boss map's id while they register in the entry map
+    let expedition = match EXPEDITIONS
+        .iter()
+        .find(|info| info.entry_map_id == field_id)
+    {
+        Some(info) => session.state.get_expedition(info.boss_map_id)?,
+        None => session.state.get_expedition(field_id)?,
+    };
+
+    let found = {
+        let expedition = expedition.lock().await;
+        let expedition_field_id = if expedition.started {
+            expedition.field_id
+        } else {
+            expedition.info.entry_map_id
+        };
+
+        expedition_field_id == field_id && expedition.is_member(character_id)
+    };
+
+    found.then_some(expedition)
+}
+
+fn get_info_by_npc(npc_id: i32) -> Option<&'static ExpeditionInfo> {
+    EXPEDITIONS.iter().find(|info| info.npc_id == npc_id)
+}
+
+/// Ends the expedition once its time runs out
+async fn run_timer(state: Arc<State>, expedition: SharedExpedition) {
+    loop {
+        let ends_at = {
+            let expedition = expedition.lock().await;
+
+            if expedition.closed {
+                return;
+            }
+
+            expedition.ends_at
+        };
+
+        let now = Utc::now().timestamp_millis();
+
+        if ends_at <= now {
+            break;
+        }
+
+        time::sleep(Duration::from_millis((ends_at - now) as u64)).await;
+    }
+
+    end(&state, &expedition).await;
+}
+
+/// Ends an expedition, sending anyone still in its copy of the boss map back to the entry map before removing it
+async fn end(state: &State, expedition: &SharedExpedition) {
+    let (info, started, field_id, character_ids) = {
+        let mut expedition = expedition.lock().await;
+
+        if expedition.closed {
+            return;
+        }
+
+        expedition.closed = true;
+        let character_ids: Vec<i32> = expedition
+            .members
+            .iter()
+            .map(|member| member.character_id)
+            .collect();
+        (
+            expedition.info,
+            expedition.started,
+            expedition.field_id,
+            character_ids,
+        )
+    };
+
+    if !started {
+        state.remove_expedition(info.boss_map_id);
+
+        let text = format!("The expedition to fight {} has been disbanded.", info.name);
+        broadcast(
+            state,
+            info.entry_map_id,
+            packet::server_notice(NoticeType::Notice, &text),
+        );
+        return;
+    }
+
+    state.remove_expedition(field_id);
+    log::info!("The expedition to fight {} ended", info.name);
+
+    let message = SessionMessage::ChangeMap {
+        map_id: info.entry_map_id,
+        portal_id: 0,
+    };
+
+    for character_id in character_ids {
+        state.send_to_session(character_id, message.clone()).await;
+    }
+
+    // Whatever is left of the boss goes with the expedition's copy of the boss map
+    state.remove_instance(field_id);
+}
+
+/// Sends a packet to everyone in a map
+fn broadcast(state: &State, map_id: i32, packet: Packet) {
+    // Nobody might be in the map to see it
+    let _ = state
+        .get_map_broadcast_tx(map_id)
+        .send(MapBroadcast::Packet(PacketBroadcast {
+            packet,
+            sender_id: 0,
+            send_to_sender: true,
+        }));
+}
+
+async fn say(session: &mut ChannelSession, npc_id: i32, text: &str) -> anyhow::Result<()> {
+    session
+        .stream
+        .write_packet(packet::npc_talk(npc_id, NpcDialog::Ok, text))
+        .await
+}
@@ -0,0 +1,134 @@
+use crate::session::ChannelSession;
+use rand::Rng;
+use slate_data::{
+    nx,
+    packet::{self, NoticeType, Stat},
+    sql,
+};
+
+/// The fishing chair, characters have to be sitting on it to fish
+const FISHING_CHAIR: i32 = 3011000;
+
+/// Fishing bait and high quality fishing bait, one is used up every time a character casts their line
+const BAITS: [i32; 2] = [2300000, 2300001];
+
+/// The fishing lagoon maps
+const LAGOON_MAP_IDS: [i32; 3] = [749050500, 749050501, 749050502];
+
+/// Rolls for a catch if the character is fishing in the lagoon, a bait is used up when something is caught
+/// Rewards are picked from the fishing rewards table by weight, with their items, exp and mesos scaled by the fishing rate
+pub async fn handle_catch(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let character = session.character.as_ref().unwrap();
+
+    if !LAGOON_MAP_IDS.contains(&character.data.map) || character.chair != FISHING_CHAIR {
+        return Ok(());
+    }
+
+    let bait = match BAITS
+        .iter()
+        .find(|bait| character.get_item_amount(**bait) > 0)
+    {
+        Some(bait) => *bait,
+        None => {
+            let text = "You need bait to fish.";
+            return session
+                .stream
+                .write_packet(packet::server_notice(NoticeType::PinkText, text))
+                .await;
+        }
+    };
+
+    let reward = match roll(sql::FishingReward::load_all(&session.db).await?) {
+        Some(reward) if reward.is_catch() => reward,
+        _ => return Ok(()),
+    };
+
+    if let Some(item) = session.character.as_mut().unwrap().remove_item(bait, 1) {
+        item.update_amount(&session.db).await?;
+        session
+            .stream
+            .write_packet(packet::update_item_amount(&item))
+            .await?;
+    }
+
+    let rate = session.state.world.fishing_rate.max(1);
+    let mut catches = Vec::new();
+
+    if let Some(item_id) = reward.item_id {
+        let amount = reward.amount.saturating_mul(rate);
+        let character = session.character.as_mut().unwrap();
+
+        match character.add_item(item_id, amount, &session.db).await? {
+            Some(changes) => {
+                session
+                    .stream
+                    .write_packet(packet::update_inventory(&changes))
+                    .await?;
+                session
+                    .stream
+                    .write_packet(packet::show_item_gain(item_id, amount))
+                    .await?;
+
+                let name = nx::Item::load_name(item_id).unwrap_or_else(|| item_id.to_string());
+                catches.push(format!("{} {}", amount, name));
+            }
+            None => {
+                let text = "Your line snapped, make some room in your inventory.";
+                session
+                    .stream
+                    .write_packet(packet::server_notice(NoticeType::PinkText, text))
+                    .await?;
+            }
+        }
+    }
+
+    if reward.mesos > 0 {
+        let mesos = reward.mesos.saturating_mul(rate);
+        let character = session.character.as_mut().unwrap();
+        character.data.mesos = character.data.mesos.saturating_add(mesos);
+        let total = character.data.mesos;
+
+        session
+            .stream
+            .write_packet(packet::update_stats(&[(Stat::Mesos, total)]))
+            .await?;
+        catches.push(format!("{} mesos", mesos));
+    }
+
+    if reward.exp > 0 {
+        let exp = reward.exp.saturating_mul(rate);
+        session
+            .stream
+            .write_packet(packet::show_exp_gain(exp, 0, true, true))
+            .await?;
+        session.gain_exp(exp).await?;
+        catches.push(format!("{} exp", exp));
+    }
+
+    if catches.is_empty() {
+        return Ok(());
+    }
+
+    let name = session.character.as_ref().unwrap().data.name.clone();
+    let text = format!("{} caught {}!", name, catches.join(", "));
+    session.broadcast_packet(
+        packet::server_notice(NoticeType::LightBlueText, &text),
+        true,
+    )
+}
+
+/// Picks a random reward, rewards with a higher weight are more likely to be picked
+fn roll(rewards: Vec<sql::FishingReward>) -> Option<sql::FishingReward> {
+    let total: i32 = rewards.iter().map(|reward| reward.weight.max(0)).sum();
+
+    if total <= 0 {
+        return None;
+    }
+
+    let mut roll = rand::thread_rng().gen_range(0..total);
+
+    rewards.into_iter().find(|reward| {
+        roll -= reward.weight.max(0);
+        roll < 0
+    })
+}
@@ -0,0 +1,236 @@
+use crate::{
+    alliance,
+    session::{ChannelSession, SessionMessage},
+    world,
+};
+use slate_data::{
+    packet::{self, GuildMessage, NpcDialog, Stat},
+    sql::{self, guild::MAX_GUILD_CAPACITY},
+};
+use slate_net::Packet;
+
+/// Heracle, who creates and manages guilds
+pub const GUILD_NPC: i32 = 2010007;
+
+/// The mesos it costs to create a guild
+pub const CREATE_COST: i32 = 1500000;
+
+/// The mesos it costs to change a guild's emblem
+pub const EMBLEM_COST: i32 = 5000000;
+
+/// The mesos it costs to increase a guild's capacity the first time, each increase costs more
+const CAPACITY_COST: i32 = 500000;
+
+/// How many members each capacity increase adds
+const CAPACITY_INCREASE: i32 = 5;
+
+/// Sends the character their guild's info
+pub async fn send_info(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let character = session.character.as_ref().unwrap();
+
+    let members = match &character.guild {
+        Some(guild) => sql::GuildMember::load_all(guild.id, &session.db).await?,
+        None => Vec::new(),
+    };
+
+    let packet = packet::guild_info(character.guild.as_ref(), &members);
+    session.stream.write_packet(packet).await
+}
+
+/// Sends a packet to every online member of a guild
+pub async fn send_to_members(
+    session: &ChannelSession,
+    guild_id: i32,
+    packet: Packet,
+) -> anyhow::Result<()> {
+    let member_ids = sql::Character::load_guild_member_ids(guild_id, &session.db).await?;
+    world::send_packet_to_all(session, &member_ids, packet).await?;
+    Ok(())
+}
+
+/// Tells the given characters that their guild changed, so they reload it
+pub async fn notify_changed(session: &ChannelSession, character_ids: &[i32]) -> anyhow::Result<()> {
+    world::send_to_all(session, character_ids, SessionMessage::GuildChanged).await?;
+    Ok(())
+}
+
+/// Reloads the character's guild after it changes, and shows the change to the map
+pub async fn reload(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let character_id = session.character.as_ref().unwrap().data.id;
+    let data = sql::Character::load(character_id, &session.db).await?;
+
+    let guild = match data.guild {
+        Some(guild_id) => sql::Guild::load_optional(guild_id, &session.db).await?,
+        None => None,
+    };
+
+    let character = session.character.as_mut().unwrap();
+    character.data.guild = data.guild;
+    character.data.guild_rank = data.guild_rank;
+    character.data.alliance_rank = data.alliance_rank;
+    character.guild = guild;
+
+    let guild = session.character.as_ref().unwrap().guild.as_ref();
+    session.broadcast_packet(
+        packet::update_foreign_guild_name(character_id, guild),
+        false,
+    )?;
+    session.broadcast_packet(
+        packet::update_foreign_guild_emblem(character_id, guild),
+        false,
+    )?;
+
+    send_info(session).await?;
+    alliance::send_info(session).await
+}
+
+/// Shows the character as online or offline to their guild
+/// Called when the character logs in or out
+pub async fn update_member_online(session: &ChannelSession, online: bool) -> anyhow::Result<()> {
+    let character = session.character.as_ref().unwrap();
+
+    let guild_id = match character.data.guild {
+        Some(guild_id) => guild_id,
+        None => return Ok(()),
+    };
+
+    let packet = packet::update_guild_member_online(guild_id, character.data.id, online);
+    send_to_members(session, guild_id, packet).await
+}
+
+/// Shows the character's new level and job to their guild
+pub async fn update_member_level(session: &ChannelSession) -> anyhow::Result<()> {
+    let character = session.character.as_ref().unwrap();
+
+    let guild_id = match character.data.guild {
+        Some(guild_id) => guild_id,
+        None => return Ok(()),
+    };
+
+    let packet = packet::update_guild_member_level(
+        guild_id,
+        character.data.id,
+        character.data.level,
+        character.data.job,
+    );
+    send_to_members(session, guild_id, packet).await
+}
+
+/// Gives guild points to a guild
+pub async fn gain_points(
+    session: &ChannelSession,
+    guild_id: i32,
+    amount: i32,
+) -> anyhow::Result<()> {
+    let mut guild = match sql::Guild::load_optional(guild_id, &session.db).await? {
+        Some(guild) => guild,
+        None => return Ok(()),
+    };
+
+    guild.points = guild.points.saturating_add(amount).max(0);
+    guild.update_points(&session.db).await?;
+
+    let packet = packet::update_guild_points(guild.id, guild.points);
+    send_to_members(session, guild.id, packet).await
+}
+
+/// Disbands a guild, removing all of its members
+pub async fn disband(session: &ChannelSession, guild: &sql::Guild) -> anyhow::Result<()> {
+    let member_ids = sql::Character::load_guild_member_ids(guild.id, &session.db).await?;
+    guild.disband(&session.db).await?;
+
+    world::send_packet_to_all(session, &member_ids, packet::guild_disbanded(guild.id)).await?;
+    notify_changed(session, &member_ids).await
+}
+
+/// Shows the guild npc's options
+pub async fn talk(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let text = "What would you like to do?\r\n\
+        #L0#Create a guild#l\r\n\
+        #L1#Increase my guild's capacity#l\r\n\
+        #L2#Change my guild's emblem#l\r\n\
+        #L3#Disband my guild#l";
+
+    session
+        .stream
+        .write_packet(packet::npc_talk(GUILD_NPC, NpcDialog::Selection, text))
+        .await
+}
+
+/// Handles the option the character picked from the guild npc
+pub async fn respond(session: &mut ChannelSession, selection: i32) -> anyhow::Result<()> {
+    let character = session.character.as_ref().unwrap();
+    let is_leader = character.data.guild_rank == Some(1);
+
+    match selection {
+        0 if character.data.guild.is_some() => say(session, "You're already in a guild.").await,
+        0 => {
+            session
+                .stream
+                .write_packet(packet::guild_message(GuildMessage::ShowCreate))
+                .await
+        }
+        1..=3 if !is_leader => say(session, "Only the guild master can do that.").await,
+        1 => increase_capacity(session).await,
+        2 => {
+            session
+                .stream
+                .write_packet(packet::guild_message(GuildMessage::ShowEmblem))
+                .await
+        }
+        3 => {
+            let guild = session.character.as_ref().unwrap().guild.clone();
+
+            match guild {
+                Some(guild) if guild.alliance_id.is_some() => {
+                    say(session, "Your guild has to leave its alliance first.").await
+                }
+                Some(guild) => disband(session, &guild).await,
+                None => Ok(()),
+            }
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Increases the character's guild's capacity
+async fn increase_capacity(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let mut guild = match session.character.as_ref().unwrap().guild.clone() {
+        Some(guild) => guild,
+        None => return Ok(()),
+    };
+
+    if guild.capacity >= MAX_GUILD_CAPACITY {
+        return say(session, "Your guild can't get any bigger.").await;
+    }
+
+    let cost = CAPACITY_COST * ((guild.capacity - 10) / CAPACITY_INCREASE + 1);
+    let character = session.character.as_mut().unwrap();
+
+    if character.data.mesos < cost {
+        let text = format!("You need {} mesos to increase your guild's capacity.", cost);
+        return say(session, &text).await;
+    }
+
+    character.data.mesos -= cost;
+    let mesos = character.data.mesos;
+    session
+        .stream
+        .write_packet(packet::update_stats(&[(Stat::Mesos, mesos)]))
+        .await?;
+
+    guild.capacity = (guild.capacity + CAPACITY_INCREASE).min(MAX_GUILD_CAPACITY);
+    guild.update_capacity(&session.db).await?;
+    session.character.as_mut().unwrap().guild = Some(guild.clone());
+
+    let packet = packet::update_guild_capacity(guild.id, guild.capacity);
+    send_to_members(session, guild.id, packet).await
+}
+
+/// Shows a message from the guild npc
+async fn say(session: &mut ChannelSession, text: &str) -> anyhow::Result<()> {
+    session
+        .stream
+        .write_packet(packet::npc_talk(GUILD_NPC, NpcDialog::Ok, text))
+        .await
+}
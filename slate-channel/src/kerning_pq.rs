@@ -0,0 +1,418 @@
+use crate::{
+    event::{self, Event, EventInfo, SharedEvent},
+    session::ChannelSession,
+};
+use rand::{seq::SliceRandom, Rng};
+use slate_data::{
+    nx,
+    packet::{self, NpcDialog},
+    sql,
+};
+
+/// Lakelis, lets parties into the party quest from Kerning City
+pub const LAKELIS: i32 = 9020000;
+
+/// Cloto, gives out each stage's puzzle and checks if it's been solved
+pub const CLOTO: i32 = 9020001;
+
+/// Nella, takes characters back to Kerning City from the exit map
+pub const NELLA: i32 = 9020002;
+
+const KERNING_CITY: i32 = 103000000;
+
+/// Dropped by the monsters in the first stage, characters bring Cloto as many as the answer to their question
+const COUPON: i32 = 4001007;
+
+/// Given out by Cloto for answering a question, and dropped by the monsters in the last stage
+const PASS: i32 = 4001008;
+
+/// The passes the party leader brings Cloto to clear the last stage
+const LAST_STAGE_PASSES: i32 = 10;
+
+/// The number of characters that have to stand in the right areas to clear the rope, platform and barrel stages
+const COMBINATION_SIZE: usize = 3;
+
+/// Marks a character who already answered their question in the first stage
+const ANSWERED: i32 = -1;
+
+/// The first stage's questions, along with how many coupons answer them
+const QUESTIONS: [(&str, i32); 6] = [
+    (
+        "the level needed to make the first job advancement as a warrior",
+        10,
+    ),
+    (
+        "the level needed to make the first job advancement as a magician",
+        8,
+    ),
+    (
+        "the STR needed to make the first job advancement as a warrior",
+        35,
+    ),
+    (
+        "the INT needed to make the first job advancement as a magician",
+        20,
+    ),
+    (
+        "the DEX needed to make the first job advancement as a bowman",
+        25,
+    ),
+    (
+        "the DEX needed to make the first job advancement as a thief",
+        25,
+    ),
+];
+
+pub static KERNING_PQ: EventInfo = EventInfo {
+    name: "Kerning PQ",
+    stage_map_ids: &[103000800, 103000801, 103000802, 103000803, 103000804],
+    bonus_map_id: Some(103000805),
+    exit_map_id: 103000890,
+    min_members: 3,
+    max_members: 4,
+    min_level: 21,
+    max_level: 30,
+    time_limit: 30 * 60,
+    bonus_time_limit: 60,
+    stage_exp: &[100, 200, 400, 800, 1500],
+    stage_drops: &[Some(COUPON), None, None, None, Some(PASS)],
+    event_items: &[COUPON, PASS],
+    start_stage,
+};
+
+/// Picks the areas characters have to stand in for the rope, platform and barrel stages
+fn start_stage(event: &mut Event) -> anyhow::Result<()> {
+    if !(1..=3).contains(&event.stage) {
+        return Ok(());
+    }
+
+    let map = nx::Map::load(event.info.stage_map_ids[event.stage])?;
+    let mut areas: Vec<String> = map.areas.into_keys().collect();
+    areas.shuffle(&mut rand::thread_rng());
+    areas.truncate(COMBINATION_SIZE);
+
+    event.combination = areas;
+    Ok(())
+}
+
+/// Asks the character if they want to enter the party quest with their party
+pub async fn talk_lakelis(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let text = format!(
+        "Want to test your party's skills? Bring a party of {} to {} members, all between level {} and {}, \
+        and you'll be able to take on the challenge. Would you like to enter?",
+        KERNING_PQ.min_members, KERNING_PQ.max_members, KERNING_PQ.min_level, KERNING_PQ.max_level
+    );
+
+    session
+        .stream
+        .write_packet(packet::npc_talk(LAKELIS, NpcDialog::YesNo, &text))
+        .await
+}
+
+/// Starts the party quest for the character's party, if it meets the requirements
+pub async fn enter(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let character = session.character.as_ref().unwrap();
+    let (character_id, field_id) = (character.data.id, character.field_id);
+
+    let party_id = match character.data.party {
+        Some(party_id) => party_id,
+        None => return say(session, LAKELIS, "You'll need to be in a party first.").await,
+    };
+
+    match sql::Party::load_optional(party_id, &session.db).await? {
+        Some(party) if party.leader_id == character_id => {}
+        _ => {
+            return say(
+                session,
+                LAKELIS,
+                "Only your party leader can talk to me about entering.",
+            )
+            .await
+        }
+    }
+
+    let members: Vec<_> = session
+        .state
+        .get_map_characters(field_id)
+        .into_iter()
+        .filter(|(_, member)| member.party_id == Some(party_id))
+        .collect();
+
+    if !(KERNING_PQ.min_members..=KERNING_PQ.max_members).contains(&members.len()) {
+        let text = format!(
+            "Your party needs {} to {} members here with you.",
+            KERNING_PQ.min_members, KERNING_PQ.max_members
+        );
+        return say(session, LAKELIS, &text).await;
+    }
+
+    if members
+        .iter()
+        .any(|(_, member)| !(KERNING_PQ.min_level..=KERNING_PQ.max_level).contains(&member.level))
+    {
+        let text = format!(
+            "Everyone in your party has to be between level {} and {}.",
+            KERNING_PQ.min_level, KERNING_PQ.max_level
+        );
+        return say(session, LAKELIS, &text).await;
+    }
+
+    let character_ids = members.into_iter().map(|(id, _)| id).collect();
+
+    event::start(session, &KERNING_PQ, party_id, character_ids).await
+}
+
+/// Talks to Cloto about the stage the character is on, clearing it if its puzzle is solved
+pub async fn talk_cloto(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let event = match event::get(session).await {
+        Some(event) => event,
+        None => {
+            return say(
+                session,
+                CLOTO,
+                "Only those taking on the challenge may pass.",
+            )
+            .await
+        }
+    };
+
+    let character = session.character.as_ref().unwrap();
+    let (character_id, map_id) = (character.data.id, character.data.map);
+
+    let (stage, cleared, is_leader, is_last_stage) = {
+        let event = event.lock().await;
+        (
+            event.stage,
+            event.stage_cleared,
+            event.leader_id == character_id,
+            event.is_last_stage(),
+        )
+    };
+
+    if KERNING_PQ.get_stage(map_id) != Some(stage) {
+        return say(session, CLOTO, "Hurry along and catch up with your party!").await;
+    }
+
+    if cleared && is_last_stage && is_leader && event::start_bonus(session, &event).await? {
+        return Ok(());
+    }
+
+    if cleared {
+        return say(session, CLOTO, "The way forward is open, hurry on!").await;
+    }
+
+    match stage {
+        0 => first_stage(session, &event, is_leader).await,
+        1..=3 => combination_stage(session, &event, is_leader).await,
+        _ => last_stage(session, &event, is_leader).await,
+    }
+}
+
+/// Asks the character if they want to go back to Kerning City
+pub async fn talk_nella(session: &mut ChannelSession) -> anyhow::Result<()> {
+    session
+        .stream
+        .write_packet(packet::npc_talk(
+            NELLA,
+            NpcDialog::YesNo,
+            "Would you like to go back to Kerning City?",
+        ))
+        .await
+}
+
+/// Takes the character back to Kerning City
+pub async fn leave(session: &mut ChannelSession) -> anyhow::Result<()> {
+    session.change_map(KERNING_CITY, 0).await
+}
+
+/// Each character other than the leader answers a question with coupons to get a pass
+/// The leader clears the stage by bringing a pass for each of them
+async fn first_stage(
+    session: &mut ChannelSession,
+    event: &SharedEvent,
+    is_leader: bool,
+) -> anyhow::Result<()> {
+    let character = session.character.as_ref().unwrap();
+    let character_id = character.data.id;
+
+    if is_leader {
+        let passes = event.lock().await.character_ids.len() as i32 - 1;
+
+        if character.get_item_amount(PASS) < passes || !take(session, PASS, passes).await? {
+            let text = format!(
+                "Each of your party members gets a pass from me by answering my question. \
+                Bring me {} passes and I'll open the way.",
+                passes
+            );
+            return say(session, CLOTO, &text).await;
+        }
+
+        event::clear_stage(session, event).await?;
+        return say(
+            session,
+            CLOTO,
+            "Well done! The way to the next stage is open.",
+        )
+        .await;
+    }
+
+    let question = {
+        let mut event = event.lock().await;
+        *event
+            .answers
+            .entry(character_id)
+            .or_insert_with(|| rand::thread_rng().gen_range(0..QUESTIONS.len() as i32))
+    };
+
+    if question == ANSWERED {
+        return say(session, CLOTO, "Give your pass to your party leader.").await;
+    }
+
+    let (text, answer) = QUESTIONS[question as usize];
+
+    if character.get_item_amount(COUPON) != answer || !take(session, COUPON, answer).await? {
+        let text = format!(
+            "Bring me as many coupons as {}. The Ligators here carry them.",
+            text
+        );
+        return say(session, CLOTO, &text).await;
+    }
+
+    let character = session.character.as_mut().unwrap();
+
+    match character.add_item(PASS, 1, &session.db).await? {
+        Some(changes) => {
+            session
+                .stream
+                .write_packet(packet::update_inventory(&changes))
+                .await?
+        }
+        None => {
+            return say(
+                session,
+                CLOTO,
+                "Make some room in your etc inventory first.",
+            )
+            .await
+        }
+    }
+
+    event.lock().await.answers.insert(character_id, ANSWERED);
+    say(session, CLOTO, "That's right! Here's your pass.").await
+}
+
+/// The leader clears the stage once the right number of party members are standing in the right areas
+async fn combination_stage(
+    session: &mut ChannelSession,
+    event: &SharedEvent,
+    is_leader: bool,
+) -> anyhow::Result<()> {
+    if !is_leader {
+        let text = format!(
+            "Find the right spots for {} of your party members to stand on, then have your party leader talk to me.",
+            COMBINATION_SIZE
+        );
+        return say(session, CLOTO, &text).await;
+    }
+
+    let character = session.character.as_ref().unwrap();
+    let map = nx::Map::load(character.data.map)?;
+    let map_characters = session.state.get_map_characters(character.field_id);
+
+    let (character_ids, mut combination) = {
+        let event = event.lock().await;
+        (event.character_ids.clone(), event.combination.clone())
+    };
+
+    // Each character can only be standing in one area
+    let mut occupied: Vec<String> = character_ids
+        .iter()
+        .filter_map(|character_id| map_characters.get(character_id))
+        .filter_map(|member| {
+            map.areas
+                .iter()
+                .find(|(_, area)| area.contains(member.pos))
+                .map(|(name, _)| name.clone())
+        })
+        .collect();
+
+    if occupied.len() != COMBINATION_SIZE {
+        let text = format!(
+            "Exactly {} of your party members have to be standing on a spot.",
+            COMBINATION_SIZE
+        );
+        return say(session, CLOTO, &text).await;
+    }
+
+    occupied.sort();
+    combination.sort();
+
+    if occupied != combination {
+        event::fail_stage(session)?;
+        return say(session, CLOTO, "That's not the right combination.").await;
+    }
+
+    event::clear_stage(session, event).await?;
+    say(
+        session,
+        CLOTO,
+        "That's the one! The way to the next stage is open.",
+    )
+    .await
+}
+
+/// The leader clears the last stage by bringing passes from its monsters
+async fn last_stage(
+    session: &mut ChannelSession,
+    event: &SharedEvent,
+    is_leader: bool,
+) -> anyhow::Result<()> {
+    let character = session.character.as_ref().unwrap();
+
+    if !is_leader || character.get_item_amount(PASS) < LAST_STAGE_PASSES {
+        let text = format!(
+            "Defeat King Slime and the Ligators, and have your party leader bring me {} passes.",
+            LAST_STAGE_PASSES
+        );
+        return say(session, CLOTO, &text).await;
+    }
+
+    if !take(session, PASS, LAST_STAGE_PASSES).await? {
+        return say(session, CLOTO, "Put all of your passes together first.").await;
+    }
+
+    event::clear_stage(session, event).await?;
+    say(
+        session,
+        CLOTO,
+        "You've done it! Talk to me again to head to the bonus stage.",
+    )
+    .await
+}
+
+/// Takes an amount of an item from a single stack in the character's inventory, returns false if no stack has enough
+async fn take(session: &mut ChannelSession, item_id: i32, amount: i32) -> anyhow::Result<bool> {
+    let item = match session
+        .character
+        .as_mut()
+        .unwrap()
+        .remove_item(item_id, amount)
+    {
+        Some(item) => item,
+        None => return Ok(false),
+    };
+
+    item.update_amount(&session.db).await?;
+    session
+        .stream
+        .write_packet(packet::update_item_amount(&item))
+        .await?;
+    Ok(true)
+}
+
+async fn say(session: &mut ChannelSession, npc_id: i32, text: &str) -> anyhow::Result<()> {
+    session
+        .stream
+        .write_packet(packet::npc_talk(npc_id, NpcDialog::Ok, text))
+        .await
+}
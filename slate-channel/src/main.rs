@@ -6,11 +6,35 @@ use sqlx::{
 };
 use std::{env, str::FromStr};
 
+mod alliance;
+mod buddy;
+mod cash_shop;
+mod command;
+mod drop;
+mod event;
+mod expedition;
+mod fishing;
+mod guild;
+mod kerning_pq;
+mod minigame;
+mod monster;
+mod mount;
+mod mts;
+mod npc;
 mod packet_handler;
+mod party;
+mod pet;
+mod player_shop;
+mod reactor;
 mod server;
 mod session;
+mod shop;
 mod shutdown;
 mod state;
+mod storage;
+mod trade;
+mod transport;
+mod world;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
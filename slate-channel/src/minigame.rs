@@ -0,0 +1,845 @@
+use crate::{
+    player_shop,
+    session::{ChannelSession, SessionMessage},
+};
+use rand::seq::SliceRandom;
+use slate_data::{
+    packet::{self, MinigameBox, MinigamePlayer, MinigameResult, NoticeType, RoomLeaveReason},
+    sql::{self, item::InventoryType, MinigameType},
+};
+use slate_net::Packet;
+use std::{cmp::Ordering, sync::Arc};
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+/// Omok sets are numbered from the first set, each set has its own pieces
+const FIRST_OMOK_SET: i32 = 4080000;
+const OMOK_SETS: u8 = 12;
+const MATCH_CARDS: i32 = 4080100;
+
+/// The number of spaces on each side of the omok board
+const OMOK_BOARD_SIZE: usize = 15;
+
+/// A minigame room shared by the sessions of the characters in it
+pub type SharedMinigame = Arc<Mutex<Minigame>>;
+
+/// An omok or match cards room, the owner plays against a visitor
+#[derive(Debug)]
+pub struct Minigame {
+    pub id: i32,
+    pub game_type: MinigameType,
+    pub title: String,
+    pub password: Option<String>,
+    /// The omok piece set, or the match cards board size
+    pub piece: u8,
+    /// The instance of the map the room is in
+    pub field_id: i32,
+    /// The characters in the room by their slot, the owner is in slot 0
+    pub players: Vec<Player>,
+    /// Whether the visitor is ready for the owner to start a game
+    pub ready: bool,
+    pub game: Option<Game>,
+    /// The slot of the player that moves first in the next game, the loser of the last game
+    pub first: usize,
+    pub closed: bool,
+}
+
+#[derive(Debug)]
+pub struct Player {
+    pub data: sql::Character,
+    pub equipment: Vec<sql::Equipment>,
+    pub record: sql::MinigameRecord,
+    /// Whether the player leaves the room once the current game ends
+    pub leave_after_game: bool,
+}
+
+/// A game in progress in a minigame room
+#[derive(Debug)]
+pub struct Game {
+    /// The slot of the player whose turn it is
+    turn: usize,
+    /// The slot of the player that asked to end the game in a tie
+    tie_request: Option<usize>,
+    board: Board,
+}
+
+#[derive(Debug)]
+enum Board {
+    /// The omok board by row then column, each space has the piece of the player in slot `piece - 1` (0 if empty)
+    Omok([[u8; OMOK_BOARD_SIZE]; OMOK_BOARD_SIZE]),
+    MatchCards {
+        /// The shuffled cards, each pair of cards shares a number
+        cards: Vec<i32>,
+        matched: Vec<bool>,
+        /// The first card flipped this turn
+        flipped: Option<usize>,
+        /// The number of pairs each player matched
+        pairs: [i32; 2],
+    },
+}
+
+impl Minigame {
+    /// Gets the character's slot in the room, or None if they aren't in it
+    fn get_slot(&self, character_id: i32) -> Option<usize> {
+        self.players
+            .iter()
+            .position(|player| player.data.id == character_id)
+    }
+
+    fn get_box(&self) -> MinigameBox<'_> {
+        MinigameBox {
+            object_id: self.id,
+            game_type: self.game_type,
+            title: &self.title,
+            has_password: self.password.is_some(),
+            piece: self.piece,
+            characters: self.players.len() as u8,
+            in_progress: self.game.is_some(),
+        }
+    }
+
+    /// Gets the packet that shows the room's box above its owner in the map
+    pub fn box_packet(&self) -> Packet {
+        packet::update_minigame_box(self.players[0].data.id, Some(self.get_box()))
+    }
+
+    /// Gets the packet that opens the room's window for the character in `slot`
+    fn room_packet(&self, slot: usize) -> Packet {
+        let players: Vec<MinigamePlayer> = self
+            .players
+            .iter()
+            .map(|player| (&player.data, player.equipment.as_slice(), &player.record))
+            .collect();
+
+        packet::minigame_room(
+            self.game_type,
+            slot as u8,
+            &players,
+            &self.title,
+            self.piece,
+        )
+    }
+}
+
+/// Opens a minigame room, other characters in the map can then join it to play against the character
+/// `piece` is the omok piece set or the match cards board size
+pub async fn create(
+    session: &mut ChannelSession,
+    game_type: MinigameType,
+    title: String,
+    password: Option<String>,
+    piece: u8,
+) -> anyhow::Result<()> {
+    if session.trade.is_some()
+        || player_shop::is_in_shop(session).await
+        || is_in_room(session).await
+    {
+        return Ok(());
+    }
+
+    let item_id = match game_type {
+        MinigameType::Omok if piece < OMOK_SETS => FIRST_OMOK_SET + piece as i32,
+        MinigameType::MatchCards if piece <= 2 => MATCH_CARDS,
+        _ => return Ok(()),
+    };
+
+    let character = session.character.as_ref().unwrap();
+
+    if !character
+        .items
+        .iter()
+        .any(|item| item.inventory_type == InventoryType::Etc && item.item_id == item_id)
+    {
+        return Ok(());
+    }
+
+    let record = sql::MinigameRecord::load(character.data.id, game_type, &session.db).await?;
+
+    let minigame = Minigame {
+        id: session.state.next_object_id(),
+        game_type,
+        title,
+        password,
+        piece,
+        field_id: character.field_id,
+        players: vec![Player {
+            data: character.data.clone(),
+            equipment: character.equipment.clone(),
+            record,
+            leave_after_game: false,
+        }],
+        ready: false,
+        game: None,
+        first: 0,
+        closed: false,
+    };
+
+    session.stream.write_packet(minigame.room_packet(0)).await?;
+    session.broadcast_packet(minigame.box_packet(), true)?;
+
+    let (field_id, minigame_id) = (minigame.field_id, minigame.id);
+    let minigame = Arc::new(Mutex::new(minigame));
+    session
+        .state
+        .add_minigame(field_id, minigame_id, minigame.clone());
+    session.minigame = Some(minigame);
+    Ok(())
+}
+
+/// Joins a minigame room in the character's map
+/// Returns false if there's no minigame room with the id, it may be a player shop instead
+pub async fn visit(
+    session: &mut ChannelSession,
+    minigame_id: i32,
+    password: Option<String>,
+) -> anyhow::Result<bool> {
+    let character = session.character.as_ref().unwrap();
+
+    let shared_minigame = match session.state.get_minigame(character.field_id, minigame_id) {
+        Some(minigame) => minigame,
+        None => return Ok(false),
+    };
+
+    if session.trade.is_some()
+        || player_shop::is_in_shop(session).await
+        || is_in_room(session).await
+    {
+        return Ok(true);
+    }
+
+    let mut minigame = shared_minigame.lock().await;
+
+    if minigame.closed {
+        send_notice(session, "The room has already been closed.").await?;
+        return Ok(true);
+    }
+
+    if minigame.players.len() >= 2 {
+        send_notice(session, "The room is already full.").await?;
+        return Ok(true);
+    }
+
+    if minigame.password.is_some() && minigame.password != password {
+        send_notice(session, "The password is incorrect.").await?;
+        return Ok(true);
+    }
+
+    let character = session.character.as_ref().unwrap();
+    let record =
+        sql::MinigameRecord::load(character.data.id, minigame.game_type, &session.db).await?;
+
+    let player = Player {
+        data: character.data.clone(),
+        equipment: character.equipment.clone(),
+        record,
+        leave_after_game: false,
+    };
+
+    let packet = packet::minigame_visitor_joined(&player.data, &player.equipment, &player.record);
+    send_to(session, minigame.players[0].data.id, packet).await?;
+
+    minigame.players.push(player);
+    minigame.ready = false;
+
+    session.stream.write_packet(minigame.room_packet(1)).await?;
+    session.broadcast_packet(minigame.box_packet(), true)?;
+
+    drop(minigame);
+    session.minigame = Some(shared_minigame);
+    Ok(true)
+}
+
+/// Sends a chat message to both characters in the room
+pub async fn chat(session: &mut ChannelSession, message: &str) -> anyhow::Result<()> {
+    let (minigame, slot) = match lock_current(session).await {
+        Some(current) => current,
+        None => return Ok(()),
+    };
+
+    let name = session.character.as_ref().unwrap().data.name.clone();
+    let packet = packet::room_chat(slot as u8, &name, message);
+    send_to_all(session, &minigame, packet).await
+}
+
+/// Leaves the character's minigame room, forfeiting the game in progress
+/// The room closes when its owner leaves
+/// Called when the character closes the room window, changes maps, or logs out
+pub async fn leave(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let (mut minigame, slot) = match lock_current(session).await {
+        Some(current) => current,
+        None => return Ok(()),
+    };
+
+    session.minigame = None;
+
+    if minigame.game.is_some() {
+        end_game(session, &mut minigame, MinigameResult::Forfeit, 1 - slot).await?;
+
+        // The room may have closed if its owner asked to leave after the game
+        if minigame.closed {
+            return Ok(());
+        }
+    }
+
+    if slot == 0 {
+        close(session, &mut minigame).await
+    } else {
+        remove_visitor(session, &mut minigame).await
+    }
+}
+
+/// Removes the visitor from the character's minigame room
+pub async fn expel(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let (mut minigame, slot) = match lock_current(session).await {
+        Some(current) => current,
+        None => return Ok(()),
+    };
+
+    if slot != 0 || minigame.game.is_some() || minigame.players.len() < 2 {
+        return Ok(());
+    }
+
+    let visitor_id = minigame.players[1].data.id;
+    remove_visitor(session, &mut minigame).await?;
+
+    let packet = packet::room_closed(1, RoomLeaveReason::Banned);
+    send_to(session, visitor_id, packet).await
+}
+
+/// Marks the visitor of a minigame room as ready to start a game, or no longer ready
+pub async fn set_ready(session: &mut ChannelSession, ready: bool) -> anyhow::Result<()> {
+    let (mut minigame, slot) = match lock_current(session).await {
+        Some(current) => current,
+        None => return Ok(()),
+    };
+
+    if slot != 1 || minigame.game.is_some() || minigame.ready == ready {
+        return Ok(());
+    }
+
+    minigame.ready = ready;
+    send_to_all(session, &minigame, packet::minigame_ready(ready)).await
+}
+
+/// Starts a game in the character's minigame room once the visitor is ready
+pub async fn start(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let (mut minigame, slot) = match lock_current(session).await {
+        Some(current) => current,
+        None => return Ok(()),
+    };
+
+    if slot != 0 || minigame.game.is_some() || !minigame.ready {
+        return Ok(());
+    }
+
+    let board = match minigame.game_type {
+        MinigameType::Omok => Board::Omok([[0; OMOK_BOARD_SIZE]; OMOK_BOARD_SIZE]),
+        MinigameType::MatchCards => {
+            let pairs = match minigame.piece {
+                0 => 6,
+                1 => 10,
+                _ => 15,
+            };
+
+            let mut cards: Vec<i32> = (0..pairs).flat_map(|card| [card, card]).collect();
+            cards.shuffle(&mut rand::thread_rng());
+
+            Board::MatchCards {
+                matched: vec![false; cards.len()],
+                cards,
+                flipped: None,
+                pairs: [0; 2],
+            }
+        }
+    };
+
+    let cards = match &board {
+        Board::MatchCards { cards, .. } => Some(cards.clone()),
+        Board::Omok(_) => None,
+    };
+
+    let first = minigame.first;
+    minigame.game = Some(Game {
+        turn: first,
+        tie_request: None,
+        board,
+    });
+
+    let packet = packet::minigame_started(first as u8, cards.as_deref());
+    send_to_all(session, &minigame, packet).await?;
+    session.broadcast_packet(minigame.box_packet(), true)
+}
+
+/// Places one of the character's pieces on the omok board, winning the game with five in a row
+pub async fn place_omok_piece(session: &mut ChannelSession, x: i32, y: i32) -> anyhow::Result<()> {
+    let (mut minigame, slot) = match lock_current(session).await {
+        Some(current) => current,
+        None => return Ok(()),
+    };
+
+    let game = match minigame.game.as_mut() {
+        Some(game) if game.turn == slot => game,
+        _ => return Ok(()),
+    };
+
+    let board = match &mut game.board {
+        Board::Omok(board) => board,
+        Board::MatchCards { .. } => return Ok(()),
+    };
+
+    let size = 0..OMOK_BOARD_SIZE as i32;
+
+    if !size.contains(&x) || !size.contains(&y) || board[y as usize][x as usize] != 0 {
+        return Ok(());
+    }
+
+    let piece = slot as u8 + 1;
+    board[y as usize][x as usize] = piece;
+
+    let result = get_omok_result(board, x as usize, y as usize);
+    game.turn = 1 - slot;
+
+    send_to_all(session, &minigame, packet::omok_piece_placed(x, y, piece)).await?;
+
+    if let Some(result) = result {
+        end_game(session, &mut minigame, result, slot).await?;
+    }
+
+    Ok(())
+}
+
+/// Flips a card in a match cards game, the character keeps their turn while the cards they flip match
+/// `first` is whether the card is the first of the two cards flipped each turn
+pub async fn flip_card(
+    session: &mut ChannelSession,
+    first: bool,
+    card: usize,
+) -> anyhow::Result<()> {
+    let (mut minigame, slot) = match lock_current(session).await {
+        Some(current) => current,
+        None => return Ok(()),
+    };
+
+    let opponent_id = match minigame.players.get(1 - slot) {
+        Some(opponent) => opponent.data.id,
+        None => return Ok(()),
+    };
+
+    let game = match minigame.game.as_mut() {
+        Some(game) if game.turn == slot => game,
+        _ => return Ok(()),
+    };
+
+    let (cards, matched, flipped, pairs) = match &mut game.board {
+        Board::MatchCards {
+            cards,
+            matched,
+            flipped,
+            pairs,
+        } => (cards, matched, flipped, pairs),
+        Board::Omok(_) => return Ok(()),
+    };
+
+    if !matches!(matched.get(card), Some(false)) {
+        return Ok(());
+    }
+
+    if first {
+        *flipped = Some(card);
+        return send_to(session, opponent_id, packet::match_card_flipped(card as u8)).await;
+    }
+
+    let first_card = match flipped.take() {
+        Some(first_card) if first_card != card => first_card,
+        _ => return Ok(()),
+    };
+
+    let is_match = cards[card] == cards[first_card];
+
+    if is_match {
+        matched[card] = true;
+        matched[first_card] = true;
+        pairs[slot] += 1;
+    } else {
+        game.turn = 1 - slot;
+    }
+
+    let finished = matched.iter().all(|matched| *matched);
+    let pairs = *pairs;
+
+    let packet = packet::match_cards_compared(card as u8, first_card as u8, slot as u8, is_match);
+    send_to_all(session, &minigame, packet).await?;
+
+    if finished {
+        let (result, winner) = get_match_cards_result(pairs);
+        end_game(session, &mut minigame, result, winner).await?;
+    }
+
+    Ok(())
+}
+
+/// Passes the character's turn in an omok game
+pub async fn skip_turn(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let (mut minigame, slot) = match lock_current(session).await {
+        Some(current) => current,
+        None => return Ok(()),
+    };
+
+    match minigame.game.as_mut() {
+        Some(game) if game.turn == slot && matches!(game.board, Board::Omok(_)) => {
+            game.turn = 1 - slot
+        }
+        _ => return Ok(()),
+    }
+
+    send_to_all(
+        session,
+        &minigame,
+        packet::minigame_turn_skipped(slot as u8),
+    )
+    .await
+}
+
+/// Asks the character's opponent to end the game in a tie
+pub async fn request_tie(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let (mut minigame, slot) = match lock_current(session).await {
+        Some(current) => current,
+        None => return Ok(()),
+    };
+
+    match minigame.game.as_mut() {
+        Some(game) if game.tie_request.is_none() => game.tie_request = Some(slot),
+        _ => return Ok(()),
+    }
+
+    let opponent_id = minigame.players[1 - slot].data.id;
+    send_to(session, opponent_id, packet::minigame_tie_requested()).await
+}
+
+/// Accepts or declines the opponent's request to end the game in a tie
+pub async fn answer_tie(session: &mut ChannelSession, accepted: bool) -> anyhow::Result<()> {
+    let (mut minigame, slot) = match lock_current(session).await {
+        Some(current) => current,
+        None => return Ok(()),
+    };
+
+    match minigame.game.as_mut() {
+        Some(game) if game.tie_request == Some(1 - slot) => game.tie_request = None,
+        _ => return Ok(()),
+    }
+
+    if accepted {
+        return end_game(session, &mut minigame, MinigameResult::Tie, slot).await;
+    }
+
+    let opponent_id = minigame.players[1 - slot].data.id;
+    send_to(session, opponent_id, packet::minigame_tie_denied()).await
+}
+
+/// Forfeits the game in progress
+pub async fn give_up(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let (mut minigame, slot) = match lock_current(session).await {
+        Some(current) => current,
+        None => return Ok(()),
+    };
+
+    if minigame.game.is_none() {
+        return Ok(());
+    }
+
+    end_game(session, &mut minigame, MinigameResult::Forfeit, 1 - slot).await
+}
+
+/// Sets whether the character leaves the room once the game in progress ends
+pub async fn set_leave_after_game(
+    session: &mut ChannelSession,
+    leave_after_game: bool,
+) -> anyhow::Result<()> {
+    let (mut minigame, slot) = match lock_current(session).await {
+        Some(current) => current,
+        None => return Ok(()),
+    };
+
+    if minigame.game.is_some() {
+        minigame.players[slot].leave_after_game = leave_after_game;
+    }
+
+    Ok(())
+}
+
+/// Checks if the character is in a minigame room, forgetting the room if they were removed from it
+pub async fn is_in_room(session: &mut ChannelSession) -> bool {
+    lock_current(session).await.is_some()
+}
+
+/// Locks the minigame room the character is in, along with their slot in it
+/// Forgets the room if the character is no longer in it (ex. it was closed or they were expelled)
+async fn lock_current(session: &mut ChannelSession) -> Option<(OwnedMutexGuard<Minigame>, usize)> {
+    let minigame = session.minigame.clone()?.lock_owned().await;
+    let character_id = session.character.as_ref().unwrap().data.id;
+
+    match minigame.get_slot(character_id) {
+        Some(slot) if !minigame.closed => Some((minigame, slot)),
+        _ => {
+            session.minigame = None;
+            None
+        }
+    }
+}
+
+/// Ends the game in progress, updating both players' records
+/// `winner` is the slot of the player that won, it's ignored for ties
+async fn end_game(
+    session: &mut ChannelSession,
+    minigame: &mut Minigame,
+    result: MinigameResult,
+    winner: usize,
+) -> anyhow::Result<()> {
+    minigame.game = None;
+    minigame.ready = false;
+
+    if minigame.players.len() < 2 {
+        return Ok(());
+    }
+
+    if result == MinigameResult::Tie {
+        for player in minigame.players.iter_mut() {
+            player.record.ties += 1;
+        }
+    } else {
+        minigame.players[winner].record.wins += 1;
+        minigame.players[1 - winner].record.losses += 1;
+        minigame.first = 1 - winner;
+    }
+
+    for player in minigame.players.iter() {
+        player.record.save(&session.db).await?;
+    }
+
+    let records = [&minigame.players[0].record, &minigame.players[1].record];
+    let packet = packet::minigame_result(result, winner as u8, records);
+    send_to_all(session, minigame, packet).await?;
+
+    // The room closes if its owner asked to leave after the game
+    if minigame.players[0].leave_after_game {
+        let owner_id = minigame.players[0].data.id;
+        close(session, minigame).await?;
+        let packet = packet::room_closed(0, RoomLeaveReason::Closed);
+        return send_to(session, owner_id, packet).await;
+    }
+
+    if minigame.players[1].leave_after_game {
+        let visitor_id = minigame.players[1].data.id;
+        remove_visitor(session, minigame).await?;
+        let packet = packet::room_closed(1, RoomLeaveReason::Closed);
+        return send_to(session, visitor_id, packet).await;
+    }
+
+    session.broadcast_packet(minigame.box_packet(), true)
+}
+
+/// Closes the room, called with its owner's session
+async fn close(session: &mut ChannelSession, minigame: &mut Minigame) -> anyhow::Result<()> {
+    minigame.closed = true;
+    session
+        .state
+        .remove_minigame(minigame.field_id, minigame.id);
+
+    let owner_id = minigame.players[0].data.id;
+    let packet = packet::update_minigame_box(owner_id, None);
+    session.broadcast_packet(packet, true)?;
+
+    if let Some(visitor) = minigame.players.get(1) {
+        let packet = packet::room_closed(1, RoomLeaveReason::Closed);
+        send_to(session, visitor.data.id, packet).await?;
+    }
+
+    Ok(())
+}
+
+/// Removes the visitor from the room, showing the owner that they left
+async fn remove_visitor(
+    session: &mut ChannelSession,
+    minigame: &mut Minigame,
+) -> anyhow::Result<()> {
+    if minigame.players.len() < 2 {
+        return Ok(());
+    }
+
+    minigame.players.truncate(1);
+    minigame.ready = false;
+
+    let owner_id = minigame.players[0].data.id;
+    send_to(session, owner_id, packet::room_visitor_left(1)).await?;
+    session.broadcast_packet(minigame.box_packet(), true)
+}
+
+/// Gets how an omok game ends once a piece is placed at a space, None if it goes on
+/// The player that placed the piece wins with five or more in a row, and the game is tied once the board is full
+fn get_omok_result(
+    board: &[[u8; OMOK_BOARD_SIZE]; OMOK_BOARD_SIZE],
+    x: usize,
+    y: usize,
+) -> Option<MinigameResult> {
+    if is_five_in_a_row(board, x, y) {
+        Some(MinigameResult::Win)
+    } else if board.iter().flatten().all(|space| *space != 0) {
+        Some(MinigameResult::Tie)
+    } else {
+        None
+    }
+}
+
+/// Gets how a finished match cards game ends from the pairs each player matched, along with the slot of its winner
+fn get_match_cards_result(pairs: [i32; 2]) -> (MinigameResult, usize) {
+    match pairs[0].cmp(&pairs[1]) {
+        Ordering::Greater => (MinigameResult::Win, 0),
+        Ordering::Less => (MinigameResult::Win, 1),
+        Ordering::Equal => (MinigameResult::Tie, 0),
+    }
+}
+
+/// Checks if the piece at a space on the omok board is part of five or more of the same pieces in a row
+fn is_five_in_a_row(board: &[[u8; OMOK_BOARD_SIZE]; OMOK_BOARD_SIZE], x: usize, y: usize) -> bool {
+    let piece = board[y][x];
+
+    [(1, 0), (0, 1), (1, 1), (1, -1)].iter().any(|(dx, dy)| {
+        // Counts the same pieces in a row from the space in one direction
+        let count = |sign: i32| {
+            (1..5)
+                .take_while(|distance| {
+                    let x = x as i32 + dx * distance * sign;
+                    let y = y as i32 + dy * distance * sign;
+                    let size = 0..OMOK_BOARD_SIZE as i32;
+                    size.contains(&x) && size.contains(&y) && board[y as usize][x as usize] == piece
+                })
+                .count()
+        };
+
+        1 + count(1) + count(-1) >= 5
+    })
+}
+
+/// Sends a packet to both characters in the room
+async fn send_to_all(
+    session: &mut ChannelSession,
+    minigame: &Minigame,
+    packet: Packet,
+) -> anyhow::Result<()> {
+    for player in minigame.players.iter() {
+        send_to(session, player.data.id, packet.clone()).await?;
+    }
+
+    Ok(())
+}
+
+/// Sends a packet to a character in the room, both characters are always on the same channel
+async fn send_to(
+    session: &mut ChannelSession,
+    character_id: i32,
+    packet: Packet,
+) -> anyhow::Result<()> {
+    if session.character.as_ref().unwrap().data.id == character_id {
+        return session.stream.write_packet(packet).await;
+    }
+
+    session
+        .state
+        .send_to_session(character_id, SessionMessage::Packet(packet))
+        .await;
+    Ok(())
+}
+
+async fn send_notice(session: &mut ChannelSession, message: &str) -> anyhow::Result<()> {
+    session
+        .stream
+        .write_packet(packet::server_notice(NoticeType::Popup, message))
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests an omok game where the owner wins with five in a row diagonally, after the visitor blocks a row
+    #[test]
+    fn omok_win() {
+        let mut board = [[0; OMOK_BOARD_SIZE]; OMOK_BOARD_SIZE];
+
+        // The owner (1) and visitor (2) take turns, the visitor blocks the owner's row at (7, 10)
+        let moves = [
+            (3, 10, 1),
+            (7, 10, 2),
+            (4, 10, 1),
+            (0, 0, 2),
+            (5, 10, 1),
+            (1, 0, 2),
+            (6, 10, 1),
+            (2, 0, 2),
+            (4, 3, 1),
+            (3, 0, 2),
+            (5, 4, 1),
+            (14, 14, 2),
+            (6, 5, 1),
+            (13, 14, 2),
+            (7, 6, 1),
+            (12, 14, 2),
+        ];
+
+        for (x, y, piece) in moves {
+            board[y][x] = piece;
+            assert_eq!(get_omok_result(&board, x, y), None);
+        }
+
+        board[7][8] = 1;
+        assert_eq!(get_omok_result(&board, 8, 7), Some(MinigameResult::Win));
+        assert_eq!(get_omok_result(&board, 4, 3), Some(MinigameResult::Win));
+        assert_eq!(get_omok_result(&board, 6, 10), None);
+    }
+
+    /// Tests that five in a row counts against the edges of the board, and that more than five still wins
+    #[test]
+    fn omok_win_edges() {
+        let last = OMOK_BOARD_SIZE - 1;
+        let mut board = [[0; OMOK_BOARD_SIZE]; OMOK_BOARD_SIZE];
+
+        for row in board[last - 4..].iter_mut() {
+            row[last] = 2;
+        }
+
+        assert_eq!(
+            get_omok_result(&board, last, last),
+            Some(MinigameResult::Win)
+        );
+
+        board[0][..6].fill(1);
+
+        assert_eq!(get_omok_result(&board, 2, 0), Some(MinigameResult::Win));
+    }
+
+    /// Tests that a full board without five in a row ends the game in a tie
+    #[test]
+    fn omok_tie() {
+        let mut board = [[0; OMOK_BOARD_SIZE]; OMOK_BOARD_SIZE];
+
+        // Pairs of pieces alternating along each row, shifted by one each row, never line up five
+        for (y, row) in board.iter_mut().enumerate() {
+            for (x, space) in row.iter_mut().enumerate() {
+                *space = ((x / 2 + y) % 2) as u8 + 1;
+            }
+        }
+
+        board[7][7] = 0;
+        assert_eq!(get_omok_result(&board, 0, 0), None);
+
+        board[7][7] = 1;
+        assert_eq!(get_omok_result(&board, 7, 7), Some(MinigameResult::Tie));
+    }
+
+    /// Tests that the player with the most pairs wins match cards, and equal pairs tie
+    #[test]
+    fn match_cards_result() {
+        assert_eq!(get_match_cards_result([4, 2]), (MinigameResult::Win, 0));
+        assert_eq!(get_match_cards_result([7, 8]), (MinigameResult::Win, 1));
+        assert_eq!(get_match_cards_result([3, 3]).0, MinigameResult::Tie);
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{
+    event, expedition,
+    session::{ChannelSession, SessionMessage},
+};
+use slate_data::{
+    maple::{self, exp::PartyExpMember},
+    packet,
+};
+use std::collections::{HashMap, HashSet};
+
+/// Kills a monster, giving exp to the characters that attacked it and quest credit to them and their party
+pub async fn kill(session: &mut ChannelSession, monster: maple::Monster) -> anyhow::Result<()> {
+    session.broadcast_packet(packet::kill_monster(monster.object_id, 1), true)?;
+
+    let field_id = session.character.as_ref().unwrap().field_id;
+    let map_characters = session.state.get_map_characters(field_id);
+    let exp = monster.data.exp as f64 * session.state.world.exp_rate as f64;
+    let max_hp = monster.data.max_hp.max(1) as f64;
+
+    let mut party_exp: HashMap<i32, f64> = HashMap::new();
+    let mut credited = HashSet::new();
+
+    // Each attacker earns exp based on how much of the monster's hp they took
+    for (&character_id, &damage) in monster.attackers.iter() {
+        let map_character = match map_characters.get(&character_id) {
+            Some(map_character) if map_character.is_alive => map_character,
+            _ => continue,
+        };
+
+        let share = exp * damage as f64 / max_hp;
+
+        match map_character.party_id {
+            Some(party_id) => *party_exp.entry(party_id).or_default() += share,
+            None => {
+                let message = SessionMessage::GainExp {
+                    exp: share as i32,
+                    party_bonus: 0,
+                };
+                send(session, character_id, message).await?;
+                credited.insert(character_id);
+            }
+        }
+    }
+
+    // Party exp is split between the party members in the map
+    for (party_id, exp) in party_exp {
+        let members: Vec<_> = map_characters
+            .iter()
+            .filter(|(_, member)| member.party_id == Some(party_id) && member.is_alive)
+            .map(|(&character_id, member)| PartyExpMember {
+                character_id,
+                level: member.level,
+                damage: monster.attackers.get(&character_id).copied().unwrap_or(0),
+            })
+            .collect();
+
+        for member in members.iter() {
+            credited.insert(member.character_id);
+        }
+
+        for (character_id, exp, party_bonus) in
+            maple::exp::split_party_exp(exp, monster.data.level, &members)
+        {
+            let message = SessionMessage::GainExp { exp, party_bonus };
+            send(session, character_id, message).await?;
+        }
+    }
+
+    for character_id in credited {
+        send(
+            session,
+            character_id,
+            SessionMessage::KillCredit(monster.data.id),
+        )
+        .await?;
+    }
+
+    // Monsters in party quests drop the items needed to clear their stage
+    event::on_monster_killed(session, &monster).await?;
+    expedition::on_monster_killed(session, &monster).await
+}
+
+/// Shows a boss's hp bar to everyone in the map, bosses without an hp tag color don't have one
+pub fn show_boss_hp(session: &ChannelSession, monster: &maple::Monster) -> anyhow::Result<()> {
+    if monster.data.hp_tag_color == 0 {
+        return Ok(());
+    }
+
+    let packet = packet::show_boss_hp(
+        monster.data.id,
+        monster.hp.max(0),
+        monster.data.max_hp,
+        monster.data.hp_tag_color,
+        monster.data.hp_tag_bg_color,
+    );
+    session.broadcast_packet(packet, true)
+}
+
+/// Sends a message to a character in the map, messages for the session's own character are handled right away
+pub async fn send(
+    session: &mut ChannelSession,
+    character_id: i32,
+    message: SessionMessage,
+) -> anyhow::Result<()> {
+    if character_id == session.character.as_ref().unwrap().data.id {
+        return session.handle_message(message).await;
+    }
+
+    session.state.send_to_session(character_id, message).await;
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use crate::session::ChannelSession;
+use slate_data::{
+    maple::{self, mount::MAX_TIREDNESS},
+    nx, packet,
+};
+
+/// Monster Riding (beginner, noblesse and legend), rides the character's equipped mount
+const MONSTER_RIDING: [i32; 3] = [1004, 10001004, 20001004];
+
+/// Checks if the given skill is Monster Riding
+pub fn is_monster_riding(skill_id: i32) -> bool {
+    MONSTER_RIDING.contains(&skill_id)
+}
+
+/// Starts riding the character's equipped mount, the ride lasts until the buff is cancelled
+pub async fn ride(session: &mut ChannelSession, skill_id: i32) -> anyhow::Result<()> {
+    let character = session.character.as_mut().unwrap();
+    let character_id = character.data.id;
+
+    let item_id = match character.get_mount_item_id() {
+        Some(item_id) if !character.is_riding() && character.mount.tiredness < MAX_TIREDNESS => {
+            item_id
+        }
+        _ => return session.stream.write_packet(packet::update_stats(&[])).await,
+    };
+
+    // Only items with mount data can be ridden
+    nx::Mount::load(item_id)?;
+
+    let buff = maple::Buff::mount(skill_id);
+
+    session
+        .stream
+        .write_packet(packet::give_mount_buff(item_id, &buff))
+        .await?;
+    session.broadcast_packet(
+        packet::give_foreign_mount_buff(character_id, item_id, &buff),
+        false,
+    )?;
+    session.character.as_mut().unwrap().apply_buff(buff);
+    Ok(())
+}
+
+/// Stops riding the character's mount
+pub async fn dismount(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let character = session.character.as_mut().unwrap();
+    let character_id = character.data.id;
+
+    let buff = match MONSTER_RIDING
+        .iter()
+        .find_map(|skill_id| character.cancel_buff(*skill_id))
+    {
+        Some(buff) => buff,
+        None => return Ok(()),
+    };
+
+    session
+        .stream
+        .write_packet(packet::cancel_buff(&buff))
+        .await?;
+    session.broadcast_packet(packet::cancel_foreign_buff(character_id, &buff), false)
+}
+
+/// Feeds the character's mount with mount food from their use inventory, making it less tired
+pub async fn feed(session: &mut ChannelSession, position: i32, item_id: i32) -> anyhow::Result<()> {
+    let character = session.character.as_ref().unwrap();
+
+    if item_id / 10000 != 226
+        || character.get_mount_item_id().is_none()
+        || !session.use_item(position, item_id).await?
+    {
+        return session.stream.write_packet(packet::update_stats(&[])).await;
+    }
+
+    let character = session.character.as_mut().unwrap();
+    let leveled_up = maple::mount::feed(&mut character.mount);
+    let packet = packet::update_mount(character.data.id, &character.mount, leveled_up);
+    session.broadcast_packet(packet, true)
+}
+
+/// Makes the character's mount more tired while they're riding it
+/// They stop riding once it's too tired
+pub async fn handle_tiredness(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let character = session.character.as_mut().unwrap();
+
+    if !character.is_riding() {
+        return Ok(());
+    }
+
+    let fatigue = match character.get_mount_item_id() {
+        Some(item_id) => nx::Mount::load(item_id)?.fatigue,
+        None => return dismount(session).await,
+    };
+
+    let too_tired = maple::mount::tire(&mut character.mount, fatigue);
+    let packet = packet::update_mount(character.data.id, &character.mount, false);
+    session.broadcast_packet(packet, true)?;
+
+    if too_tired {
+        dismount(session).await?;
+    }
+
+    Ok(())
+}
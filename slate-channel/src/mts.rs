@@ -0,0 +1,462 @@
+use crate::{packet_handler::connect, pet, session::ChannelSession};
+use anyhow::anyhow;
+use slate_data::{
+    maple::character::InventoryChange,
+    nx,
+    packet::{self, MtsResult, Stat},
+    sql::{
+        self,
+        item::InventoryType,
+        mts::{LISTING_DURATION, LISTING_FEE},
+    },
+};
+use sqlx::types::chrono::Utc;
+
+/// The number of listings shown on each page
+const PAGE_SIZE: usize = 16;
+
+/// The lowest price an item can be listed for
+const MIN_PRICE: i32 = 110;
+
+/// The tab listing every item for sale
+const SALE_TAB: i32 = 1;
+
+/// The tab listing the items on the character's wanted list
+const WANTED_TAB: i32 = 4;
+
+/// The MTS page a character is browsing, instead of a map
+#[derive(Debug)]
+pub struct Mts {
+    pub tab: i32,
+    /// The inventory type (1 to 5) listings are filtered by, 0 for every listing
+    pub category: i32,
+    pub page: i32,
+    pub search: Option<MtsSearch>,
+}
+
+/// A search the character made in the MTS
+#[derive(Debug)]
+pub struct MtsSearch {
+    /// Whether listings are searched by their seller's name instead of their item's name
+    pub by_seller: bool,
+    pub text: String,
+}
+
+/// Moves the character out of their map into the MTS
+/// Their listings that ended in the meantime are moved into their transfer inventory
+pub async fn enter(session: &mut ChannelSession) -> anyhow::Result<()> {
+    if session.mts.is_some() || session.cash_shop.is_some() {
+        return Ok(());
+    }
+
+    let account_id = session.account_id.unwrap();
+    let account = sql::Account::load_optional_by_id(account_id, &session.db)
+        .await?
+        .ok_or_else(|| anyhow!("Account {} not found", account_id))?;
+
+    session.leave_map().await?;
+
+    let character = session.character.as_ref().unwrap();
+    let character_id = character.data.id;
+
+    let now = Utc::now().timestamp_millis();
+    let minigame_records = sql::MinigameRecord::load_all(character_id, &session.db).await?;
+    sql::MtsListing::return_expired(character_id, now, &session.db).await?;
+
+    let packet = connect::open_mts(character, &minigame_records, &account.name);
+    session.stream.write_packet(packet).await?;
+
+    session.mts = Some(Mts {
+        tab: SALE_TAB,
+        category: 0,
+        page: 0,
+        search: None,
+    });
+
+    refresh(session).await
+}
+
+/// Leaves the MTS, the client reconnects to the channel and the character enters their map again
+pub async fn leave(session: &mut ChannelSession) -> anyhow::Result<()> {
+    if session.mts.take().is_none() {
+        return Ok(());
+    }
+
+    session.migrate(session.channel_id).await
+}
+
+/// Shows the character a page of listings in one of the MTS tabs
+pub async fn show_page(
+    session: &mut ChannelSession,
+    tab: i32,
+    category: i32,
+    page: i32,
+) -> anyhow::Result<()> {
+    let mts = match session.mts.as_mut() {
+        Some(mts) => mts,
+        None => return Ok(()),
+    };
+
+    mts.tab = tab;
+    mts.category = category;
+    mts.page = page.max(0);
+    mts.search = None;
+
+    send_listings(session).await
+}
+
+/// Shows the character the first page of listings matching their search
+pub async fn search(
+    session: &mut ChannelSession,
+    tab: i32,
+    category: i32,
+    search: MtsSearch,
+) -> anyhow::Result<()> {
+    let mts = match session.mts.as_mut() {
+        Some(mts) => mts,
+        None => return Ok(()),
+    };
+
+    mts.tab = tab;
+    mts.category = category;
+    mts.page = 0;
+    mts.search = Some(search);
+
+    send_listings(session).await
+}
+
+/// Lists an item from the character's inventory for sale in NX Credit, for the MTS listing fee
+/// `position` is the item's position in its inventory
+pub async fn list_item(
+    session: &mut ChannelSession,
+    item_id: i32,
+    position: i32,
+    quantity: i32,
+    price: i32,
+) -> anyhow::Result<()> {
+    if session.mts.is_none() {
+        return Ok(());
+    }
+
+    let inventory_type = match InventoryType::from_item_id(item_id) {
+        Some(inventory_type) => inventory_type,
+        None => return Ok(()),
+    };
+
+    let data = nx::Item::load(item_id)?;
+    let character = session.character.as_mut().unwrap();
+
+    if price < MIN_PRICE || data.is_untradeable || character.data.mesos < LISTING_FEE {
+        return Ok(());
+    }
+
+    let item = match character
+        .items
+        .iter()
+        .find(|item| item.inventory_type == inventory_type && item.position == position)
+    {
+        // Timed items would expire while they're listed
+        Some(item) if item.item_id == item_id && item.expires_at.is_none() => item,
+        _ => return Ok(()),
+    };
+
+    // Throwing stars and bullets can only be listed as a whole set
+    let amount = if data.is_rechargeable() {
+        item.amount
+    } else {
+        quantity
+    };
+
+    // Summoned pets are put away first
+    if let Some(pet_id) = item.cash_id {
+        pet::unsummon(session, pet_id, false).await?;
+    }
+
+    let character = session.character.as_mut().unwrap();
+    let (item, whole_stack) = match character.take_item(inventory_type, position, amount) {
+        Some(taken) => taken,
+        None => return Ok(()),
+    };
+
+    let ends_at = Utc::now().timestamp_millis() + LISTING_DURATION;
+
+    let listed = sql::MtsListing::list(
+        &item,
+        whole_stack,
+        session.world_id,
+        &character.data.name,
+        price,
+        ends_at,
+        &session.db,
+    )
+    .await?;
+
+    if listed.is_none() {
+        log::warn!(
+            "Item {} is no longer in character {}'s inventory",
+            item.id,
+            item.character_id
+        );
+        return Ok(());
+    }
+
+    let change = if whole_stack {
+        character.remove_pet(&item, &session.db).await?;
+        InventoryChange::Remove(item)
+    } else {
+        let remaining = character
+            .items
+            .iter()
+            .find(|remaining| remaining.id == item.id)
+            .unwrap();
+        InventoryChange::Update(remaining.clone())
+    };
+
+    character.data.mesos -= LISTING_FEE;
+    let mesos = character.data.mesos;
+
+    session
+        .stream
+        .write_packet(packet::update_inventory(&[change]))
+        .await?;
+    session
+        .stream
+        .write_packet(packet::update_stats(&[(Stat::Mesos, mesos)]))
+        .await?;
+    session
+        .stream
+        .write_packet(packet::mts_result(MtsResult::Listed))
+        .await?;
+    refresh(session).await
+}
+
+/// Cancels one of the character's listings, moving the item into their transfer inventory
+pub async fn cancel_listing(session: &mut ChannelSession, listing_id: i32) -> anyhow::Result<()> {
+    if session.mts.is_none() {
+        return Ok(());
+    }
+
+    let character_id = session.character.as_ref().unwrap().data.id;
+
+    let listing = match sql::MtsListing::load_optional(listing_id, &session.db).await? {
+        Some(listing) if listing.character_id == character_id && !listing.in_transfer => listing,
+        _ => return Ok(()),
+    };
+
+    if !listing.cancel(&session.db).await? {
+        return Ok(());
+    }
+
+    session
+        .stream
+        .write_packet(packet::mts_result(MtsResult::Cancelled))
+        .await?;
+    refresh(session).await
+}
+
+/// Buys a listed item into the character's transfer inventory, paid for with their account's NX Credit
+pub async fn buy(session: &mut ChannelSession, listing_id: i32) -> anyhow::Result<()> {
+    if session.mts.is_none() {
+        return Ok(());
+    }
+
+    let character_id = session.character.as_ref().unwrap().data.id;
+
+    let listing = match sql::MtsListing::load_optional(listing_id, &session.db).await? {
+        Some(listing)
+            if listing.world_id == session.world_id && listing.character_id != character_id =>
+        {
+            listing
+        }
+        _ => return session.stream.write_packet(packet::mts_buy_failed()).await,
+    };
+
+    let now = Utc::now().timestamp_millis();
+
+    if !listing
+        .buy(character_id, session.account_id.unwrap(), now, &session.db)
+        .await?
+    {
+        return session.stream.write_packet(packet::mts_buy_failed()).await;
+    }
+
+    session
+        .stream
+        .write_packet(packet::mts_result(MtsResult::Bought))
+        .await?;
+    refresh(session).await
+}
+
+/// Moves an item from the character's transfer inventory into their inventory
+pub async fn transfer_item(session: &mut ChannelSession, listing_id: i32) -> anyhow::Result<()> {
+    if session.mts.is_none() {
+        return Ok(());
+    }
+
+    let character = session.character.as_mut().unwrap();
+
+    let listing = match sql::MtsListing::load_optional(listing_id, &session.db).await? {
+        Some(listing) if listing.character_id == character.data.id && listing.in_transfer => {
+            listing
+        }
+        _ => return Ok(()),
+    };
+
+    let position = match character.get_free_position(listing.inventory_type) {
+        Some(position) => position,
+        None => return Ok(()),
+    };
+
+    let item = match listing.take(position, &session.db).await? {
+        Some(item) => item,
+        None => return Ok(()),
+    };
+
+    character.items.push(item.clone());
+    character.add_pet(&item, &session.db).await?;
+
+    session
+        .stream
+        .write_packet(packet::update_inventory(&[InventoryChange::Add(
+            item.clone(),
+        )]))
+        .await?;
+    session
+        .stream
+        .write_packet(packet::mts_item_transferred(&item))
+        .await?;
+
+    // Pets are shown with their level, tameness and fullness
+    if let Some(pet_id) = item.cash_id {
+        pet::update_pet(session, pet_id, false).await?;
+    }
+    refresh(session).await
+}
+
+/// Adds a listing to the character's wanted list, or removes it when `wanted` isn't set
+pub async fn update_wanted(
+    session: &mut ChannelSession,
+    listing_id: i32,
+    wanted: bool,
+) -> anyhow::Result<()> {
+    if session.mts.is_none() {
+        return Ok(());
+    }
+
+    let entry = sql::MtsWanted {
+        character_id: session.character.as_ref().unwrap().data.id,
+        listing_id,
+    };
+
+    let result = if wanted {
+        let for_sale = sql::MtsListing::load_optional(listing_id, &session.db)
+            .await?
+            .is_some_and(|listing| {
+                listing.world_id == session.world_id
+                    && listing.character_id != entry.character_id
+                    && !listing.in_transfer
+            });
+
+        if !for_sale {
+            return Ok(());
+        }
+
+        entry.insert(&session.db).await?;
+        MtsResult::WantedAdded
+    } else {
+        entry.delete(&session.db).await?;
+        MtsResult::WantedRemoved
+    };
+
+    session
+        .stream
+        .write_packet(packet::mts_result(result))
+        .await?;
+    send_listings(session).await
+}
+
+/// Shows the character their balance, their listings, their transfer inventory and the page they're browsing
+async fn refresh(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let account_id = session.account_id.unwrap();
+    let account = sql::Account::load_optional_by_id(account_id, &session.db)
+        .await?
+        .ok_or_else(|| anyhow!("Account {} not found", account_id))?;
+
+    let character_id = session.character.as_ref().unwrap().data.id;
+    let selling = sql::MtsListing::load_selling(character_id, &session.db).await?;
+    let transfer = sql::MtsListing::load_transfer(character_id, &session.db).await?;
+
+    session
+        .stream
+        .write_packet(packet::mts_balance(account.nx_credit, account.maple_points))
+        .await?;
+    session
+        .stream
+        .write_packet(packet::mts_transfer_inventory(&transfer))
+        .await?;
+    session
+        .stream
+        .write_packet(packet::mts_not_yet_sold(&selling))
+        .await?;
+    send_listings(session).await
+}
+
+/// Shows the character the page of listings they're browsing
+async fn send_listings(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let mts = match session.mts.as_ref() {
+        Some(mts) => mts,
+        None => return Ok(()),
+    };
+
+    let character_id = session.character.as_ref().unwrap().data.id;
+    let now = Utc::now().timestamp_millis();
+
+    let inventory_type = match mts.category {
+        1 => Some(InventoryType::Equip),
+        2 => Some(InventoryType::Use),
+        3 => Some(InventoryType::Setup),
+        4 => Some(InventoryType::Etc),
+        5 => Some(InventoryType::Cash),
+        _ => None,
+    };
+
+    let mut listings = match mts.tab {
+        SALE_TAB => {
+            sql::MtsListing::load_for_sale(session.world_id, inventory_type, now, &session.db)
+                .await?
+        }
+        WANTED_TAB => sql::MtsListing::load_wanted(character_id, now, &session.db)
+            .await?
+            .into_iter()
+            .filter(|listing| {
+                inventory_type.is_none() || Some(listing.inventory_type) == inventory_type
+            })
+            .collect(),
+        // TODO auctions and wanted item requests
+        _ => Vec::new(),
+    };
+
+    if let Some(search) = mts.search.as_ref() {
+        let text = search.text.to_lowercase();
+
+        listings.retain(|listing| {
+            let name = if search.by_seller {
+                Some(listing.seller_name.clone())
+            } else {
+                nx::Item::load_name(listing.item_id)
+            };
+
+            name.is_some_and(|name| name.to_lowercase().contains(&text))
+        });
+    }
+
+    let total = listings.len() as i32;
+    let page: Vec<sql::MtsListing> = listings
+        .into_iter()
+        .skip(mts.page as usize * PAGE_SIZE)
+        .take(PAGE_SIZE)
+        .collect();
+
+    let packet = packet::mts_listings(&page, total, mts.tab, mts.category, mts.page);
+    session.stream.write_packet(packet).await
+}
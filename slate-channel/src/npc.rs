@@ -0,0 +1,72 @@
+use crate::{
+    alliance, expedition, guild, kerning_pq, player_shop, session::ChannelSession, shop, storage,
+    transport,
+};
+use slate_data::packet;
+
+/// Starts a conversation with an npc, or opens its shop or storage if it has one
+// TODO npc scripts, for now only npcs with built in conversations can be talked to
+pub async fn talk(session: &mut ChannelSession, npc_id: i32) -> anyhow::Result<()> {
+    match npc_id {
+        guild::GUILD_NPC => guild::talk(session).await?,
+        alliance::ALLIANCE_NPC => alliance::talk(session).await?,
+        player_shop::FREDRICK_NPC => return player_shop::open_fredrick(session, npc_id).await,
+        kerning_pq::LAKELIS => kerning_pq::talk_lakelis(session).await?,
+        kerning_pq::CLOTO => return kerning_pq::talk_cloto(session).await,
+        kerning_pq::NELLA => kerning_pq::talk_nella(session).await?,
+        _ => {
+            if shop::open(session, npc_id).await?
+                || storage::open(session, npc_id).await?
+                || transport::talk(session, npc_id).await?
+                || expedition::talk(session, npc_id).await?
+            {
+                return Ok(());
+            }
+
+            log::debug!("Npc {} doesn't have a conversation", npc_id);
+            return session.stream.write_packet(packet::update_stats(&[])).await;
+        }
+    }
+
+    session.npc_conversation = Some(npc_id);
+    Ok(())
+}
+
+/// Continues the character's conversation with an npc after they respond
+/// `selection` is None if the character ended the conversation
+pub async fn respond(session: &mut ChannelSession, selection: Option<i32>) -> anyhow::Result<()> {
+    let npc_id = match session.npc_conversation.take() {
+        Some(npc_id) => npc_id,
+        None => return Ok(()),
+    };
+
+    let selection = match selection {
+        Some(selection) => selection,
+        None => return Ok(()),
+    };
+
+    match npc_id {
+        guild::GUILD_NPC => guild::respond(session, selection).await,
+        alliance::ALLIANCE_NPC => alliance::respond(session, selection).await,
+        kerning_pq::LAKELIS => kerning_pq::enter(session).await,
+        kerning_pq::NELLA => kerning_pq::leave(session).await,
+        _ if transport::is_transport_npc(npc_id) => transport::board(session, npc_id).await,
+        _ if expedition::is_expedition_npc(npc_id) => {
+            expedition::respond(session, npc_id, selection).await
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Continues the character's conversation with an npc after they enter text
+pub async fn respond_text(session: &mut ChannelSession, text: &str) -> anyhow::Result<()> {
+    let npc_id = match session.npc_conversation.take() {
+        Some(npc_id) => npc_id,
+        None => return Ok(()),
+    };
+
+    match npc_id {
+        alliance::ALLIANCE_NPC => alliance::respond_text(session, text).await,
+        _ => Ok(()),
+    }
+}
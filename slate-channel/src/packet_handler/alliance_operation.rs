@@ -0,0 +1,288 @@
+use crate::{
+    alliance, guild,
+    session::{ChannelSession, SessionMessage},
+    world,
+};
+use slate_data::{
+    packet::{self, NoticeType},
+    sql::{
+        self,
+        alliance::{ALLIANCE_LEADER_RANK, GUILD_MASTER_ALLIANCE_RANK, LOWEST_ALLIANCE_RANK},
+    },
+};
+use slate_net::Packet;
+
+/// Channel server: alliance operation packet (0x8F)
+/// Called when a guild master manages their guild's alliance
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let operation = packet.read_byte();
+
+    match operation {
+        0x01 => alliance::send_info(session).await?,
+        0x02 => leave(session).await?,
+        0x03 => invite(&packet.read_string(), session).await?,
+        0x04 => join(packet.read_int(), session).await?,
+        0x06 => {
+            let guild_id = packet.read_int();
+            let _alliance_id = packet.read_int();
+            expel(guild_id, session).await?;
+        }
+        0x07 => change_leader(packet.read_int(), session).await?,
+        0x08 => {
+            let titles: Vec<String> = (0..5).map(|_| packet.read_string()).collect();
+            change_rank_titles(titles, session).await?;
+        }
+        0x09 => {
+            let character_id = packet.read_int();
+            let raise = packet.read_byte() > 0;
+            change_rank(character_id, raise, session).await?;
+        }
+        0x0A => change_notice(packet.read_string(), session).await?,
+        _ => log::error!("Invalid alliance operation: {}", operation),
+    }
+
+    Ok(())
+}
+
+/// Removes the character's guild from its alliance, the alliance leader has to disband it instead
+async fn leave(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let character = session.character.as_ref().unwrap();
+
+    if character.data.guild_rank != Some(1) || character.data.alliance_rank == ALLIANCE_LEADER_RANK
+    {
+        return Ok(());
+    }
+
+    let guild_id = character.guild.as_ref().unwrap().id;
+
+    match alliance::load_alliance(session).await? {
+        Some(alliance) => alliance::remove_guild(session, &alliance, guild_id).await,
+        None => Ok(()),
+    }
+}
+
+/// Invites a guild to the alliance, its guild master has to be online to accept
+async fn invite(guild_name: &str, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let alliance = match load_leader_alliance(session).await? {
+        Some(alliance) => alliance,
+        None => return Ok(()),
+    };
+
+    let guild =
+        match sql::Guild::load_optional_by_name(guild_name, session.world_id, &session.db).await? {
+            Some(guild) => guild,
+            None => return send_notice(session, "That guild doesn't exist.").await,
+        };
+
+    if guild.alliance_id.is_some() {
+        return send_notice(session, "That guild is already in an alliance.").await;
+    }
+
+    let guilds = sql::Guild::load_all_by_alliance(alliance.id, &session.db).await?;
+
+    if guilds.len() as i32 >= alliance.capacity {
+        return send_notice(session, "Your alliance is already full.").await;
+    }
+
+    let inviter_name = &session.character.as_ref().unwrap().data.name;
+    let packet = packet::alliance_invite(alliance.id, inviter_name);
+    let message = SessionMessage::AllianceInvite(alliance.id, packet);
+
+    if !world::send(session, guild.leader_id, message).await? {
+        return send_notice(session, "That guild's master isn't online.").await;
+    }
+
+    Ok(())
+}
+
+/// Adds the character's guild to an alliance they were invited to
+async fn join(alliance_id: i32, session: &mut ChannelSession) -> anyhow::Result<()> {
+    if session.alliance_invite.take() != Some(alliance_id) {
+        log::warn!("Tried to join alliance {} without an invite", alliance_id);
+        return Ok(());
+    }
+
+    let character = session.character.as_ref().unwrap();
+
+    let guild = match (&character.guild, character.data.guild_rank) {
+        (Some(guild), Some(1)) if guild.alliance_id.is_none() => guild.clone(),
+        _ => return Ok(()),
+    };
+
+    let alliance = match sql::Alliance::load_optional(alliance_id, &session.db).await? {
+        Some(alliance) => alliance,
+        None => return Ok(()),
+    };
+
+    let guilds = sql::Guild::load_all_by_alliance(alliance.id, &session.db).await?;
+
+    if guilds.len() as i32 >= alliance.capacity {
+        return send_notice(session, "The alliance is already full.").await;
+    }
+
+    alliance.add_guild(guild.id, &session.db).await?;
+    alliance::notify_changed(session, alliance.id).await
+}
+
+/// Removes another guild from the alliance, only the alliance leader can expel guilds
+async fn expel(guild_id: i32, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let alliance = match load_leader_alliance(session).await? {
+        Some(alliance) => alliance,
+        None => return Ok(()),
+    };
+
+    if session.character.as_ref().unwrap().data.guild == Some(guild_id) {
+        return Ok(());
+    }
+
+    match sql::Guild::load_optional(guild_id, &session.db).await? {
+        Some(guild) if guild.alliance_id == Some(alliance.id) => {
+            alliance::remove_guild(session, &alliance, guild.id).await
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Gives leadership of the alliance to the master of another guild in it
+async fn change_leader(target_id: i32, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let alliance = match load_leader_alliance(session).await? {
+        Some(alliance) => alliance,
+        None => return Ok(()),
+    };
+
+    if !is_member(target_id, alliance.id, session).await? {
+        return Ok(());
+    }
+
+    let target = sql::Character::load(target_id, &session.db).await?;
+
+    if target.alliance_rank != GUILD_MASTER_ALLIANCE_RANK {
+        return Ok(());
+    }
+
+    let character_id = session.character.as_ref().unwrap().data.id;
+    sql::Alliance::update_member_rank(character_id, GUILD_MASTER_ALLIANCE_RANK, &session.db)
+        .await?;
+    sql::Alliance::update_member_rank(target_id, ALLIANCE_LEADER_RANK, &session.db).await?;
+
+    let packet =
+        packet::update_alliance_member_rank(alliance.id, character_id, GUILD_MASTER_ALLIANCE_RANK);
+    alliance::send_to_members(session, alliance.id, packet).await?;
+    let packet = packet::update_alliance_member_rank(alliance.id, target_id, ALLIANCE_LEADER_RANK);
+    alliance::send_to_members(session, alliance.id, packet).await?;
+
+    guild::notify_changed(session, &[character_id, target_id]).await
+}
+
+/// Renames the alliance's ranks
+async fn change_rank_titles(
+    titles: Vec<String>,
+    session: &mut ChannelSession,
+) -> anyhow::Result<()> {
+    let mut alliance = match load_leader_alliance(session).await? {
+        Some(alliance) => alliance,
+        None => return Ok(()),
+    };
+
+    // The leader and guild master titles are required, the rest can be left empty
+    for (i, title) in titles.iter().enumerate() {
+        if title.len() > 12 || (i < 2 && title.is_empty()) {
+            return Ok(());
+        }
+    }
+
+    let [rank1, rank2, rank3, rank4, rank5]: [String; 5] = titles.try_into().unwrap();
+    alliance.rank1_title = rank1;
+    alliance.rank2_title = rank2;
+    alliance.rank3_title = rank3;
+    alliance.rank4_title = rank4;
+    alliance.rank5_title = rank5;
+    alliance.update_rank_titles(&session.db).await?;
+
+    let packet = packet::update_alliance_rank_titles(&alliance);
+    alliance::send_to_members(session, alliance.id, packet).await
+}
+
+/// Promotes or demotes a guild member in the alliance, guild masters keep their rank
+async fn change_rank(
+    target_id: i32,
+    raise: bool,
+    session: &mut ChannelSession,
+) -> anyhow::Result<()> {
+    let alliance = match load_leader_alliance(session).await? {
+        Some(alliance) => alliance,
+        None => return Ok(()),
+    };
+
+    if !is_member(target_id, alliance.id, session).await? {
+        return Ok(());
+    }
+
+    let target = sql::Character::load(target_id, &session.db).await?;
+
+    if target.alliance_rank <= GUILD_MASTER_ALLIANCE_RANK {
+        return Ok(());
+    }
+
+    let rank = if raise {
+        target.alliance_rank - 1
+    } else {
+        target.alliance_rank + 1
+    };
+
+    if rank <= GUILD_MASTER_ALLIANCE_RANK || rank > LOWEST_ALLIANCE_RANK {
+        return Ok(());
+    }
+
+    sql::Alliance::update_member_rank(target_id, rank, &session.db).await?;
+
+    let packet = packet::update_alliance_member_rank(alliance.id, target_id, rank);
+    alliance::send_to_members(session, alliance.id, packet).await?;
+    guild::notify_changed(session, &[target_id]).await
+}
+
+/// Changes the alliance's notice, guild masters in the alliance can change it
+async fn change_notice(notice: String, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let mut alliance = match alliance::load_alliance(session).await? {
+        Some(alliance) => alliance,
+        None => return Ok(()),
+    };
+
+    let character = session.character.as_ref().unwrap();
+
+    if character.data.alliance_rank > GUILD_MASTER_ALLIANCE_RANK || notice.len() > 100 {
+        return Ok(());
+    }
+
+    alliance.notice = notice;
+    alliance.update_notice(&session.db).await?;
+
+    let packet = packet::update_alliance_notice(alliance.id, &alliance.notice);
+    alliance::send_to_members(session, alliance.id, packet).await
+}
+
+/// Loads the character's alliance if they're its leader
+async fn load_leader_alliance(session: &ChannelSession) -> anyhow::Result<Option<sql::Alliance>> {
+    if session.character.as_ref().unwrap().data.alliance_rank != ALLIANCE_LEADER_RANK {
+        return Ok(None);
+    }
+
+    alliance::load_alliance(session).await
+}
+
+/// Checks if a character is a member of one of the alliance's guilds
+async fn is_member(
+    character_id: i32,
+    alliance_id: i32,
+    session: &ChannelSession,
+) -> anyhow::Result<bool> {
+    let member_ids = sql::Character::load_alliance_member_ids(alliance_id, &session.db).await?;
+    Ok(member_ids.contains(&character_id))
+}
+
+async fn send_notice(session: &mut ChannelSession, message: &str) -> anyhow::Result<()> {
+    session
+        .stream
+        .write_packet(packet::server_notice(NoticeType::Popup, message))
+        .await
+}
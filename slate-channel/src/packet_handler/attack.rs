@@ -0,0 +1,239 @@
+use crate::{expedition, monster, session::ChannelSession};
+use slate_data::{
+    nx,
+    packet::{self, Stat},
+};
+use slate_net::Packet;
+
+/// Skills that are charged up before attacking, the client sends how long they were charged for
+const CHARGED_SKILLS: [i32; 11] = [
+    2121001, 2221001, 2321001, 3121004, 3221001, 5101004, 5201002, 5221004, 13111002, 14111006,
+    15101003,
+];
+
+/// Ranged skills that are held down while attacking
+const KEY_DOWN_SKILLS: [i32; 4] = [3121004, 3221001, 5221004, 13111002];
+
+const MESO_EXPLOSION: i32 = 4211006;
+const RAPID_FIRE: i32 = 5221004;
+
+/// Attacks are read and broadcast differently depending on their type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttackType {
+    CloseRange,
+    Ranged,
+    Magic,
+}
+
+/// An attack sent by the client
+struct Attack {
+    /// The number of monsters hit in the high nibble, and the number of damage lines in the low nibble
+    num_attacked_and_damage: u8,
+    skill_id: i32,
+    display: u8,
+    direction: u8,
+    stance: u8,
+    speed: u8,
+    /// The damage lines dealt to each monster, by object id
+    targets: Vec<(i32, Vec<i32>)>,
+}
+
+/// Channel server: close range attack (0x2C), ranged attack (0x2D), and magic attack (0x2E) packets
+/// Called when a character attacks, with or without a skill
+pub async fn handle(
+    mut packet: Packet,
+    session: &mut ChannelSession,
+    attack_type: AttackType,
+) -> anyhow::Result<()> {
+    let attack = match read_attack(&mut packet, attack_type) {
+        Some(attack) => attack,
+        None => return Ok(()),
+    };
+
+    let character = session.character.as_mut().unwrap();
+
+    if !character.is_alive() {
+        return Ok(());
+    }
+
+    let mut skill_level = 0;
+
+    if attack.skill_id > 0 {
+        skill_level = character.get_skill_level(attack.skill_id);
+
+        if skill_level == 0 {
+            log::warn!(
+                "Character {} attacked with skill {} without owning it",
+                character.data.id,
+                attack.skill_id
+            );
+            return Ok(());
+        }
+
+        // TODO skill cooldowns
+        let skill = nx::Skill::load(attack.skill_id)?;
+
+        if let Some(effect) = skill.level(skill_level) {
+            if character.data.mp < effect.mp_con {
+                return Ok(());
+            }
+
+            character.data.mp -= effect.mp_con;
+            let mp = character.data.mp;
+            session
+                .stream
+                .write_packet(packet::update_stats(&[(Stat::Mp, mp)]))
+                .await?;
+        }
+    }
+
+    let character = session.character.as_ref().unwrap();
+    let (character_id, field_id) = (character.data.id, character.field_id);
+
+    session.broadcast_packet(
+        attack_packet(character_id, &attack, skill_level, attack_type),
+        false,
+    )?;
+
+    // TODO check for damage over the character's max damage
+    for (object_id, damage) in attack.targets.iter() {
+        let total = damage
+            .iter()
+            .fold(0i32, |total, damage| total.saturating_add(*damage.max(&0)));
+
+        let monster = match session
+            .state
+            .damage_monster(field_id, *object_id, character_id, total)
+        {
+            Some(monster) => monster,
+            None => continue,
+        };
+
+        // Bosses have their own hp bar, shared by every part of an expedition's boss
+        if monster.data.is_boss && !expedition::on_boss_damaged(session, &monster, total).await? {
+            monster::show_boss_hp(session, &monster)?;
+        }
+
+        if !monster.is_alive() {
+            monster::kill(session, monster).await?;
+        } else if !monster.data.is_boss {
+            session
+                .stream
+                .write_packet(packet::show_monster_hp(*object_id, monster.hp_percent()))
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads an attack from the packet, returns None for attacks that aren't supported yet
+fn read_attack(packet: &mut Packet, attack_type: AttackType) -> Option<Attack> {
+    packet.skip(1);
+    let num_attacked_and_damage = packet.read_byte();
+    let num_attacked = num_attacked_and_damage >> 4;
+    let num_damage = num_attacked_and_damage & 0xF;
+    let skill_id = packet.read_int();
+
+    if skill_id == MESO_EXPLOSION {
+        // TODO meso explosion is sent with the meso drops it explodes
+        return None;
+    }
+
+    if CHARGED_SKILLS.contains(&skill_id) {
+        packet.skip(4); // charge time
+    }
+
+    packet.skip(8);
+    let display = packet.read_byte();
+    let direction = packet.read_byte();
+    let stance = packet.read_byte();
+
+    let speed = if attack_type == AttackType::Ranged {
+        packet.skip(1);
+        let speed = packet.read_byte();
+        packet.skip(9);
+
+        if KEY_DOWN_SKILLS.contains(&skill_id) {
+            packet.skip(4);
+        }
+
+        speed
+    } else {
+        packet.skip(1);
+        let speed = packet.read_byte();
+        packet.skip(4);
+        speed
+    };
+
+    let mut targets = Vec::new();
+
+    for _ in 0..num_attacked {
+        let object_id = packet.read_int();
+        packet.skip(14);
+
+        let damage = (0..num_damage).map(|_| packet.read_int()).collect();
+
+        if skill_id != RAPID_FIRE {
+            packet.skip(4);
+        }
+
+        targets.push((object_id, damage));
+    }
+
+    Some(Attack {
+        num_attacked_and_damage,
+        skill_id,
+        display,
+        direction,
+        stance,
+        speed,
+        targets,
+    })
+}
+
+/// Shows a character's attack to the other characters in the map
+fn attack_packet(
+    character_id: i32,
+    attack: &Attack,
+    skill_level: i32,
+    attack_type: AttackType,
+) -> Packet {
+    let op_code = match attack_type {
+        AttackType::CloseRange => 0xBA,
+        AttackType::Ranged => 0xBB,
+        AttackType::Magic => 0xBC,
+    };
+
+    let mut packet = Packet::new(op_code);
+    packet.write_int(character_id);
+    packet.write_byte(attack.num_attacked_and_damage);
+    packet.write_byte(0x5B);
+    packet.write_byte(skill_level as u8);
+
+    if skill_level > 0 {
+        packet.write_int(attack.skill_id);
+    }
+
+    packet.write_byte(attack.display);
+    packet.write_byte(attack.direction);
+    packet.write_byte(attack.stance);
+    packet.write_byte(attack.speed);
+    packet.write_byte(0x0A); // mastery
+    packet.write_int(0); // TODO projectile item id for ranged attacks
+
+    for (object_id, damage) in attack.targets.iter() {
+        packet.write_int(*object_id);
+        packet.write_byte(0);
+
+        for damage in damage.iter() {
+            packet.write_int(*damage);
+        }
+    }
+
+    if attack_type == AttackType::Ranged {
+        packet.write_int(0);
+    }
+
+    packet
+}
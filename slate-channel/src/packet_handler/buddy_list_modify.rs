@@ -0,0 +1,159 @@
+use crate::{buddy, session::ChannelSession, world};
+use slate_data::{
+    packet::{self, BuddyMessage},
+    sql::{self, buddy::DEFAULT_GROUP},
+};
+use slate_net::Packet;
+
+/// Channel server: buddy list modify packet (0x82)
+/// Called when a character adds, accepts, or deletes a buddy
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let mode = packet.read_byte();
+
+    match mode {
+        1 => {
+            let name = packet.read_string();
+            let group_name = packet.read_string();
+            add(&name, &group_name, session).await?;
+        }
+        2 => accept(packet.read_int(), session).await?,
+        3 => delete(packet.read_int(), session).await?,
+        _ => log::error!("Invalid buddy list mode: {}", mode),
+    }
+
+    Ok(())
+}
+
+/// Adds a character to the buddy list and sends them a buddy request
+/// Adding a character who is already a buddy moves them to the given group
+async fn add(name: &str, group_name: &str, session: &mut ChannelSession) -> anyhow::Result<()> {
+    if group_name.is_empty() || group_name.len() > 16 {
+        return Ok(());
+    }
+
+    let character = session.character.as_ref().unwrap();
+    let (character_id, capacity) = (character.data.id, character.data.buddy_capacity);
+
+    let target =
+        match sql::Character::load_optional_by_name(name, session.world_id, &session.db).await? {
+            Some(target) if target.id != character_id => target,
+            _ => return send_message(session, BuddyMessage::CharacterNotFound).await,
+        };
+
+    if let Some(mut buddy) = sql::Buddy::load_optional(character_id, target.id, &session.db).await?
+    {
+        // The target already sent us a request, adding them back accepts it
+        if buddy.pending {
+            return accept(target.id, session).await;
+        }
+
+        if buddy.group_name == group_name {
+            return send_message(session, BuddyMessage::AlreadyRegistered).await;
+        }
+
+        buddy.group_name = group_name.to_string();
+        buddy.update(&session.db).await?;
+        return buddy::send_list(session).await;
+    }
+
+    if sql::Buddy::get_count(character_id, &session.db).await? >= capacity {
+        return send_message(session, BuddyMessage::ListFull).await;
+    }
+
+    if sql::Buddy::get_count(target.id, &session.db).await? >= target.buddy_capacity {
+        return send_message(session, BuddyMessage::OtherListFull).await;
+    }
+
+    sql::Buddy {
+        character_id,
+        buddy_id: target.id,
+        group_name: group_name.to_string(),
+        pending: false,
+    }
+    .insert(&session.db)
+    .await?;
+
+    // The target only needs to accept if they don't have us as a buddy yet
+    let is_mutual = sql::Buddy::load_optional(target.id, character_id, &session.db)
+        .await?
+        .is_some_and(|buddy| !buddy.pending);
+
+    buddy::send_list(session).await?;
+
+    if is_mutual {
+        let packet = packet::update_buddy_channel(character_id, session.channel_id);
+        world::send_packet(session, target.id, packet).await?;
+        return Ok(());
+    }
+
+    sql::Buddy {
+        character_id: target.id,
+        buddy_id: character_id,
+        group_name: DEFAULT_GROUP.to_string(),
+        pending: true,
+    }
+    .insert(&session.db)
+    .await?;
+
+    // Offline characters see the request when they log in
+    let name = &session.character.as_ref().unwrap().data.name;
+    let packet = packet::buddy_request(character_id, name, target.id);
+    world::send_packet(session, target.id, packet).await?;
+    Ok(())
+}
+
+/// Accepts a buddy request
+async fn accept(buddy_id: i32, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let character = session.character.as_ref().unwrap();
+    let (character_id, capacity) = (character.data.id, character.data.buddy_capacity);
+
+    let mut buddy = match sql::Buddy::load_optional(character_id, buddy_id, &session.db).await? {
+        Some(buddy) if buddy.pending => buddy,
+        _ => return Ok(()),
+    };
+
+    if sql::Buddy::get_count(character_id, &session.db).await? >= capacity {
+        return send_message(session, BuddyMessage::ListFull).await;
+    }
+
+    buddy.pending = false;
+    buddy.update(&session.db).await?;
+
+    buddy::send_list(session).await?;
+
+    let packet = packet::update_buddy_channel(character_id, session.channel_id);
+    world::send_packet(session, buddy_id, packet).await?;
+
+    buddy::send_next_request(session).await
+}
+
+/// Removes a buddy, or declines their buddy request if it's still pending
+async fn delete(buddy_id: i32, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let character_id = session.character.as_ref().unwrap().data.id;
+
+    let buddy = match sql::Buddy::load_optional(character_id, buddy_id, &session.db).await? {
+        Some(buddy) => buddy,
+        None => return Ok(()),
+    };
+
+    buddy.delete(&session.db).await?;
+
+    if buddy.pending {
+        return buddy::send_next_request(session).await;
+    }
+
+    buddy::send_list(session).await?;
+
+    // We're no longer shown as online to the removed buddy
+    let packet = packet::update_buddy_channel(character_id, -1);
+    world::send_packet(session, buddy_id, packet).await?;
+    Ok(())
+}
+
+/// Sends a buddy list message to the current player
+async fn send_message(session: &mut ChannelSession, message: BuddyMessage) -> anyhow::Result<()> {
+    session
+        .stream
+        .write_packet(packet::buddy_message(message))
+        .await
+}
@@ -0,0 +1,27 @@
+use crate::{mount, session::ChannelSession};
+use slate_data::packet;
+use slate_net::Packet;
+
+/// Channel server: cancel buff packet (0x5C)
+/// Called when a character cancels one of their buffs, including getting off their mount
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let skill_id = packet.read_int();
+
+    if mount::is_monster_riding(skill_id) {
+        return mount::dismount(session).await;
+    }
+
+    let character = session.character.as_mut().unwrap();
+    let character_id = character.data.id;
+
+    let buff = match character.cancel_buff(skill_id) {
+        Some(buff) => buff,
+        None => return Ok(()),
+    };
+
+    session
+        .stream
+        .write_packet(packet::cancel_buff(&buff))
+        .await?;
+    session.broadcast_packet(packet::cancel_foreign_buff(character_id, &buff), false)
+}
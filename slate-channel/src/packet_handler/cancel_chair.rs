@@ -0,0 +1,19 @@
+use crate::session::ChannelSession;
+use slate_data::packet;
+use slate_net::Packet;
+
+/// Channel server: cancel chair packet (0x2A)
+/// Called when a character stands up from a chair
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    // TODO seats that are part of the map
+    if packet.read_short() != -1 {
+        return Ok(());
+    }
+
+    let character = session.character.as_mut().unwrap();
+    character.chair = 0;
+    let character_id = character.data.id;
+
+    session.stream.write_packet(packet::cancel_chair()).await?;
+    session.broadcast_packet(packet::show_chair(character_id, 0), false)
+}
@@ -0,0 +1,12 @@
+use crate::{cash_shop, session::ChannelSession};
+use slate_net::Packet;
+
+/// Channel server: cash shop balance packet (0xE4)
+/// Called when the client wants to show the account's NX Credit and Maple Points in the cash shop
+pub async fn handle(_packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    if session.cash_shop.is_none() {
+        return Ok(());
+    }
+
+    cash_shop::send_balance(session).await
+}
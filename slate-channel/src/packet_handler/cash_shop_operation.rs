@@ -0,0 +1,57 @@
+use crate::{cash_shop, session::ChannelSession};
+use slate_data::sql::item::InventoryType;
+use slate_net::Packet;
+
+/// Channel server: cash shop operation packet (0xE5)
+/// Called when a character buys or gifts an item, changes their wishlist, or moves items in and out of their locker
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let action = packet.read_byte();
+
+    match action {
+        // Buy an item
+        0x03 => {
+            packet.skip(1);
+            let currency = packet.read_int();
+            let serial_number = packet.read_int();
+            cash_shop::buy(session, currency, serial_number).await?
+        }
+        // Gift an item
+        0x04 => {
+            packet.skip(4); // TODO check the account's birthday
+            let serial_number = packet.read_int();
+            let recipient = packet.read_string();
+            let message = packet.read_string();
+            cash_shop::gift(session, serial_number, &recipient, &message).await?
+        }
+        // Update the wishlist
+        0x05 => {
+            let serial_numbers: Vec<i32> = (0..10).map(|_| packet.read_int()).collect();
+            cash_shop::update_wishlist(session, &serial_numbers).await?
+        }
+        // Take an item out of the locker
+        0x0D => {
+            // Serial numbers are sent as longs, but never get past an int
+            let cash_id = packet.read_int() as i64;
+            cash_shop::take_item(session, cash_id).await?
+        }
+        // Put an item into the locker
+        0x0E => {
+            let cash_id = packet.read_int() as i64;
+            packet.skip(4);
+
+            let inventory_type = match packet.read_byte() {
+                1 => InventoryType::Equip,
+                2 => InventoryType::Use,
+                3 => InventoryType::Setup,
+                4 => InventoryType::Etc,
+                5 => InventoryType::Cash,
+                _ => return Ok(()),
+            };
+
+            cash_shop::store_item(session, cash_id, inventory_type).await?
+        }
+        _ => log::debug!("Unhandled cash shop action: {}", action),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use crate::{cash_shop, event, expedition, mts, session::ChannelSession};
+use slate_data::{maple, packet};
+use slate_net::Packet;
+
+/// Channel server: change map packet (0x26)
+/// Called when a character enters a portal, or revives after dying
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    // Leaving the cash shop or the MTS sends an empty packet
+    if packet.remaining() == 0 {
+        if session.mts.is_some() {
+            return mts::leave(session).await;
+        }
+
+        return cash_shop::leave(session).await;
+    }
+
+    packet.skip(1); // 1 if the character is reviving
+    let target_map_id = packet.read_int();
+    let portal_name = packet.read_string();
+
+    let character = session.character.as_ref().unwrap();
+
+    if !character.is_alive() {
+        return session.revive().await;
+    }
+
+    let map = maple::Map::load(character.data.map)?;
+
+    if let Some(portal) = map.get_portal_by_name(&portal_name) {
+        // TODO make const -- MAP_NONE
+        if portal.target_map_id == 999999999 {
+            return session.stream.write_packet(packet::update_stats(&[])).await;
+        }
+
+        // Party quests only open the way to the next stage once the current one is cleared
+        if !event::can_enter(session, portal.target_map_id).await {
+            return session.stream.write_packet(packet::update_stats(&[])).await;
+        }
+
+        // Boss maps are only open to the expedition fighting the boss
+        if !expedition::can_enter(session, portal.target_map_id).await {
+            return session.stream.write_packet(packet::update_stats(&[])).await;
+        }
+
+        // TODO portal scripts
+        let target_map = maple::Map::load(portal.target_map_id)?;
+
+        let target_portal_id = target_map
+            .get_portal_by_name(&portal.target)
+            .map(|portal| portal.id)
+            .unwrap_or(0);
+
+        return session.change_map(target_map.id, target_portal_id).await;
+    }
+
+    // GMs can warp to any map by id from the client
+    if target_map_id != -1 && character.data.gm > 0 {
+        return session.change_map(target_map_id, 0).await;
+    }
+
+    session.stream.write_packet(packet::update_stats(&[])).await
+}
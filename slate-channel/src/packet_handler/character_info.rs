@@ -0,0 +1,17 @@
+use crate::session::{ChannelSession, SessionMessage};
+use slate_net::Packet;
+
+/// Channel server: character info packet (0x61)
+/// Called when a character opens another character's info window
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    packet.skip(4);
+    let character_id = packet.read_int();
+    let requester_id = session.character.as_ref().unwrap().data.id;
+
+    // The other character's session sends their info back
+    session
+        .state
+        .send_to_session(character_id, SessionMessage::CharacterInfo(requester_id))
+        .await;
+    Ok(())
+}
@@ -1,17 +1,11 @@
-use crate::session::ChannelSession;
-use rand::random;
+use crate::{alliance, buddy, guild, party, session::ChannelSession};
 use slate_data::{
-    maple::{
-        self,
-        map::{MapBroadcast, PacketBroadcast},
-    },
-    nx, packet,
+    maple, packet,
     sql::{self, account::LoginState, item::InventoryType, quest::QuestStatus},
 };
 use slate_net::Packet;
 use sqlx::types::chrono::{Local, Utc};
-use std::{collections::HashMap, time::Duration};
-use tokio::{sync::mpsc, time::timeout};
+use std::collections::HashMap;
 
 /// Channel server: connect packet (0x14)
 /// Called when the client transitions from login to channel server
@@ -43,6 +37,7 @@ pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow:
         };
 
     session.account_id = Some(account.id);
+    session.login_session_id = Some(login_session.id);
 
     // Ensure that account has the `Transitioning` state
     if !matches!(account.state, LoginState::Transitioning) {
@@ -86,9 +81,15 @@ pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow:
     //     }
     // }
 
+    let minigame_records = sql::MinigameRecord::load_all(character.data.id, &session.db).await?;
+
     session
         .stream
-        .write_packet(character_info(login_session.channel_id, &character))
+        .write_packet(character_info(
+            login_session.channel_id,
+            &character,
+            &minigame_records,
+        ))
         .await?;
 
     session
@@ -96,70 +97,39 @@ pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow:
         .write_packet(character_keymap(&character))
         .await?;
 
-    let broadcast_tx = session.state.get_map_broadcast_tx(map.id).clone();
-
-    let (tx, mut rx) = mpsc::channel(64);
-    let joined_broadcast = MapBroadcast::Joined(tx);
-
-    // Notify other characters that we joined the map, and get the number of characters we notified
-    // NOTE: it is possible that we send a broadcast to a character who disconnects before responding --
-    // this is handled by adding a timeout to the below rx.recv call
-    let other_characters = broadcast_tx.send(joined_broadcast)? - 1;
-
-    log::debug!("Start receiving {} map characters...", other_characters);
-
-    // Listen for responses from the map's characters
-    // TODO should listen for spawn_character packet, is probably less bytes
-    for i in 0..other_characters {
-        log::debug!("Receiving character {}", i);
-
-        // TODO tweak timeout
-        let character = match timeout(Duration::from_secs(1), rx.recv()).await {
-            Ok(Some(character)) => character,
-            _ => {
-                log::warn!("Didn't receive character {} in time", i);
-                continue;
-            }
-        };
-
-        log::debug!("Received character {}!", i);
-
-        session
-            .stream
-            .write_packet(spawn_character(&character, false))
-            .await?;
-    }
-
-    // Send the map's npcs
-    for npc in map.data.npcs.values() {
-        session.stream.write_packet(spawn_npc(npc)).await?;
-        session
-            .stream
-            .write_packet(spawn_npc_request_controller(npc))
-            .await?;
-    }
-
-    // Send the map's portals
-    for portal in map.data.portals.values() {
-        session
-            .stream
-            .write_packet(spawn_portal(map.id, portal))
-            .await?;
+    // Mark the character as online so they can be found from other channels
+    sql::OnlineCharacter {
+        character_id: character.data.id,
+        account_id: account.id,
+        name: character.data.name.clone(),
+        world_id: session.world_id,
+        channel_id: session.channel_id,
+        map_id: character.data.map,
     }
+    .insert(&session.db)
+    .await?;
 
-    let broadcast = MapBroadcast::Packet(PacketBroadcast {
-        packet: spawn_character(&character, true),
-        sender_id: character.data.id,
-        send_to_sender: false,
-    });
-    broadcast_tx.send(broadcast)?;
+    session
+        .state
+        .add_session(character.data.id, session.session_tx.clone());
 
     // Move the character into the current session
     session.character = Some(character);
+    session.enter_map(&map).await?;
+
+    // Show the character as online in their party
+    party::update_members(session).await?;
+
+    // Show the character as online to their buddies, and show any requests sent while they were offline
+    buddy::send_list(session).await?;
+    buddy::update_buddies(session, session.channel_id).await?;
+    buddy::send_next_request(session).await?;
 
-    // Subscribe to the current map's broadcast channel
-    session.map_broadcast_tx = Some(broadcast_tx.clone());
-    session.map_broadcast_rx = Some(broadcast_tx.subscribe());
+    // Show the character as online to their guild and alliance
+    guild::send_info(session).await?;
+    guild::update_member_online(session, true).await?;
+    alliance::send_info(session).await?;
+    alliance::update_member_online(session, true).await?;
 
     Ok(())
 }
@@ -177,7 +147,11 @@ fn connect_error(error: ConnectError) -> Packet {
 }
 
 ///
-fn character_info(channel_id: i32, character: &maple::Character) -> Packet {
+fn character_info(
+    channel_id: i32,
+    character: &maple::Character,
+    minigame_records: &[sql::MinigameRecord],
+) -> Packet {
     let mut packet = Packet::new(0x7D);
     packet.write_int(channel_id);
     packet.write_byte(1);
@@ -186,7 +160,7 @@ fn character_info(channel_id: i32, character: &maple::Character) -> Packet {
     packet.write_int(rand::random());
     packet.write_int(rand::random());
     packet.write_int(rand::random());
-    write_character(&mut packet, character);
+    write_character(&mut packet, character, minigame_records);
 
     // FIXME this is ugly
     let current_time = Utc::now().timestamp_millis() * 10000;
@@ -197,11 +171,70 @@ fn character_info(channel_id: i32, character: &maple::Character) -> Packet {
     packet
 }
 
+/// Packet that moves the character into the cash shop, containing their data like `character_info`
+pub fn open_cash_shop(
+    character: &maple::Character,
+    minigame_records: &[sql::MinigameRecord],
+    account_name: &str,
+) -> Packet {
+    let mut packet = Packet::new(0x7F);
+    write_character(&mut packet, character, minigame_records);
+    packet.write_byte(1);
+    packet.write_string(account_name);
+    packet.write_int(0);
+    packet.write_short(0); // TODO discounted commodities
+    packet.write_bytes(&[0; 121]);
+
+    // TODO best selling commodities, 5 for each category and gender
+    for category in 1..=8 {
+        for gender in 0..2 {
+            for _ in 0..5 {
+                packet.write_int(category);
+                packet.write_int(gender);
+                packet.write_int(0);
+            }
+        }
+    }
+
+    packet.write_int(0);
+    packet.write_short(0);
+    packet.write_byte(0);
+    packet.write_int(75);
+    packet
+}
+
+/// Moves the current player into the MTS
+pub fn open_mts(
+    character: &maple::Character,
+    minigame_records: &[sql::MinigameRecord],
+    account_name: &str,
+) -> Packet {
+    let mut packet = Packet::new(0x7E);
+    write_character(&mut packet, character, minigame_records);
+    packet.write_string(account_name);
+    packet.write_int(sql::mts::LISTING_FEE);
+    packet.write_int(7);
+    packet.write_int(500);
+    packet.write_int(24);
+    packet.write_int((sql::mts::LISTING_DURATION / 3600000) as i32); // hours items stay listed
+    packet.write_long(packet::to_filetime(Utc::now().timestamp_millis()));
+    packet
+}
+
 ///
-fn write_character(packet: &mut Packet, character: &maple::Character) {
+fn write_character(
+    packet: &mut Packet,
+    character: &maple::Character,
+    minigame_records: &[sql::MinigameRecord],
+) {
     packet.write_long(-1);
     packet.write_byte(0);
-    packet::write_character_stats(packet, &character.data);
+    let pet_ids: Vec<i64> = character
+        .get_summoned_pets()
+        .iter()
+        .map(|pet| pet.data.id)
+        .collect();
+    packet::write_character_stats(packet, &character.data, &pet_ids);
     packet.write_byte(character.data.buddy_capacity as u8);
 
     // TODO blessing of the fairy stuff
@@ -211,7 +244,7 @@ fn write_character(packet: &mut Packet, character: &maple::Character) {
     write_character_inventory(packet, character);
     write_character_skills(packet, character);
     write_character_quests(packet, character);
-    packet.write_short(0); // TODO minigame info?
+    write_character_minigame_records(packet, minigame_records);
     write_character_rings(packet, character);
     write_character_teleport_rock_maps(packet, character);
     write_character_monster_book(packet, character);
@@ -233,7 +266,7 @@ fn write_character_inventory(packet: &mut Packet, character: &maple::Character)
 
     // Write equips
     for equip in character.equipment.iter() {
-        write_equip(packet, equip);
+        packet::write_equip(packet, equip);
     }
 
     packet.write_short(0);
@@ -253,7 +286,7 @@ fn write_character_inventory(packet: &mut Packet, character: &maple::Character)
     // Write equip inventory
     if let Some(equip_inventory) = inventory.get(&(InventoryType::Equip as i32)) {
         for item in equip_inventory.iter() {
-            write_item(packet, item);
+            packet::write_item(packet, item);
         }
     }
 
@@ -262,7 +295,7 @@ fn write_character_inventory(packet: &mut Packet, character: &maple::Character)
     // Write use inventory
     if let Some(use_inventory) = inventory.get(&(InventoryType::Use as i32)) {
         for item in use_inventory.iter() {
-            write_item(packet, item);
+            packet::write_item(packet, item);
         }
     }
 
@@ -271,7 +304,7 @@ fn write_character_inventory(packet: &mut Packet, character: &maple::Character)
     // Write setup inventory
     if let Some(setup_inventory) = inventory.get(&(InventoryType::Setup as i32)) {
         for item in setup_inventory.iter() {
-            write_item(packet, item);
+            packet::write_item(packet, item);
         }
     }
 
@@ -280,7 +313,7 @@ fn write_character_inventory(packet: &mut Packet, character: &maple::Character)
     // Write etc inventory
     if let Some(etc_inventory) = inventory.get(&(InventoryType::Etc as i32)) {
         for item in etc_inventory.iter() {
-            write_item(packet, item);
+            packet::write_item(packet, item);
         }
     }
 
@@ -289,72 +322,18 @@ fn write_character_inventory(packet: &mut Packet, character: &maple::Character)
     // Write cash inventory
     if let Some(cash_inventory) = inventory.get(&(InventoryType::Cash as i32)) {
         for item in cash_inventory.iter() {
-            write_item(packet, item);
+            match character
+                .pets
+                .iter()
+                .find(|pet| Some(pet.data.id) == item.cash_id)
+            {
+                Some(pet) => packet::write_pet_item(packet, item, &pet.data),
+                None => packet::write_item(packet, item),
+            }
         }
     }
 }
 
-/// Writes an equip's data to a packet
-fn write_equip(packet: &mut Packet, equip: &sql::Equipment) {
-    let mut pos = equip.position.abs();
-
-    if pos > 100 {
-        pos -= 100;
-    }
-
-    packet.write_short(pos as i16);
-    packet.write_byte(1); // item type (equip)
-    packet.write_int(equip.item_id);
-    packet.write_byte(0); // TODO is cash
-                          // TODO if is cash write id again?
-    packet.write_long(-1); // TODO equip expiration time if not permanent
-    packet.write_byte(equip.upgrade_slots as u8);
-    packet.write_byte(equip.level as u8);
-    packet.write_short(equip.str as i16);
-    packet.write_short(equip.dex as i16);
-    packet.write_short(equip.int as i16);
-    packet.write_short(equip.luk as i16);
-    packet.write_short(equip.hp as i16);
-    packet.write_short(equip.mp as i16);
-    packet.write_short(equip.w_atk as i16);
-    packet.write_short(equip.m_atk as i16);
-    packet.write_short(equip.w_def as i16);
-    packet.write_short(equip.m_def as i16);
-    packet.write_short(equip.acc as i16);
-    packet.write_short(equip.avoid as i16);
-    packet.write_short(equip.hands as i16);
-    packet.write_short(equip.speed as i16);
-    packet.write_short(equip.jump as i16);
-    packet.write_string(&equip.owner);
-    packet.write_short(equip.flag as i16);
-    // TODO if iscash write 10 0x40 bytes? and dont wirte item level stuff
-
-    packet.write_byte(0);
-    packet.write_byte(equip.item_level as u8);
-    packet.write_int(0); // TODO exp nibble?
-    packet.write_int(equip.vicious);
-    packet.write_long(0);
-
-    // UTC zero-timestamp
-    packet.write_long(94354848000000000);
-    packet.write_int(-1);
-}
-
-/// Writes an item's data to a packet
-fn write_item(packet: &mut Packet, item: &sql::Item) {
-    // Positions are 0-indexed in db, client expects 1-indexed
-    packet.write_byte((item.position + 1) as u8);
-    packet.write_byte(2); // item type (item)
-    packet.write_int(item.item_id);
-    packet.write_byte(0); // TODO is cash
-                          // TODO if is cash write id again?
-    packet.write_long(-1); // TODO item expiration time if not permanent
-    packet.write_short(item.amount as i16);
-    packet.write_string(&item.owner);
-    packet.write_short(item.flag as i16);
-    // TODO if item is rechargable, sent int(2), bytes (0x54, 0, 0, 0x34)?
-}
-
 /// Writes a character's skills to a packet
 fn write_character_skills(packet: &mut Packet, character: &maple::Character) {
     packet.write_byte(0);
@@ -404,8 +383,8 @@ fn write_character_quests(packet: &mut Packet, character: &maple::Character) {
 
         for quest in started_quests.iter() {
             packet.write_short(quest.id as i16);
-            packet.write_string(""); // TODO quest progress data
-                                     // TODO quest info number stuff?
+            packet.write_string(&character.get_quest_progress(quest.id).unwrap_or_default());
+            // TODO quest info number stuff?
         }
     } else {
         packet.write_short(0);
@@ -423,6 +402,15 @@ fn write_character_quests(packet: &mut Packet, character: &maple::Character) {
     }
 }
 
+/// Writes a character's wins, ties, and losses in each minigame they've played to a packet
+fn write_character_minigame_records(packet: &mut Packet, records: &[sql::MinigameRecord]) {
+    packet.write_short(records.len() as i16);
+
+    for record in records.iter() {
+        packet::write_minigame_record(packet, record);
+    }
+}
+
 /// Writes a character's rings to a packet
 fn write_character_rings(packet: &mut Packet, character: &maple::Character) {
     packet.write_short(0); // TODO crush rings size
@@ -480,174 +468,3 @@ fn character_keymap(character: &maple::Character) -> Packet {
 
     packet
 }
-
-///
-fn spawn_character(character: &maple::Character, entering: bool) -> Packet {
-    let mut packet = Packet::new(0xA0);
-    packet.write_int(character.data.id);
-    packet.write_byte(character.data.level as u8);
-    packet.write_string(&character.data.name);
-
-    match character.data.guild {
-        Some(guild_id) => {
-            // TODO load guild data by id, if found write guild data
-            packet.write_string("");
-            packet.write_bytes(&[0, 0, 0, 0, 0, 0]);
-        }
-        None => {
-            packet.write_string("");
-            packet.write_bytes(&[0, 0, 0, 0, 0, 0]);
-        }
-    };
-
-    write_buffs(&mut packet);
-    // TODO need to get the correct job id based on the job, create an enum that maps all jobs to job ids? (see Job class)
-    packet.write_short(0); // FIXME job id
-    packet::write_character_style(&mut packet, &character.data);
-    packet::write_character_equipment(&mut packet, &character.equipment);
-    packet.write_int(0); // TODO # of heart shaped chocolate in cash inv??? why
-    packet.write_int(0); // TODO item effect
-    packet.write_int(0); // TODO chair id
-
-    // Check if character is already present in the map
-    if entering {
-        packet.write_position((character.pos.0, character.pos.1 - 42));
-        packet.write_byte(6);
-    } else {
-        packet.write_position(character.pos);
-        packet.write_byte(character.stance);
-    }
-
-    packet.write_short(0);
-    packet.write_byte(0);
-
-    // TODO pet info
-    for i in 0..3 {
-        // TODO write pet[i]
-    }
-
-    packet.write_byte(0);
-
-    // TODO mount info
-    packet.write_int(1);
-    packet.write_long(0);
-
-    // TODO shop and minigame info
-    packet.write_byte(0);
-
-    // TODO chalkboard
-    packet.write_byte(0);
-
-    // TODO crush ring
-    packet.write_byte(0);
-
-    // TODO friendship ring
-    packet.write_byte(0);
-
-    // TODO marriage ring
-    packet.write_byte(0);
-
-    // TODO new years card info
-    packet.write_byte(0);
-
-    packet.write_byte(0);
-    packet.write_byte(0);
-    packet.write_byte(0); // TODO team
-    packet
-}
-
-///
-fn write_buffs(packet: &mut Packet) {
-    packet.write_int(0);
-    packet.write_short(0);
-    packet.write_byte(0xFC);
-    packet.write_byte(1);
-    packet.write_int(0); // TODO morph
-
-    let buff_mask = 0i64;
-    // TODO compute buff mask
-    packet.write_int(((buff_mask >> 32) & 0xffffffffi64) as i32);
-    // TODO buff value
-    packet.write_int((buff_mask & 0xffffffffi64) as i32);
-
-    // TODO energy
-    packet.write_int(0);
-    packet.write_short(0);
-    packet.write_bytes(&[0u8; 4]);
-
-    // TODO dash buff
-    packet.write_int(0);
-    packet.write_bytes(&[0u8; 11]);
-    packet.write_short(0);
-
-    // TODO dash jump
-    packet.write_bytes(&[0u8; 9]);
-    packet.write_int(0);
-    packet.write_short(0);
-    packet.write_byte(0);
-
-    // TODO monster riding
-    packet.write_long(0);
-
-    let char_magic_spawn = random::<i32>();
-    packet.write_int(char_magic_spawn);
-
-    // Speed Infusion
-    packet.write_bytes(&[0u8; 8]);
-    packet.write_int(char_magic_spawn);
-    packet.write_byte(0);
-    packet.write_int(char_magic_spawn);
-    packet.write_short(0);
-
-    // Homing Beacon
-    packet.write_bytes(&[0u8; 9]);
-    packet.write_int(char_magic_spawn);
-    packet.write_int(0);
-
-    // Zombify
-    packet.write_bytes(&[0u8; 9]);
-    packet.write_int(char_magic_spawn);
-    packet.write_short(0);
-    packet.write_short(0);
-}
-
-///
-fn spawn_npc(npc: &nx::map::Life) -> Packet {
-    let mut packet = Packet::new(0x101);
-    packet.write_int(npc.object_id);
-    packet.write_int(npc.id);
-    packet.write_short(npc.position.0);
-    packet.write_short(npc.cy);
-    packet.write_byte((npc.f != 1) as u8);
-    packet.write_short(npc.fh);
-    packet.write_short(npc.rx0);
-    packet.write_short(npc.rx1);
-    packet.write_byte(1);
-    packet
-}
-
-///
-fn spawn_npc_request_controller(npc: &nx::map::Life) -> Packet {
-    let mut packet = Packet::new(0x103);
-    packet.write_byte(1);
-    packet.write_int(npc.object_id);
-    packet.write_int(npc.id);
-    packet.write_short(npc.position.0);
-    packet.write_short(npc.cy);
-    packet.write_byte((npc.f != 1) as u8);
-    packet.write_short(npc.fh);
-    packet.write_short(npc.rx0);
-    packet.write_short(npc.rx1);
-    packet.write_byte(1);
-    packet
-}
-
-// TODO DoorObject.sendSpawnData
-fn spawn_portal(map_id: i32, portal: &nx::Portal) -> Packet {
-    let mut packet = Packet::new(0x43);
-    packet.write_int(map_id);
-    packet.write_int(portal.target_map_id);
-    packet.write_short(portal.x as i16);
-    packet.write_short(portal.y as i16);
-    packet
-}
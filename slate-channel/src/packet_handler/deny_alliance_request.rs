@@ -0,0 +1,39 @@
+use crate::{session::ChannelSession, world};
+use slate_data::{
+    packet::{self, NoticeType},
+    sql,
+};
+use slate_net::Packet;
+
+/// Channel server: deny alliance request packet (0x90)
+/// Called when a guild master declines an alliance invite
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    packet.skip(1); // mode
+    let inviter_name = packet.read_string();
+
+    if session.alliance_invite.take().is_none() {
+        return Ok(());
+    }
+
+    let inviter = match sql::OnlineCharacter::load_optional_by_name(
+        &inviter_name,
+        session.world_id,
+        &session.db,
+    )
+    .await?
+    {
+        Some(inviter) => inviter,
+        None => return Ok(()),
+    };
+
+    let guild_name = match &session.character.as_ref().unwrap().guild {
+        Some(guild) => &guild.name,
+        None => return Ok(()),
+    };
+
+    let text = format!("The {} guild declined your alliance invite.", guild_name);
+    let packet = packet::server_notice(NoticeType::Popup, &text);
+    world::send_packet(session, inviter.character_id, packet).await?;
+
+    Ok(())
+}
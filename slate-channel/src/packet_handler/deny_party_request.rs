@@ -0,0 +1,34 @@
+use crate::{session::ChannelSession, world};
+use slate_data::{
+    packet::{self, PartyMessage},
+    sql,
+};
+use slate_net::Packet;
+
+/// Channel server: deny party request packet (0x7D)
+/// Called when a character declines a party invite
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    packet.skip(1); // mode
+    let inviter_name = packet.read_string();
+
+    if session.party_invite.take().is_none() {
+        return Ok(());
+    }
+
+    let inviter = match sql::OnlineCharacter::load_optional_by_name(
+        &inviter_name,
+        session.world_id,
+        &session.db,
+    )
+    .await?
+    {
+        Some(inviter) => inviter,
+        None => return Ok(()),
+    };
+
+    let name = &session.character.as_ref().unwrap().data.name;
+    let packet = packet::party_message(PartyMessage::InviteDenied, Some(name));
+    world::send_packet(session, inviter.character_id, packet).await?;
+
+    Ok(())
+}
@@ -0,0 +1,8 @@
+use crate::{cash_shop, session::ChannelSession};
+use slate_net::Packet;
+
+/// Channel server: enter cash shop packet (0x28)
+/// Called when a character opens the cash shop
+pub async fn handle(_packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    cash_shop::enter(session).await
+}
@@ -0,0 +1,8 @@
+use crate::{mts, session::ChannelSession};
+use slate_net::Packet;
+
+/// Channel server: enter MTS packet (0x9C)
+/// Called when a character opens the MTS (Maple Trading System)
+pub async fn handle(_packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    mts::enter(session).await
+}
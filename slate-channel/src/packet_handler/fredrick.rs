@@ -0,0 +1,18 @@
+use crate::{player_shop, session::ChannelSession};
+use slate_net::Packet;
+
+/// Channel server: fredrick packet (0x40)
+/// Called when a character retrieves the items and mesos left with Fredrick, or closes his window
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let action = packet.read_byte();
+
+    match action {
+        // Retrieve items and mesos
+        0x1A => player_shop::retrieve_from_fredrick(session).await?,
+        // Close the window
+        0x1C => {}
+        _ => log::debug!("Unhandled fredrick action: {}", action),
+    }
+
+    Ok(())
+}
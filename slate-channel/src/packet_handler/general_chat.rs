@@ -0,0 +1,40 @@
+use crate::{command, session::ChannelSession};
+use slate_data::maple::map::{MapBroadcast, PacketBroadcast};
+use slate_net::Packet;
+
+/// Channel server: general chat packet (0x31)
+/// Called when a character sends a message in the map chat
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let message = packet.read_string();
+    let show = packet.read_byte();
+
+    if command::handle(&message, session).await? {
+        return Ok(());
+    }
+
+    let character = session.character.as_ref().unwrap();
+
+    let packet_broadcast = MapBroadcast::Packet(PacketBroadcast {
+        packet: chat_text(character.data.id, character.data.gm > 0, &message, show),
+        sender_id: character.data.id,
+        send_to_sender: true,
+    });
+    session
+        .map_broadcast_tx
+        .as_ref()
+        .unwrap()
+        .send(packet_broadcast)?;
+
+    Ok(())
+}
+
+/// Packet containing a character's chat message
+/// `show` is 1 if the message should only be shown in the chat box (not over the character's head)
+fn chat_text(character_id: i32, is_gm: bool, message: &str, show: u8) -> Packet {
+    let mut packet = Packet::new(0xA2);
+    packet.write_int(character_id);
+    packet.write_byte(is_gm as u8);
+    packet.write_string(message);
+    packet.write_byte(show);
+    packet
+}
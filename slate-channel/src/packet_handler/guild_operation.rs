@@ -0,0 +1,399 @@
+use crate::{
+    guild,
+    session::{ChannelSession, SessionMessage},
+    world,
+};
+use slate_data::{
+    packet::{self, GuildMessage, NoticeType, Stat},
+    sql::{self, alliance::LOWEST_ALLIANCE_RANK, guild::LOWEST_GUILD_RANK},
+};
+use slate_net::Packet;
+
+/// The fewest party members (including the leader) needed to create a guild
+const MIN_CREATE_PARTY_SIZE: usize = 6;
+
+/// Channel server: guild operation packet (0x7E)
+/// Called when a character creates, joins, leaves, or manages a guild
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let operation = packet.read_byte();
+
+    match operation {
+        0x02 => create(&packet.read_string(), session).await?,
+        0x05 => invite(&packet.read_string(), session).await?,
+        0x06 => {
+            let guild_id = packet.read_int();
+            let character_id = packet.read_int();
+
+            if character_id == session.character.as_ref().unwrap().data.id {
+                join(guild_id, session).await?;
+            }
+        }
+        0x07 => leave(session).await?,
+        0x08 => expel(packet.read_int(), session).await?,
+        0x0D => {
+            let titles: Vec<String> = (0..5).map(|_| packet.read_string()).collect();
+            change_rank_titles(titles, session).await?;
+        }
+        0x0E => {
+            let character_id = packet.read_int();
+            let rank = packet.read_byte() as i32;
+            change_rank(character_id, rank, session).await?;
+        }
+        0x0F => {
+            let logo_bg = packet.read_short() as i32;
+            let logo_bg_color = packet.read_byte() as i32;
+            let logo = packet.read_short() as i32;
+            let logo_color = packet.read_byte() as i32;
+            change_emblem((logo, logo_color, logo_bg, logo_bg_color), session).await?;
+        }
+        0x10 => change_notice(packet.read_string(), session).await?,
+        _ => log::error!("Invalid guild operation: {}", operation),
+    }
+
+    Ok(())
+}
+
+/// Creates a guild, the character's party members in the same map join it
+async fn create(name: &str, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let character = session.character.as_ref().unwrap();
+    let character_id = character.data.id;
+
+    if character.data.guild.is_some() {
+        return send_message(session, GuildMessage::AlreadyInGuild).await;
+    }
+
+    if !(3..=12).contains(&name.len()) || !name.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return send_message(session, GuildMessage::CreateFailed).await;
+    }
+
+    if character.data.mesos < guild::CREATE_COST {
+        return send_notice(session, "You don't have enough mesos to create a guild.").await;
+    }
+
+    let party = match character.data.party {
+        Some(party_id) => sql::Party::load_optional(party_id, &session.db).await?,
+        None => None,
+    };
+
+    let party = match party {
+        Some(party) if party.leader_id == character_id => party,
+        _ => {
+            return send_notice(
+                session,
+                "You must be the leader of a party to create a guild.",
+            )
+            .await
+        }
+    };
+
+    // Every member has to be in the same map, and not in a guild already
+    let map_characters = session.state.get_map_characters(character.field_id);
+    let members = sql::PartyMember::load_all(party.id, &session.db).await?;
+    let mut member_ids = Vec::new();
+
+    for member in members.iter().filter(|member| member.id != character_id) {
+        if !map_characters.contains_key(&member.id) {
+            let text = "Every party member must be in the same map to create a guild.";
+            return send_notice(session, text).await;
+        }
+
+        if sql::Character::load(member.id, &session.db)
+            .await?
+            .guild
+            .is_some()
+        {
+            let text = format!("{} is already in a guild.", member.name);
+            return send_notice(session, &text).await;
+        }
+
+        member_ids.push(member.id);
+    }
+
+    if member_ids.len() + 1 < MIN_CREATE_PARTY_SIZE {
+        let text = format!(
+            "You need a party of {} to create a guild.",
+            MIN_CREATE_PARTY_SIZE
+        );
+        return send_notice(session, &text).await;
+    }
+
+    if sql::Guild::name_exists(name, session.world_id, &session.db).await? {
+        return send_message(session, GuildMessage::NameInUse).await;
+    }
+
+    sql::Guild::create(
+        session.world_id,
+        name,
+        character_id,
+        &member_ids,
+        &session.db,
+    )
+    .await?;
+
+    let character = session.character.as_mut().unwrap();
+    character.data.mesos -= guild::CREATE_COST;
+    let mesos = character.data.mesos;
+    session
+        .stream
+        .write_packet(packet::update_stats(&[(Stat::Mesos, mesos)]))
+        .await?;
+
+    guild::reload(session).await?;
+    guild::notify_changed(session, &member_ids).await
+}
+
+/// Invites a character to the guild
+async fn invite(name: &str, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let character = session.character.as_ref().unwrap();
+
+    let guild = match (&character.guild, character.data.guild_rank) {
+        (Some(guild), Some(rank)) if rank <= 2 => guild.clone(),
+        _ => return Ok(()),
+    };
+
+    let online_character =
+        match sql::OnlineCharacter::load_optional_by_name(name, session.world_id, &session.db)
+            .await?
+        {
+            Some(online_character) => online_character,
+            None => return send_message(session, GuildMessage::NotInChannel).await,
+        };
+
+    let invited = sql::Character::load(online_character.character_id, &session.db).await?;
+
+    if invited.guild.is_some() {
+        return send_message(session, GuildMessage::AlreadyInGuild).await;
+    }
+
+    let members = sql::GuildMember::load_all(guild.id, &session.db).await?;
+
+    if members.len() as i32 >= guild.capacity {
+        return send_notice(session, "Your guild is already full.").await;
+    }
+
+    let packet = packet::guild_invite(guild.id, &character.data.name);
+    let message = SessionMessage::GuildInvite(guild.id, packet);
+    world::send(session, invited.id, message).await?;
+    Ok(())
+}
+
+/// Joins a guild the character was invited to
+async fn join(guild_id: i32, session: &mut ChannelSession) -> anyhow::Result<()> {
+    if session.guild_invite.take() != Some(guild_id) {
+        log::warn!("Tried to join guild {} without an invite", guild_id);
+        return Ok(());
+    }
+
+    if session.character.as_ref().unwrap().data.guild.is_some() {
+        return send_message(session, GuildMessage::AlreadyInGuild).await;
+    }
+
+    let guild = match sql::Guild::load_optional(guild_id, &session.db).await? {
+        Some(guild) => guild,
+        None => return send_message(session, GuildMessage::NotInGuild).await,
+    };
+
+    let members = sql::GuildMember::load_all(guild.id, &session.db).await?;
+
+    if members.len() as i32 >= guild.capacity {
+        return send_notice(session, "The guild is already full.").await;
+    }
+
+    let character = session.character.as_ref().unwrap();
+    guild.add_member(character.data.id, &session.db).await?;
+
+    let member = sql::GuildMember {
+        id: character.data.id,
+        name: character.data.name.clone(),
+        job: character.data.job,
+        level: character.data.level,
+        guild_rank: LOWEST_GUILD_RANK,
+        alliance_rank: LOWEST_ALLIANCE_RANK,
+        channel_id: Some(session.channel_id),
+    };
+    let packet = packet::guild_member_joined(guild.id, &member);
+    guild::send_to_members(session, guild.id, packet).await?;
+
+    guild::reload(session).await
+}
+
+/// Leaves the character's guild, the guild master has to disband the guild instead
+async fn leave(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let character = session.character.as_ref().unwrap();
+    let (character_id, name) = (character.data.id, character.data.name.clone());
+
+    let guild = match (&character.guild, character.data.guild_rank) {
+        (Some(guild), Some(rank)) if rank > 1 => guild.clone(),
+        _ => return Ok(()),
+    };
+
+    let packet = packet::guild_member_left(guild.id, character_id, &name, false);
+    guild::send_to_members(session, guild.id, packet).await?;
+    guild.remove_member(character_id, &session.db).await?;
+
+    guild::reload(session).await
+}
+
+/// Expels a lower ranked member from the guild
+async fn expel(target_id: i32, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let character = session.character.as_ref().unwrap();
+
+    let (guild, rank) = match (&character.guild, character.data.guild_rank) {
+        (Some(guild), Some(rank)) if rank <= 2 => (guild.clone(), rank),
+        _ => return Ok(()),
+    };
+
+    let members = sql::GuildMember::load_all(guild.id, &session.db).await?;
+
+    let target = match members.iter().find(|member| member.id == target_id) {
+        Some(target) if target.guild_rank > rank => target,
+        _ => return Ok(()),
+    };
+
+    let packet = packet::guild_member_left(guild.id, target.id, &target.name, true);
+    guild::send_to_members(session, guild.id, packet).await?;
+    guild.remove_member(target.id, &session.db).await?;
+
+    guild::notify_changed(session, &[target.id]).await
+}
+
+/// Renames the guild's ranks
+async fn change_rank_titles(
+    titles: Vec<String>,
+    session: &mut ChannelSession,
+) -> anyhow::Result<()> {
+    let character = session.character.as_ref().unwrap();
+
+    let mut guild = match (&character.guild, character.data.guild_rank) {
+        (Some(guild), Some(1)) => guild.clone(),
+        _ => return Ok(()),
+    };
+
+    // The master and jr. master titles are required, the rest can be left empty
+    for (i, title) in titles.iter().enumerate() {
+        if title.len() > 12 || (i < 2 && title.is_empty()) {
+            return Ok(());
+        }
+    }
+
+    let [rank1, rank2, rank3, rank4, rank5]: [String; 5] = titles.try_into().unwrap();
+    guild.rank1_title = rank1;
+    guild.rank2_title = rank2;
+    guild.rank3_title = rank3;
+    guild.rank4_title = rank4;
+    guild.rank5_title = rank5;
+    guild.update_rank_titles(&session.db).await?;
+
+    let packet = packet::update_guild_rank_titles(&guild);
+    guild::send_to_members(session, guild.id, packet).await?;
+
+    let member_ids = sql::Character::load_guild_member_ids(guild.id, &session.db).await?;
+    guild::notify_changed(session, &member_ids).await
+}
+
+/// Promotes or demotes a lower ranked member
+async fn change_rank(
+    target_id: i32,
+    new_rank: i32,
+    session: &mut ChannelSession,
+) -> anyhow::Result<()> {
+    let character = session.character.as_ref().unwrap();
+
+    let (guild, rank) = match (&character.guild, character.data.guild_rank) {
+        (Some(guild), Some(rank)) if rank <= 2 => (guild.clone(), rank),
+        _ => return Ok(()),
+    };
+
+    // Members can only be given ranks below the character's own rank
+    if new_rank <= rank || new_rank > LOWEST_GUILD_RANK {
+        return Ok(());
+    }
+
+    let members = sql::GuildMember::load_all(guild.id, &session.db).await?;
+
+    match members.iter().find(|member| member.id == target_id) {
+        Some(target) if target.guild_rank > rank => {}
+        _ => return Ok(()),
+    }
+
+    guild
+        .update_member_rank(target_id, new_rank, &session.db)
+        .await?;
+
+    let packet = packet::update_guild_member_rank(guild.id, target_id, new_rank);
+    guild::send_to_members(session, guild.id, packet).await?;
+    guild::notify_changed(session, &[target_id]).await
+}
+
+/// Changes the guild's emblem
+async fn change_emblem(
+    (logo, logo_color, logo_bg, logo_bg_color): (i32, i32, i32, i32),
+    session: &mut ChannelSession,
+) -> anyhow::Result<()> {
+    let character = session.character.as_ref().unwrap();
+
+    let mut guild = match (&character.guild, character.data.guild_rank) {
+        (Some(guild), Some(1)) => guild.clone(),
+        _ => return Ok(()),
+    };
+
+    if character.data.mesos < guild::EMBLEM_COST {
+        return send_notice(session, "You don't have enough mesos to change the emblem.").await;
+    }
+
+    let character = session.character.as_mut().unwrap();
+    character.data.mesos -= guild::EMBLEM_COST;
+    let mesos = character.data.mesos;
+    session
+        .stream
+        .write_packet(packet::update_stats(&[(Stat::Mesos, mesos)]))
+        .await?;
+
+    guild.logo = logo;
+    guild.logo_color = logo_color;
+    guild.logo_bg = logo_bg;
+    guild.logo_bg_color = logo_bg_color;
+    guild.update_emblem(&session.db).await?;
+
+    let packet = packet::update_guild_emblem(&guild);
+    guild::send_to_members(session, guild.id, packet).await?;
+
+    // Members reload the guild to show the new emblem to their maps
+    let member_ids = sql::Character::load_guild_member_ids(guild.id, &session.db).await?;
+    guild::notify_changed(session, &member_ids).await
+}
+
+/// Changes the guild's notice
+async fn change_notice(notice: String, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let character = session.character.as_ref().unwrap();
+
+    let mut guild = match (&character.guild, character.data.guild_rank) {
+        (Some(guild), Some(rank)) if rank <= 2 => guild.clone(),
+        _ => return Ok(()),
+    };
+
+    if notice.len() > 100 {
+        return Ok(());
+    }
+
+    guild.notice = notice;
+    guild.update_notice(&session.db).await?;
+    session.character.as_mut().unwrap().guild = Some(guild.clone());
+
+    let packet = packet::update_guild_notice(guild.id, &guild.notice);
+    guild::send_to_members(session, guild.id, packet).await
+}
+
+async fn send_message(session: &mut ChannelSession, message: GuildMessage) -> anyhow::Result<()> {
+    session
+        .stream
+        .write_packet(packet::guild_message(message))
+        .await
+}
+
+async fn send_notice(session: &mut ChannelSession, message: &str) -> anyhow::Result<()> {
+    session
+        .stream
+        .write_packet(packet::server_notice(NoticeType::Popup, message))
+        .await
+}
@@ -0,0 +1,8 @@
+use crate::{player_shop, session::ChannelSession};
+use slate_net::Packet;
+
+/// Channel server: hired merchant request packet (0x3F)
+/// Called when a character uses a hired merchant permit
+pub async fn handle(_packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    player_shop::request_hired_merchant(session).await
+}
@@ -0,0 +1,16 @@
+use crate::{reactor, session::ChannelSession};
+use slate_net::Packet;
+
+/// Channel server: hit reactor packet (0xCD)
+/// Called when a character hits a reactor
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let object_id = packet.read_int();
+    let character_pos = packet.read_int();
+    let stance = packet.read_short();
+    packet.skip(4);
+    let skill_id = packet.read_int();
+
+    // 0 and 2 are hits from the left of the reactor, in the air and on the ground
+    let from_left = character_pos == 0 || character_pos == 2;
+    reactor::hit(session, object_id, from_left, stance, skill_id).await
+}
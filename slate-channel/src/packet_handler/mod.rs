@@ -1,9 +1,51 @@
 use crate::session::ChannelSession;
+use attack::AttackType;
 use slate_net::Packet;
 
-mod connect;
-mod move_character;
+mod alliance_operation;
+mod attack;
+mod buddy_list_modify;
+mod cancel_buff;
+mod cancel_chair;
+mod cash_shop_balance;
+mod cash_shop_operation;
+mod change_map;
+mod character_info;
+pub mod connect;
+mod deny_alliance_request;
+mod deny_guild_request;
+mod deny_party_request;
+mod enter_cash_shop;
+mod enter_mts;
+mod fredrick;
+mod general_chat;
+mod guild_operation;
+mod hired_merchant_request;
+mod hit_reactor;
+mod mount_food;
+pub mod move_character;
+mod move_pet;
+mod mts_operation;
+mod multi_chat;
+mod npc_shop;
+mod npc_talk;
+mod npc_talk_more;
+mod party_operation;
+mod pet_auto_potion;
+mod pet_chat;
+mod pet_command;
+mod pet_food;
+mod pet_loot;
+mod pick_up_item;
+mod player_interaction;
 mod quest_action;
+mod spawn_pet;
+mod special_move;
+mod storage;
+mod take_damage;
+mod touch_reactor;
+mod use_chair;
+mod whisper;
 
 /// Gets a packet handler for the given op code
 pub async fn handle_packet(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
@@ -11,8 +53,51 @@ pub async fn handle_packet(mut packet: Packet, session: &mut ChannelSession) ->
 
     match op_code {
         0x14 => connect::handle(packet, session).await?,
+        0x26 => change_map::handle(packet, session).await?,
+        0x28 => enter_cash_shop::handle(packet, session).await?,
         0x29 => move_character::handle(packet, session).await?,
+        0x2A => cancel_chair::handle(packet, session).await?,
+        0x2B => use_chair::handle(packet, session).await?,
+        0x2C => attack::handle(packet, session, AttackType::CloseRange).await?,
+        0x2D => attack::handle(packet, session, AttackType::Ranged).await?,
+        0x2E => attack::handle(packet, session, AttackType::Magic).await?,
+        0x30 => take_damage::handle(packet, session).await?,
+        0x31 => general_chat::handle(packet, session).await?,
+        0x3A => npc_talk::handle(packet, session).await?,
+        0x3C => npc_talk_more::handle(packet, session).await?,
+        0x3D => npc_shop::handle(packet, session).await?,
+        0x3E => storage::handle(packet, session).await?,
+        0x3F => hired_merchant_request::handle(packet, session).await?,
+        0x40 => fredrick::handle(packet, session).await?,
+        0x4C => pet_food::handle(packet, session).await?,
+        0x4D => mount_food::handle(packet, session).await?,
+        0x5A => spawn_pet::handle(packet, session).await?,
+        0x5B => special_move::handle(packet, session).await?,
+        0x5C => cancel_buff::handle(packet, session).await?,
+        0x61 => character_info::handle(packet, session).await?,
         0x6B => quest_action::handle(packet, session).await?,
+        0x77 => multi_chat::handle(packet, session).await?,
+        0x78 => whisper::handle(packet, session).await?,
+        0x7B => player_interaction::handle(packet, session).await?,
+        0x7C => party_operation::handle(packet, session).await?,
+        0x7D => deny_party_request::handle(packet, session).await?,
+        0x7E => guild_operation::handle(packet, session).await?,
+        0x7F => deny_guild_request::handle(packet, session).await?,
+        0x82 => buddy_list_modify::handle(packet, session).await?,
+        0x8F => alliance_operation::handle(packet, session).await?,
+        0x90 => deny_alliance_request::handle(packet, session).await?,
+        0x9C => enter_mts::handle(packet, session).await?,
+        0xA7 => move_pet::handle(packet, session).await?,
+        0xA8 => pet_chat::handle(packet, session).await?,
+        0xA9 => pet_command::handle(packet, session).await?,
+        0xAA => pet_loot::handle(packet, session).await?,
+        0xAB => pet_auto_potion::handle(packet, session).await?,
+        0xCA => pick_up_item::handle(packet, session).await?,
+        0xCD => hit_reactor::handle(packet, session).await?,
+        0xCE => touch_reactor::handle(packet, session).await?,
+        0xE4 => cash_shop_balance::handle(packet, session).await?,
+        0xE5 => cash_shop_operation::handle(packet, session).await?,
+        0xFD => mts_operation::handle(packet, session).await?,
         _ => log::info!("Unhandled packet: [{:02X?}]", op_code),
     };
 
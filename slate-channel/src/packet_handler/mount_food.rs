@@ -0,0 +1,11 @@
+use crate::{mount, session::ChannelSession};
+use slate_net::Packet;
+
+/// Channel server: mount food packet (0x4D)
+/// Called when a character feeds their mount
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    packet.skip(4);
+    let position = packet.read_short() as i32 - 1;
+    let item_id = packet.read_int();
+    mount::feed(session, position, item_id).await
+}
@@ -7,6 +7,39 @@ use slate_net::Packet;
 pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
     packet.skip(9);
     let packet_copy = packet.clone();
+    let (new_pos, new_stance) = parse_movement(&mut packet);
+
+    // Character hasn't moved -- do nothing
+    if new_pos.is_none() && new_stance.is_none() {
+        return Ok(());
+    }
+
+    let character = session.character.as_mut().unwrap();
+    character.pos = new_pos.unwrap_or(character.pos);
+    character.stance = new_stance.unwrap_or(character.stance);
+
+    let packet_broadcast = MapBroadcast::Packet(PacketBroadcast {
+        packet: move_player(character.data.id, packet_copy),
+        sender_id: character.data.id,
+        send_to_sender: false,
+    });
+    session
+        .map_broadcast_tx
+        .as_ref()
+        .unwrap()
+        .send(packet_broadcast)?;
+
+    // Party quests check where characters are standing
+    if new_pos.is_some() {
+        session.update_map_character();
+    }
+
+    Ok(())
+}
+
+/// Reads a movement path, returning the final position and stance if they were changed
+/// Shared with other movable things (ex. pets) which use the same movement format
+pub fn parse_movement(packet: &mut Packet) -> (Option<(i32, i32)>, Option<u8>) {
     let num_commands = packet.read_byte();
 
     let mut new_pos: Option<(i32, i32)> = None;
@@ -61,27 +94,7 @@ pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow:
         }
     }
 
-    // Character hasn't moved -- do nothing
-    if new_pos.is_none() && new_stance.is_none() {
-        return Ok(());
-    }
-
-    let character = session.character.as_mut().unwrap();
-    character.pos = new_pos.unwrap_or(character.pos);
-    character.stance = new_stance.unwrap_or(character.stance);
-
-    let packet_broadcast = MapBroadcast::Packet(PacketBroadcast {
-        packet: move_player(character.data.id, packet_copy),
-        sender_id: character.data.id,
-        send_to_sender: false,
-    });
-    session
-        .map_broadcast_tx
-        .as_ref()
-        .unwrap()
-        .send(packet_broadcast)?;
-
-    Ok(())
+    (new_pos, new_stance)
 }
 
 ///
@@ -0,0 +1,13 @@
+use crate::{packet_handler::move_character, pet, session::ChannelSession};
+use slate_net::Packet;
+
+/// Channel server: move pet packet (0xA7)
+/// Called when one of a character's pets is moved
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let pet_id = packet.read_long();
+    packet.skip(4);
+    let packet_copy = packet.clone();
+    let (new_pos, new_stance) = move_character::parse_movement(&mut packet);
+
+    pet::move_pet(session, pet_id, new_pos, new_stance, &packet_copy).await
+}
@@ -0,0 +1,96 @@
+use crate::{
+    mts::{self, MtsSearch},
+    session::ChannelSession,
+};
+use slate_net::Packet;
+
+/// Channel server: MTS operation packet (0xFD)
+/// Called when a character lists, buys or cancels an item, browses listings, or moves items out of their transfer inventory
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let action = packet.read_byte();
+
+    match action {
+        // List an item
+        0x02 => {
+            // Equipment is sent with its stats instead of an amount and flag
+            let is_equip = packet.read_byte() == 1;
+
+            // The client sends the whole item, only its id and position are needed
+            let item_id = packet.read_int();
+            packet.skip(9);
+
+            let amount = if is_equip {
+                packet.skip(32);
+                1
+            } else {
+                packet.read_short() as i32
+            };
+
+            packet.read_string(); // owner
+            packet.skip(if is_equip { 32 } else { 2 });
+
+            // Throwing stars and bullets are always listed as a whole set
+            let rechargeable = matches!(item_id / 10000, 207 | 233);
+
+            if rechargeable {
+                packet.skip(8);
+            }
+
+            let position = packet.read_int() - 1;
+
+            let quantity = if rechargeable {
+                packet.skip(4);
+                amount
+            } else {
+                packet.read_int()
+            };
+
+            let price = packet.read_int();
+            mts::list_item(session, item_id, position, quantity, price).await?
+        }
+        // Change the page, tab or category
+        0x05 => {
+            let tab = packet.read_int();
+            let category = packet.read_int();
+            let page = packet.read_int();
+            mts::show_page(session, tab, category, page).await?
+        }
+        // Search listings
+        0x06 => {
+            let tab = packet.read_int();
+            let category = packet.read_int();
+            packet.skip(4);
+            let by_seller = packet.read_int() != 0;
+            let text = packet.read_string();
+            mts::search(session, tab, category, MtsSearch { by_seller, text }).await?
+        }
+        // Cancel a listing
+        0x07 => {
+            let listing_id = packet.read_int();
+            mts::cancel_listing(session, listing_id).await?
+        }
+        // Move an item out of the transfer inventory
+        0x08 => {
+            let listing_id = packet.read_int();
+            mts::transfer_item(session, listing_id).await?
+        }
+        // Add a listing to the wanted list
+        0x09 => {
+            let listing_id = packet.read_int();
+            mts::update_wanted(session, listing_id, true).await?
+        }
+        // Remove a listing from the wanted list
+        0x0A => {
+            let listing_id = packet.read_int();
+            mts::update_wanted(session, listing_id, false).await?
+        }
+        // Buy an item, either from the listings or from the wanted list
+        0x10 | 0x11 => {
+            let listing_id = packet.read_int();
+            mts::buy(session, listing_id).await?
+        }
+        _ => log::debug!("Unhandled MTS action: {}", action),
+    }
+
+    Ok(())
+}
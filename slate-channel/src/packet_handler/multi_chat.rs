@@ -0,0 +1,70 @@
+use crate::{session::ChannelSession, world};
+use slate_data::sql;
+use slate_net::Packet;
+
+/// Channel server: multi chat packet (0x77)
+/// Called when a character sends a message to their buddies, party, guild, or alliance
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let chat_type = packet.read_byte();
+    let num_recipients = packet.read_byte();
+    let mut recipient_ids = Vec::new();
+
+    for _ in 0..num_recipients {
+        recipient_ids.push(packet.read_int());
+    }
+
+    let message = packet.read_string();
+    let character = session.character.as_ref().unwrap();
+
+    let member_ids = match chat_type {
+        // Buddy chat -- the client sends the ids of the buddies to send to
+        0 => {
+            let buddy_ids = sql::Buddy::load_mutual_ids(character.data.id, &session.db).await?;
+            recipient_ids
+                .into_iter()
+                .filter(|id| buddy_ids.contains(id))
+                .collect()
+        }
+        // Party chat
+        1 => match character.data.party {
+            Some(party_id) => sql::Character::load_party_member_ids(party_id, &session.db).await?,
+            None => return Ok(()),
+        },
+        // Guild chat
+        2 => match character.data.guild {
+            Some(guild_id) => sql::Character::load_guild_member_ids(guild_id, &session.db).await?,
+            None => return Ok(()),
+        },
+        // Alliance chat
+        3 => match character.guild.as_ref().and_then(|guild| guild.alliance_id) {
+            Some(alliance_id) => {
+                sql::Character::load_alliance_member_ids(alliance_id, &session.db).await?
+            }
+            None => return Ok(()),
+        },
+        _ => {
+            log::error!("Invalid multi chat type: {}", chat_type);
+            return Ok(());
+        }
+    };
+
+    // Don't send the message back to the sender
+    let member_ids: Vec<i32> = member_ids
+        .into_iter()
+        .filter(|id| *id != character.data.id)
+        .collect();
+
+    // Offline members are skipped, they won't receive the message when they log in
+    let packet = multi_chat(chat_type, &character.data.name, &message);
+    world::send_packet_to_all(session, &member_ids, packet).await?;
+    Ok(())
+}
+
+/// Packet containing a buddy, party, guild, or alliance chat message
+fn multi_chat(chat_type: u8, sender_name: &str, message: &str) -> Packet {
+    let mut packet = Packet::new(0x86);
+    packet.write_byte(chat_type);
+    packet.write_string(sender_name);
+    packet.write_string(message);
+    packet
+}
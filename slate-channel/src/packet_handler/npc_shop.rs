@@ -0,0 +1,37 @@
+use crate::{session::ChannelSession, shop};
+use slate_net::Packet;
+
+/// Channel server: npc shop packet (0x3D)
+/// Called when a character buys, sells, or recharges an item at an npc's shop, or leaves the shop
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let action = packet.read_byte();
+
+    match action {
+        // Buy an item
+        0x00 => {
+            let slot = packet.read_short() as usize;
+            let item_id = packet.read_int();
+            let quantity = packet.read_short() as i32;
+            shop::buy(session, slot, item_id, quantity).await?
+        }
+        // Sell an item
+        0x01 => {
+            let position = packet.read_short() as i32;
+            let item_id = packet.read_int();
+            let quantity = packet.read_short() as i32;
+
+            // Positions are 1-indexed by the client
+            shop::sell(session, position - 1, item_id, quantity).await?
+        }
+        // Recharge throwing stars or bullets
+        0x02 => {
+            let position = packet.read_short() as i32;
+            shop::recharge(session, position - 1).await?
+        }
+        // Leave the shop
+        0x03 => session.shop = None,
+        _ => log::debug!("Unhandled npc shop action: {}", action),
+    }
+
+    Ok(())
+}
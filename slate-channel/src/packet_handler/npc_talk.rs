@@ -0,0 +1,24 @@
+use crate::{npc, session::ChannelSession};
+use slate_data::maple;
+use slate_net::Packet;
+
+/// Channel server: npc talk packet (0x3A)
+/// Called when a character clicks on an npc
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let object_id = packet.read_int();
+    let character = session.character.as_ref().unwrap();
+
+    if !character.is_alive() {
+        return Ok(());
+    }
+
+    let map = maple::Map::load(character.data.map)?;
+
+    match map.data.npcs.get(&object_id) {
+        Some(npc) => npc::talk(session, npc.id).await,
+        None => {
+            log::warn!("Npc {} isn't in map {}", object_id, map.id);
+            Ok(())
+        }
+    }
+}
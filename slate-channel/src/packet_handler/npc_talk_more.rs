@@ -0,0 +1,29 @@
+use crate::{npc, session::ChannelSession};
+use slate_net::Packet;
+
+/// Channel server: npc talk more packet (0x3C)
+/// Called when a character responds to an npc's dialog
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let dialog_type = packet.read_byte();
+    let action = packet.read_byte() as i8;
+
+    // Closing the dialog (or answering no) ends the conversation
+    if action <= 0 {
+        return npc::respond(session, None).await;
+    }
+
+    // Text dialogs respond with the text the character entered
+    if dialog_type == 2 {
+        return npc::respond_text(session, &packet.read_string()).await;
+    }
+
+    let selection = if packet.remaining() >= 4 {
+        packet.read_int()
+    } else if packet.remaining() > 0 {
+        packet.read_byte() as i32
+    } else {
+        action as i32
+    };
+
+    npc::respond(session, Some(selection)).await
+}
@@ -0,0 +1,239 @@
+use crate::{
+    event, party,
+    session::{ChannelSession, SessionMessage},
+    world,
+};
+use slate_data::{
+    packet::{self, PartyMessage, PartyOperation},
+    sql::{self, party::MAX_PARTY_SIZE},
+};
+use slate_net::Packet;
+
+/// Channel server: party operation packet (0x7C)
+/// Called when a character creates, leaves, joins, or manages a party
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let operation = packet.read_byte();
+
+    match operation {
+        1 => create(session).await,
+        2 => leave(session).await,
+        3 => {
+            let party_id = packet.read_int();
+            join(party_id, session).await
+        }
+        4 => {
+            let name = packet.read_string();
+            invite(&name, session).await
+        }
+        5 => {
+            let character_id = packet.read_int();
+            expel(character_id, session).await
+        }
+        6 => {
+            let character_id = packet.read_int();
+            change_leader(character_id, session).await
+        }
+        _ => {
+            log::error!("Invalid party operation: {}", operation);
+            Ok(())
+        }
+    }
+}
+
+/// Creates a new party led by the character
+async fn create(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let character = session.character.as_ref().unwrap();
+
+    if character.data.party.is_some() {
+        return send_message(session, PartyMessage::AlreadyInParty).await;
+    }
+
+    if character.is_beginner() && character.data.level < 10 {
+        return send_message(session, PartyMessage::BeginnerCantCreate).await;
+    }
+
+    let party = sql::Party::create(session.world_id, character.data.id, &session.db).await?;
+    session.character.as_mut().unwrap().data.party = Some(party.id);
+    session.update_map_character();
+
+    session
+        .stream
+        .write_packet(packet::party_created(party.id))
+        .await
+}
+
+/// Leaves the character's party, or disbands it if they're the leader
+async fn leave(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let party = match load_party(session).await? {
+        Some(party) => party,
+        None => return send_message(session, PartyMessage::NotInParty).await,
+    };
+
+    let character = session.character.as_ref().unwrap();
+    let (character_id, name) = (character.data.id, character.data.name.clone());
+
+    if party.leader_id == character_id {
+        party::disband(session, &party).await?;
+    } else {
+        party.remove_member(character_id, &session.db).await?;
+
+        let operation = PartyOperation::Leave {
+            character_id,
+            name: &name,
+            expelled: false,
+        };
+        party::send_update(session, &party, operation, Some(character_id)).await?;
+    }
+
+    session.character.as_mut().unwrap().data.party = None;
+    session.update_map_character();
+    event::on_party_changed(session).await
+}
+
+/// Joins a party the character was invited to
+async fn join(party_id: i32, session: &mut ChannelSession) -> anyhow::Result<()> {
+    if session.party_invite.take() != Some(party_id) {
+        log::warn!("Tried to join party {} without an invite", party_id);
+        return Ok(());
+    }
+
+    if session.character.as_ref().unwrap().data.party.is_some() {
+        return send_message(session, PartyMessage::AlreadyInParty).await;
+    }
+
+    let party = match sql::Party::load_optional(party_id, &session.db).await? {
+        Some(party) => party,
+        None => return send_message(session, PartyMessage::NotInParty).await,
+    };
+
+    let members = sql::PartyMember::load_all(party.id, &session.db).await?;
+
+    if members.len() >= MAX_PARTY_SIZE {
+        return send_message(session, PartyMessage::PartyFull).await;
+    }
+
+    let character = session.character.as_mut().unwrap();
+    party.add_member(character.data.id, &session.db).await?;
+    character.data.party = Some(party.id);
+
+    let name = character.data.name.clone();
+    party::send_update(session, &party, PartyOperation::Join(&name), None).await?;
+    session.update_map_character();
+    session.update_party_hp()
+}
+
+/// Invites a character to the party, creating one if the character isn't in a party yet
+async fn invite(name: &str, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let party = match load_party(session).await? {
+        Some(party) => party,
+        None => {
+            create(session).await?;
+
+            match load_party(session).await? {
+                Some(party) => party,
+                None => return Ok(()),
+            }
+        }
+    };
+
+    let online_character =
+        match sql::OnlineCharacter::load_optional_by_name(name, session.world_id, &session.db)
+            .await?
+        {
+            Some(online_character) => online_character,
+            None => return send_message(session, PartyMessage::CharacterNotFound).await,
+        };
+
+    let invited = sql::Character::load(online_character.character_id, &session.db).await?;
+
+    if invited.party.is_some() {
+        return send_message(session, PartyMessage::AlreadyInParty).await;
+    }
+
+    let members = sql::PartyMember::load_all(party.id, &session.db).await?;
+
+    if members.len() >= MAX_PARTY_SIZE {
+        return send_message(session, PartyMessage::PartyFull).await;
+    }
+
+    let inviter_name = &session.character.as_ref().unwrap().data.name;
+    let message =
+        SessionMessage::PartyInvite(party.id, packet::party_invite(party.id, inviter_name));
+
+    if !world::send(session, invited.id, message).await? {
+        return send_message(session, PartyMessage::CharacterNotFound).await;
+    }
+
+    Ok(())
+}
+
+/// Expels a member from the character's party, only the leader can expel members
+async fn expel(character_id: i32, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let party = match load_party(session).await? {
+        Some(party) if party.leader_id == session.character.as_ref().unwrap().data.id => party,
+        _ => return Ok(()),
+    };
+
+    let members = sql::PartyMember::load_all(party.id, &session.db).await?;
+
+    let member = match members.iter().find(|member| member.id == character_id) {
+        Some(member) if member.id != party.leader_id => member,
+        _ => return Ok(()),
+    };
+
+    party.remove_member(member.id, &session.db).await?;
+
+    let operation = PartyOperation::Leave {
+        character_id: member.id,
+        name: &member.name,
+        expelled: true,
+    };
+    party::send_update(session, &party, operation, Some(member.id)).await?;
+    world::send(session, member.id, SessionMessage::PartyChanged(None)).await?;
+
+    Ok(())
+}
+
+/// Gives leadership of the party to another member, only the leader can change the leader
+async fn change_leader(character_id: i32, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let mut party = match load_party(session).await? {
+        Some(party) if party.leader_id == session.character.as_ref().unwrap().data.id => party,
+        _ => return Ok(()),
+    };
+
+    let members = sql::PartyMember::load_all(party.id, &session.db).await?;
+
+    // The new leader has to be online
+    if !members
+        .iter()
+        .any(|member| member.id == character_id && member.channel_id.is_some())
+    {
+        return Ok(());
+    }
+
+    party.leader_id = character_id;
+    party.update_leader(&session.db).await?;
+
+    party::send_update(
+        session,
+        &party,
+        PartyOperation::ChangeLeader(character_id),
+        None,
+    )
+    .await
+}
+
+/// Loads the character's party if they're in one
+async fn load_party(session: &ChannelSession) -> anyhow::Result<Option<sql::Party>> {
+    match session.character.as_ref().unwrap().data.party {
+        Some(party_id) => sql::Party::load_optional(party_id, &session.db).await,
+        None => Ok(None),
+    }
+}
+
+async fn send_message(session: &mut ChannelSession, message: PartyMessage) -> anyhow::Result<()> {
+    session
+        .stream
+        .write_packet(packet::party_message(message, None))
+        .await
+}
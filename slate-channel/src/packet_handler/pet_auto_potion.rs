@@ -0,0 +1,11 @@
+use crate::{pet, session::ChannelSession};
+use slate_net::Packet;
+
+/// Channel server: pet auto potion packet (0xAB)
+/// Called when a character's pet uses a potion for them because their hp or mp is low
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    packet.skip(13);
+    let position = packet.read_short() as i32 - 1;
+    let item_id = packet.read_int();
+    pet::use_potion(session, position, item_id).await
+}
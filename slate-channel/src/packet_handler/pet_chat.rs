@@ -0,0 +1,12 @@
+use crate::{pet, session::ChannelSession};
+use slate_net::Packet;
+
+/// Channel server: pet chat packet (0xA8)
+/// Called when one of a character's pets talks
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let pet_id = packet.read_long();
+    packet.skip(1);
+    let action = packet.read_byte();
+    let text = packet.read_string();
+    pet::chat(session, pet_id, action, &text).await
+}
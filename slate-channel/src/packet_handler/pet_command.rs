@@ -0,0 +1,11 @@
+use crate::{pet, session::ChannelSession};
+use slate_net::Packet;
+
+/// Channel server: pet command packet (0xA9)
+/// Called when a character gives one of their pets a command
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let pet_id = packet.read_long();
+    packet.skip(1);
+    let command = packet.read_byte();
+    pet::command(session, pet_id, command).await
+}
@@ -0,0 +1,11 @@
+use crate::{pet, session::ChannelSession};
+use slate_net::Packet;
+
+/// Channel server: pet food packet (0x4C)
+/// Called when a character feeds their pets
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    packet.skip(4);
+    let position = packet.read_short() as i32 - 1;
+    let item_id = packet.read_int();
+    pet::feed(session, position, item_id).await
+}
@@ -0,0 +1,11 @@
+use crate::{pet, session::ChannelSession};
+use slate_net::Packet;
+
+/// Channel server: pet loot packet (0xAA)
+/// Called when one of a character's summoned pets picks up an item dropped in the map
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let pet_id = packet.read_long();
+    packet.skip(9);
+    let object_id = packet.read_int();
+    pet::pick_up(session, pet_id, object_id).await
+}
@@ -0,0 +1,10 @@
+use crate::{drop, session::ChannelSession};
+use slate_net::Packet;
+
+/// Channel server: pick up item packet (0xCA)
+/// Called when a character picks up an item dropped in the map
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    packet.skip(9);
+    let object_id = packet.read_int();
+    drop::pick_up(session, object_id, None).await
+}
@@ -0,0 +1,194 @@
+use crate::{minigame, player_shop, session::ChannelSession, trade};
+use slate_data::sql::{item::InventoryType, MinigameType};
+use slate_net::Packet;
+
+/// Channel server: player interaction packet (0x7B)
+/// Called when a character uses a trade, player shop, or minigame room
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let action = packet.read_byte();
+
+    match action {
+        // Create a room
+        0x00 => {
+            let room_type = packet.read_byte();
+
+            match room_type {
+                1 | 2 => {
+                    let title = packet.read_string();
+                    let password = match packet.read_byte() {
+                        0 => None,
+                        _ => Some(packet.read_string()),
+                    };
+                    let piece = packet.read_byte();
+
+                    let game_type = if room_type == 1 {
+                        MinigameType::Omok
+                    } else {
+                        MinigameType::MatchCards
+                    };
+
+                    minigame::create(session, game_type, title, password, piece).await?
+                }
+                3 => trade::open(session).await?,
+                4 | 5 => {
+                    let title = packet.read_string();
+                    packet.skip(3);
+                    let permit_id = packet.read_int();
+                    player_shop::create(session, room_type == 5, title, permit_id).await?
+                }
+                _ => log::debug!("Unsupported room type: {}", room_type),
+            }
+        }
+        // Invite a character
+        0x02 => trade::invite(session, packet.read_int()).await?,
+        // Decline an invite
+        0x03 => trade::decline(session).await?,
+        // Visit a room, the character joins a trade they were invited to or visits a room in their map
+        0x04 => {
+            let room_id = packet.read_int();
+            let password = match packet.remaining() > 0 && packet.read_byte() != 0 {
+                true => Some(packet.read_string()),
+                false => None,
+            };
+
+            if session.trade_invite == Some(room_id) {
+                trade::join(session, room_id).await?
+            } else if !minigame::visit(session, room_id, password).await? {
+                player_shop::visit(session, room_id).await?
+            }
+        }
+        // Chat
+        0x06 => {
+            let message = packet.read_string();
+
+            if session.trade.is_some() {
+                trade::chat(session, &message).await?
+            } else if session.minigame.is_some() {
+                minigame::chat(session, &message).await?
+            } else {
+                player_shop::chat(session, &message).await?
+            }
+        }
+        // Leave a room
+        0x0A => {
+            if session.trade.is_some() {
+                trade::cancel(session).await?
+            } else if session.minigame.is_some() {
+                minigame::leave(session).await?
+            } else {
+                player_shop::leave(session).await?
+            }
+        }
+        // Open a player shop or hired merchant to visitors
+        0x0B => player_shop::open(session).await?,
+        // Put an item into a trade
+        0x0F => {
+            let inventory_type = match read_inventory_type(&mut packet) {
+                Some(inventory_type) => inventory_type,
+                None => return Ok(()),
+            };
+            let position = packet.read_short() as i32;
+            let amount = packet.read_short() as i32;
+            let slot = packet.read_byte();
+
+            // Positions are 1-indexed by the client
+            trade::add_item(session, inventory_type, position - 1, amount, slot).await?
+        }
+        // Put mesos into a trade
+        0x10 => trade::add_mesos(session, packet.read_int()).await?,
+        // Confirm a trade
+        0x11 => trade::confirm(session).await?,
+        // List an item in a player shop or hired merchant
+        0x16 | 0x21 => {
+            let inventory_type = match read_inventory_type(&mut packet) {
+                Some(inventory_type) => inventory_type,
+                None => return Ok(()),
+            };
+            let position = packet.read_short() as i32;
+            let bundles = packet.read_short() as i32;
+            let amount = packet.read_short() as i32;
+            let price = packet.read_int();
+
+            // Positions are 1-indexed by the client
+            player_shop::add_item(
+                session,
+                inventory_type,
+                position - 1,
+                amount,
+                bundles,
+                price,
+            )
+            .await?
+        }
+        // Buy an item from a player shop or hired merchant
+        0x17 | 0x22 => {
+            let index = packet.read_byte() as usize;
+            let bundles = packet.read_short() as i32;
+            player_shop::buy(session, index, bundles).await?
+        }
+        // Take back an item listed in a player shop or hired merchant
+        0x1B | 0x26 => player_shop::remove_item(session, packet.read_short() as usize).await?,
+        // Ban a visitor from a player shop
+        0x1C => player_shop::ban(session, &packet.read_string()).await?,
+        // Finish maintaining a hired merchant
+        0x27 => player_shop::open(session).await?,
+        // Organize a hired merchant
+        0x28 => player_shop::organize(session).await?,
+        // Close a hired merchant
+        0x29 => player_shop::close_merchant(session).await?,
+        // Collect a hired merchant's mesos
+        0x2B => player_shop::withdraw_mesos(session).await?,
+        // View a hired merchant's visitors
+        0x2E => player_shop::show_visit_list(session).await?,
+        // View a hired merchant's blacklist
+        0x2F => player_shop::show_blacklist(session).await?,
+        // Add a character to a hired merchant's blacklist
+        0x30 => player_shop::update_blacklist(session, packet.read_string(), true).await?,
+        // Remove a character from a hired merchant's blacklist
+        0x31 => player_shop::update_blacklist(session, packet.read_string(), false).await?,
+        // Ask the opponent to end a minigame in a tie
+        0x32 => minigame::request_tie(session).await?,
+        // Answer the opponent's request to end a minigame in a tie
+        0x33 => minigame::answer_tie(session, packet.read_byte() != 0).await?,
+        // Forfeit a minigame
+        0x34 => minigame::give_up(session).await?,
+        // Leave a minigame room once the game ends, or stay after all
+        0x38 => minigame::set_leave_after_game(session, true).await?,
+        0x39 => minigame::set_leave_after_game(session, false).await?,
+        // Get ready to play a minigame, or take it back
+        0x3A => minigame::set_ready(session, true).await?,
+        0x3B => minigame::set_ready(session, false).await?,
+        // Expel the visitor from a minigame room
+        0x3C => minigame::expel(session).await?,
+        // Start a minigame
+        0x3D => minigame::start(session).await?,
+        // Pass the turn in an omok game
+        0x3F => minigame::skip_turn(session).await?,
+        // Place an omok piece
+        0x40 => {
+            let x = packet.read_int();
+            let y = packet.read_int();
+            minigame::place_omok_piece(session, x, y).await?
+        }
+        // Flip a card in a match cards game
+        0x44 => {
+            let first = packet.read_byte() == 1;
+            let card = packet.read_byte() as usize;
+            minigame::flip_card(session, first, card).await?
+        }
+        _ => log::debug!("Unhandled player interaction: {}", action),
+    }
+
+    Ok(())
+}
+
+fn read_inventory_type(packet: &mut Packet) -> Option<InventoryType> {
+    match packet.read_byte() {
+        1 => Some(InventoryType::Equip),
+        2 => Some(InventoryType::Use),
+        3 => Some(InventoryType::Setup),
+        4 => Some(InventoryType::Etc),
+        5 => Some(InventoryType::Cash),
+        _ => None,
+    }
+}
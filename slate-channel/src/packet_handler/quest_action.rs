@@ -14,7 +14,7 @@ pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow:
     let quest_id = packet.read_short();
 
     let quest = nx::Quest::load(quest_id)?;
-    let character = session.character.as_ref().unwrap();
+    let character = session.character.as_mut().unwrap();
 
     match action {
         // Restore lost item
@@ -0,0 +1,10 @@
+use crate::{pet, session::ChannelSession};
+use slate_net::Packet;
+
+/// Channel server: spawn pet packet (0x5A)
+/// Called when a character summons or puts away a pet
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    packet.skip(4);
+    let position = packet.read_byte() as i32 - 1;
+    pet::toggle(session, position).await
+}
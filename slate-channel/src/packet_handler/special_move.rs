@@ -0,0 +1,163 @@
+use crate::{mount, session::ChannelSession};
+use rand::Rng;
+use slate_data::{
+    maple::map::{MapBroadcast, PartySkillBroadcast},
+    nx,
+    packet::{self, Stat},
+    sql,
+};
+use slate_net::Packet;
+use sqlx::types::chrono::Utc;
+
+const HEAL: i32 = 2301002;
+const MYSTIC_DOOR: i32 = 2311002;
+
+/// Channel server: special move packet (0x5B)
+/// Called when a character uses a non-attack skill (buffs, heals, summons, etc.)
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    packet.skip(4); // timestamp
+    let skill_id = packet.read_int();
+    let level = packet.read_byte() as i32;
+
+    let character = session.character.as_mut().unwrap();
+
+    // The client sends the skill level it thinks the character has, make sure it matches
+    if level == 0 || character.get_skill_level(skill_id) != level {
+        log::warn!(
+            "Character {} used skill {} at level {} without owning it",
+            character.data.id,
+            skill_id,
+            level
+        );
+        return session.stream.write_packet(packet::update_stats(&[])).await;
+    }
+
+    if mount::is_monster_riding(skill_id) {
+        return mount::ride(session, skill_id).await;
+    }
+
+    let skill = nx::Skill::load(skill_id)?;
+
+    let effect = match skill.level(level) {
+        Some(effect) => effect,
+        None => {
+            log::error!("Skill {} has no data for level {}", skill_id, level);
+            return session.stream.write_packet(packet::update_stats(&[])).await;
+        }
+    };
+
+    let now = Utc::now().timestamp_millis();
+
+    if character.is_on_cooldown(skill_id, now)
+        || character.data.hp <= 0
+        || character.data.hp <= effect.hp_con
+        || character.data.mp < effect.mp_con
+    {
+        return session.stream.write_packet(packet::update_stats(&[])).await;
+    }
+
+    // Summons and doors are placed at the position sent by the client
+    let pos = if packet.remaining() >= 5 {
+        let x = packet.read_short() as i32;
+        let y = packet.read_short() as i32;
+        (x, y)
+    } else {
+        character.pos
+    };
+
+    character.data.hp -= effect.hp_con;
+    character.data.mp -= effect.mp_con;
+
+    let stats = [(Stat::Hp, character.data.hp), (Stat::Mp, character.data.mp)];
+
+    if effect.cooltime > 0 {
+        character.cooldowns.push(sql::Cooldown {
+            character_id: character.data.id,
+            skill_id,
+            start: now,
+            length: effect.cooltime as i64 * 1000,
+        });
+    }
+
+    // Healing is based on the caster's magic, and is the same for each party member
+    let hp = if skill_id == HEAL {
+        let rate = effect.hp as f64 / 100.0;
+        let magic = character.data.int as f64;
+        let min = (magic * 3.0 * rate) as i32;
+        let max = (magic * 5.0 * rate) as i32;
+        rand::thread_rng().gen_range(min..=max.max(min))
+    } else {
+        0
+    };
+
+    let character_id = character.data.id;
+    let party_id = character.data.party;
+    let bounds = effect.bounds(character.pos, character.is_facing_left());
+
+    session
+        .stream
+        .write_packet(packet::update_stats(&stats))
+        .await?;
+
+    if effect.hp_con > 0 {
+        session.update_party_hp()?;
+    }
+
+    if effect.cooltime > 0 {
+        session
+            .stream
+            .write_packet(packet::skill_cooldown(skill_id, effect.cooltime))
+            .await?;
+    }
+
+    if skill_id == MYSTIC_DOOR {
+        // TODO spawn the town side of the door in the map's return map
+        session.broadcast_packet(spawn_door(character_id, pos), true)?;
+    } else if skill.is_summon {
+        session.broadcast_packet(spawn_summon(character_id, skill_id, level, pos), true)?;
+    }
+
+    session.apply_skill_effect(skill_id, effect, hp, 1).await?;
+
+    // Apply the skill to the rest of the party if it has an area of effect
+    if let (Some(party_id), Some(bounds)) = (party_id, bounds) {
+        let broadcast = MapBroadcast::PartySkill(PartySkillBroadcast {
+            skill_id,
+            level,
+            party_id,
+            sender_id: character_id,
+            hp,
+            bounds,
+        });
+        session.map_broadcast_tx.as_ref().unwrap().send(broadcast)?;
+    }
+
+    Ok(())
+}
+
+/// Packet spawning a character's summon in the map
+fn spawn_summon(character_id: i32, skill_id: i32, level: i32, pos: (i32, i32)) -> Packet {
+    let mut packet = Packet::new(0xAF);
+    packet.write_int(character_id);
+    // FIXME same object id problem as map life
+    packet.write_int(rand::thread_rng().gen_range(10000000..=i32::MAX));
+    packet.write_int(skill_id);
+    packet.write_byte(0x0A);
+    packet.write_byte(level as u8);
+    packet.write_position(pos);
+    packet.write_byte(4); // stance
+    packet.write_short(0);
+    packet.write_byte(1); // movement type (follow)
+    packet.write_byte(1); // not a puppet
+    packet.write_byte(0); // animated
+    packet
+}
+
+/// Packet spawning a character's mystic door in the map
+fn spawn_door(character_id: i32, pos: (i32, i32)) -> Packet {
+    let mut packet = Packet::new(0x113);
+    packet.write_byte(0); // launched
+    packet.write_int(character_id);
+    packet.write_position(pos);
+    packet
+}
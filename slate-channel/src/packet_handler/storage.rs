@@ -0,0 +1,43 @@
+use crate::{session::ChannelSession, storage};
+use slate_data::sql::item::InventoryType;
+use slate_net::Packet;
+
+/// Channel server: storage packet (0x3E)
+/// Called when a character takes out or stores items or mesos in their storage, or closes it
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let action = packet.read_byte();
+
+    match action {
+        // Take out an item
+        0x04 => {
+            let inventory_type = match packet.read_byte() {
+                1 => InventoryType::Equip,
+                2 => InventoryType::Use,
+                3 => InventoryType::Setup,
+                4 => InventoryType::Etc,
+                5 => InventoryType::Cash,
+                _ => return Ok(()),
+            };
+            let slot = packet.read_byte() as usize;
+            storage::take(session, inventory_type, slot).await?
+        }
+        // Store an item
+        0x05 => {
+            let position = packet.read_short() as i32;
+            let item_id = packet.read_int();
+            let quantity = packet.read_short() as i32;
+
+            // Positions are 1-indexed by the client
+            storage::store(session, position - 1, item_id, quantity).await?
+        }
+        // Arrange the items
+        0x06 => storage::arrange(session).await?,
+        // Store or take out mesos
+        0x07 => storage::move_mesos(session, packet.read_int()).await?,
+        // Close the storage
+        0x08 => session.storage = None,
+        _ => log::debug!("Unhandled storage action: {}", action),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,161 @@
+use crate::session::ChannelSession;
+use slate_data::{
+    maple::buff::BuffStat,
+    packet::{self, Stat},
+};
+use slate_net::Packet;
+
+/// Damage dealt by touching a monster
+const TOUCH_DAMAGE: i8 = -1;
+/// Damage dealt by the map (ex. falling into water)
+const MAP_DAMAGE: i8 = -3;
+
+/// Channel server: take damage packet (0x30)
+/// Called when a character is hit by a monster (touch or monster skill) or the map
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    packet.skip(4); // timestamp
+    let damage_from = packet.read_byte() as i8;
+    packet.skip(1); // element
+    let mut damage = packet.read_int();
+
+    let mut monster_id = 0;
+    let mut direction = 0;
+
+    if damage_from != MAP_DAMAGE && damage_from != -4 {
+        monster_id = packet.read_int();
+        packet.skip(4); // monster object id
+        direction = packet.read_byte();
+    }
+
+    // TODO validate damage against the monster's attack (touch) or skill (damage_from >= 0) data
+
+    let character = session.character.as_mut().unwrap();
+
+    if !character.is_alive() {
+        return Ok(());
+    }
+
+    let mut stats = Vec::new();
+    let mut cancelled_buffs = Vec::new();
+
+    // Thieves show a fake "miss" skill effect when they avoid an attack
+    let mut fake_skill = 0;
+
+    if damage == -1 && character.data.job / 100 == 4 {
+        fake_skill = 4020002 + ((character.data.job / 10) - 40) * 100000;
+    }
+
+    if damage > 0 {
+        // Power guard reflects part of the touch damage back at the monster
+        if damage_from == TOUCH_DAMAGE {
+            if let Some(power_guard) = character.get_buff_value(BuffStat::PowerGuard) {
+                let reflected = damage * power_guard as i32 / 100;
+                damage -= reflected;
+                // TODO damage the monster once monsters are tracked server side
+            }
+        }
+
+        // Meso guard halves damage at the cost of mesos
+        if let Some(meso_guard) = character.get_buff_value(BuffStat::MesoGuard) {
+            damage /= 2;
+            let meso_loss = damage * meso_guard as i32 / 100;
+
+            if character.data.mesos < meso_loss {
+                character.data.mesos = 0;
+                cancelled_buffs.extend(character.cancel_buff_stat(BuffStat::MesoGuard));
+            } else {
+                character.data.mesos -= meso_loss;
+            }
+
+            stats.push((Stat::Mesos, character.data.mesos));
+        }
+
+        // Magic guard moves part of the damage to mp
+        let (mut hp_loss, mut mp_loss) = (damage, 0);
+
+        if let Some(magic_guard) = character.get_buff_value(BuffStat::MagicGuard) {
+            mp_loss = damage * magic_guard as i32 / 100;
+            hp_loss = damage - mp_loss;
+
+            if mp_loss > character.data.mp {
+                hp_loss += mp_loss - character.data.mp;
+                mp_loss = character.data.mp;
+            }
+        }
+
+        character.data.hp = (character.data.hp - hp_loss).max(0);
+        character.data.mp -= mp_loss;
+        stats.push((Stat::Hp, character.data.hp));
+
+        if mp_loss > 0 {
+            stats.push((Stat::Mp, character.data.mp));
+        }
+    }
+
+    let character_id = character.data.id;
+    let is_dead = !character.is_alive();
+
+    if !stats.is_empty() {
+        session
+            .stream
+            .write_packet(packet::update_stats(&stats))
+            .await?;
+        session.update_party_hp()?;
+    }
+
+    for buff in cancelled_buffs.iter() {
+        session
+            .stream
+            .write_packet(packet::cancel_buff(buff))
+            .await?;
+        session.broadcast_packet(packet::cancel_foreign_buff(character_id, buff), false)?;
+    }
+
+    session.broadcast_packet(
+        damage_character(
+            character_id,
+            damage_from,
+            damage,
+            monster_id,
+            direction,
+            fake_skill,
+        ),
+        false,
+    )?;
+
+    if is_dead {
+        session.on_death().await?;
+    }
+
+    Ok(())
+}
+
+/// Shows a character taking damage for everyone else in the map
+fn damage_character(
+    character_id: i32,
+    damage_from: i8,
+    damage: i32,
+    monster_id: i32,
+    direction: u8,
+    fake_skill: i32,
+) -> Packet {
+    let mut packet = Packet::new(0xC0);
+    packet.write_int(character_id);
+    packet.write_byte(damage_from as u8);
+    packet.write_int(damage);
+
+    if damage_from != -4 {
+        packet.write_int(monster_id);
+        packet.write_byte(direction);
+        packet.write_short(0); // TODO power guard reflection info
+        packet.write_int(damage);
+
+        if fake_skill > 0 {
+            packet.write_int(fake_skill);
+        }
+    } else {
+        packet.write_int(damage);
+    }
+
+    packet
+}
@@ -0,0 +1,15 @@
+use crate::{reactor, session::ChannelSession};
+use slate_net::Packet;
+
+/// Channel server: touch reactor packet (0xCE)
+/// Called when a character starts or stops touching a reactor
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let object_id = packet.read_int();
+    let touching = packet.read_byte() != 0;
+
+    if !touching {
+        return Ok(());
+    }
+
+    reactor::touch(session, object_id).await
+}
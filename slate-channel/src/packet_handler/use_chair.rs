@@ -0,0 +1,22 @@
+use crate::session::ChannelSession;
+use slate_data::{packet, sql::item::InventoryType};
+use slate_net::Packet;
+
+/// Channel server: use chair packet (0x2B)
+/// Called when a character sits on a chair from their setup inventory
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let item_id = packet.read_int();
+    let character = session.character.as_mut().unwrap();
+
+    if InventoryType::from_item_id(item_id) != Some(InventoryType::Setup)
+        || character.get_item_amount(item_id) == 0
+    {
+        return session.stream.write_packet(packet::update_stats(&[])).await;
+    }
+
+    character.chair = item_id;
+    let character_id = character.data.id;
+
+    session.broadcast_packet(packet::show_chair(character_id, item_id), false)?;
+    session.stream.write_packet(packet::update_stats(&[])).await
+}
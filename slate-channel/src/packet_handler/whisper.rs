@@ -0,0 +1,104 @@
+use crate::{session::ChannelSession, world};
+use slate_data::sql;
+use slate_net::Packet;
+
+/// Channel server: whisper packet (0x78)
+/// Called when a character whispers another character, or uses /find
+pub async fn handle(mut packet: Packet, session: &mut ChannelSession) -> anyhow::Result<()> {
+    let mode = packet.read_byte();
+    let recipient_name = packet.read_string();
+
+    let recipient =
+        sql::OnlineCharacter::load_optional_by_name(&recipient_name, session.world_id, &session.db)
+            .await?;
+
+    match mode {
+        // Find
+        5 => {
+            let packet = match recipient {
+                Some(recipient) if recipient.channel_id == session.channel_id => {
+                    find_result(&recipient.name, FindResult::SameChannel(recipient.map_id))
+                }
+                Some(recipient) => find_result(
+                    &recipient.name,
+                    FindResult::DifferentChannel(recipient.channel_id),
+                ),
+                None => whisper_result(&recipient_name, false),
+            };
+
+            session.stream.write_packet(packet).await?;
+        }
+        // Whisper
+        6 => {
+            let message = packet.read_string();
+            let character = session.character.as_ref().unwrap();
+
+            let sent = match recipient {
+                Some(recipient) => {
+                    let whisper =
+                        whisper_received(&character.data.name, session.channel_id, &message);
+                    world::send_packet(session, recipient.character_id, whisper).await?
+                }
+                None => false,
+            };
+
+            session
+                .stream
+                .write_packet(whisper_result(&recipient_name, sent))
+                .await?;
+        }
+        _ => {
+            log::debug!("Unhandled whisper mode: {}", mode);
+        }
+    }
+
+    Ok(())
+}
+
+/// Where a character was found with /find
+enum FindResult {
+    /// The character is in the given map on our channel
+    SameChannel(i32),
+    /// The character is on the given channel
+    DifferentChannel(i32),
+}
+
+/// Packet containing the result of a /find
+fn find_result(name: &str, result: FindResult) -> Packet {
+    let mut packet = Packet::new(0x87);
+    packet.write_byte(9);
+    packet.write_string(name);
+
+    match result {
+        FindResult::SameChannel(map_id) => {
+            packet.write_byte(1);
+            packet.write_int(map_id);
+            packet.write_bytes(&[0u8; 8]);
+        }
+        FindResult::DifferentChannel(channel_id) => {
+            packet.write_byte(3);
+            packet.write_int(channel_id);
+        }
+    }
+
+    packet
+}
+
+/// Packet telling the sender whether their whisper was sent
+fn whisper_result(name: &str, sent: bool) -> Packet {
+    let mut packet = Packet::new(0x87);
+    packet.write_byte(0x0A);
+    packet.write_string(name);
+    packet.write_byte(sent as u8);
+    packet
+}
+
+/// Packet containing a whisper sent to the current player
+fn whisper_received(sender_name: &str, channel_id: i32, message: &str) -> Packet {
+    let mut packet = Packet::new(0x87);
+    packet.write_byte(0x12);
+    packet.write_string(sender_name);
+    packet.write_short(channel_id as i16);
+    packet.write_string(message);
+    packet
+}
@@ -0,0 +1,77 @@
+use crate::{
+    session::{ChannelSession, SessionMessage},
+    world,
+};
+use slate_data::{
+    packet::{self, PartyOperation},
+    sql,
+};
+
+/// Sends a party update to each of the party's online members
+/// `former_member` also receives the update, for members who just left or were expelled
+pub async fn send_update(
+    session: &ChannelSession,
+    party: &sql::Party,
+    operation: PartyOperation<'_>,
+    former_member: Option<i32>,
+) -> anyhow::Result<()> {
+    let members = sql::PartyMember::load_all(party.id, &session.db).await?;
+
+    // The update packet depends on which channel the recipient is on
+    let mut recipients: Vec<(i32, i32)> = members
+        .iter()
+        .filter_map(|member| Some((member.id, member.channel_id?)))
+        .collect();
+
+    if let Some(character_id) = former_member {
+        if let Some(online_character) =
+            sql::OnlineCharacter::load_optional(character_id, &session.db).await?
+        {
+            recipients.push((character_id, online_character.channel_id));
+        }
+    }
+
+    for (character_id, channel_id) in recipients {
+        let packet = packet::update_party(party, &members, channel_id, operation);
+        world::send_packet(session, character_id, packet).await?;
+    }
+
+    Ok(())
+}
+
+/// Updates the party window of the character's party members
+/// Called when the character logs in, logs out, or changes maps
+pub async fn update_members(session: &ChannelSession) -> anyhow::Result<()> {
+    let party_id = match session.character.as_ref().unwrap().data.party {
+        Some(party_id) => party_id,
+        None => return Ok(()),
+    };
+
+    match sql::Party::load_optional(party_id, &session.db).await? {
+        Some(party) => send_update(session, &party, PartyOperation::Update, None).await,
+        None => Ok(()),
+    }
+}
+
+/// Disbands a party, removing all of its members
+pub async fn disband(session: &ChannelSession, party: &sql::Party) -> anyhow::Result<()> {
+    let members = sql::PartyMember::load_all(party.id, &session.db).await?;
+    party.disband(&session.db).await?;
+
+    let operation = PartyOperation::Disband {
+        leader_id: party.leader_id,
+    };
+
+    for member in members.iter() {
+        let channel_id = match member.channel_id {
+            Some(channel_id) => channel_id,
+            None => continue,
+        };
+
+        let packet = packet::update_party(party, &members, channel_id, operation);
+        world::send_packet(session, member.id, packet).await?;
+        world::send(session, member.id, SessionMessage::PartyChanged(None)).await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,378 @@
+use crate::{drop, session::ChannelSession};
+use rand::Rng;
+use slate_data::{
+    nx,
+    packet::{self, Stat},
+    sql::item::InventoryType,
+};
+use slate_net::Packet;
+
+/// Follow the Lead (beginner, noblesse and legend), lets characters summon up to 3 pets at once
+const FOLLOW_THE_LEAD: [i32; 3] = [8, 10000018, 20000024];
+
+/// The most pets a character can have summoned at once
+const MAX_PETS: usize = 3;
+
+/// The chance (out of 100) a pet gains tameness from being fed
+const FEED_TAMENESS_CHANCE: i32 = 50;
+
+/// Summons the pet at a position in the character's cash inventory, or puts it away if it's already summoned
+/// Without Follow the Lead, the character's other pet is put away first
+pub async fn toggle(session: &mut ChannelSession, position: i32) -> anyhow::Result<()> {
+    let character = session.character.as_mut().unwrap();
+
+    let pet_id = match character
+        .items
+        .iter()
+        .find(|item| item.inventory_type == InventoryType::Cash && item.position == position)
+        .and_then(|item| item.cash_id)
+    {
+        Some(pet_id) => pet_id,
+        None => return session.stream.write_packet(packet::update_stats(&[])).await,
+    };
+
+    let summoned = match character.get_pet_mut(pet_id) {
+        Some(pet) => pet.is_summoned(),
+        None => return session.stream.write_packet(packet::update_stats(&[])).await,
+    };
+
+    if summoned {
+        return unsummon(session, pet_id, false).await;
+    }
+
+    let has_lead = FOLLOW_THE_LEAD
+        .iter()
+        .any(|skill_id| character.get_skill_level(*skill_id) > 0);
+    let summoned_ids: Vec<i64> = character
+        .get_summoned_pets()
+        .iter()
+        .map(|pet| pet.data.id)
+        .collect();
+
+    if !has_lead {
+        for summoned_id in summoned_ids {
+            unsummon(session, summoned_id, false).await?;
+        }
+    } else if summoned_ids.len() >= MAX_PETS {
+        return session.stream.write_packet(packet::update_stats(&[])).await;
+    }
+
+    // TODO summoning a pet as the leader puts it in front of the others
+    let character = session.character.as_mut().unwrap();
+    let slot = character.get_summoned_pets().len() as i32;
+    let pos = character.pos;
+    let character_id = character.data.id;
+
+    let pet = character.get_pet_mut(pet_id).unwrap();
+    pet.data.slot = Some(slot);
+    pet.pos = pos;
+    pet.stance = 0;
+
+    let packet = packet::spawn_pet(character_id, slot as u8, pet);
+    session.broadcast_packet(packet, true)?;
+    send_pet_stats(session).await
+}
+
+/// Puts a summoned pet back into the character's inventory, `hungry` shows it left because it was hungry
+pub async fn unsummon(
+    session: &mut ChannelSession,
+    pet_id: i64,
+    hungry: bool,
+) -> anyhow::Result<()> {
+    let character = session.character.as_mut().unwrap();
+    let character_id = character.data.id;
+
+    let slot = match character
+        .get_pet_mut(pet_id)
+        .and_then(|pet| pet.data.slot.take())
+    {
+        Some(slot) => slot,
+        None => return Ok(()),
+    };
+
+    // The pets behind it move up a slot
+    for pet in character.pets.iter_mut() {
+        if let Some(other_slot) = pet.data.slot.as_mut() {
+            if *other_slot > slot {
+                *other_slot -= 1;
+            }
+        }
+    }
+
+    let packet = packet::remove_pet(character_id, slot as u8, hungry);
+    session.broadcast_packet(packet, true)?;
+    send_pet_stats(session).await
+}
+
+/// Shows the character's summoned pets to the character once they've entered a map
+/// Everyone else in the map sees them along with the character
+pub async fn show_summoned(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let character = session.character.as_mut().unwrap();
+    let character_id = character.data.id;
+    let pos = character.pos;
+
+    for pet in character.pets.iter_mut().filter(|pet| pet.is_summoned()) {
+        pet.pos = pos;
+        pet.stance = 0;
+    }
+
+    let packets: Vec<Packet> = character
+        .get_summoned_pets()
+        .iter()
+        .map(|pet| packet::spawn_pet(character_id, pet.data.slot.unwrap() as u8, pet))
+        .collect();
+
+    for packet in packets {
+        session.stream.write_packet(packet).await?;
+    }
+
+    Ok(())
+}
+
+/// Picks up a dropped item in the character's map with one of their summoned pets
+pub async fn pick_up(
+    session: &mut ChannelSession,
+    pet_id: i64,
+    object_id: i32,
+) -> anyhow::Result<()> {
+    let character = session.character.as_mut().unwrap();
+
+    let slot = match character.get_pet_mut(pet_id).and_then(|pet| pet.data.slot) {
+        Some(slot) => slot,
+        None => return Ok(()),
+    };
+
+    drop::pick_up(session, object_id, Some(slot)).await
+}
+
+/// Moves one of the character's summoned pets for everyone else in the map
+pub async fn move_pet(
+    session: &mut ChannelSession,
+    pet_id: i64,
+    pos: Option<(i32, i32)>,
+    stance: Option<u8>,
+    movement_data: &Packet,
+) -> anyhow::Result<()> {
+    let character = session.character.as_mut().unwrap();
+    let character_id = character.data.id;
+
+    let pet = match character.get_pet_mut(pet_id) {
+        Some(pet) if pet.is_summoned() => pet,
+        _ => return Ok(()),
+    };
+
+    pet.pos = pos.unwrap_or(pet.pos);
+    pet.stance = stance.unwrap_or(pet.stance);
+
+    let packet = packet::move_pet(character_id, pet.data.slot.unwrap() as u8, movement_data);
+    session.broadcast_packet(packet, false)
+}
+
+/// Shows one of the character's summoned pets talking
+pub async fn chat(
+    session: &mut ChannelSession,
+    pet_id: i64,
+    action: u8,
+    text: &str,
+) -> anyhow::Result<()> {
+    let character = session.character.as_mut().unwrap();
+    let character_id = character.data.id;
+
+    let slot = match character.get_pet_mut(pet_id).and_then(|pet| pet.data.slot) {
+        Some(slot) => slot,
+        None => return Ok(()),
+    };
+
+    session.broadcast_packet(
+        packet::pet_chat(character_id, slot as u8, action, text),
+        true,
+    )
+}
+
+/// Gives one of the character's summoned pets a command, which it might listen to and become tamer
+pub async fn command(
+    session: &mut ChannelSession,
+    pet_id: i64,
+    command_id: u8,
+) -> anyhow::Result<()> {
+    let character = session.character.as_mut().unwrap();
+    let character_id = character.data.id;
+
+    let pet = match character.get_pet_mut(pet_id) {
+        Some(pet) if pet.is_summoned() => pet,
+        _ => return Ok(()),
+    };
+
+    let command = match nx::Pet::load(pet.data.item_id)?
+        .commands
+        .get(&(command_id as i32))
+    {
+        Some(command) if (command.min_level..=command.max_level).contains(&pet.data.level) => {
+            command.clone()
+        }
+        _ => return Ok(()),
+    };
+
+    let slot = pet.data.slot.unwrap() as u8;
+    let success = rand::thread_rng().gen_range(0..100) < command.probability;
+    let leveled_up = success && pet.gain_tameness(command.tameness);
+
+    let packet = packet::pet_command_response(character_id, slot, false, command_id, success);
+    session.broadcast_packet(packet, true)?;
+
+    if success {
+        update_pet(session, pet_id, leveled_up).await?;
+    }
+
+    Ok(())
+}
+
+/// Feeds the hungriest of the character's summoned pets with pet food from their use inventory
+/// Feeding a pet that's already full makes it less tame
+pub async fn feed(session: &mut ChannelSession, position: i32, item_id: i32) -> anyhow::Result<()> {
+    let character = session.character.as_mut().unwrap();
+    let character_id = character.data.id;
+
+    let pet_id = match character
+        .get_summoned_pets()
+        .iter()
+        .min_by_key(|pet| pet.data.fullness)
+    {
+        Some(pet) => pet.data.id,
+        None => return session.stream.write_packet(packet::update_stats(&[])).await,
+    };
+
+    let fullness = nx::Item::load(item_id)?.pet_fullness;
+
+    if fullness <= 0 || !session.use_item(position, item_id).await? {
+        return session.stream.write_packet(packet::update_stats(&[])).await;
+    }
+
+    let character = session.character.as_mut().unwrap();
+    let pet = character.get_pet_mut(pet_id).unwrap();
+    let slot = pet.data.slot.unwrap() as u8;
+    let tamed = rand::thread_rng().gen_range(0..100) < FEED_TAMENESS_CHANCE;
+    let (hungry, leveled_up) = pet.feed(fullness, tamed);
+
+    let packet = packet::pet_command_response(character_id, slot, true, 1, hungry);
+    session.broadcast_packet(packet, true)?;
+    update_pet(session, pet_id, leveled_up).await
+}
+
+/// Uses an hp or mp potion from the character's use inventory for them, when their hp or mp runs low
+pub async fn use_potion(
+    session: &mut ChannelSession,
+    position: i32,
+    item_id: i32,
+) -> anyhow::Result<()> {
+    let character = session.character.as_ref().unwrap();
+
+    if !character.is_alive() || character.get_summoned_pets().is_empty() {
+        return session.stream.write_packet(packet::update_stats(&[])).await;
+    }
+
+    let data = nx::Item::load(item_id)?;
+
+    if !session.use_item(position, item_id).await? {
+        return session.stream.write_packet(packet::update_stats(&[])).await;
+    }
+
+    let character = session.character.as_mut().unwrap();
+    let hp = data.hp + character.data.max_hp * data.hp_rate / 100;
+    let mp = data.mp + character.data.max_mp * data.mp_rate / 100;
+    character.heal(hp, mp);
+
+    let stats = [(Stat::Hp, character.data.hp), (Stat::Mp, character.data.mp)];
+    session
+        .stream
+        .write_packet(packet::update_stats(&stats))
+        .await?;
+    session.update_party_hp()
+}
+
+/// Makes the character's summoned pets hungrier, they go back into the character's inventory once they're too hungry
+pub async fn handle_hunger(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let character = session.character.as_mut().unwrap();
+    let mut hungry = Vec::new();
+    let mut updated = Vec::new();
+
+    for pet in character.pets.iter_mut().filter(|pet| pet.is_summoned()) {
+        let hunger = nx::Pet::load(pet.data.item_id)?.hunger;
+
+        if pet.starve(hunger) {
+            hungry.push(pet.data.id);
+        }
+
+        updated.push(pet.data.id);
+    }
+
+    for pet_id in hungry {
+        unsummon(session, pet_id, true).await?;
+    }
+
+    for pet_id in updated {
+        update_pet(session, pet_id, false).await?;
+    }
+
+    Ok(())
+}
+
+/// Shows the character a pet's new level, tameness and fullness, along with its level up if it leveled up
+pub async fn update_pet(
+    session: &mut ChannelSession,
+    pet_id: i64,
+    leveled_up: bool,
+) -> anyhow::Result<()> {
+    let character = session.character.as_mut().unwrap();
+    let character_id = character.data.id;
+
+    let item = match character
+        .items
+        .iter()
+        .find(|item| item.cash_id == Some(pet_id))
+    {
+        Some(item) => item.clone(),
+        None => return Ok(()),
+    };
+
+    let pet = match character.get_pet_mut(pet_id) {
+        Some(pet) => pet.data.clone(),
+        None => return Ok(()),
+    };
+
+    session
+        .stream
+        .write_packet(packet::update_pet_item(&item, &pet))
+        .await?;
+
+    if let (true, Some(slot)) = (leveled_up, pet.slot) {
+        session
+            .stream
+            .write_packet(packet::show_pet_level_up(slot as u8))
+            .await?;
+        session.broadcast_packet(
+            packet::show_foreign_pet_level_up(character_id, slot as u8),
+            false,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Shows the character which of their pets are summoned
+async fn send_pet_stats(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let pet_ids: Vec<i64> = session
+        .character
+        .as_ref()
+        .unwrap()
+        .get_summoned_pets()
+        .iter()
+        .map(|pet| pet.data.id)
+        .collect();
+
+    session
+        .stream
+        .write_packet(packet::update_pet_stats(&pet_ids))
+        .await?;
+    session.stream.write_packet(packet::update_stats(&[])).await
+}
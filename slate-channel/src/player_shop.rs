@@ -0,0 +1,1076 @@
+use crate::{
+    minigame,
+    session::{ChannelSession, SessionMessage},
+};
+use slate_data::{
+    maple::character::InventoryChange,
+    nx,
+    packet::{self, FredrickMessage, NoticeType, RoomLeaveReason, RoomVisitor, ShopBox, Stat},
+    sql::{self, item::InventoryType},
+};
+use slate_net::Packet;
+use sqlx::types::chrono::Utc;
+use std::{ops::RangeInclusive, sync::Arc};
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+/// Fredrick keeps the items and mesos left in closed player shops and hired merchants
+pub const FREDRICK_NPC: i32 = 9030000;
+
+/// Player shops and hired merchants can only be set up in the free market rooms
+const FREE_MARKET_ROOMS: RangeInclusive<i32> = 910000001..=910000022;
+
+/// The number of items a shop can list
+const SHOP_SLOTS: usize = 16;
+
+/// The number of visits a hired merchant remembers for its owner
+const VISIT_LIST_SIZE: usize = 30;
+
+/// A player shop or hired merchant shared by the sessions of the characters in it
+pub type SharedPlayerShop = Arc<Mutex<PlayerShop>>;
+
+/// A player shop or hired merchant in a free market room
+/// Listed items are kept in the db, so anything left when the shop closes can be retrieved from Fredrick
+#[derive(Debug)]
+pub struct PlayerShop {
+    pub id: i32,
+    /// Whether the shop is a hired merchant, which stays open while its owner is away
+    pub hired: bool,
+    pub owner: sql::Character,
+    pub owner_equipment: Vec<sql::Equipment>,
+    pub permit_id: i32,
+    pub title: String,
+    pub map_id: i32,
+    pub pos: (i32, i32),
+    /// Whether visitors can enter the shop
+    pub open: bool,
+    /// Whether the shop has been shown in the map, which happens once the owner first opens it
+    pub spawned: bool,
+    /// Whether the owner is in the shop, a hired merchant's owner is only there while maintaining it
+    pub owner_present: bool,
+    pub closed: bool,
+    /// Sold out items stay listed until the shop is organized, so the client's item slots don't shift
+    pub items: Vec<sql::PlayerShopItem>,
+    /// The mesos the shop earned that its owner hasn't collected yet
+    pub mesos: i32,
+    pub visitors: [Option<ShopVisitor>; 3],
+    /// Characters that can't visit the hired merchant, by name
+    pub blacklist: Vec<String>,
+    /// Characters that visited the hired merchant, with how long they stayed in milliseconds
+    pub visit_list: Vec<(String, i32)>,
+}
+
+#[derive(Debug)]
+pub struct ShopVisitor {
+    pub data: sql::Character,
+    pub equipment: Vec<sql::Equipment>,
+    pub entered_at: i64,
+}
+
+impl PlayerShop {
+    /// Gets the character's slot in the shop (0 for the owner), or None if they aren't in it
+    fn get_slot(&self, character_id: i32) -> Option<u8> {
+        if self.owner.id == character_id {
+            return self.owner_present.then_some(0);
+        }
+
+        self.visitors
+            .iter()
+            .position(|visitor| matches!(visitor, Some(visitor) if visitor.data.id == character_id))
+            .map(|index| index as u8 + 1)
+    }
+
+    /// Gets the ids of the characters in the shop
+    fn get_character_ids(&self) -> Vec<i32> {
+        let owner = self.owner_present.then_some(self.owner.id);
+        let visitors = self
+            .visitors
+            .iter()
+            .flatten()
+            .map(|visitor| visitor.data.id);
+        owner.into_iter().chain(visitors).collect()
+    }
+
+    fn get_box(&self) -> ShopBox<'_> {
+        let visitors = self.visitors.iter().flatten().count() as u8;
+
+        ShopBox {
+            object_id: self.id,
+            title: &self.title,
+            permit_id: self.permit_id,
+            characters: match self.open {
+                true if self.hired => visitors,
+                true => visitors + 1,
+                false => 0,
+            },
+        }
+    }
+
+    /// Gets the packet that shows the shop to a character in its map
+    pub fn spawn_packet(&self) -> Packet {
+        if self.hired {
+            packet::spawn_hired_merchant(self.owner.id, &self.owner.name, self.pos, self.get_box())
+        } else {
+            packet::update_character_box(self.owner.id, Some(self.get_box()))
+        }
+    }
+
+    /// Gets the packet that updates the shop's box in its map
+    fn box_packet(&self) -> Packet {
+        if self.hired {
+            packet::update_hired_merchant(self.owner.id, self.get_box())
+        } else {
+            packet::update_character_box(self.owner.id, Some(self.get_box()))
+        }
+    }
+
+    fn remove_packet(&self) -> Packet {
+        if self.hired {
+            packet::remove_hired_merchant(self.owner.id)
+        } else {
+            packet::update_character_box(self.owner.id, None)
+        }
+    }
+
+    /// Gets the packet that opens the shop's window for the character in `slot`
+    fn room_packet(&self, slot: u8, mesos: i32, first_time: bool) -> Packet {
+        let visitors: Vec<RoomVisitor> = self
+            .visitors
+            .iter()
+            .enumerate()
+            .filter_map(|(index, visitor)| {
+                let visitor = visitor.as_ref()?;
+                Some((index as u8 + 1, &visitor.data, visitor.equipment.as_slice()))
+            })
+            .collect();
+
+        if self.hired {
+            packet::hired_merchant_room(
+                slot,
+                &self.owner.name,
+                &visitors,
+                self.get_box(),
+                first_time,
+                mesos,
+                &self.items,
+            )
+        } else {
+            packet::player_shop_room(
+                slot,
+                (&self.owner, &self.owner_equipment),
+                &visitors,
+                &self.title,
+                &self.items,
+            )
+        }
+    }
+}
+
+/// Sets up a player shop or hired merchant, the character can then list items before opening it
+/// `permit_id` is the cash item used to place the shop
+pub async fn create(
+    session: &mut ChannelSession,
+    hired: bool,
+    title: String,
+    permit_id: i32,
+) -> anyhow::Result<()> {
+    if session.trade.is_some() || is_in_shop(session).await || minigame::is_in_room(session).await {
+        return Ok(());
+    }
+
+    let permit_type = if hired { 503 } else { 514 };
+
+    if permit_id / 10000 != permit_type {
+        return Ok(());
+    }
+
+    if let Some(error) = get_open_error(session, permit_id).await? {
+        return send_notice(session, error).await;
+    }
+
+    let character = session.character.as_ref().unwrap();
+
+    let shop = PlayerShop {
+        id: session.state.next_object_id(),
+        hired,
+        owner: character.data.clone(),
+        owner_equipment: character.equipment.clone(),
+        permit_id,
+        title,
+        map_id: character.data.map,
+        pos: character.pos,
+        open: false,
+        spawned: false,
+        owner_present: true,
+        closed: false,
+        items: Vec::new(),
+        mesos: 0,
+        visitors: Default::default(),
+        blacklist: Vec::new(),
+        visit_list: Vec::new(),
+    };
+
+    session
+        .stream
+        .write_packet(shop.room_packet(0, 0, true))
+        .await?;
+
+    let (map_id, shop_id) = (shop.map_id, shop.id);
+    let shop = Arc::new(Mutex::new(shop));
+    session.state.add_player_shop(map_id, shop_id, shop.clone());
+    session.player_shop = Some(shop);
+    Ok(())
+}
+
+/// Checks if the character uses a hired merchant permit they can set up a merchant with, showing them the setup
+/// window if they can
+pub async fn request_hired_merchant(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let character = session.character.as_ref().unwrap();
+
+    let permit_id = match character
+        .items
+        .iter()
+        .find(|item| item.inventory_type == InventoryType::Cash && item.item_id / 10000 == 503)
+    {
+        Some(item) => item.item_id,
+        None => return Ok(()),
+    };
+
+    if let Some(error) = get_open_error(session, permit_id).await? {
+        return send_notice(session, error).await;
+    }
+
+    session
+        .stream
+        .write_packet(packet::show_hired_merchant_setup())
+        .await
+}
+
+/// Opens the character's shop to visitors once they're done setting it up or maintaining it
+/// The owner of a hired merchant leaves it once it's open
+pub async fn open(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let (mut shop, slot) = match lock_current(session).await {
+        Some(current) => current,
+        None => return Ok(()),
+    };
+
+    if slot != 0 || shop.open {
+        return Ok(());
+    }
+
+    open_shop(session, &mut shop).await
+}
+
+/// Visits a player shop or hired merchant in the character's map
+/// The owner of a hired merchant visiting it closes it to visitors while they maintain it
+pub async fn visit(session: &mut ChannelSession, shop_id: i32) -> anyhow::Result<()> {
+    if session.trade.is_some() || is_in_shop(session).await || minigame::is_in_room(session).await {
+        return Ok(());
+    }
+
+    let character = session.character.as_ref().unwrap();
+    let (data, equipment) = (character.data.clone(), character.equipment.clone());
+
+    let shared_shop = match session.state.get_player_shop(data.map, shop_id) {
+        Some(shop) => shop,
+        None => return send_notice(session, "The shop has already been closed.").await,
+    };
+
+    let mut shop = shared_shop.lock().await;
+
+    if shop.closed || !shop.spawned {
+        return send_notice(session, "The shop has already been closed.").await;
+    }
+
+    if shop.owner.id == data.id {
+        if !shop.hired || shop.owner_present {
+            return Ok(());
+        }
+
+        shop.open = false;
+        kick_visitors(session, &mut shop).await?;
+        shop.owner_present = true;
+        session.broadcast_packet(shop.box_packet(), true)?;
+
+        let packet = shop.room_packet(0, shop.mesos, false);
+        session.stream.write_packet(packet).await?;
+
+        drop(shop);
+        session.player_shop = Some(shared_shop);
+        return Ok(());
+    }
+
+    if !shop.open {
+        return send_notice(session, "The shop is being maintained right now.").await;
+    }
+
+    if shop.hired && shop.blacklist.contains(&data.name) {
+        return send_notice(session, "You can't enter this shop.").await;
+    }
+
+    let index = match shop.visitors.iter().position(Option::is_none) {
+        Some(index) => index,
+        None => return send_notice(session, "The shop is full.").await,
+    };
+
+    let slot = index as u8 + 1;
+
+    for character_id in shop.get_character_ids() {
+        let packet = packet::room_visitor_joined(slot, &data, &equipment);
+        send_to(session, character_id, packet).await?;
+    }
+
+    let mesos = data.mesos;
+    shop.visitors[index] = Some(ShopVisitor {
+        data,
+        equipment,
+        entered_at: Utc::now().timestamp_millis(),
+    });
+
+    let packet = shop.room_packet(slot, mesos, false);
+    session.stream.write_packet(packet).await?;
+    session.broadcast_packet(shop.box_packet(), true)?;
+
+    drop(shop);
+    session.player_shop = Some(shared_shop);
+    Ok(())
+}
+
+/// Sends a chat message to everyone in the character's shop
+pub async fn chat(session: &mut ChannelSession, message: &str) -> anyhow::Result<()> {
+    let (shop, slot) = match lock_current(session).await {
+        Some(current) => current,
+        None => return Ok(()),
+    };
+
+    let name = session.character.as_ref().unwrap().data.name.clone();
+
+    for character_id in shop.get_character_ids() {
+        let packet = packet::room_chat(slot, &name, message);
+        send_to(session, character_id, packet).await?;
+    }
+
+    Ok(())
+}
+
+/// Leaves the character's shop
+/// A player shop closes when its owner leaves, a hired merchant reopens once its owner is done maintaining it
+/// Called when the character closes the shop window, changes maps, or logs out
+pub async fn leave(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let (mut shop, slot) = match lock_current(session).await {
+        Some(current) => current,
+        None => return Ok(()),
+    };
+
+    session.player_shop = None;
+
+    if slot != 0 {
+        return remove_visitor(session, &mut shop, slot).await;
+    }
+
+    if shop.hired && shop.spawned {
+        open_shop(session, &mut shop).await
+    } else {
+        close(session, &mut shop).await
+    }
+}
+
+/// Lists an item from the character's inventory in their shop, in `bundles` bundles of `amount` for `price` each
+pub async fn add_item(
+    session: &mut ChannelSession,
+    inventory_type: InventoryType,
+    position: i32,
+    amount: i32,
+    bundles: i32,
+    price: i32,
+) -> anyhow::Result<()> {
+    let (mut shop, slot) = match lock_current(session).await {
+        Some(current) => current,
+        None => return Ok(()),
+    };
+
+    if slot != 0 || shop.open || shop.items.len() >= SHOP_SLOTS {
+        return Ok(());
+    }
+
+    if amount <= 0 || bundles <= 0 || price <= 0 {
+        return Ok(());
+    }
+
+    let character = session.character.as_mut().unwrap();
+
+    let item = match character
+        .items
+        .iter()
+        .find(|item| item.inventory_type == inventory_type && item.position == position)
+    {
+        Some(item) => item,
+        None => return Ok(()),
+    };
+
+    let data = nx::Item::load(item.item_id)?;
+
+    // Listed items don't keep when they expire, so items that expire can't be sold either
+    if data.is_untradeable || item.expires_at.is_some() {
+        return send_notice(session, "That item can't be sold.").await;
+    }
+
+    // Throwing stars and bullets can only be sold as a whole set
+    let (amount, bundles) = if data.is_rechargeable() {
+        (item.amount, 1)
+    } else {
+        (amount, bundles)
+    };
+
+    let total = match amount.checked_mul(bundles) {
+        Some(total) => total,
+        None => return Ok(()),
+    };
+
+    let (item, whole_stack) = match character.take_item(inventory_type, position, total) {
+        Some(taken) => taken,
+        None => return Ok(()),
+    };
+
+    let shop_position = shop
+        .items
+        .iter()
+        .map(|listed| listed.position + 1)
+        .max()
+        .unwrap_or(0);
+
+    let listed = match sql::PlayerShopItem::list(
+        &item,
+        whole_stack,
+        bundles,
+        price,
+        shop_position,
+        &session.db,
+    )
+    .await?
+    {
+        Some(listed) => listed,
+        None => {
+            log::warn!(
+                "Item {} is no longer in character {}'s inventory",
+                item.id,
+                item.character_id
+            );
+            return Ok(());
+        }
+    };
+
+    shop.items.push(listed);
+
+    let change = if whole_stack {
+        InventoryChange::Remove(item)
+    } else {
+        let remaining = character
+            .items
+            .iter()
+            .find(|remaining| remaining.id == item.id)
+            .unwrap();
+        InventoryChange::Update(remaining.clone())
+    };
+
+    session
+        .stream
+        .write_packet(packet::update_inventory(&[change]))
+        .await?;
+    send_items(session, &shop).await
+}
+
+/// Buys bundles of an item from the shop the character is visiting
+/// `index` is the item's place in the shop's list
+pub async fn buy(session: &mut ChannelSession, index: usize, bundles: i32) -> anyhow::Result<()> {
+    let (mut shop, slot) = match lock_current(session).await {
+        Some(current) => current,
+        None => return Ok(()),
+    };
+
+    if slot == 0 || !shop.open {
+        return Ok(());
+    }
+
+    let item = match shop.items.get(index) {
+        Some(item) if bundles > 0 && item.bundles >= bundles => item.clone(),
+        _ => return Ok(()),
+    };
+
+    let price = item.price as i64 * bundles as i64;
+    let character = session.character.as_mut().unwrap();
+
+    if price > character.data.mesos as i64 {
+        return send_notice(session, "You don't have enough mesos.").await;
+    }
+
+    if price + shop.mesos as i64 > i32::MAX as i64 {
+        return send_notice(session, "The shop can't hold any more mesos.").await;
+    }
+
+    let position = match character.get_free_position(item.inventory_type) {
+        Some(position) => position,
+        None => return send_notice(session, "Your inventory is full.").await,
+    };
+
+    let bought = match item
+        .buy(bundles, character.data.id, position, &session.db)
+        .await?
+    {
+        Some(bought) => bought,
+        None => return Ok(()),
+    };
+
+    character.data.mesos -= price as i32;
+    character.items.push(bought.clone());
+    let mesos = character.data.mesos;
+    let character_id = character.data.id;
+
+    shop.items[index].bundles -= bundles;
+    shop.mesos += price as i32;
+
+    session
+        .stream
+        .write_packet(packet::update_inventory(&[InventoryChange::Add(bought)]))
+        .await?;
+    session
+        .stream
+        .write_packet(packet::update_stats(&[(Stat::Mesos, mesos)]))
+        .await?;
+    send_items(session, &shop).await?;
+
+    // Everyone else in the shop sees the item's new amount with their own mesos
+    for id in shop.get_character_ids() {
+        if id != character_id {
+            session
+                .state
+                .send_to_session(id, SessionMessage::PlayerShopChanged)
+                .await;
+        }
+    }
+
+    Ok(())
+}
+
+/// Takes an item the character listed in their shop back into their inventory
+/// `index` is the item's place in the shop's list
+pub async fn remove_item(session: &mut ChannelSession, index: usize) -> anyhow::Result<()> {
+    let (mut shop, slot) = match lock_current(session).await {
+        Some(current) => current,
+        None => return Ok(()),
+    };
+
+    if slot != 0 || shop.open {
+        return Ok(());
+    }
+
+    let item = match shop.items.get(index) {
+        Some(item) if item.bundles > 0 => item.clone(),
+        _ => return Ok(()),
+    };
+
+    let character = session.character.as_mut().unwrap();
+
+    let position = match character.get_free_position(item.inventory_type) {
+        Some(position) => position,
+        None => return send_notice(session, "Your inventory is full.").await,
+    };
+
+    let returned = match item.take_back(position, &session.db).await? {
+        Some(returned) => returned,
+        None => return Ok(()),
+    };
+
+    character.items.push(returned.clone());
+    shop.items.remove(index);
+
+    session
+        .stream
+        .write_packet(packet::update_inventory(&[InventoryChange::Add(returned)]))
+        .await?;
+    send_items(session, &shop).await
+}
+
+/// Removes a visitor from the character's player shop
+pub async fn ban(session: &mut ChannelSession, name: &str) -> anyhow::Result<()> {
+    let (mut shop, slot) = match lock_current(session).await {
+        Some(current) => current,
+        None => return Ok(()),
+    };
+
+    if slot != 0 || shop.hired {
+        return Ok(());
+    }
+
+    let (slot, visitor_id) = match shop
+        .visitors
+        .iter()
+        .enumerate()
+        .find_map(|(index, visitor)| {
+            let visitor = visitor.as_ref()?;
+            (visitor.data.name == name).then_some((index as u8 + 1, visitor.data.id))
+        }) {
+        Some(visitor) => visitor,
+        None => return Ok(()),
+    };
+
+    remove_visitor(session, &mut shop, slot).await?;
+
+    let packet = packet::room_closed(slot, RoomLeaveReason::Banned);
+    send_to(session, visitor_id, packet).await
+}
+
+/// Removes the sold out items from the character's hired merchant and collects its mesos
+pub async fn organize(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let (mut shop, slot) = match lock_current(session).await {
+        Some(current) => current,
+        None => return Ok(()),
+    };
+
+    if slot != 0 || !shop.hired {
+        return Ok(());
+    }
+
+    shop.items.retain(|item| item.bundles > 0);
+    collect_mesos(session, &mut shop).await?;
+    send_items(session, &shop).await
+}
+
+/// Collects the mesos the character's hired merchant earned
+pub async fn withdraw_mesos(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let (mut shop, slot) = match lock_current(session).await {
+        Some(current) => current,
+        None => return Ok(()),
+    };
+
+    if slot != 0 || !shop.hired {
+        return Ok(());
+    }
+
+    collect_mesos(session, &mut shop).await?;
+    send_items(session, &shop).await
+}
+
+/// Closes the hired merchant the character is maintaining
+pub async fn close_merchant(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let (mut shop, slot) = match lock_current(session).await {
+        Some(current) => current,
+        None => return Ok(()),
+    };
+
+    if slot != 0 || !shop.hired {
+        return Ok(());
+    }
+
+    session.player_shop = None;
+    close(session, &mut shop).await?;
+
+    let packet = packet::room_closed(0, RoomLeaveReason::Closed);
+    session.stream.write_packet(packet).await
+}
+
+/// Shows the owner of a hired merchant who visited it
+pub async fn show_visit_list(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let (shop, slot) = match lock_current(session).await {
+        Some(current) => current,
+        None => return Ok(()),
+    };
+
+    if slot != 0 || !shop.hired {
+        return Ok(());
+    }
+
+    let packet = packet::hired_merchant_visit_list(&shop.visit_list);
+    session.stream.write_packet(packet).await
+}
+
+/// Shows the owner of a hired merchant who can't visit it
+pub async fn show_blacklist(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let (shop, slot) = match lock_current(session).await {
+        Some(current) => current,
+        None => return Ok(()),
+    };
+
+    if slot != 0 || !shop.hired {
+        return Ok(());
+    }
+
+    let packet = packet::hired_merchant_blacklist(&shop.blacklist);
+    session.stream.write_packet(packet).await
+}
+
+/// Adds a character to or removes them from the blacklist of the hired merchant the character is maintaining
+pub async fn update_blacklist(
+    session: &mut ChannelSession,
+    name: String,
+    blacklisted: bool,
+) -> anyhow::Result<()> {
+    let (mut shop, slot) = match lock_current(session).await {
+        Some(current) => current,
+        None => return Ok(()),
+    };
+
+    if slot != 0 || !shop.hired {
+        return Ok(());
+    }
+
+    if !blacklisted {
+        shop.blacklist.retain(|blacklisted| *blacklisted != name);
+    } else if !shop.blacklist.contains(&name) {
+        shop.blacklist.push(name);
+    }
+
+    Ok(())
+}
+
+/// Shows the character the current items of the shop they're in, called after another character bought from it
+/// The owner of a player shop collects the mesos it earned right away
+pub async fn refresh(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let (mut shop, slot) = match lock_current(session).await {
+        Some(current) => current,
+        None => return Ok(()),
+    };
+
+    if slot == 0 && !shop.hired {
+        collect_mesos(session, &mut shop).await?;
+    }
+
+    send_items(session, &shop).await
+}
+
+/// Shows the character the items and mesos their closed shops left with Fredrick
+pub async fn open_fredrick(session: &mut ChannelSession, npc_id: i32) -> anyhow::Result<()> {
+    let character_id = session.character.as_ref().unwrap().data.id;
+
+    // The items of an open hired merchant are still for sale
+    if sql::HiredMerchant::load_optional(character_id, &session.db)
+        .await?
+        .is_some()
+    {
+        send_notice(session, "Please close your hired merchant first.").await?;
+        return session.stream.write_packet(packet::update_stats(&[])).await;
+    }
+
+    let items = sql::PlayerShopItem::load_all(character_id, &session.db).await?;
+    let mesos = sql::Character::load_merchant_mesos(character_id, &session.db).await?;
+
+    session
+        .stream
+        .write_packet(packet::fredrick_items(npc_id, mesos, &items))
+        .await
+}
+
+/// Moves the items and mesos Fredrick is keeping back to the character
+/// Nothing is retrieved unless all of the items fit in the character's inventory
+pub async fn retrieve_from_fredrick(session: &mut ChannelSession) -> anyhow::Result<()> {
+    if session.player_shop.is_some() {
+        return Ok(());
+    }
+
+    let character = session.character.as_mut().unwrap();
+    let character_id = character.data.id;
+
+    if sql::HiredMerchant::load_optional(character_id, &session.db)
+        .await?
+        .is_some()
+    {
+        return Ok(());
+    }
+
+    let items = sql::PlayerShopItem::load_all(character_id, &session.db).await?;
+
+    let mut free_positions = [
+        InventoryType::Equip,
+        InventoryType::Use,
+        InventoryType::Setup,
+        InventoryType::Etc,
+        InventoryType::Cash,
+    ]
+    .map(|inventory_type| character.get_free_positions(inventory_type));
+
+    let mut positions = Vec::new();
+
+    for item in items.iter() {
+        let free = &mut free_positions[item.inventory_type as usize];
+
+        if free.is_empty() {
+            return session
+                .stream
+                .write_packet(packet::fredrick_message(FredrickMessage::InventoryFull))
+                .await;
+        }
+
+        positions.push(free.remove(0));
+    }
+
+    let mut changes = Vec::new();
+
+    for (item, position) in items.iter().zip(positions) {
+        if let Some(returned) = item.take_back(position, &session.db).await? {
+            character.items.push(returned.clone());
+            changes.push(InventoryChange::Add(returned));
+        }
+    }
+
+    let max = i32::MAX - character.data.mesos;
+    let mesos = sql::Character::take_merchant_mesos(character_id, max, &session.db).await?;
+    character.data.mesos += mesos;
+    let total = character.data.mesos;
+
+    if !changes.is_empty() {
+        session
+            .stream
+            .write_packet(packet::update_inventory(&changes))
+            .await?;
+    }
+
+    if mesos > 0 {
+        session
+            .stream
+            .write_packet(packet::update_stats(&[(Stat::Mesos, total)]))
+            .await?;
+    }
+
+    session
+        .stream
+        .write_packet(packet::fredrick_message(FredrickMessage::Retrieved))
+        .await
+}
+
+/// Gets why the character can't set up a shop with the permit, or None if they can
+async fn get_open_error(
+    session: &ChannelSession,
+    permit_id: i32,
+) -> anyhow::Result<Option<&'static str>> {
+    let character = session.character.as_ref().unwrap();
+    let character_id = character.data.id;
+
+    if !FREE_MARKET_ROOMS.contains(&character.data.map) {
+        return Ok(Some("You can only open a shop in the Free Market rooms."));
+    }
+
+    if !character
+        .items
+        .iter()
+        .any(|item| item.inventory_type == InventoryType::Cash && item.item_id == permit_id)
+    {
+        return Ok(Some("You don't have a permit to open a shop."));
+    }
+
+    if sql::HiredMerchant::load_optional(character_id, &session.db)
+        .await?
+        .is_some()
+    {
+        return Ok(Some("You already have an open hired merchant."));
+    }
+
+    // Items left from an earlier shop would be mixed up with the new shop's items
+    if !sql::PlayerShopItem::load_all(character_id, &session.db)
+        .await?
+        .is_empty()
+        || sql::Character::load_merchant_mesos(character_id, &session.db).await? > 0
+    {
+        return Ok(Some(
+            "Please retrieve your items and mesos from Fredrick first.",
+        ));
+    }
+
+    Ok(None)
+}
+
+/// Checks if the character is in a player shop or hired merchant, forgetting the shop if they were removed from it
+pub async fn is_in_shop(session: &mut ChannelSession) -> bool {
+    lock_current(session).await.is_some()
+}
+
+/// Locks the shop the character is in, along with their slot in it
+/// Forgets the shop if the character is no longer in it (ex. it was closed or they were banned)
+async fn lock_current(session: &mut ChannelSession) -> Option<(OwnedMutexGuard<PlayerShop>, u8)> {
+    let shop = session.player_shop.clone()?.lock_owned().await;
+    let character_id = session.character.as_ref().unwrap().data.id;
+
+    match shop.get_slot(character_id) {
+        Some(slot) if !shop.closed => Some((shop, slot)),
+        _ => {
+            session.player_shop = None;
+            None
+        }
+    }
+}
+
+/// Opens the shop to visitors, showing it in the map the first time it's opened
+async fn open_shop(session: &mut ChannelSession, shop: &mut PlayerShop) -> anyhow::Result<()> {
+    shop.open = true;
+
+    if shop.hired {
+        shop.owner_present = false;
+        session.player_shop = None;
+
+        if !shop.spawned {
+            let merchant = sql::HiredMerchant {
+                character_id: shop.owner.id,
+                world_id: session.world_id,
+                channel_id: session.channel_id,
+                map_id: shop.map_id,
+            };
+            merchant.insert(&session.db).await?;
+        }
+    }
+
+    let packet = if shop.spawned {
+        shop.box_packet()
+    } else {
+        shop.spawn_packet()
+    };
+
+    shop.spawned = true;
+    session.broadcast_packet(packet, true)
+}
+
+/// Closes the shop, called with its owner's session
+/// The owner gets their items back while they have inventory space, anything else is left with Fredrick
+async fn close(session: &mut ChannelSession, shop: &mut PlayerShop) -> anyhow::Result<()> {
+    shop.closed = true;
+    session.state.remove_player_shop(shop.map_id, shop.id);
+    kick_visitors(session, shop).await?;
+
+    if shop.spawned {
+        session.broadcast_packet(shop.remove_packet(), true)?;
+    }
+
+    if shop.hired {
+        sql::HiredMerchant::delete(shop.owner.id, &session.db).await?;
+    }
+
+    let character = session.character.as_mut().unwrap();
+    let mut changes = Vec::new();
+
+    for item in shop.items.drain(..).filter(|item| item.bundles > 0) {
+        let position = match character.get_free_position(item.inventory_type) {
+            Some(position) => position,
+            None => continue,
+        };
+
+        if let Some(returned) = item.take_back(position, &session.db).await? {
+            character.items.push(returned.clone());
+            changes.push(InventoryChange::Add(returned));
+        }
+    }
+
+    if !changes.is_empty() {
+        session
+            .stream
+            .write_packet(packet::update_inventory(&changes))
+            .await?;
+    }
+
+    collect_mesos(session, shop).await
+}
+
+/// Removes every visitor from the shop
+async fn kick_visitors(session: &mut ChannelSession, shop: &mut PlayerShop) -> anyhow::Result<()> {
+    for index in 0..shop.visitors.len() {
+        let visitor_id = match &shop.visitors[index] {
+            Some(visitor) => visitor.data.id,
+            None => continue,
+        };
+
+        let slot = index as u8 + 1;
+        remove_visitor(session, shop, slot).await?;
+
+        let packet = packet::room_closed(slot, RoomLeaveReason::Closed);
+        send_to(session, visitor_id, packet).await?;
+    }
+
+    Ok(())
+}
+
+/// Removes the visitor in `slot` from the shop, showing the shop's other characters that they left
+async fn remove_visitor(
+    session: &mut ChannelSession,
+    shop: &mut PlayerShop,
+    slot: u8,
+) -> anyhow::Result<()> {
+    let visitor = match shop.visitors[slot as usize - 1].take() {
+        Some(visitor) => visitor,
+        None => return Ok(()),
+    };
+
+    if shop.hired {
+        let duration = Utc::now().timestamp_millis() - visitor.entered_at;
+        shop.visit_list.push((visitor.data.name, duration as i32));
+
+        if shop.visit_list.len() > VISIT_LIST_SIZE {
+            shop.visit_list.remove(0);
+        }
+    }
+
+    for character_id in shop.get_character_ids() {
+        send_to(session, character_id, packet::room_visitor_left(slot)).await?;
+    }
+
+    if shop.spawned && !shop.closed {
+        session.broadcast_packet(shop.box_packet(), true)?;
+    }
+
+    Ok(())
+}
+
+/// Moves the mesos the shop earned to its owner, called with the owner's session
+async fn collect_mesos(session: &mut ChannelSession, shop: &mut PlayerShop) -> anyhow::Result<()> {
+    if shop.mesos == 0 {
+        return Ok(());
+    }
+
+    let character = session.character.as_mut().unwrap();
+    let max = shop.mesos.min(i32::MAX - character.data.mesos);
+    let mesos = sql::Character::take_merchant_mesos(character.data.id, max, &session.db).await?;
+
+    shop.mesos -= mesos;
+    character.data.mesos += mesos;
+    let total = character.data.mesos;
+
+    session
+        .stream
+        .write_packet(packet::update_stats(&[(Stat::Mesos, total)]))
+        .await
+}
+
+/// Shows the character the shop's items
+/// The owner of a hired merchant sees its uncollected mesos, everyone else sees their own mesos
+async fn send_items(session: &mut ChannelSession, shop: &PlayerShop) -> anyhow::Result<()> {
+    let character = session.character.as_ref().unwrap();
+
+    let packet = if !shop.hired {
+        packet::update_player_shop_items(&shop.items)
+    } else if character.data.id == shop.owner.id {
+        packet::update_hired_merchant_items(shop.mesos, &shop.items)
+    } else {
+        packet::update_hired_merchant_items(character.data.mesos, &shop.items)
+    };
+
+    session.stream.write_packet(packet).await
+}
+
+/// Sends a packet to a character in the shop, everyone in a shop is on the same channel
+async fn send_to(
+    session: &mut ChannelSession,
+    character_id: i32,
+    packet: Packet,
+) -> anyhow::Result<()> {
+    if session.character.as_ref().unwrap().data.id == character_id {
+        return session.stream.write_packet(packet).await;
+    }
+
+    session
+        .state
+        .send_to_session(character_id, SessionMessage::Packet(packet))
+        .await;
+    Ok(())
+}
+
+async fn send_notice(session: &mut ChannelSession, message: &str) -> anyhow::Result<()> {
+    session
+        .stream
+        .write_packet(packet::server_notice(NoticeType::Popup, message))
+        .await
+}
@@ -0,0 +1,146 @@
+use crate::{drop, session::ChannelSession};
+use rand::Rng;
+use slate_data::{
+    maple::{
+        self,
+        map::{MapBroadcast, PacketBroadcast},
+    },
+    nx, packet, sql,
+    sql::quest::QuestStatus,
+};
+use std::time::Duration;
+use tokio::time;
+
+// TODO reactors triggered by dropping items onto them, once characters can drop items
+
+/// The crack in the Origin of Clocktower, summons Papulatus once it's broken
+const PAPULATUS_CRACK: i32 = 2208001;
+const PAPULATUS: i32 = 8500000;
+
+/// The altar in Zakum's map, summons Zakum once it's broken
+const ZAKUM_ALTAR: i32 = 2111001;
+const ZAKUM: i32 = 8800000;
+
+/// Hits a reactor in the character's map, triggering it once it breaks
+/// `from_left` is set when the character hit the reactor from its left
+pub async fn hit(
+    session: &mut ChannelSession,
+    object_id: i32,
+    from_left: bool,
+    stance: i16,
+    skill_id: i32,
+) -> anyhow::Result<()> {
+    let field_id = session.character.as_ref().unwrap().field_id;
+
+    let reactor = match session
+        .state
+        .hit_reactor(field_id, object_id, skill_id, from_left)
+    {
+        Some(reactor) => reactor,
+        None => return Ok(()),
+    };
+
+    session.broadcast_packet(packet::trigger_reactor(&reactor, stance), true)?;
+
+    if reactor.is_broken() {
+        act(session, &reactor).await?;
+        schedule_respawn(session, &reactor);
+    }
+
+    Ok(())
+}
+
+/// Triggers a reactor the character touched, for reactors that are activated by touch
+pub async fn touch(session: &mut ChannelSession, object_id: i32) -> anyhow::Result<()> {
+    let field_id = session.character.as_ref().unwrap().field_id;
+
+    match session.state.get_reactor(field_id, object_id) {
+        Some(reactor) if reactor.data.activate_by_touch => {
+            hit(session, object_id, false, 0, 0).await
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Runs what a reactor does once it breaks, summoning bosses or dropping its items
+async fn act(session: &mut ChannelSession, reactor: &maple::Reactor) -> anyhow::Result<()> {
+    match reactor.data.id {
+        PAPULATUS_CRACK => summon(session, PAPULATUS, reactor.pos),
+        ZAKUM_ALTAR => summon(session, ZAKUM, reactor.pos),
+        _ => drop_items(session, reactor).await,
+    }
+}
+
+/// Spawns a monster in the character's map
+fn summon(session: &mut ChannelSession, monster_id: i32, pos: (i32, i32)) -> anyhow::Result<()> {
+    let field_id = session.character.as_ref().unwrap().field_id;
+    let data = nx::Mob::load(monster_id)?;
+
+    // TODO use the foothold below the reactor once footholds are tracked
+    let monster = maple::Monster::new(session.state.next_object_id(), data, pos, 0);
+    session.broadcast_packet(packet::spawn_monster(&monster, true), true)?;
+    session.state.add_monster(field_id, monster);
+    Ok(())
+}
+
+/// Drops a broken reactor's items (ex. party quest boxes), quest items only drop for characters on the quest
+async fn drop_items(session: &mut ChannelSession, reactor: &maple::Reactor) -> anyhow::Result<()> {
+    let drops = sql::ReactorDrop::load_all(reactor.data.id, &session.db).await?;
+    let character = session.character.as_ref().unwrap();
+    let mut rng = rand::thread_rng();
+
+    let item_ids: Vec<i32> = drops
+        .iter()
+        .filter(|drop| match drop.quest_id {
+            Some(quest_id) => character
+                .quests
+                .iter()
+                .any(|quest| quest.id == quest_id && matches!(quest.status, QuestStatus::Started)),
+            None => true,
+        })
+        .filter(|drop| rng.gen_range(0..drop.chance.max(1)) == 0)
+        .map(|drop| drop.item_id)
+        .collect();
+
+    drop::spawn(session, reactor.object_id, reactor.pos, &item_ids)
+}
+
+/// Respawns a broken reactor after its reactor time, reactors without a reactor time stay broken
+fn schedule_respawn(session: &ChannelSession, reactor: &maple::Reactor) {
+    if reactor.reactor_time <= 0 {
+        return;
+    }
+
+    let state = session.state.clone();
+    let field_id = session.character.as_ref().unwrap().field_id;
+    let object_id = reactor.object_id;
+    let delay = Duration::from_secs(reactor.reactor_time as u64);
+
+    tokio::spawn(async move {
+        time::sleep(delay).await;
+
+        let broken = match state.get_reactor(field_id, object_id) {
+            Some(broken) => broken,
+            None => return,
+        };
+
+        let reactor = match state.reset_reactor(field_id, object_id) {
+            Some(reactor) => reactor,
+            None => return,
+        };
+
+        let tx = state.get_map_broadcast_tx(field_id);
+
+        for packet in [
+            packet::destroy_reactor(&broken),
+            packet::spawn_reactor(&reactor),
+        ] {
+            // Nobody might be in the map to see it respawn
+            let _ = tx.send(MapBroadcast::Packet(PacketBroadcast {
+                packet,
+                sender_id: 0,
+                send_to_sender: true,
+            }));
+        }
+    });
+}
@@ -1,5 +1,6 @@
-use crate::{session::ChannelSession, shutdown::Shutdown, state::State};
-use slate_data::sql;
+use crate::{session::ChannelSession, shutdown::Shutdown, state::State, transport, world};
+use anyhow::anyhow;
+use slate_data::{sql, Config};
 use slate_net::MapleStream;
 use sqlx::{MySql, Pool};
 use std::{env, sync::Arc, time::Instant};
@@ -73,7 +74,23 @@ impl ChannelServer {
         );
 
         let mut session_id = 0;
-        let state = Arc::new(State::new());
+        let world = Config::load()
+            .worlds
+            .into_iter()
+            .nth(self.data.world_id as usize)
+            .ok_or_else(|| anyhow!("World {} is missing from the config", self.data.world_id))?;
+        let state = Arc::new(State::new(world));
+
+        // Deliver messages sent to this channel's characters from other channels
+        tokio::spawn(world::listen(
+            state.clone(),
+            self.db.clone(),
+            self.data.world_id,
+            self.data.id,
+        ));
+
+        // Boats, trains and subways run on a schedule
+        transport::start(state.clone());
 
         loop {
             let stream = match listener.accept().await {
@@ -86,6 +103,8 @@ impl ChannelServer {
 
             session_id += 1;
 
+            let (session_tx, session_rx) = mpsc::channel(64);
+
             let session = ChannelSession {
                 id: session_id,
                 stream,
@@ -94,11 +113,27 @@ impl ChannelServer {
                 channel_id: self.data.id,
                 account_id: None,
                 character: None,
+                login_session_id: None,
+                transitioning: false,
                 shutdown: Shutdown::new(self.notify_shutdown.subscribe()),
                 _shutdown_complete: self.shutdown_complete_tx.clone(),
                 state: state.clone(),
                 map_broadcast_tx: None,
                 map_broadcast_rx: None,
+                session_tx,
+                session_rx,
+                party_invite: None,
+                guild_invite: None,
+                alliance_invite: None,
+                trade: None,
+                trade_invite: None,
+                npc_conversation: None,
+                shop: None,
+                storage: None,
+                player_shop: None,
+                minigame: None,
+                cash_shop: None,
+                mts: None,
             };
 
             // Spawn a task for handling the new login session
@@ -119,6 +154,12 @@ impl ChannelServer {
             .execute(&self.db)
             .await?;
 
+        // Clean up after the channel if it wasn't shut down gracefully
+        sql::OnlineCharacter::delete_all(self.data.world_id, self.data.id, &self.db).await?;
+        sql::WorldMessage::delete_up_to(self.data.world_id, self.data.id, i32::MAX, &self.db)
+            .await?;
+        sql::HiredMerchant::delete_all(self.data.world_id, self.data.id, &self.db).await?;
+
         log::info!("Finished startup tasks in {:?}", start.elapsed());
         Ok(())
     }
@@ -134,6 +175,9 @@ impl ChannelServer {
             .execute(&self.db)
             .await?;
 
+        sql::OnlineCharacter::delete_all(self.data.world_id, self.data.id, &self.db).await?;
+        sql::HiredMerchant::delete_all(self.data.world_id, self.data.id, &self.db).await?;
+
         log::info!("Finished shutdown tasks in {:?}", start.elapsed());
         Ok(())
     }
@@ -1,12 +1,43 @@
-use crate::{packet_handler, shutdown::Shutdown, state::State};
+use crate::{
+    alliance, buddy,
+    cash_shop::CashShop,
+    event, expedition, fishing, guild,
+    minigame::{self, SharedMinigame},
+    mount,
+    mts::Mts,
+    packet_handler, party, pet,
+    player_shop::{self, SharedPlayerShop},
+    shop::Shop,
+    shutdown::Shutdown,
+    state::{MapCharacter, State},
+    storage::OpenStorage,
+    trade::{self, SharedTrade},
+    transport,
+};
+use anyhow::anyhow;
 use slate_data::{
-    maple::{self, map::MapBroadcast},
-    sql::{self, account::LoginState},
+    maple::{
+        self,
+        buff::BuffStat,
+        character::InventoryChange,
+        map::{MapBroadcast, PacketBroadcast, PartyPacketBroadcast, PartySkillBroadcast},
+    },
+    nx,
+    packet::{self, NoticeType, SpecialEffect, Stat},
+    sql::{self, account::LoginState, item::InventoryType},
+};
+use slate_net::{MapleStream, Packet};
+use sqlx::{types::chrono::Utc, MySql, Pool};
+use std::{env, sync::Arc, time::Duration};
+use tokio::{
+    sync::{broadcast, mpsc},
+    time::{self, timeout},
 };
-use slate_net::MapleStream;
-use sqlx::{MySql, Pool};
-use std::sync::Arc;
-use tokio::sync::{broadcast, mpsc};
+
+/// Safety charms prevent exp loss when a character dies
+const SAFETY_CHARM: i32 = 5130000;
+const IMPROVED_HP_RECOVERY: i32 = 1000000;
+const IMPROVED_MP_RECOVERY: i32 = 2000000;
 
 pub struct ChannelSession {
     pub id: i32,
@@ -18,6 +49,13 @@ pub struct ChannelSession {
     pub account_id: Option<i32>,
     pub character: Option<maple::Character>,
 
+    // The login session the client connected with, the client sends its id again when it migrates to a new
+    // connection
+    pub login_session_id: Option<i32>,
+
+    // Set once the client has been told to migrate to a new connection, the account stays logged in
+    pub transitioning: bool,
+
     // Graceful shutdown handlers
     pub shutdown: Shutdown,
     pub _shutdown_complete: mpsc::Sender<()>,
@@ -28,6 +66,90 @@ pub struct ChannelSession {
     // Broadcast sender + receiver for the current map
     pub map_broadcast_tx: Option<broadcast::Sender<MapBroadcast>>,
     pub map_broadcast_rx: Option<broadcast::Receiver<MapBroadcast>>,
+
+    // Sender + receiver for messages sent directly to this session (whispers, etc.)
+    pub session_tx: mpsc::Sender<SessionMessage>,
+    pub session_rx: mpsc::Receiver<SessionMessage>,
+
+    // The party the character was last invited to
+    pub party_invite: Option<i32>,
+
+    // The guild the character was last invited to
+    pub guild_invite: Option<i32>,
+
+    // The alliance the character's guild was last invited to
+    pub alliance_invite: Option<i32>,
+
+    // The trade the character is in
+    pub trade: Option<SharedTrade>,
+
+    // The trade the character was last invited to
+    pub trade_invite: Option<i32>,
+
+    // The npc the character is talking to
+    pub npc_conversation: Option<i32>,
+
+    // The npc shop the character has open
+    pub shop: Option<Arc<Shop>>,
+
+    // The account storage the character has open
+    pub storage: Option<OpenStorage>,
+
+    // The player shop or hired merchant the character is in
+    pub player_shop: Option<SharedPlayerShop>,
+
+    // The minigame room the character is in
+    pub minigame: Option<SharedMinigame>,
+
+    // The cash shop the character is in, instead of a map
+    pub cash_shop: Option<CashShop>,
+
+    // The MTS the character is in, instead of a map
+    pub mts: Option<Mts>,
+}
+
+/// A message sent directly to a session, possibly from another channel
+#[derive(Debug, Clone)]
+pub enum SessionMessage {
+    Packet(Packet),
+    /// Disconnects the session (ex. when the character is banned)
+    Disconnect,
+    /// An invite to a party, by party id
+    PartyInvite(i32, Packet),
+    /// The character joined a party, or left their party if None
+    PartyChanged(Option<i32>),
+    /// Exp from a monster kill, along with the bonus exp from sharing it with a party
+    GainExp {
+        exp: i32,
+        party_bonus: i32,
+    },
+    /// A monster the character gets quest credit for, by monster id
+    KillCredit(i32),
+    /// An invite to a guild, by guild id
+    GuildInvite(i32, Packet),
+    /// The character's guild, rank, or guild emblem changed
+    GuildChanged,
+    /// An invite to an alliance, by alliance id
+    AllianceInvite(i32, Packet),
+    /// An invite to a trade, by trade id
+    TradeInvite(i32, Packet),
+    /// The character's trade was completed or cancelled by their trade partner
+    TradeClosed,
+    /// The items of the player shop or hired merchant the character is in changed
+    PlayerShopChanged,
+    /// Another character opened this character's info window, by character id
+    CharacterInfo(i32),
+    /// Moves the character to another map (ex. when their party quest ends)
+    ChangeMap {
+        map_id: i32,
+        portal_id: i32,
+    },
+    /// Moves the character to a private copy of a map (ex. when their party quest starts)
+    ChangeField {
+        map_id: i32,
+        field_id: i32,
+        portal_id: i32,
+    },
 }
 
 impl ChannelSession {
@@ -41,9 +163,21 @@ impl ChannelSession {
             return;
         }
 
-        // Keep reading packets from the client in a loop until they disconnect,
-        // an error occurs, or the server is shutting down
-        while !self.shutdown.is_shutdown() {
+        // Ticks once a second to handle timed character events (buff expiration, etc.)
+        let mut tick = time::interval(Duration::from_secs(1));
+
+        // Natural hp/mp regeneration happens every 10 seconds
+        let mut regen = time::interval(Duration::from_secs(10));
+
+        // Summoned pets get hungrier every minute, ridden mounts get more tired, and fishing characters get a catch
+        let minute = Duration::from_secs(60);
+        let mut pet_hunger = time::interval_at(time::Instant::now() + minute, minute);
+        let mut mount_tiredness = time::interval_at(time::Instant::now() + minute, minute);
+        let mut fishing = time::interval_at(time::Instant::now() + minute, minute);
+
+        // Keep reading packets from the client in a loop until they disconnect, an error occurs, the server is
+        // shutting down, or the client is migrating to a new connection
+        while !self.shutdown.is_shutdown() && !self.transitioning {
             tokio::select! {
                 res = { self.stream.read_packet() } => {
                     let packet = match res {
@@ -73,6 +207,40 @@ impl ChannelSession {
 
                     self.handle_broadcast(map_broadcast).await;
                 }
+                Some(message) = self.session_rx.recv() => {
+                    if matches!(message, SessionMessage::Disconnect) {
+                        break;
+                    }
+
+                    if let Err(e) = self.handle_message(message).await {
+                        log::error!("Error handling session message: {} [id: {}]", e, self.id);
+                    }
+                }
+                _ = tick.tick(), if self.character.is_some() => {
+                    if let Err(e) = self.handle_tick().await {
+                        log::error!("Error handling tick: {} [id: {}]", e, self.id);
+                    }
+                }
+                _ = regen.tick(), if self.character.is_some() => {
+                    if let Err(e) = self.handle_regen().await {
+                        log::error!("Error handling regen: {} [id: {}]", e, self.id);
+                    }
+                }
+                _ = pet_hunger.tick(), if self.character.is_some() => {
+                    if let Err(e) = pet::handle_hunger(&mut self).await {
+                        log::error!("Error handling pet hunger: {} [id: {}]", e, self.id);
+                    }
+                }
+                _ = mount_tiredness.tick(), if self.character.is_some() => {
+                    if let Err(e) = mount::handle_tiredness(&mut self).await {
+                        log::error!("Error handling mount tiredness: {} [id: {}]", e, self.id);
+                    }
+                }
+                _ = fishing.tick(), if self.character.is_some() => {
+                    if let Err(e) = fishing::handle_catch(&mut self).await {
+                        log::error!("Error handling fishing: {} [id: {}]", e, self.id);
+                    }
+                }
                 _ = self.shutdown.recv() => break,
             };
         }
@@ -114,11 +282,760 @@ impl ChannelSession {
                     );
                 }
             }
+            MapBroadcast::PartySkill(broadcast) => {
+                if let Err(e) = self.handle_party_skill(broadcast).await {
+                    log::error!("Error applying party skill: {} [id: {}]", e, self.id);
+                }
+            }
+            MapBroadcast::PartyPacket(broadcast) => {
+                let character = self.character.as_ref().unwrap();
+
+                if broadcast.sender_id == character.data.id
+                    || character.data.party != Some(broadcast.party_id)
+                {
+                    return;
+                }
+
+                if let Err(e) = self.stream.write_packet(broadcast.packet).await {
+                    log::error!("Error writing party packet: {} [id: {}]", e, self.id);
+                }
+            }
+        }
+    }
+
+    /// Handles a message sent directly to this session
+    pub async fn handle_message(&mut self, message: SessionMessage) -> anyhow::Result<()> {
+        match message {
+            SessionMessage::Packet(packet) => self.stream.write_packet(packet).await?,
+            // Handled in the session loop
+            SessionMessage::Disconnect => {}
+            SessionMessage::PartyInvite(party_id, packet) => {
+                self.party_invite = Some(party_id);
+                self.stream.write_packet(packet).await?;
+            }
+            SessionMessage::PartyChanged(party_id) => {
+                self.character.as_mut().unwrap().data.party = party_id;
+                self.update_map_character();
+                self.update_party_hp()?;
+                event::on_party_changed(self).await?;
+            }
+            SessionMessage::GainExp { exp, party_bonus } => {
+                self.gain_monster_exp(exp, party_bonus).await?
+            }
+            SessionMessage::KillCredit(mob_id) => self.add_quest_kill(mob_id).await?,
+            SessionMessage::GuildInvite(guild_id, packet) => {
+                self.guild_invite = Some(guild_id);
+                self.stream.write_packet(packet).await?;
+            }
+            SessionMessage::GuildChanged => guild::reload(self).await?,
+            SessionMessage::AllianceInvite(alliance_id, packet) => {
+                self.alliance_invite = Some(alliance_id);
+                self.stream.write_packet(packet).await?;
+            }
+            SessionMessage::TradeInvite(trade_id, packet) => {
+                self.trade_invite = Some(trade_id);
+                self.stream.write_packet(packet).await?;
+            }
+            SessionMessage::TradeClosed => trade::apply_outcome(self).await?,
+            SessionMessage::PlayerShopChanged => player_shop::refresh(self).await?,
+            SessionMessage::CharacterInfo(requester_id) => {
+                let character = self.character.as_ref().unwrap();
+
+                let alliance = match character.guild.as_ref().and_then(|guild| guild.alliance_id) {
+                    Some(alliance_id) => {
+                        sql::Alliance::load_optional(alliance_id, &self.db).await?
+                    }
+                    None => None,
+                };
+                let alliance_name = alliance.map(|alliance| alliance.name).unwrap_or_default();
+
+                let packet = packet::character_info(character, &alliance_name);
+                self.state
+                    .send_to_session(requester_id, SessionMessage::Packet(packet))
+                    .await;
+            }
+            SessionMessage::ChangeMap { map_id, portal_id } => {
+                self.change_map(map_id, portal_id).await?
+            }
+            SessionMessage::ChangeField {
+                map_id,
+                field_id,
+                portal_id,
+            } => self.change_field(map_id, field_id, portal_id).await?,
+        }
+
+        Ok(())
+    }
+
+    /// Applies a skill used by another party member if we are in range
+    async fn handle_party_skill(&mut self, broadcast: PartySkillBroadcast) -> anyhow::Result<()> {
+        let character = self.character.as_ref().unwrap();
+
+        if broadcast.sender_id == character.data.id
+            || character.data.party != Some(broadcast.party_id)
+            || !broadcast.in_range(character.pos)
+        {
+            return Ok(());
+        }
+
+        let skill = nx::Skill::load(broadcast.skill_id)?;
+
+        if let Some(effect) = skill.level(broadcast.level) {
+            self.apply_skill_effect(broadcast.skill_id, effect, broadcast.hp, 2)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Handles timed character events
+    async fn handle_tick(&mut self) -> anyhow::Result<()> {
+        let now = Utc::now().timestamp_millis();
+        let character = self.character.as_mut().unwrap();
+        let character_id = character.data.id;
+
+        character.remove_expired_cooldowns(now);
+
+        for buff in character.take_expired_buffs(now) {
+            self.stream.write_packet(packet::cancel_buff(&buff)).await?;
+            self.broadcast_packet(packet::cancel_foreign_buff(character_id, &buff), false)?;
+        }
+
+        // Expired items are removed from the character's inventory and equipment
+        let character = self.character.as_mut().unwrap();
+        let items = character.take_expired_items(now);
+        let equipment = character.take_expired_equipment(now);
+
+        for item in items {
+            item.delete(&self.db).await?;
+
+            // Expired pets are put away for good
+            if let Some(pet_id) = item.cash_id {
+                pet::unsummon(self, pet_id, false).await?;
+                let character = self.character.as_mut().unwrap();
+                character.remove_pet(&item, &self.db).await?;
+            }
+
+            let item_id = item.item_id;
+            let change = InventoryChange::Remove(item);
+            self.stream
+                .write_packet(packet::update_inventory(&[change]))
+                .await?;
+            self.stream
+                .write_packet(packet::show_item_expired(item_id))
+                .await?;
+        }
+
+        if equipment.is_empty() {
+            return Ok(());
+        }
+
+        for equip in equipment.iter() {
+            equip.delete(&self.db).await?;
+            self.stream
+                .write_packet(packet::remove_equipment(equip))
+                .await?;
+            self.stream
+                .write_packet(packet::show_item_expired(equip.item_id))
+                .await?;
+        }
+
+        let character = self.character.as_ref().unwrap();
+        self.broadcast_packet(
+            packet::update_character_look(&character.data, &character.equipment),
+            false,
+        )
+    }
+
+    /// Regenerates the character's hp and mp
+    async fn handle_regen(&mut self) -> anyhow::Result<()> {
+        let character = self.character.as_mut().unwrap();
+
+        if !character.is_alive() {
+            return Ok(());
+        }
+
+        let mut hp = 10;
+        let mut mp = 3 + character.data.int / 10;
+
+        // TODO additional recovery when sitting on a chair
+        for (skill_id, regen) in [
+            (IMPROVED_HP_RECOVERY, &mut hp),
+            (IMPROVED_MP_RECOVERY, &mut mp),
+        ] {
+            let level = character.get_skill_level(skill_id);
+
+            if level > 0 {
+                if let Some(effect) = nx::Skill::load(skill_id)?.level(level) {
+                    *regen += effect.x;
+                }
+            }
+        }
+
+        if character.data.hp >= character.data.max_hp && character.data.mp >= character.data.max_mp
+        {
+            return Ok(());
         }
+
+        character.heal(hp, mp);
+
+        let stats = [(Stat::Hp, character.data.hp), (Stat::Mp, character.data.mp)];
+        self.stream
+            .write_packet(packet::update_stats(&stats))
+            .await?;
+        self.update_party_hp()
+    }
+
+    /// Handles the character dying, they lose exp unless they have a safety charm
+    pub async fn on_death(&mut self) -> anyhow::Result<()> {
+        let map = maple::Map::load(self.character.as_ref().unwrap().data.map)?;
+
+        // Dead characters don't share exp with their party
+        self.update_map_character();
+
+        let character = self.character.as_mut().unwrap();
+
+        if character.is_beginner() {
+            return Ok(());
+        }
+
+        // TODO don't lose exp in maps with the no exp decrease field limit
+        match character.remove_item(SAFETY_CHARM, 1) {
+            Some(charm) => {
+                charm.update_amount(&self.db).await?;
+                self.stream
+                    .write_packet(packet::update_item_amount(&charm))
+                    .await?;
+                self.stream
+                    .write_packet(packet::show_special_effect(SpecialEffect::SafetyCharms))
+                    .await?;
+                self.stream
+                    .write_packet(packet::server_notice(
+                        NoticeType::PinkText,
+                        "You have used a safety charm, so your EXP points have not been decreased.",
+                    ))
+                    .await?;
+            }
+            None => {
+                let loss = character.get_death_exp_loss(map.data.town == 1);
+                character.lose_exp(loss);
+                let exp = character.data.exp;
+                self.stream
+                    .write_packet(packet::update_stats(&[(Stat::Exp, exp)]))
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Revives a dead character, sending them to the current map's return map
+    pub async fn revive(&mut self) -> anyhow::Result<()> {
+        let character = self.character.as_mut().unwrap();
+        character.data.hp = 50;
+
+        let character_id = character.data.id;
+        let map = maple::Map::load(character.data.map)?;
+        let buffs: Vec<_> = character.buffs.drain(..).collect();
+        let now = Utc::now().timestamp_millis();
+
+        // Everyone else in the map stops seeing the buffs too
+        for mut buff in buffs {
+            buff.expires_at = now;
+            self.stream.write_packet(packet::cancel_buff(&buff)).await?;
+            self.broadcast_packet(packet::cancel_foreign_buff(character_id, &buff), false)?;
+        }
+
+        self.change_map(map.get_return_map_id(), 0).await
+    }
+
+    /// Moves the character to the given map, at the given portal
+    /// Characters in an event or expedition go to its private copy of the map, if it has one
+    pub async fn change_map(&mut self, map_id: i32, portal_id: i32) -> anyhow::Result<()> {
+        let field_id = match event::get_field_id(self, map_id).await {
+            Some(field_id) => field_id,
+            None => expedition::get_field_id(self, map_id)
+                .await
+                .unwrap_or(map_id),
+        };
+
+        self.change_field(map_id, field_id, portal_id).await
+    }
+
+    /// Moves the character to the given instance of a map, at the given portal
+    pub async fn change_field(
+        &mut self,
+        map_id: i32,
+        field_id: i32,
+        portal_id: i32,
+    ) -> anyhow::Result<()> {
+        let map = maple::Map::load(map_id)?;
+
+        let portal = match map
+            .data
+            .portals
+            .get(&portal_id)
+            .or_else(|| map.data.portals.get(&0))
+        {
+            Some(portal) => portal,
+            None => return Err(anyhow!("Map {} doesn't have a portal to spawn at", map_id)),
+        };
+
+        self.leave_map().await?;
+
+        let character_id = self.character.as_ref().unwrap().data.id;
+        let character = self.character.as_mut().unwrap();
+        let previous_field_id = character.field_id;
+        character.data.map = map.id;
+        character.field_id = field_id;
+        character.data.spawn_point = portal.id;
+        character.pos = (portal.x, portal.y);
+        character.chair = 0;
+
+        sql::OnlineCharacter::update_map(character_id, map.id, &self.db).await?;
+
+        let hp = character.data.hp;
+        self.stream
+            .write_packet(packet::warp_to_map(self.channel_id, map.id, portal.id, hp))
+            .await?;
+
+        self.enter_map(&map).await?;
+        event::on_change_map(self, previous_field_id).await?;
+        expedition::on_change_map(self, previous_field_id).await?;
+
+        // Party members on this channel see which map the character is in
+        party::update_members(self).await
+    }
+
+    /// Removes the character from their current map, along with any trade or shop they're in
+    pub async fn leave_map(&mut self) -> anyhow::Result<()> {
+        if self.map_broadcast_tx.is_none() {
+            return Ok(());
+        }
+
+        // Trades and shops can only have characters in the same map
+        trade::cancel(self).await?;
+        player_shop::leave(self).await?;
+        minigame::leave(self).await?;
+        self.shop = None;
+        self.storage = None;
+
+        let character = self.character.as_ref().unwrap();
+        let character_id = character.data.id;
+        let field_id = character.field_id;
+        self.broadcast_packet(packet::remove_character(character_id), false)?;
+        self.state.remove_map_character(field_id, character_id);
+        self.map_broadcast_tx = None;
+        self.map_broadcast_rx = None;
+        Ok(())
+    }
+
+    /// Spawns the character in the given map, and sends them the map's characters, npcs, and portals
+    pub async fn enter_map(&mut self, map: &maple::Map) -> anyhow::Result<()> {
+        let field_id = self.character.as_ref().unwrap().field_id;
+        let broadcast_tx = self.state.get_map_broadcast_tx(field_id).clone();
+
+        let (tx, mut rx) = mpsc::channel(64);
+        let joined_broadcast = MapBroadcast::Joined(tx);
+
+        // Notify other characters that we joined the map, and get the number of characters we notified
+        // NOTE: it is possible that we send a broadcast to a character who disconnects before responding --
+        // this is handled by adding a timeout to the below rx.recv call
+        let other_characters = broadcast_tx.send(joined_broadcast)? - 1;
+
+        log::debug!("Start receiving {} map characters...", other_characters);
+
+        // Listen for responses from the map's characters
+        // TODO should listen for spawn_character packet, is probably less bytes
+        for i in 0..other_characters {
+            log::debug!("Receiving character {}", i);
+
+            // TODO tweak timeout
+            let character = match timeout(Duration::from_secs(1), rx.recv()).await {
+                Ok(Some(character)) => character,
+                _ => {
+                    log::warn!("Didn't receive character {} in time", i);
+                    continue;
+                }
+            };
+
+            log::debug!("Received character {}!", i);
+
+            self.stream
+                .write_packet(packet::spawn_character(&character, false))
+                .await?;
+
+            // Show the hp bars of party members already in the map
+            if character.data.party.is_some()
+                && character.data.party == self.character.as_ref().unwrap().data.party
+            {
+                self.stream
+                    .write_packet(packet::update_party_member_hp(
+                        character.data.id,
+                        character.data.hp,
+                        character.data.max_hp,
+                    ))
+                    .await?;
+            }
+        }
+
+        // Send the map's npcs
+        for npc in map.data.npcs.values() {
+            self.stream.write_packet(packet::spawn_npc(npc)).await?;
+            self.stream
+                .write_packet(packet::spawn_npc_request_controller(npc))
+                .await?;
+        }
+
+        // Send the map's monsters
+        for monster in self.state.get_monsters(field_id).iter() {
+            self.stream
+                .write_packet(packet::spawn_monster(monster, false))
+                .await?;
+        }
+
+        // Send the map's reactors, broken ones show up again once they respawn
+        for reactor in self.state.get_reactors(field_id, map) {
+            if !reactor.is_broken() {
+                self.stream
+                    .write_packet(packet::spawn_reactor(&reactor))
+                    .await?;
+            }
+        }
+
+        // Send the map's dropped items
+        for drop in self.state.get_drops(field_id) {
+            self.stream
+                .write_packet(packet::spawn_drop(&drop, false))
+                .await?;
+        }
+
+        // Send the map's player shops and hired merchants
+        for shop in self.state.get_player_shops(field_id) {
+            let shop = shop.lock().await;
+
+            if shop.spawned && !shop.closed {
+                self.stream.write_packet(shop.spawn_packet()).await?;
+            }
+        }
+
+        // Send the map's minigame rooms
+        for minigame in self.state.get_minigames(field_id) {
+            let minigame = minigame.lock().await;
+
+            if !minigame.closed {
+                self.stream.write_packet(minigame.box_packet()).await?;
+            }
+        }
+
+        // Send the map's portals
+        for portal in map.data.portals.values() {
+            self.stream
+                .write_packet(packet::spawn_portal(map.id, portal))
+                .await?;
+        }
+
+        // Summoned pets follow the character into the map
+        pet::show_summoned(self).await?;
+
+        // Characters in a party quest see how much time they have left
+        event::on_enter_map(self).await?;
+
+        // Characters fighting a boss see how much time they have left, and the boss's hp
+        expedition::on_enter_map(self).await?;
+
+        // Characters at a station see the docked ship, and waiting characters see when it departs
+        transport::on_enter_map(self).await?;
+
+        let character = self.character.as_ref().unwrap();
+        let broadcast = MapBroadcast::Packet(PacketBroadcast {
+            packet: packet::spawn_character(character, true),
+            sender_id: character.data.id,
+            send_to_sender: false,
+        });
+        broadcast_tx.send(broadcast)?;
+
+        // Subscribe to the current map's broadcast channel
+        self.map_broadcast_tx = Some(broadcast_tx.clone());
+        self.map_broadcast_rx = Some(broadcast_tx.subscribe());
+
+        self.update_map_character();
+        self.update_party_hp()
+    }
+
+    /// Updates the character's entry in the current map's characters
+    pub fn update_map_character(&self) {
+        // Characters in the cash shop aren't in a map
+        if self.map_broadcast_tx.is_none() {
+            return;
+        }
+
+        let character = self.character.as_ref().unwrap();
+
+        self.state.update_map_character(
+            character.field_id,
+            character.data.id,
+            MapCharacter {
+                party_id: character.data.party,
+                level: character.data.level,
+                is_alive: character.is_alive(),
+                pos: character.pos,
+            },
+        );
+    }
+
+    /// Gives the character exp, handling any level ups
+    pub async fn gain_exp(&mut self, exp: i32) -> anyhow::Result<()> {
+        let character = self.character.as_mut().unwrap();
+        let levels = character.gain_exp(exp);
+        let character_id = character.data.id;
+
+        if levels == 0 {
+            let exp = character.data.exp;
+            return self
+                .stream
+                .write_packet(packet::update_stats(&[(Stat::Exp, exp)]))
+                .await;
+        }
+
+        let stats = [
+            (Stat::Level, character.data.level),
+            (Stat::Exp, character.data.exp),
+            (Stat::Ap, character.data.ap),
+            (Stat::Sp, character.data.get_sp()),
+            (Stat::Hp, character.data.hp),
+            (Stat::MaxHp, character.data.max_hp),
+            (Stat::Mp, character.data.mp),
+            (Stat::MaxMp, character.data.max_mp),
+        ];
+        self.stream
+            .write_packet(packet::update_stats(&stats))
+            .await?;
+        self.stream
+            .write_packet(packet::show_special_effect(SpecialEffect::LevelUp))
+            .await?;
+        self.broadcast_packet(
+            packet::show_foreign_effect(character_id, SpecialEffect::LevelUp),
+            false,
+        )?;
+
+        self.update_map_character();
+        self.update_party_hp()?;
+
+        // Party and guild members see the character's new level
+        party::update_members(self).await?;
+        guild::update_member_level(self).await
+    }
+
+    /// Gives the character exp from a monster kill, with the bonus from holy symbol
+    pub async fn gain_monster_exp(&mut self, exp: i32, party_bonus: i32) -> anyhow::Result<()> {
+        let character = self.character.as_ref().unwrap();
+
+        if !character.is_alive() {
+            return Ok(());
+        }
+
+        // TODO other exp multipliers (exp cards, etc.)
+        let holy_symbol = character.get_buff_value(BuffStat::HolySymbol).unwrap_or(0) as i64;
+        let bonus = party_bonus as i64 + (exp + party_bonus) as i64 * holy_symbol / 100;
+        let bonus = bonus.min(i32::MAX as i64) as i32;
+
+        self.stream
+            .write_packet(packet::show_exp_gain(exp, bonus, true, false))
+            .await?;
+        self.gain_exp(exp.saturating_add(bonus)).await
+    }
+
+    /// Counts a monster kill towards the character's started quests
+    pub async fn add_quest_kill(&mut self, mob_id: i32) -> anyhow::Result<()> {
+        let character = self.character.as_mut().unwrap();
+
+        for quest_id in character.add_quest_kill(mob_id, &self.db).await? {
+            let progress = character.get_quest_progress(quest_id)?;
+            self.stream
+                .write_packet(packet::update_quest_progress(quest_id, &progress))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Applies a skill's effect (healing and buffs) to the current character
+    /// `effect_type` is 1 if the character used the skill, or 2 if they were affected by another character's skill
+    pub async fn apply_skill_effect(
+        &mut self,
+        skill_id: i32,
+        effect: &nx::SkillLevel,
+        hp: i32,
+        effect_type: u8,
+    ) -> anyhow::Result<()> {
+        let character = self.character.as_mut().unwrap();
+        let character_id = character.data.id;
+
+        if hp > 0 {
+            character.heal(hp, 0);
+            let hp = character.data.hp;
+            self.stream
+                .write_packet(packet::update_stats(&[(Stat::Hp, hp)]))
+                .await?;
+            self.update_party_hp()?;
+        }
+
+        self.stream
+            .write_packet(packet::show_skill_effect(
+                skill_id,
+                effect.level,
+                effect_type,
+            ))
+            .await?;
+        self.broadcast_packet(
+            packet::show_foreign_skill_effect(character_id, skill_id, effect.level, effect_type),
+            false,
+        )?;
+
+        if let Some(buff) = maple::Buff::from_skill(skill_id, effect) {
+            self.stream.write_packet(packet::give_buff(&buff)).await?;
+            self.broadcast_packet(packet::give_foreign_buff(character_id, &buff), false)?;
+            self.character.as_mut().unwrap().apply_buff(buff);
+        }
+
+        Ok(())
+    }
+
+    /// Moves the client to a new connection to one of the world's channels, the same way as logging in
+    /// The character is saved and the session ends once the client has been sent the channel's address
+    pub async fn migrate(&mut self, channel_id: i32) -> anyhow::Result<()> {
+        let login_session_id = self
+            .login_session_id
+            .ok_or_else(|| anyhow!("Session {} has no login session to migrate", self.id))?;
+        let account_id = self.account_id.unwrap();
+
+        self.leave_map().await?;
+
+        // TODO keep buffs when migrating
+        let character = self.character.take().unwrap();
+        self.state.remove_session(character.data.id);
+        sql::OnlineCharacter::delete(character.data.id, &self.db).await?;
+        character.save(&self.db).await?;
+
+        sqlx::query(
+            "INSERT INTO login_sessions (id, account_id, character_id, world_id, channel_id)
+            VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(login_session_id)
+        .bind(account_id)
+        .bind(character.data.id)
+        .bind(self.world_id)
+        .bind(channel_id)
+        .execute(&self.db)
+        .await?;
+
+        // Don't log out the account when this session ends
+        self.transitioning = true;
+        sql::Account::update_login_state(account_id, LoginState::Transitioning, &self.db).await?;
+
+        sqlx::query(
+            "UPDATE channels SET connected_players = connected_players + 1 WHERE world_id = ? AND id = ?"
+        )
+        .bind(self.world_id)
+        .bind(channel_id)
+        .execute(&self.db)
+        .await?;
+
+        let ip: Vec<u8> = env::var("CHANNEL_IP")?
+            .split('.')
+            .map(|part| part.parse())
+            .collect::<Result<_, _>>()?;
+        let ip: [u8; 4] = ip
+            .try_into()
+            .map_err(|_| anyhow!("Channel ip should be an IPv4 address"))?;
+        let base_port: i32 = env::var("CHANNEL_BASE_PORT")?.parse()?;
+        let port = base_port + (self.world_id * 1000) + channel_id;
+
+        self.stream
+            .write_packet(packet::change_channel(ip, port))
+            .await
+    }
+
+    /// Broadcasts a packet to every character in the current map
+    pub fn broadcast_packet(&self, packet: Packet, send_to_sender: bool) -> anyhow::Result<()> {
+        // Nobody sees characters in the cash shop
+        let tx = match &self.map_broadcast_tx {
+            Some(tx) => tx,
+            None => return Ok(()),
+        };
+
+        let broadcast = MapBroadcast::Packet(PacketBroadcast {
+            packet,
+            sender_id: self.character.as_ref().unwrap().data.id,
+            send_to_sender,
+        });
+        tx.send(broadcast)?;
+        Ok(())
+    }
+
+    /// Uses up one of the item at a position in the character's use inventory
+    /// Returns false if the character doesn't have the item there
+    pub async fn use_item(&mut self, position: i32, item_id: i32) -> anyhow::Result<bool> {
+        let character = self.character.as_mut().unwrap();
+
+        match character
+            .items
+            .iter()
+            .find(|item| item.inventory_type == InventoryType::Use && item.position == position)
+        {
+            Some(item) if item.item_id == item_id => {}
+            _ => return Ok(false),
+        }
+
+        let (item, whole_stack) = match character.take_item(InventoryType::Use, position, 1) {
+            Some(taken) => taken,
+            None => return Ok(false),
+        };
+
+        let change = if whole_stack {
+            item.delete(&self.db).await?;
+            InventoryChange::Remove(item)
+        } else {
+            let remaining = character
+                .items
+                .iter()
+                .find(|remaining| remaining.id == item.id)
+                .unwrap();
+            remaining.update_amount(&self.db).await?;
+            InventoryChange::Update(remaining.clone())
+        };
+
+        self.stream
+            .write_packet(packet::update_inventory(&[change]))
+            .await?;
+        Ok(true)
+    }
+
+    /// Shows the character's hp bar to their party members in the same map
+    pub fn update_party_hp(&self) -> anyhow::Result<()> {
+        let character = self.character.as_ref().unwrap();
+
+        let (party_id, tx) = match (character.data.party, &self.map_broadcast_tx) {
+            (Some(party_id), Some(tx)) => (party_id, tx),
+            _ => return Ok(()),
+        };
+
+        let broadcast = MapBroadcast::PartyPacket(PartyPacketBroadcast {
+            packet: packet::update_party_member_hp(
+                character.data.id,
+                character.data.hp,
+                character.data.max_hp,
+            ),
+            party_id,
+            sender_id: character.data.id,
+        });
+        tx.send(broadcast)?;
+        Ok(())
     }
 
     /// Execute disconnection tasks
-    async fn on_disconnect(&self) -> anyhow::Result<()> {
+    async fn on_disconnect(&mut self) -> anyhow::Result<()> {
         sqlx::query(
             "UPDATE channels SET connected_players = connected_players - 1 WHERE world_id = ? AND id = ?"
         )
@@ -127,7 +1044,7 @@ impl ChannelSession {
         .execute(&self.db)
         .await?;
 
-        if self.account_id.is_some() {
+        if self.account_id.is_some() && !self.transitioning {
             sql::Account::update_login_state(
                 self.account_id.unwrap(),
                 LoginState::LoggedOut,
@@ -136,6 +1053,48 @@ impl ChannelSession {
             .await?;
         }
 
+        if self.character.is_some() {
+            // Get back anything held in a trade before the character is saved
+            // The trade window packets fail to send once the client is gone, which is fine
+            if let Err(e) = trade::cancel(self).await {
+                log::debug!("Error closing trade on disconnect: {} [id: {}]", e, self.id);
+            }
+
+            if let Err(e) = player_shop::leave(self).await {
+                log::debug!(
+                    "Error leaving player shop on disconnect: {} [id: {}]",
+                    e,
+                    self.id
+                );
+            }
+
+            if let Err(e) = event::on_disconnect(self).await {
+                log::debug!("Error leaving event on disconnect: {} [id: {}]", e, self.id);
+            }
+
+            if let Err(e) = expedition::on_disconnect(self).await {
+                log::debug!(
+                    "Error leaving expedition on disconnect: {} [id: {}]",
+                    e,
+                    self.id
+                );
+            }
+        }
+
+        if let Some(character) = &self.character {
+            self.state.remove_session(character.data.id);
+            self.state
+                .remove_map_character(character.field_id, character.data.id);
+            sql::OnlineCharacter::delete(character.data.id, &self.db).await?;
+            character.save(&self.db).await?;
+
+            // Show the character as offline in their party, buddy lists, guild, and alliance
+            party::update_members(self).await?;
+            buddy::update_buddies(self, -1).await?;
+            guild::update_member_online(self, false).await?;
+            alliance::update_member_online(self, false).await?;
+        }
+
         Ok(())
     }
 }
@@ -0,0 +1,269 @@
+use crate::session::ChannelSession;
+use slate_data::{
+    maple::character::InventoryChange,
+    nx,
+    packet::{self, ShopResult, Stat},
+    sql::{self, item::InventoryType},
+};
+use std::sync::Arc;
+
+/// An npc's shop, along with the data of the items it sells
+#[derive(Debug)]
+pub struct Shop {
+    pub npc_id: i32,
+    pub items: Vec<(sql::ShopItem, nx::Item)>,
+}
+
+impl Shop {
+    /// Checks if the shop sells throwing stars or bullets, which lets it recharge them too
+    fn can_recharge(&self) -> bool {
+        self.items.iter().any(|(_, data)| data.is_rechargeable())
+    }
+}
+
+/// Loads the shop an npc runs, using the cached shop if it was already loaded
+pub async fn load(session: &ChannelSession, npc_id: i32) -> anyhow::Result<Option<Arc<Shop>>> {
+    if let Some(shop) = session.state.get_shop(npc_id) {
+        return Ok(shop);
+    }
+
+    let shop = match sql::Shop::load_optional_by_npc(npc_id, &session.db).await? {
+        Some(shop) => shop,
+        None => {
+            session.state.add_shop(npc_id, None);
+            return Ok(None);
+        }
+    };
+
+    let mut items = Vec::new();
+
+    for item in sql::ShopItem::load_all(shop.id, &session.db).await? {
+        let data = nx::Item::load(item.item_id)?;
+        items.push((item, data));
+    }
+
+    let shop = Arc::new(Shop { npc_id, items });
+    session.state.add_shop(npc_id, Some(shop.clone()));
+
+    Ok(Some(shop))
+}
+
+/// Opens an npc's shop for the character
+/// Returns false if the npc doesn't have a shop
+pub async fn open(session: &mut ChannelSession, npc_id: i32) -> anyhow::Result<bool> {
+    let shop = match load(session, npc_id).await? {
+        Some(shop) => shop,
+        None => return Ok(false),
+    };
+
+    session
+        .stream
+        .write_packet(packet::open_shop(shop.npc_id, &shop.items))
+        .await?;
+
+    session.shop = Some(shop);
+    Ok(true)
+}
+
+/// Buys an item from the character's open shop
+/// `slot` is the item's place in the shop's list
+pub async fn buy(
+    session: &mut ChannelSession,
+    slot: usize,
+    item_id: i32,
+    quantity: i32,
+) -> anyhow::Result<()> {
+    let shop = match &session.shop {
+        Some(shop) => shop.clone(),
+        None => return Ok(()),
+    };
+
+    let (shop_item, data) = match shop.items.get(slot) {
+        Some((shop_item, data)) if shop_item.item_id == item_id => (shop_item, data),
+        _ => return Ok(()),
+    };
+
+    if quantity <= 0 {
+        return Ok(());
+    }
+
+    // Throwing stars and bullets are sold as full sets
+    let (amount, cost) = if data.is_rechargeable() {
+        (data.slot_max, shop_item.price as i64)
+    } else {
+        (quantity, shop_item.price as i64 * quantity as i64)
+    };
+
+    let character = session.character.as_mut().unwrap();
+
+    if cost > character.data.mesos as i64 {
+        return send_result(session, ShopResult::NotEnoughMesos).await;
+    }
+
+    // The items and the mesos paid for them are saved together
+    let mut tx = session.db.begin().await?;
+
+    let changes = match character.add_item_in(item_id, amount, &mut tx).await? {
+        Some(changes) => changes,
+        None => return send_result(session, ShopResult::InventoryFull).await,
+    };
+
+    character.data.mesos -= cost as i32;
+    let mesos = character.data.mesos;
+    sql::Character::update_mesos(character.data.id, mesos, &mut *tx).await?;
+    tx.commit().await?;
+
+    session
+        .stream
+        .write_packet(packet::update_inventory(&changes))
+        .await?;
+    session
+        .stream
+        .write_packet(packet::update_stats(&[(Stat::Mesos, mesos)]))
+        .await?;
+    send_result(session, ShopResult::Bought).await
+}
+
+/// Sells an item from the character's inventory to their open shop, for the price in the item's data
+/// `position` is the item's position in its inventory
+pub async fn sell(
+    session: &mut ChannelSession,
+    position: i32,
+    item_id: i32,
+    quantity: i32,
+) -> anyhow::Result<()> {
+    if session.shop.is_none() {
+        return Ok(());
+    }
+
+    let inventory_type = match InventoryType::from_item_id(item_id) {
+        Some(inventory_type) => inventory_type,
+        None => return Ok(()),
+    };
+
+    let character = session.character.as_mut().unwrap();
+
+    let item = match character
+        .items
+        .iter()
+        .find(|item| item.inventory_type == inventory_type && item.position == position)
+    {
+        Some(item) if item.item_id == item_id => item,
+        _ => return Ok(()),
+    };
+
+    let data = nx::Item::load(item_id)?;
+
+    // Throwing stars and bullets can only be sold as a whole set, their price includes each star or bullet
+    let (amount, price) = if data.is_rechargeable() {
+        let amount = item.amount;
+        (
+            amount,
+            data.price as i64 + (data.unit_price * amount as f64) as i64,
+        )
+    } else {
+        (quantity, data.price as i64 * quantity as i64)
+    };
+
+    if price + character.data.mesos as i64 > i32::MAX as i64 {
+        return Ok(());
+    }
+
+    let (item, whole_stack) = match character.take_item(inventory_type, position, amount) {
+        Some(taken) => taken,
+        None => return Ok(()),
+    };
+
+    // The sold items and the mesos paid for them are saved together
+    let mut tx = session.db.begin().await?;
+
+    let change = if whole_stack {
+        item.delete(&mut *tx).await?;
+        InventoryChange::Remove(item)
+    } else {
+        let remaining = character
+            .items
+            .iter()
+            .find(|remaining| remaining.id == item.id)
+            .unwrap();
+        remaining.update_amount(&mut *tx).await?;
+        InventoryChange::Update(remaining.clone())
+    };
+
+    character.data.mesos += price as i32;
+    let mesos = character.data.mesos;
+    sql::Character::update_mesos(character.data.id, mesos, &mut *tx).await?;
+    tx.commit().await?;
+
+    session
+        .stream
+        .write_packet(packet::update_inventory(&[change]))
+        .await?;
+    session
+        .stream
+        .write_packet(packet::update_stats(&[(Stat::Mesos, mesos)]))
+        .await?;
+    send_result(session, ShopResult::Sold).await
+}
+
+/// Refills a set of throwing stars or bullets at the character's open shop, paying for each star or bullet added
+/// `position` is the set's position in the use inventory
+pub async fn recharge(session: &mut ChannelSession, position: i32) -> anyhow::Result<()> {
+    match &session.shop {
+        Some(shop) if shop.can_recharge() => {}
+        _ => return Ok(()),
+    }
+
+    let character = session.character.as_mut().unwrap();
+
+    let item = match character
+        .items
+        .iter_mut()
+        .find(|item| item.inventory_type == InventoryType::Use && item.position == position)
+    {
+        Some(item) => item,
+        None => return Ok(()),
+    };
+
+    let data = nx::Item::load(item.item_id)?;
+
+    // TODO claw and gun mastery increase the max set size
+    if !data.is_rechargeable() || item.amount >= data.slot_max {
+        return Ok(());
+    }
+
+    let cost = (data.unit_price * (data.slot_max - item.amount) as f64).ceil() as i32;
+
+    if cost > character.data.mesos {
+        return send_result(session, ShopResult::NotEnoughMesos).await;
+    }
+
+    // The recharged set and the mesos paid for it are saved together
+    let mut tx = session.db.begin().await?;
+
+    item.amount = data.slot_max;
+    item.update_amount(&mut *tx).await?;
+    let change = InventoryChange::Update(item.clone());
+
+    character.data.mesos -= cost;
+    let mesos = character.data.mesos;
+    sql::Character::update_mesos(character.data.id, mesos, &mut *tx).await?;
+    tx.commit().await?;
+
+    session
+        .stream
+        .write_packet(packet::update_inventory(&[change]))
+        .await?;
+    session
+        .stream
+        .write_packet(packet::update_stats(&[(Stat::Mesos, mesos)]))
+        .await?;
+    send_result(session, ShopResult::Recharged).await
+}
+
+async fn send_result(session: &mut ChannelSession, result: ShopResult) -> anyhow::Result<()> {
+    session
+        .stream
+        .write_packet(packet::shop_result(result))
+        .await
+}
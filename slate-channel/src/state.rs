@@ -1,8 +1,43 @@
-use dashmap::DashMap;
-use slate_data::maple::map::MapBroadcast;
-use tokio::sync::broadcast;
+use crate::{
+    event::SharedEvent, expedition::SharedExpedition, minigame::SharedMinigame,
+    player_shop::SharedPlayerShop, session::SessionMessage, shop::Shop, trade::SharedTrade,
+    transport::TransportStatus,
+};
+use dashmap::{mapref::entry::Entry, DashMap};
+use slate_data::{
+    config,
+    maple::{self, map::MapBroadcast},
+    nx,
+};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicI32, Ordering},
+        Arc,
+    },
+};
+use tokio::sync::{broadcast, mpsc};
 
+/// Object ids start high enough to not collide with character ids
+const FIRST_OBJECT_ID: i32 = 10000000;
+
+/// Private copies of maps get field ids above every map id
+const FIRST_INSTANCE_FIELD_ID: i32 = 1000000000;
+
+/// A character in a map, used to find the party members that share exp from a monster
+#[derive(Debug, Clone, Copy)]
+pub struct MapCharacter {
+    pub party_id: Option<i32>,
+    pub level: i32,
+    pub is_alive: bool,
+    pub pos: (i32, i32),
+}
+
+/// Maps in the channel are kept by field id, which is the map's id, or the id of a private copy of the map
 pub struct State {
+    /// The config of the world this channel belongs to (exp rate, etc.)
+    pub world: config::World,
+
     map_broadcast: DashMap<
         i32,
         (
@@ -10,23 +45,418 @@ pub struct State {
             broadcast::Receiver<MapBroadcast>,
         ),
     >,
+
+    /// Senders for messaging the sessions of characters connected to this channel, by character id
+    sessions: DashMap<i32, mpsc::Sender<SessionMessage>>,
+
+    /// Monsters spawned in each map, by field id then object id
+    monsters: DashMap<i32, HashMap<i32, maple::Monster>>,
+
+    /// Reactors in each map, by field id then object id, a map's reactors are spawned the first time it's entered
+    reactors: DashMap<i32, HashMap<i32, maple::Reactor>>,
+
+    /// Dropped items in each map, by field id then object id
+    drops: DashMap<i32, HashMap<i32, maple::Drop>>,
+
+    /// Characters in each map, by field id then character id
+    map_characters: DashMap<i32, HashMap<i32, MapCharacter>>,
+
+    /// Open trades, by trade id
+    trades: DashMap<i32, SharedTrade>,
+
+    /// Shops loaded from the db, by npc id (None if the npc doesn't have a shop)
+    shops: DashMap<i32, Option<Arc<Shop>>>,
+
+    /// Player shops and hired merchants in each map, by field id then shop id
+    player_shops: DashMap<i32, HashMap<i32, SharedPlayerShop>>,
+
+    /// Minigame rooms in each map, by field id then room id
+    minigames: DashMap<i32, HashMap<i32, SharedMinigame>>,
+
+    /// The id of the map each private copy copies, by field id
+    instances: DashMap<i32, i32>,
+
+    /// Running events (ex. party quests), by the field id of each of their private maps
+    events: DashMap<i32, SharedEvent>,
+
+    /// Boss expeditions, by their boss map's id while they register, then by the field id of their copy of it
+    expeditions: DashMap<i32, SharedExpedition>,
+
+    /// Where each scheduled transport (boats, trains, etc.) is in its cycle, by the map id of its station
+    transports: DashMap<i32, TransportStatus>,
+
+    /// The object id given to the next spawned map object
+    next_object_id: AtomicI32,
+
+    /// The field id given to the next private copy of a map
+    next_field_id: AtomicI32,
 }
 
 impl State {
-    pub fn new() -> Self {
+    pub fn new(world: config::World) -> Self {
         Self {
+            world,
             map_broadcast: DashMap::new(),
+            sessions: DashMap::new(),
+            monsters: DashMap::new(),
+            reactors: DashMap::new(),
+            drops: DashMap::new(),
+            map_characters: DashMap::new(),
+            trades: DashMap::new(),
+            shops: DashMap::new(),
+            player_shops: DashMap::new(),
+            minigames: DashMap::new(),
+            instances: DashMap::new(),
+            events: DashMap::new(),
+            expeditions: DashMap::new(),
+            transports: DashMap::new(),
+            next_object_id: AtomicI32::new(FIRST_OBJECT_ID),
+            next_field_id: AtomicI32::new(FIRST_INSTANCE_FIELD_ID),
         }
     }
 
     // TODO we can get rid of the dashmap if we just create channels for every map on init -- not sure how big memory
     // would take
-    pub fn get_map_broadcast_tx(&self, map_id: i32) -> broadcast::Sender<MapBroadcast> {
-        if !self.map_broadcast.contains_key(&map_id) {
+    pub fn get_map_broadcast_tx(&self, field_id: i32) -> broadcast::Sender<MapBroadcast> {
+        if !self.map_broadcast.contains_key(&field_id) {
             // TODO tweak channel size
-            self.map_broadcast.insert(map_id, broadcast::channel(64));
+            self.map_broadcast.insert(field_id, broadcast::channel(64));
+        }
+
+        self.map_broadcast.get(&field_id).unwrap().0.clone()
+    }
+
+    /// Registers a character's session so it can receive messages
+    pub fn add_session(&self, character_id: i32, tx: mpsc::Sender<SessionMessage>) {
+        self.sessions.insert(character_id, tx);
+    }
+
+    /// Unregisters a character's session
+    pub fn remove_session(&self, character_id: i32) {
+        self.sessions.remove(&character_id);
+    }
+
+    /// Sends a message to a character connected to this channel
+    /// Returns false if the character isn't connected to this channel
+    pub async fn send_to_session(&self, character_id: i32, message: SessionMessage) -> bool {
+        // Clone the sender so the map isn't locked while waiting to send
+        let tx = match self.sessions.get(&character_id) {
+            Some(tx) => tx.clone(),
+            None => return false,
+        };
+
+        tx.send(message).await.is_ok()
+    }
+
+    /// Gets a unique object id for a map object (monsters, drops, etc.)
+    pub fn next_object_id(&self) -> i32 {
+        self.next_object_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Adds a monster to a map
+    pub fn add_monster(&self, field_id: i32, monster: maple::Monster) {
+        self.monsters
+            .entry(field_id)
+            .or_default()
+            .insert(monster.object_id, monster);
+    }
+
+    /// Gets all of the monsters in a map
+    pub fn get_monsters(&self, field_id: i32) -> Vec<maple::Monster> {
+        match self.monsters.get(&field_id) {
+            Some(monsters) => monsters.values().cloned().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Removes and returns all of the monsters in a map
+    pub fn remove_monsters(&self, field_id: i32) -> Vec<maple::Monster> {
+        match self.monsters.remove(&field_id) {
+            Some((_, monsters)) => monsters.into_values().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Removes a monster from a map without killing it, returns None if it's no longer in the map
+    pub fn remove_monster(&self, field_id: i32, object_id: i32) -> Option<maple::Monster> {
+        self.monsters.get_mut(&field_id)?.remove(&object_id)
+    }
+
+    /// Damages a monster in a map, removing it from the map if it dies
+    /// Returns the damaged monster, or None if it's no longer in the map
+    pub fn damage_monster(
+        &self,
+        field_id: i32,
+        object_id: i32,
+        character_id: i32,
+        damage: i32,
+    ) -> Option<maple::Monster> {
+        let mut monsters = self.monsters.get_mut(&field_id)?;
+        let monster = monsters.get_mut(&object_id)?;
+        monster.damage(character_id, damage);
+
+        if monster.is_alive() {
+            Some(monster.clone())
+        } else {
+            monsters.remove(&object_id)
+        }
+    }
+
+    /// Gets all of the reactors in a map, spawning the map's reactors if it hasn't been entered before
+    pub fn get_reactors(&self, field_id: i32, map: &maple::Map) -> Vec<maple::Reactor> {
+        let reactors = self.reactors.entry(field_id).or_insert_with(|| {
+            map.data
+                .reactors
+                .values()
+                .filter_map(|map_reactor| {
+                    let data = match nx::Reactor::load(map_reactor.id) {
+                        Ok(data) => data,
+                        Err(e) => {
+                            log::warn!("Error loading reactor: {}", e);
+                            return None;
+                        }
+                    };
+
+                    Some((
+                        map_reactor.object_id,
+                        maple::Reactor::new(map_reactor, data),
+                    ))
+                })
+                .collect()
+        });
+
+        reactors.values().cloned().collect()
+    }
+
+    /// Gets a reactor in a map
+    pub fn get_reactor(&self, field_id: i32, object_id: i32) -> Option<maple::Reactor> {
+        self.reactors.get(&field_id)?.get(&object_id).cloned()
+    }
+
+    /// Hits a reactor in a map, moving it to its next state
+    /// Returns the reactor if the hit changed its state
+    pub fn hit_reactor(
+        &self,
+        field_id: i32,
+        object_id: i32,
+        skill_id: i32,
+        from_left: bool,
+    ) -> Option<maple::Reactor> {
+        let mut reactors = self.reactors.get_mut(&field_id)?;
+        let reactor = reactors.get_mut(&object_id)?;
+
+        if reactor.is_broken() || !reactor.hit(skill_id, from_left) {
+            return None;
+        }
+
+        Some(reactor.clone())
+    }
+
+    /// Moves a broken reactor in a map back to its first state
+    /// Returns the reactor, or None if it's no longer in the map
+    pub fn reset_reactor(&self, field_id: i32, object_id: i32) -> Option<maple::Reactor> {
+        let mut reactors = self.reactors.get_mut(&field_id)?;
+        let reactor = reactors.get_mut(&object_id)?;
+        reactor.reset();
+        Some(reactor.clone())
+    }
+
+    /// Adds a dropped item to a map
+    pub fn add_drop(&self, field_id: i32, drop: maple::Drop) {
+        self.drops
+            .entry(field_id)
+            .or_default()
+            .insert(drop.object_id, drop);
+    }
+
+    /// Gets all of the dropped items in a map
+    pub fn get_drops(&self, field_id: i32) -> Vec<maple::Drop> {
+        match self.drops.get(&field_id) {
+            Some(drops) => drops.values().cloned().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Removes and returns a dropped item from a map, None if someone else already picked it up
+    pub fn take_drop(&self, field_id: i32, object_id: i32) -> Option<maple::Drop> {
+        self.drops.get_mut(&field_id)?.remove(&object_id)
+    }
+
+    /// Adds or updates a character in a map
+    pub fn update_map_character(&self, field_id: i32, character_id: i32, character: MapCharacter) {
+        self.map_characters
+            .entry(field_id)
+            .or_default()
+            .insert(character_id, character);
+    }
+
+    /// Removes a character from a map
+    pub fn remove_map_character(&self, field_id: i32, character_id: i32) {
+        if let Some(mut characters) = self.map_characters.get_mut(&field_id) {
+            characters.remove(&character_id);
+        }
+    }
+
+    /// Gets all of the characters in a map
+    pub fn get_map_characters(&self, field_id: i32) -> HashMap<i32, MapCharacter> {
+        match self.map_characters.get(&field_id) {
+            Some(characters) => characters.clone(),
+            None => HashMap::new(),
+        }
+    }
+
+    /// Adds an open trade
+    pub fn add_trade(&self, trade_id: i32, trade: SharedTrade) {
+        self.trades.insert(trade_id, trade);
+    }
+
+    /// Gets an open trade
+    pub fn get_trade(&self, trade_id: i32) -> Option<SharedTrade> {
+        self.trades.get(&trade_id).map(|trade| trade.clone())
+    }
+
+    /// Removes a trade once it's closed
+    pub fn remove_trade(&self, trade_id: i32) {
+        self.trades.remove(&trade_id);
+    }
+
+    /// Gets an npc's cached shop, or None if it hasn't been loaded yet
+    pub fn get_shop(&self, npc_id: i32) -> Option<Option<Arc<Shop>>> {
+        self.shops.get(&npc_id).map(|shop| shop.clone())
+    }
+
+    /// Caches an npc's shop, or that the npc doesn't have one
+    pub fn add_shop(&self, npc_id: i32, shop: Option<Arc<Shop>>) {
+        self.shops.insert(npc_id, shop);
+    }
+
+    /// Adds a player shop or hired merchant to a map
+    pub fn add_player_shop(&self, field_id: i32, shop_id: i32, shop: SharedPlayerShop) {
+        self.player_shops
+            .entry(field_id)
+            .or_default()
+            .insert(shop_id, shop);
+    }
+
+    /// Gets a player shop or hired merchant in a map
+    pub fn get_player_shop(&self, field_id: i32, shop_id: i32) -> Option<SharedPlayerShop> {
+        self.player_shops.get(&field_id)?.get(&shop_id).cloned()
+    }
+
+    /// Gets all of the player shops and hired merchants in a map
+    pub fn get_player_shops(&self, field_id: i32) -> Vec<SharedPlayerShop> {
+        match self.player_shops.get(&field_id) {
+            Some(shops) => shops.values().cloned().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Removes a player shop or hired merchant from a map once it's closed
+    pub fn remove_player_shop(&self, field_id: i32, shop_id: i32) {
+        if let Some(mut shops) = self.player_shops.get_mut(&field_id) {
+            shops.remove(&shop_id);
         }
+    }
+
+    /// Adds a minigame room to a map
+    pub fn add_minigame(&self, field_id: i32, minigame_id: i32, minigame: SharedMinigame) {
+        self.minigames
+            .entry(field_id)
+            .or_default()
+            .insert(minigame_id, minigame);
+    }
+
+    /// Gets a minigame room in a map
+    pub fn get_minigame(&self, field_id: i32, minigame_id: i32) -> Option<SharedMinigame> {
+        self.minigames.get(&field_id)?.get(&minigame_id).cloned()
+    }
+
+    /// Gets all of the minigame rooms in a map
+    pub fn get_minigames(&self, field_id: i32) -> Vec<SharedMinigame> {
+        match self.minigames.get(&field_id) {
+            Some(minigames) => minigames.values().cloned().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Removes a minigame room from a map once it's closed
+    pub fn remove_minigame(&self, field_id: i32, minigame_id: i32) {
+        if let Some(mut minigames) = self.minigames.get_mut(&field_id) {
+            minigames.remove(&minigame_id);
+        }
+    }
+
+    /// Creates a private copy of a map, returning the copy's field id
+    pub fn create_instance(&self, map_id: i32) -> i32 {
+        let field_id = self.next_field_id.fetch_add(1, Ordering::Relaxed);
+        self.instances.insert(field_id, map_id);
+        field_id
+    }
+
+    /// Removes a private copy of a map once it's no longer needed, along with everything in it
+    pub fn remove_instance(&self, field_id: i32) {
+        if self.instances.remove(&field_id).is_none() {
+            return;
+        }
+
+        self.map_broadcast.remove(&field_id);
+        self.monsters.remove(&field_id);
+        self.reactors.remove(&field_id);
+        self.drops.remove(&field_id);
+        self.map_characters.remove(&field_id);
+        self.player_shops.remove(&field_id);
+        self.minigames.remove(&field_id);
+    }
+
+    /// Adds an event running in the given fields
+    pub fn add_event(&self, field_ids: &[i32], event: SharedEvent) {
+        for field_id in field_ids {
+            self.events.insert(*field_id, event.clone());
+        }
+    }
+
+    /// Gets the event running in a field
+    pub fn get_event(&self, field_id: i32) -> Option<SharedEvent> {
+        self.events.get(&field_id).map(|event| event.clone())
+    }
+
+    /// Removes an event from its fields once it's over
+    pub fn remove_event(&self, field_ids: &[i32]) {
+        for field_id in field_ids {
+            self.events.remove(field_id);
+        }
+    }
+
+    /// Adds an expedition under a boss map's id or a field id, returns false if another expedition is already there
+    pub fn add_expedition(&self, id: i32, expedition: SharedExpedition) -> bool {
+        match self.expeditions.entry(id) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(expedition);
+                true
+            }
+        }
+    }
+
+    /// Gets the expedition registering for a boss map, or fighting in a field
+    pub fn get_expedition(&self, id: i32) -> Option<SharedExpedition> {
+        self.expeditions
+            .get(&id)
+            .map(|expedition| expedition.clone())
+    }
+
+    /// Removes an expedition once it starts fighting in its own field, or once it's over
+    pub fn remove_expedition(&self, id: i32) {
+        self.expeditions.remove(&id);
+    }
+
+    /// Updates where a transport is in its cycle
+    pub fn set_transport_status(&self, station_map_id: i32, status: TransportStatus) {
+        self.transports.insert(station_map_id, status);
+    }
 
-        self.map_broadcast.get(&map_id).unwrap().0.clone()
+    /// Gets where a transport is in its cycle, None if it hasn't started running yet
+    pub fn get_transport_status(&self, station_map_id: i32) -> Option<TransportStatus> {
+        self.transports.get(&station_map_id).map(|status| *status)
     }
 }
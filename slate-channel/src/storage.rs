@@ -0,0 +1,300 @@
+use crate::{pet, session::ChannelSession};
+use slate_data::{
+    maple::character::InventoryChange,
+    nx,
+    packet::{self, Stat, StorageError},
+    sql::{self, item::InventoryType},
+};
+
+/// A storage opened by one of its account's characters
+#[derive(Debug)]
+pub struct OpenStorage {
+    pub npc_id: i32,
+    /// The mesos it costs to store an item
+    pub fee: i32,
+    pub data: sql::Storage,
+    pub items: Vec<sql::StorageItem>,
+}
+
+impl OpenStorage {
+    /// Gets all of the storage's items, sorted by inventory type in the order the client shows them
+    fn get_items(&self) -> Vec<&sql::StorageItem> {
+        let mut items: Vec<_> = self.items.iter().collect();
+        items.sort_by_key(|item| (item.inventory_type as u8, item.position));
+        items
+    }
+
+    /// Gets the storage's items in one of the storage's tabs
+    fn get_tab(&self, inventory_type: InventoryType) -> Vec<&sql::StorageItem> {
+        self.get_items()
+            .into_iter()
+            .filter(|item| item.inventory_type == inventory_type)
+            .collect()
+    }
+}
+
+/// Opens the character's account storage if the npc runs a storage
+/// Returns false if the npc doesn't run a storage
+pub async fn open(session: &mut ChannelSession, npc_id: i32) -> anyhow::Result<bool> {
+    let fee = match nx::Npc::load(npc_id)?.storage_fee {
+        Some(fee) => fee,
+        None => return Ok(false),
+    };
+
+    let account_id = session.account_id.unwrap();
+    let data = sql::Storage::load_or_create(account_id, session.world_id, &session.db).await?;
+    let items = sql::StorageItem::load_all(data.id, &session.db).await?;
+
+    let storage = OpenStorage {
+        npc_id,
+        fee,
+        data,
+        items,
+    };
+
+    session
+        .stream
+        .write_packet(packet::open_storage(
+            storage.npc_id,
+            &storage.data,
+            &storage.get_items(),
+        ))
+        .await?;
+
+    session.storage = Some(storage);
+    Ok(true)
+}
+
+/// Takes an item out of the character's open storage and puts it into their inventory
+/// `slot` is the item's place in its storage tab
+pub async fn take(
+    session: &mut ChannelSession,
+    inventory_type: InventoryType,
+    slot: usize,
+) -> anyhow::Result<()> {
+    let storage = match session.storage.as_mut() {
+        Some(storage) => storage,
+        None => return Ok(()),
+    };
+
+    let item = match storage.get_tab(inventory_type).get(slot) {
+        Some(item) => (*item).clone(),
+        None => return Ok(()),
+    };
+
+    let character = session.character.as_mut().unwrap();
+
+    let position = match character.get_free_position(inventory_type) {
+        Some(position) => position,
+        None => return send_error(session, StorageError::InventoryFull).await,
+    };
+
+    let taken = match item.take(character.data.id, position, &session.db).await? {
+        Some(taken) => taken,
+        None => return Ok(()),
+    };
+
+    storage.items.retain(|stored| stored.id != item.id);
+    character.items.push(taken.clone());
+    character.add_pet(&taken, &session.db).await?;
+
+    let packet = packet::storage_item_taken(
+        storage.data.slots,
+        inventory_type,
+        &storage.get_tab(inventory_type),
+    );
+
+    session
+        .stream
+        .write_packet(packet::update_inventory(&[InventoryChange::Add(
+            taken.clone(),
+        )]))
+        .await?;
+    session.stream.write_packet(packet).await?;
+
+    // Pets are shown with their level, tameness and fullness
+    match taken.cash_id {
+        Some(pet_id) => pet::update_pet(session, pet_id, false).await,
+        None => Ok(()),
+    }
+}
+
+/// Moves an item from the character's inventory into their open storage, for the storage npc's fee
+/// `position` is the item's position in its inventory
+pub async fn store(
+    session: &mut ChannelSession,
+    position: i32,
+    item_id: i32,
+    quantity: i32,
+) -> anyhow::Result<()> {
+    let storage = match session.storage.as_mut() {
+        Some(storage) => storage,
+        None => return Ok(()),
+    };
+
+    let inventory_type = match InventoryType::from_item_id(item_id) {
+        Some(inventory_type) => inventory_type,
+        None => return Ok(()),
+    };
+
+    if storage.items.len() as i32 >= storage.data.slots {
+        return send_error(session, StorageError::StorageFull).await;
+    }
+
+    let character = session.character.as_mut().unwrap();
+
+    if character.data.mesos < storage.fee {
+        return send_error(session, StorageError::NotEnoughMesos).await;
+    }
+
+    let item = match character
+        .items
+        .iter()
+        .find(|item| item.inventory_type == inventory_type && item.position == position)
+    {
+        Some(item) if item.item_id == item_id => item,
+        _ => return Ok(()),
+    };
+
+    // Throwing stars and bullets can only be stored as a whole set
+    let amount = if nx::Item::load(item_id)?.is_rechargeable() {
+        item.amount
+    } else {
+        quantity
+    };
+
+    // Summoned pets are put away first
+    if let Some(pet_id) = item.cash_id {
+        pet::unsummon(session, pet_id, false).await?;
+    }
+
+    let storage = session.storage.as_mut().unwrap();
+    let character = session.character.as_mut().unwrap();
+    let (item, whole_stack) = match character.take_item(inventory_type, position, amount) {
+        Some(taken) => taken,
+        None => return Ok(()),
+    };
+
+    let storage_position = storage
+        .items
+        .iter()
+        .map(|stored| stored.position + 1)
+        .max()
+        .unwrap_or(0);
+
+    let stored = match sql::StorageItem::store(
+        &item,
+        whole_stack,
+        storage.data.id,
+        storage_position,
+        &session.db,
+    )
+    .await?
+    {
+        Some(stored) => stored,
+        None => {
+            log::warn!(
+                "Item {} is no longer in character {}'s inventory",
+                item.id,
+                item.character_id
+            );
+            return Ok(());
+        }
+    };
+
+    storage.items.push(stored);
+
+    let change = if whole_stack {
+        character.remove_pet(&item, &session.db).await?;
+        InventoryChange::Remove(item)
+    } else {
+        let remaining = character
+            .items
+            .iter()
+            .find(|remaining| remaining.id == item.id)
+            .unwrap();
+        InventoryChange::Update(remaining.clone())
+    };
+
+    character.data.mesos -= storage.fee;
+    let mesos = character.data.mesos;
+
+    let packet = packet::storage_item_stored(
+        storage.data.slots,
+        inventory_type,
+        &storage.get_tab(inventory_type),
+    );
+
+    session
+        .stream
+        .write_packet(packet::update_inventory(&[change]))
+        .await?;
+    session
+        .stream
+        .write_packet(packet::update_stats(&[(Stat::Mesos, mesos)]))
+        .await?;
+    session.stream.write_packet(packet).await
+}
+
+/// Moves mesos between the character and their open storage
+/// `amount` is the change to the character's mesos, it's negative when they store mesos
+pub async fn move_mesos(session: &mut ChannelSession, amount: i32) -> anyhow::Result<()> {
+    let storage = match session.storage.as_mut() {
+        Some(storage) => storage,
+        None => return Ok(()),
+    };
+
+    let character = session.character.as_mut().unwrap();
+    let character_mesos = character.data.mesos as i64 + amount as i64;
+    let storage_mesos = storage.data.mesos as i64 - amount as i64;
+
+    if !(0..=i32::MAX as i64).contains(&character_mesos)
+        || !(0..=i32::MAX as i64).contains(&storage_mesos)
+    {
+        return Ok(());
+    }
+
+    character.data.mesos = character_mesos as i32;
+    storage.data.mesos = storage_mesos as i32;
+    storage
+        .data
+        .update_mesos(character.data.id, character.data.mesos, &session.db)
+        .await?;
+
+    let mesos = character.data.mesos;
+    let packet = packet::storage_mesos(storage.data.slots, storage.data.mesos);
+
+    session
+        .stream
+        .write_packet(packet::update_stats(&[(Stat::Mesos, mesos)]))
+        .await?;
+    session.stream.write_packet(packet).await
+}
+
+/// Sorts the items in the character's open storage by item id
+pub async fn arrange(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let storage = match session.storage.as_mut() {
+        Some(storage) => storage,
+        None => return Ok(()),
+    };
+
+    storage
+        .items
+        .sort_by_key(|item| (item.inventory_type as u8, item.item_id));
+
+    for (position, item) in storage.items.iter_mut().enumerate() {
+        item.position = position as i32;
+    }
+
+    sql::StorageItem::update_positions(&storage.items, &session.db).await?;
+
+    let packet = packet::storage_arranged(storage.data.slots, &storage.get_items());
+    session.stream.write_packet(packet).await
+}
+
+async fn send_error(session: &mut ChannelSession, error: StorageError) -> anyhow::Result<()> {
+    session
+        .stream
+        .write_packet(packet::storage_error(error))
+        .await
+}
@@ -0,0 +1,659 @@
+use crate::session::{ChannelSession, SessionMessage};
+use slate_data::{
+    maple::character::InventoryChange,
+    nx,
+    packet::{self, NoticeType, Stat, TradeResult},
+    sql::{
+        self,
+        item::{InventoryType, ItemTransfer},
+    },
+};
+use slate_net::Packet;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// The number of item slots each trader has in the trade window
+const TRADE_SLOTS: u8 = 9;
+
+/// Every inventory, in the order of their ids
+const INVENTORY_TYPES: [InventoryType; 5] = [
+    InventoryType::Equip,
+    InventoryType::Use,
+    InventoryType::Setup,
+    InventoryType::Etc,
+    InventoryType::Cash,
+];
+
+/// A trade shared by the sessions of both traders
+pub type SharedTrade = Arc<Mutex<Trade>>;
+
+/// A trade between two characters in the same map
+/// Items and mesos put into the trade are held by it until it closes, so they can't be used or traded twice
+/// Held items are moved out of their inventory in the db right away, see `sql::Item::hold`
+#[derive(Debug)]
+pub struct Trade {
+    pub id: i32,
+    /// The traders, by their side of the trade (0 opened the trade, 1 was invited)
+    pub traders: Vec<Trader>,
+    /// Whether the trade was completed or cancelled, each trader's session then takes their outcome
+    pub closed: bool,
+}
+
+#[derive(Debug)]
+pub struct Trader {
+    /// The trader's character, for showing them in the trade window
+    pub data: sql::Character,
+    pub equipment: Vec<sql::Equipment>,
+    pub items: Vec<TradeItem>,
+    pub mesos: i32,
+    /// Whether the trader confirmed the trade, nothing can be put into it afterwards
+    pub confirmed: bool,
+    /// What the trader gets once the trade closes
+    pub outcome: Option<Outcome>,
+}
+
+/// An item put into a trade
+#[derive(Debug)]
+pub struct TradeItem {
+    pub slot: u8,
+    /// The item as the trade holds it, with the amount being traded
+    pub item: sql::Item,
+}
+
+/// What a trader gets once their trade closes
+#[derive(Debug)]
+pub struct Outcome {
+    pub result: TradeResult,
+    pub items: Vec<sql::Item>,
+    /// Whether `items` are the trader's own items given back, rather than items they received
+    pub returned: bool,
+    pub mesos: i32,
+}
+
+impl Trader {
+    fn new(character: &slate_data::maple::Character) -> Self {
+        Self {
+            data: character.data.clone(),
+            equipment: character.equipment.clone(),
+            items: Vec::new(),
+            mesos: 0,
+            confirmed: false,
+            outcome: None,
+        }
+    }
+
+    /// Gives the trader back everything they put into the trade
+    fn return_all(&mut self, result: TradeResult) {
+        self.outcome = Some(Outcome {
+            result,
+            items: self.items.drain(..).map(|item| item.item).collect(),
+            returned: true,
+            mesos: self.mesos,
+        });
+    }
+}
+
+impl Trade {
+    /// Gets the character's side of the trade
+    fn get_number(&self, character_id: i32) -> Option<usize> {
+        self.traders
+            .iter()
+            .position(|trader| trader.data.id == character_id)
+    }
+
+    /// Checks if items and mesos can still be put into the trade
+    fn is_open(&self) -> bool {
+        !self.closed
+            && self.traders.len() == 2
+            && self.traders.iter().all(|trader| !trader.confirmed)
+    }
+}
+
+/// Opens a trade window, the character can then invite someone to it
+pub async fn open(session: &mut ChannelSession) -> anyhow::Result<()> {
+    if session.trade.is_some() {
+        return Ok(());
+    }
+
+    let character = session.character.as_ref().unwrap();
+
+    let trade = Trade {
+        id: session.state.next_object_id(),
+        traders: vec![Trader::new(character)],
+        closed: false,
+    };
+
+    let trade_id = trade.id;
+    let trade = Arc::new(Mutex::new(trade));
+    session.state.add_trade(trade_id, trade.clone());
+    session.trade = Some(trade);
+
+    let packet = packet::trade_room(0, (&character.data, &character.equipment), None);
+    session.stream.write_packet(packet).await
+}
+
+/// Invites a character in the same map to the character's trade
+pub async fn invite(session: &mut ChannelSession, character_id: i32) -> anyhow::Result<()> {
+    let trade = match session.trade.clone() {
+        Some(trade) => trade,
+        None => return Ok(()),
+    };
+
+    let trade = trade.lock().await;
+    let character = session.character.as_ref().unwrap();
+
+    if trade.closed || trade.traders.len() != 1 || character_id == character.data.id {
+        return Ok(());
+    }
+
+    if !session
+        .state
+        .get_map_characters(character.field_id)
+        .contains_key(&character_id)
+    {
+        return Ok(());
+    }
+
+    let packet = packet::trade_invite(trade.id, &character.data.name);
+    let message = SessionMessage::TradeInvite(trade.id, packet);
+
+    if !session.state.send_to_session(character_id, message).await {
+        return send_notice(session, "That character isn't in this map.").await;
+    }
+
+    Ok(())
+}
+
+/// Declines the trade the character was last invited to
+pub async fn decline(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let trade = match session
+        .trade_invite
+        .take()
+        .and_then(|trade_id| session.state.get_trade(trade_id))
+    {
+        Some(trade) => trade,
+        None => return Ok(()),
+    };
+
+    let inviter_id = trade.lock().await.traders[0].data.id;
+    let name = &session.character.as_ref().unwrap().data.name;
+    let text = format!("{} has declined your trade request.", name);
+    let packet = packet::server_notice(NoticeType::PinkText, &text);
+    session
+        .state
+        .send_to_session(inviter_id, SessionMessage::Packet(packet))
+        .await;
+
+    Ok(())
+}
+
+/// Joins a trade the character was invited to
+pub async fn join(session: &mut ChannelSession, trade_id: i32) -> anyhow::Result<()> {
+    if session.trade_invite.take() != Some(trade_id) || session.trade.is_some() {
+        return Ok(());
+    }
+
+    let shared_trade = match session.state.get_trade(trade_id) {
+        Some(trade) => trade,
+        None => return send_notice(session, "The trade has already been closed.").await,
+    };
+
+    let mut trade = shared_trade.lock().await;
+
+    if trade.closed || trade.traders.len() != 1 {
+        return send_notice(session, "The trade has already been closed.").await;
+    }
+
+    let character = session.character.as_ref().unwrap();
+    trade.traders.push(Trader::new(character));
+
+    let partner = &trade.traders[0];
+    let packet = packet::trade_room(
+        1,
+        (&character.data, &character.equipment),
+        Some((&partner.data, &partner.equipment)),
+    );
+    session.stream.write_packet(packet).await?;
+
+    let packet = packet::trade_partner_joined(&character.data, &character.equipment);
+    send_to(session, partner.data.id, packet).await?;
+
+    drop(trade);
+    session.trade = Some(shared_trade);
+    Ok(())
+}
+
+/// Sends a chat message to both traders
+pub async fn chat(session: &mut ChannelSession, message: &str) -> anyhow::Result<()> {
+    let trade = match session.trade.clone() {
+        Some(trade) => trade,
+        None => return Ok(()),
+    };
+
+    let trade = trade.lock().await;
+    let name = session.character.as_ref().unwrap().data.name.clone();
+
+    for (number, trader) in trade.traders.iter().enumerate() {
+        let packet = packet::room_chat(number as u8, &name, message);
+        send_to(session, trader.data.id, packet).await?;
+    }
+
+    Ok(())
+}
+
+/// Puts an item from the character's inventory into a trade slot
+pub async fn add_item(
+    session: &mut ChannelSession,
+    inventory_type: InventoryType,
+    position: i32,
+    amount: i32,
+    slot: u8,
+) -> anyhow::Result<()> {
+    let trade = match session.trade.clone() {
+        Some(trade) => trade,
+        None => return Ok(()),
+    };
+
+    let mut trade = trade.lock().await;
+    let character = session.character.as_mut().unwrap();
+
+    let number = match trade.get_number(character.data.id) {
+        Some(number) if trade.is_open() => number,
+        _ => return Ok(()),
+    };
+
+    let trader = &mut trade.traders[number];
+
+    if !(1..=TRADE_SLOTS).contains(&slot) || trader.items.iter().any(|item| item.slot == slot) {
+        return Ok(());
+    }
+
+    let item = match character
+        .items
+        .iter()
+        .find(|item| item.inventory_type == inventory_type && item.position == position)
+    {
+        Some(item) => item,
+        None => return Ok(()),
+    };
+
+    if nx::Item::load(item.item_id)?.is_untradeable {
+        return send_notice(session, "That item can't be traded.").await;
+    }
+
+    // Throwing stars and bullets can only be traded as a whole stack
+    let amount = match item.item_id / 10000 {
+        207 | 233 => item.amount,
+        _ => amount,
+    };
+
+    if amount <= 0 || item.amount < amount {
+        return Ok(());
+    }
+
+    let whole_stack = item.amount == amount;
+    let item = sql::Item {
+        amount,
+        ..item.clone()
+    };
+
+    // The item only leaves the inventory once the db holds it, so a failed hold changes nothing
+    let held = match sql::Item::hold(&item, whole_stack, &session.db).await? {
+        Some(held) => held,
+        None => {
+            log::warn!(
+                "Item {} is no longer in character {}'s inventory",
+                item.id,
+                item.character_id
+            );
+            return send_notice(session, "That item can't be traded right now.").await;
+        }
+    };
+
+    character.take_item(inventory_type, position, amount);
+
+    let change = if whole_stack {
+        InventoryChange::Remove(item)
+    } else {
+        let remaining = character
+            .items
+            .iter()
+            .find(|remaining| remaining.id == item.id)
+            .unwrap();
+        InventoryChange::Update(remaining.clone())
+    };
+
+    trader.items.push(TradeItem {
+        slot,
+        item: held.clone(),
+    });
+
+    session
+        .stream
+        .write_packet(packet::update_inventory(&[change]))
+        .await?;
+
+    for trader in trade.traders.iter() {
+        let packet = packet::trade_item_added(number as u8, slot, &held);
+        send_to(session, trader.data.id, packet).await?;
+    }
+
+    Ok(())
+}
+
+/// Puts mesos from the character into the trade
+pub async fn add_mesos(session: &mut ChannelSession, amount: i32) -> anyhow::Result<()> {
+    let trade = match session.trade.clone() {
+        Some(trade) => trade,
+        None => return Ok(()),
+    };
+
+    let mut trade = trade.lock().await;
+    let character = session.character.as_mut().unwrap();
+
+    let number = match trade.get_number(character.data.id) {
+        Some(number) if trade.is_open() => number,
+        _ => return Ok(()),
+    };
+
+    if amount <= 0 || amount > character.data.mesos {
+        return Ok(());
+    }
+
+    character.data.mesos -= amount;
+    let mesos = character.data.mesos;
+
+    let trader = &mut trade.traders[number];
+    trader.mesos += amount;
+    let total = trader.mesos;
+
+    session
+        .stream
+        .write_packet(packet::update_stats(&[(Stat::Mesos, mesos)]))
+        .await?;
+
+    for trader in trade.traders.iter() {
+        let packet = packet::trade_mesos_set(number as u8, total);
+        send_to(session, trader.data.id, packet).await?;
+    }
+
+    Ok(())
+}
+
+/// Confirms the trade, completing it once both traders have confirmed
+pub async fn confirm(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let shared_trade = match session.trade.clone() {
+        Some(trade) => trade,
+        None => return Ok(()),
+    };
+
+    let mut trade = shared_trade.lock().await;
+    let character_id = session.character.as_ref().unwrap().data.id;
+
+    let number = match trade.get_number(character_id) {
+        Some(number) if !trade.closed && trade.traders.len() == 2 => number,
+        _ => return Ok(()),
+    };
+
+    if trade.traders[number].confirmed {
+        return Ok(());
+    }
+
+    trade.traders[number].confirmed = true;
+    let partner_id = trade.traders[1 - number].data.id;
+
+    if !trade.traders[1 - number].confirmed {
+        return send_to(session, partner_id, packet::trade_confirmed()).await;
+    }
+
+    complete(session, &mut trade).await?;
+    drop(trade);
+
+    apply_outcome(session).await?;
+    notify_closed(session, partner_id).await
+}
+
+/// Cancels the character's trade, giving both traders back what they put into it
+/// If the trade was already closed, the character takes their outcome instead
+/// Called when the character closes the trade window, changes maps, or logs out
+pub async fn cancel(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let shared_trade = match session.trade.clone() {
+        Some(trade) => trade,
+        None => return Ok(()),
+    };
+
+    let mut trade = shared_trade.lock().await;
+
+    if !trade.closed {
+        trade.closed = true;
+        session.state.remove_trade(trade.id);
+
+        for trader in trade.traders.iter_mut() {
+            trader.return_all(TradeResult::PartnerCancelled);
+        }
+
+        let character_id = session.character.as_ref().unwrap().data.id;
+
+        for trader in trade.traders.iter() {
+            if trader.data.id != character_id {
+                notify_closed(session, trader.data.id).await?;
+            }
+        }
+    }
+
+    drop(trade);
+    apply_outcome(session).await
+}
+
+/// Gives the character their outcome of their closed trade
+pub async fn apply_outcome(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let shared_trade = match session.trade.clone() {
+        Some(trade) => trade,
+        None => return Ok(()),
+    };
+
+    let mut trade = shared_trade.lock().await;
+    let character = session.character.as_mut().unwrap();
+
+    let number = match trade.get_number(character.data.id) {
+        Some(number) if trade.closed => number,
+        _ => return Ok(()),
+    };
+
+    let outcome = match trade.traders[number].outcome.take() {
+        Some(outcome) => outcome,
+        None => return Ok(()),
+    };
+
+    drop(trade);
+    session.trade = None;
+
+    let mut changes = Vec::new();
+    let mut still_held = false;
+
+    for item in outcome.items {
+        if outcome.returned {
+            match character.restore_item(item, &session.db).await? {
+                Some(change) => changes.push(change),
+                None => still_held = true,
+            }
+        } else {
+            character.items.push(item.clone());
+            changes.push(InventoryChange::Add(item));
+        }
+    }
+
+    character.data.mesos = character.data.mesos.saturating_add(outcome.mesos);
+    let mesos = character.data.mesos;
+
+    if !changes.is_empty() {
+        session
+            .stream
+            .write_packet(packet::update_inventory(&changes))
+            .await?;
+    }
+
+    if outcome.mesos != 0 {
+        session
+            .stream
+            .write_packet(packet::update_stats(&[(Stat::Mesos, mesos)]))
+            .await?;
+    }
+
+    if still_held {
+        let text = "Your inventory is full, the rest of your items will be given back when you log in again.";
+        session
+            .stream
+            .write_packet(packet::server_notice(NoticeType::PinkText, text))
+            .await?;
+    }
+
+    session
+        .stream
+        .write_packet(packet::trade_closed(number as u8, outcome.result))
+        .await
+}
+
+/// Swaps the traded items and mesos once both traders have confirmed, and closes the trade
+async fn complete(session: &mut ChannelSession, trade: &mut Trade) -> anyhow::Result<()> {
+    trade.closed = true;
+    session.state.remove_trade(trade.id);
+
+    let mut transfers = Vec::new();
+    let mut has_space = true;
+
+    for number in 0..2 {
+        let receiver = &trade.traders[1 - number].data;
+
+        // The received items are put into the positions that are free now
+        let mut free_positions = Vec::new();
+
+        for inventory_type in INVENTORY_TYPES {
+            let slots = receiver.get_slots(inventory_type);
+            let positions =
+                sql::Item::get_free_positions(receiver.id, inventory_type, slots, &session.db)
+                    .await?;
+            free_positions.push(positions);
+        }
+
+        for trade_item in trade.traders[number].items.iter() {
+            let item = &trade_item.item;
+            let positions = &mut free_positions[item.inventory_type as usize];
+
+            if positions.is_empty() {
+                has_space = false;
+                break;
+            }
+
+            transfers.push(ItemTransfer {
+                item: item.clone(),
+                character_id: receiver.id,
+                position: positions.remove(0),
+            });
+        }
+    }
+
+    if !has_space {
+        let text = "The trade failed because there isn't enough inventory space.";
+
+        for trader in trade.traders.iter_mut() {
+            let packet = packet::server_notice(NoticeType::Popup, text);
+            send_to(session, trader.data.id, packet).await?;
+            trader.return_all(TradeResult::Unsuccessful);
+        }
+
+        return Ok(());
+    }
+
+    // Each trader receives the other's mesos, minus the trade fee
+    let received: Vec<i32> = (0..2)
+        .map(|number| {
+            let mesos = trade.traders[1 - number].mesos;
+            mesos - get_fee(mesos)
+        })
+        .collect();
+
+    // The given mesos were only taken from the traders' characters, not from the db
+    let mesos: Vec<(i32, i32)> = (0..2)
+        .map(|number| {
+            let trader = &trade.traders[number];
+            (trader.data.id, received[number] - trader.mesos)
+        })
+        .collect();
+
+    let moved = match sql::Item::trade(&transfers, &mesos, &session.db).await? {
+        Some(moved) => moved,
+        None => {
+            log::warn!("Trade {} failed, an item changed owners", trade.id);
+
+            for trader in trade.traders.iter_mut() {
+                trader.return_all(TradeResult::Unsuccessful);
+            }
+
+            return Ok(());
+        }
+    };
+
+    for (number, trader) in trade.traders.iter_mut().enumerate() {
+        trader.outcome = Some(Outcome {
+            result: TradeResult::Successful,
+            items: moved
+                .iter()
+                .filter(|item| item.character_id == trader.data.id)
+                .cloned()
+                .collect(),
+            returned: false,
+            mesos: received[number],
+        });
+    }
+
+    Ok(())
+}
+
+/// Gets the fee taken from mesos received in a trade
+fn get_fee(mesos: i32) -> i32 {
+    let rate = match mesos {
+        100000000.. => 0.06,
+        25000000.. => 0.05,
+        10000000.. => 0.04,
+        5000000.. => 0.03,
+        1000000.. => 0.018,
+        100000.. => 0.008,
+        _ => 0.0,
+    };
+
+    (mesos as f64 * rate) as i32
+}
+
+/// Tells a trader that their trade closed, so they take their outcome
+async fn notify_closed(session: &ChannelSession, character_id: i32) -> anyhow::Result<()> {
+    session
+        .state
+        .send_to_session(character_id, SessionMessage::TradeClosed)
+        .await;
+    Ok(())
+}
+
+/// Sends a packet to one of the traders, both traders are always on the same channel
+async fn send_to(
+    session: &mut ChannelSession,
+    character_id: i32,
+    packet: Packet,
+) -> anyhow::Result<()> {
+    if session.character.as_ref().unwrap().data.id == character_id {
+        return session.stream.write_packet(packet).await;
+    }
+
+    session
+        .state
+        .send_to_session(character_id, SessionMessage::Packet(packet))
+        .await;
+    Ok(())
+}
+
+async fn send_notice(session: &mut ChannelSession, message: &str) -> anyhow::Result<()> {
+    session
+        .stream
+        .write_packet(packet::server_notice(NoticeType::Popup, message))
+        .await
+}
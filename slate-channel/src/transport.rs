@@ -0,0 +1,393 @@
+use crate::{
+    session::{ChannelSession, SessionMessage},
+    state::State,
+};
+use rand::Rng;
+use slate_data::{
+    maple::{
+        self,
+        map::{MapBroadcast, PacketBroadcast},
+    },
+    nx,
+    packet::{self, NpcDialog},
+};
+use slate_net::Packet;
+use sqlx::types::chrono::Utc;
+use std::{sync::Arc, time::Duration};
+use tokio::time;
+
+/// Crog and his Crimson Balrogs invade ships on their way between Ellinia and Orbis
+const CROG: i32 = 8150001;
+const CRIMSON_BALROG: i32 = 8150000;
+
+/// The monsters that jump on board an invaded ship, Crog leads two Crimson Balrogs
+const INVADERS: [i32; 3] = [CROG, CRIMSON_BALROG, CRIMSON_BALROG];
+
+/// The chance (out of 100) that a ship gets invaded on its trip
+const INVASION_CHANCE: i32 = 50;
+
+/// The music that plays while a ship is being invaded
+const INVASION_MUSIC: &str = "Bgm04/ArabPirate";
+
+/// A transport that runs on a schedule between two stations, travel times are sped up by the world's travel rate
+struct Route {
+    name: &'static str,
+    /// The npc that lets characters board at the station
+    npc_id: i32,
+    /// The ticket characters hand over to board
+    ticket_id: i32,
+    station_map_id: i32,
+    /// Where characters wait for the transport to depart after boarding
+    waiting_map_id: i32,
+    /// Where characters are while the transport is on its way
+    travel_map_id: i32,
+    destination_map_id: i32,
+    /// Whether the transport is a ship that sails in and out of the station
+    is_ship: bool,
+    /// Where the invaders spawn when they invade the transport, if they can
+    invasion_pos: Option<(i32, i32)>,
+    /// The seconds characters can board for
+    boarding_time: u64,
+    /// The seconds between boarding closing and the transport departing
+    closed_time: u64,
+    /// The seconds the trip takes
+    travel_time: u64,
+}
+
+const ROUTES: [Route; 6] = [
+    Route {
+        name: "ship to Orbis",
+        npc_id: 1032008,
+        ticket_id: 4031045,
+        station_map_id: 101000300,
+        waiting_map_id: 101000301,
+        travel_map_id: 200090010,
+        destination_map_id: 200000100,
+        is_ship: true,
+        invasion_pos: Some((-590, -221)),
+        boarding_time: 4 * 60,
+        closed_time: 60,
+        travel_time: 10 * 60,
+    },
+    Route {
+        name: "ship to Ellinia",
+        npc_id: 2012001,
+        ticket_id: 4031047,
+        station_map_id: 200000111,
+        waiting_map_id: 200000112,
+        travel_map_id: 200090000,
+        destination_map_id: 101000300,
+        is_ship: true,
+        invasion_pos: Some((485, -221)),
+        boarding_time: 4 * 60,
+        closed_time: 60,
+        travel_time: 10 * 60,
+    },
+    Route {
+        name: "train to Ludibrium",
+        npc_id: 2012013,
+        ticket_id: 4031074,
+        station_map_id: 200000121,
+        waiting_map_id: 200000122,
+        travel_map_id: 200090100,
+        destination_map_id: 220000110,
+        is_ship: false,
+        invasion_pos: None,
+        boarding_time: 4 * 60,
+        closed_time: 60,
+        travel_time: 5 * 60,
+    },
+    Route {
+        name: "train to Orbis",
+        npc_id: 2041000,
+        ticket_id: 4031045,
+        station_map_id: 220000110,
+        waiting_map_id: 220000111,
+        travel_map_id: 200090110,
+        destination_map_id: 200000100,
+        is_ship: false,
+        invasion_pos: None,
+        boarding_time: 4 * 60,
+        closed_time: 60,
+        travel_time: 5 * 60,
+    },
+    Route {
+        name: "subway to New Leaf City",
+        npc_id: 9201057,
+        ticket_id: 4031711,
+        station_map_id: 103000100,
+        waiting_map_id: 600010004,
+        travel_map_id: 600010005,
+        destination_map_id: 600010001,
+        is_ship: false,
+        invasion_pos: None,
+        boarding_time: 4 * 60,
+        closed_time: 60,
+        travel_time: 5 * 60,
+    },
+    Route {
+        name: "subway to Kerning City",
+        npc_id: 9201057,
+        ticket_id: 4031713,
+        station_map_id: 600010001,
+        waiting_map_id: 600010002,
+        travel_map_id: 600010003,
+        destination_map_id: 103000100,
+        is_ship: false,
+        invasion_pos: None,
+        boarding_time: 4 * 60,
+        closed_time: 60,
+        travel_time: 5 * 60,
+    },
+];
+
+/// Where a transport is in its cycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Docked at the station, characters can board
+    Boarding,
+    /// Docked at the station about to depart, characters can't board anymore
+    Closed,
+    /// On its way to the destination
+    Travelling,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TransportStatus {
+    pub phase: Phase,
+    /// When the transport next departs, or arrives if it's travelling, in milliseconds
+    pub next_stop_at: i64,
+}
+
+/// Starts running every transport's schedule in the channel
+pub fn start(state: Arc<State>) {
+    for route in ROUTES.iter() {
+        tokio::spawn(run_route(state.clone(), route));
+    }
+}
+
+/// Lets the character board a transport from its station if it's boarding
+/// Returns false if the npc doesn't run a transport from the character's map
+pub async fn talk(session: &mut ChannelSession, npc_id: i32) -> anyhow::Result<bool> {
+    let route = match get_route(session, npc_id) {
+        Some(route) => route,
+        None => return Ok(false),
+    };
+
+    let boarding = matches!(
+        session.state.get_transport_status(route.station_map_id),
+        Some(status) if status.phase == Phase::Boarding
+    );
+
+    let (dialog, text) = if boarding {
+        session.npc_conversation = Some(npc_id);
+        (
+            NpcDialog::YesNo,
+            format!("The {} is boarding. Would you like to get on?", route.name),
+        )
+    } else {
+        (
+            NpcDialog::Ok,
+            format!(
+                "The {} isn't boarding right now. Please wait for the next one.",
+                route.name
+            ),
+        )
+    };
+
+    session
+        .stream
+        .write_packet(packet::npc_talk(npc_id, dialog, &text))
+        .await?;
+    Ok(true)
+}
+
+/// Puts the character into the waiting room of the transport run by an npc, if it's still boarding
+pub async fn board(session: &mut ChannelSession, npc_id: i32) -> anyhow::Result<()> {
+    let route = match get_route(session, npc_id) {
+        Some(route) => route,
+        None => return Ok(()),
+    };
+
+    let boarding = matches!(
+        session.state.get_transport_status(route.station_map_id),
+        Some(status) if status.phase == Phase::Boarding
+    );
+
+    if !boarding {
+        let text = format!("The {} has stopped boarding.", route.name);
+        return say(session, npc_id, &text).await;
+    }
+
+    let ticket = match session
+        .character
+        .as_mut()
+        .unwrap()
+        .remove_item(route.ticket_id, 1)
+    {
+        Some(ticket) => ticket,
+        None => {
+            let name =
+                nx::Item::load_name(route.ticket_id).unwrap_or_else(|| route.ticket_id.to_string());
+            let text = format!("You need a {} to board the {}.", name, route.name);
+            return say(session, npc_id, &text).await;
+        }
+    };
+
+    ticket.update_amount(&session.db).await?;
+    session
+        .stream
+        .write_packet(packet::update_item_amount(&ticket))
+        .await?;
+
+    session.change_map(route.waiting_map_id, 0).await
+}
+
+/// Checks if an npc runs a transport
+pub fn is_transport_npc(npc_id: i32) -> bool {
+    ROUTES.iter().any(|route| route.npc_id == npc_id)
+}
+
+/// Shows a character entering a map where the transport is docked, or how long until it departs or arrives
+pub async fn on_enter_map(session: &mut ChannelSession) -> anyhow::Result<()> {
+    let map_id = session.character.as_ref().unwrap().data.map;
+    let now = Utc::now().timestamp_millis();
+
+    for route in ROUTES.iter() {
+        let status = match session.state.get_transport_status(route.station_map_id) {
+            Some(status) => status,
+            None => continue,
+        };
+
+        if map_id == route.station_map_id && route.is_ship && status.phase != Phase::Travelling {
+            session.stream.write_packet(packet::move_ship(true)).await?;
+        }
+
+        let waiting = map_id == route.waiting_map_id && status.phase != Phase::Travelling;
+        let travelling = map_id == route.travel_map_id && status.phase == Phase::Travelling;
+
+        if waiting || travelling {
+            let seconds = ((status.next_stop_at - now) / 1000).max(0) as i32;
+            session.stream.write_packet(packet::clock(seconds)).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Gets the route an npc runs from the character's map
+fn get_route(session: &ChannelSession, npc_id: i32) -> Option<&'static Route> {
+    let map_id = session.character.as_ref().unwrap().data.map;
+
+    ROUTES
+        .iter()
+        .find(|route| route.npc_id == npc_id && route.station_map_id == map_id)
+}
+
+/// Runs a transport's cycle forever: boarding, closing its doors, then travelling to its destination
+async fn run_route(state: Arc<State>, route: &'static Route) {
+    let rate = state.world.travel_rate.max(1) as u32;
+    let boarding_time = Duration::from_secs(route.boarding_time) / rate;
+    let closed_time = Duration::from_secs(route.closed_time) / rate;
+    let travel_time = Duration::from_secs(route.travel_time) / rate;
+
+    loop {
+        let departs_at = now_after(boarding_time + closed_time);
+        set_phase(&state, route, Phase::Boarding, departs_at);
+
+        if route.is_ship {
+            broadcast(&state, route.station_map_id, packet::move_ship(true));
+        }
+
+        time::sleep(boarding_time).await;
+        set_phase(&state, route, Phase::Closed, departs_at);
+        time::sleep(closed_time).await;
+
+        set_phase(&state, route, Phase::Travelling, now_after(travel_time));
+
+        if route.is_ship {
+            broadcast(&state, route.station_map_id, packet::move_ship(false));
+        }
+
+        move_characters(&state, route.waiting_map_id, route.travel_map_id).await;
+
+        match route.invasion_pos {
+            Some(pos) if rand::thread_rng().gen_range(0..100) < INVASION_CHANCE => {
+                time::sleep(travel_time / 2).await;
+
+                if let Err(e) = invade(&state, route, pos) {
+                    log::error!("Error invading the {}: {}", route.name, e);
+                }
+
+                time::sleep(travel_time - travel_time / 2).await;
+            }
+            _ => time::sleep(travel_time).await,
+        }
+
+        // Any invaders left behind leave with the ship
+        state.remove_monsters(route.travel_map_id);
+        move_characters(&state, route.travel_map_id, route.destination_map_id).await;
+    }
+}
+
+/// Has Crog's ship pull up alongside a transport, with Crog and his Crimson Balrogs jumping on board
+fn invade(state: &State, route: &Route, pos: (i32, i32)) -> anyhow::Result<()> {
+    let map_id = route.travel_map_id;
+    broadcast(state, map_id, packet::move_balrog_ship(true));
+    broadcast(state, map_id, packet::change_map_music(INVASION_MUSIC));
+
+    for monster_id in INVADERS {
+        let data = nx::Mob::load(monster_id)?;
+
+        // TODO use the foothold below the position once footholds are tracked
+        let monster = maple::Monster::new(state.next_object_id(), data, pos, 0);
+        broadcast(state, map_id, packet::spawn_monster(&monster, true));
+        state.add_monster(map_id, monster);
+    }
+
+    Ok(())
+}
+
+fn set_phase(state: &State, route: &Route, phase: Phase, next_stop_at: i64) {
+    let status = TransportStatus {
+        phase,
+        next_stop_at,
+    };
+    state.set_transport_status(route.station_map_id, status);
+}
+
+/// Sends every character in one map to another
+async fn move_characters(state: &State, from_map_id: i32, to_map_id: i32) {
+    let message = SessionMessage::ChangeMap {
+        map_id: to_map_id,
+        portal_id: 0,
+    };
+
+    for character_id in state.get_map_characters(from_map_id).into_keys() {
+        state.send_to_session(character_id, message.clone()).await;
+    }
+}
+
+/// Sends a packet to everyone in a map
+fn broadcast(state: &State, map_id: i32, packet: Packet) {
+    // Nobody might be in the map to see it
+    let _ = state
+        .get_map_broadcast_tx(map_id)
+        .send(MapBroadcast::Packet(PacketBroadcast {
+            packet,
+            sender_id: 0,
+            send_to_sender: true,
+        }));
+}
+
+/// Gets the time in milliseconds after the given duration from now
+fn now_after(duration: Duration) -> i64 {
+    Utc::now().timestamp_millis() + duration.as_millis() as i64
+}
+
+async fn say(session: &mut ChannelSession, npc_id: i32, text: &str) -> anyhow::Result<()> {
+    session
+        .stream
+        .write_packet(packet::npc_talk(npc_id, NpcDialog::Ok, text))
+        .await
+}
@@ -0,0 +1,257 @@
+use crate::{
+    session::{ChannelSession, SessionMessage},
+    state::State,
+};
+use slate_data::{
+    sql::{self, world_message::WorldMessageKind},
+    Db,
+};
+use slate_net::Packet;
+use std::{sync::Arc, time::Duration};
+use tokio::time;
+
+/// How often the channel checks for messages sent from other channels
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Sends a message to a character on any channel in the session's world
+/// Returns false if the character isn't online
+pub async fn send(
+    session: &ChannelSession,
+    character_id: i32,
+    message: SessionMessage,
+) -> anyhow::Result<bool> {
+    let online_character = match sql::OnlineCharacter::load_optional(character_id, &session.db)
+        .await?
+    {
+        Some(online_character) if online_character.world_id == session.world_id => online_character,
+        _ => return Ok(false),
+    };
+
+    deliver(session, &online_character, message).await
+}
+
+/// Sends a message to every online character out of the given character ids, on any channel in the session's world
+/// Offline characters are skipped, returns the ids of the characters the message was sent to
+pub async fn send_to_all(
+    session: &ChannelSession,
+    character_ids: &[i32],
+    message: SessionMessage,
+) -> anyhow::Result<Vec<i32>> {
+    let online_characters =
+        sql::OnlineCharacter::load_all_by_ids(character_ids, &session.db).await?;
+    let mut recipients = Vec::new();
+
+    for online_character in online_characters.iter() {
+        if online_character.world_id != session.world_id {
+            continue;
+        }
+
+        if deliver(session, online_character, message.clone()).await? {
+            recipients.push(online_character.character_id);
+        }
+    }
+
+    Ok(recipients)
+}
+
+/// Delivers a message to an online character, either directly if they're on our channel or through the database
+async fn deliver(
+    session: &ChannelSession,
+    online_character: &sql::OnlineCharacter,
+    message: SessionMessage,
+) -> anyhow::Result<bool> {
+    let character_id = online_character.character_id;
+
+    // The recipient is on our channel, no need to go through the database
+    if online_character.channel_id == session.channel_id {
+        return Ok(session.state.send_to_session(character_id, message).await);
+    }
+
+    let (kind, value, packet) = message.into_parts();
+
+    sql::WorldMessage::insert(
+        session.world_id,
+        online_character.channel_id,
+        character_id,
+        kind,
+        value,
+        &packet,
+        &session.db,
+    )
+    .await?;
+
+    Ok(true)
+}
+
+/// Sends a packet to a character on any channel in the session's world
+/// Returns false if the character isn't online
+pub async fn send_packet(
+    session: &ChannelSession,
+    character_id: i32,
+    packet: Packet,
+) -> anyhow::Result<bool> {
+    send(session, character_id, SessionMessage::Packet(packet)).await
+}
+
+/// Sends a packet to every online character out of the given character ids, on any channel in the session's world
+/// Offline characters are skipped, returns the ids of the characters the packet was sent to
+pub async fn send_packet_to_all(
+    session: &ChannelSession,
+    character_ids: &[i32],
+    packet: Packet,
+) -> anyhow::Result<Vec<i32>> {
+    send_to_all(session, character_ids, SessionMessage::Packet(packet)).await
+}
+
+/// Delivers messages sent from other channels to the sessions connected to this channel
+pub async fn listen(state: Arc<State>, db: Db, world_id: i32, channel_id: i32) {
+    let mut interval = time::interval(POLL_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = deliver_messages(&state, &db, world_id, channel_id).await {
+            log::error!("Error delivering world messages: {}", e);
+        }
+    }
+}
+
+async fn deliver_messages(
+    state: &State,
+    db: &Db,
+    world_id: i32,
+    channel_id: i32,
+) -> anyhow::Result<()> {
+    let messages = sql::WorldMessage::load_all(world_id, channel_id, db).await?;
+
+    let last_id = match messages.last() {
+        Some(message) => message.id,
+        None => return Ok(()),
+    };
+
+    sql::WorldMessage::delete_up_to(world_id, channel_id, last_id, db).await?;
+
+    for message in messages {
+        let recipient_id = message.recipient_id;
+
+        // The recipient may have logged out or changed channels since the message was sent
+        if !state
+            .send_to_session(recipient_id, SessionMessage::from(message))
+            .await
+        {
+            log::debug!(
+                "World message recipient {} is no longer online",
+                recipient_id
+            );
+        }
+    }
+
+    Ok(())
+}
+
+impl SessionMessage {
+    /// Splits a message into its database representation
+    fn into_parts(self) -> (WorldMessageKind, Option<i32>, Vec<u8>) {
+        match self {
+            Self::Packet(packet) => (WorldMessageKind::Packet, None, packet.bytes.to_vec()),
+            Self::Disconnect => (WorldMessageKind::Disconnect, None, Vec::new()),
+            Self::PartyInvite(party_id, packet) => (
+                WorldMessageKind::PartyInvite,
+                Some(party_id),
+                packet.bytes.to_vec(),
+            ),
+            Self::PartyChanged(party_id) => (WorldMessageKind::PartyChanged, party_id, Vec::new()),
+            Self::GainExp { exp, party_bonus } => (
+                WorldMessageKind::GainExp,
+                Some(exp),
+                party_bonus.to_le_bytes().to_vec(),
+            ),
+            Self::KillCredit(mob_id) => (WorldMessageKind::KillCredit, Some(mob_id), Vec::new()),
+            Self::GuildInvite(guild_id, packet) => (
+                WorldMessageKind::GuildInvite,
+                Some(guild_id),
+                packet.bytes.to_vec(),
+            ),
+            Self::GuildChanged => (WorldMessageKind::GuildChanged, None, Vec::new()),
+            Self::AllianceInvite(alliance_id, packet) => (
+                WorldMessageKind::AllianceInvite,
+                Some(alliance_id),
+                packet.bytes.to_vec(),
+            ),
+            Self::TradeInvite(trade_id, packet) => (
+                WorldMessageKind::TradeInvite,
+                Some(trade_id),
+                packet.bytes.to_vec(),
+            ),
+            Self::TradeClosed => (WorldMessageKind::TradeClosed, None, Vec::new()),
+            Self::PlayerShopChanged => (WorldMessageKind::PlayerShopChanged, None, Vec::new()),
+            Self::CharacterInfo(requester_id) => (
+                WorldMessageKind::CharacterInfo,
+                Some(requester_id),
+                Vec::new(),
+            ),
+            // Private copies of maps only exist in their own channel, so the character goes to the map itself
+            Self::ChangeMap { map_id, portal_id }
+            | Self::ChangeField {
+                map_id, portal_id, ..
+            } => (
+                WorldMessageKind::ChangeMap,
+                Some(map_id),
+                portal_id.to_le_bytes().to_vec(),
+            ),
+        }
+    }
+}
+
+impl From<sql::WorldMessage> for SessionMessage {
+    fn from(message: sql::WorldMessage) -> Self {
+        match message.kind {
+            WorldMessageKind::Packet => {
+                Self::Packet(Packet::wrap(message.packet.as_slice().into()))
+            }
+            WorldMessageKind::Disconnect => Self::Disconnect,
+            WorldMessageKind::PartyInvite => Self::PartyInvite(
+                message.value.unwrap_or_default(),
+                Packet::wrap(message.packet.as_slice().into()),
+            ),
+            WorldMessageKind::PartyChanged => Self::PartyChanged(message.value),
+            WorldMessageKind::GainExp => Self::GainExp {
+                exp: message.value.unwrap_or_default(),
+                party_bonus: message
+                    .packet
+                    .get(..4)
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .map(i32::from_le_bytes)
+                    .unwrap_or_default(),
+            },
+            WorldMessageKind::KillCredit => Self::KillCredit(message.value.unwrap_or_default()),
+            WorldMessageKind::GuildInvite => Self::GuildInvite(
+                message.value.unwrap_or_default(),
+                Packet::wrap(message.packet.as_slice().into()),
+            ),
+            WorldMessageKind::GuildChanged => Self::GuildChanged,
+            WorldMessageKind::AllianceInvite => Self::AllianceInvite(
+                message.value.unwrap_or_default(),
+                Packet::wrap(message.packet.as_slice().into()),
+            ),
+            WorldMessageKind::TradeInvite => Self::TradeInvite(
+                message.value.unwrap_or_default(),
+                Packet::wrap(message.packet.as_slice().into()),
+            ),
+            WorldMessageKind::TradeClosed => Self::TradeClosed,
+            WorldMessageKind::PlayerShopChanged => Self::PlayerShopChanged,
+            WorldMessageKind::CharacterInfo => {
+                Self::CharacterInfo(message.value.unwrap_or_default())
+            }
+            WorldMessageKind::ChangeMap => Self::ChangeMap {
+                map_id: message.value.unwrap_or_default(),
+                portal_id: message
+                    .packet
+                    .get(..4)
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .map(i32::from_le_bytes)
+                    .unwrap_or_default(),
+            },
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use crate::nx;
+use sqlx::types::chrono::Utc;
+
+#[derive(Debug, Clone)]
+pub struct Buff {
+    pub skill_id: i32,
+    pub level: i32,
+    pub stats: Vec<(BuffStat, i16)>,
+    pub duration: i32,
+    pub expires_at: i64,
+}
+
+impl Buff {
+    /// Creates a buff from a skill's effect data
+    /// Returns None if the skill level doesn't grant any buff stats
+    pub fn from_skill(skill_id: i32, effect: &nx::SkillLevel) -> Option<Self> {
+        let mut stats = Vec::new();
+
+        let mut push = |stat: BuffStat, value: i32| {
+            if value != 0 {
+                stats.push((stat, value as i16));
+            }
+        };
+
+        push(BuffStat::WeaponAttack, effect.pad);
+        push(BuffStat::WeaponDefense, effect.pdd);
+        push(BuffStat::MagicAttack, effect.mad);
+        push(BuffStat::MagicDefense, effect.mdd);
+        push(BuffStat::Accuracy, effect.acc);
+        push(BuffStat::Avoid, effect.eva);
+        push(BuffStat::Speed, effect.speed);
+        push(BuffStat::Jump, effect.jump);
+
+        // Skills whose effect is driven by their x/y values rather than a flat stat increase
+        match skill_id {
+            // Hyper Body (Spearman, GM)
+            1301007 | 9101008 => {
+                push(BuffStat::HyperBodyHp, effect.x);
+                push(BuffStat::HyperBodyMp, effect.y);
+            }
+            // Magic Guard
+            2001002 => push(BuffStat::MagicGuard, effect.x),
+            // Power Guard (Fighter, Page)
+            1101007 | 1201007 => push(BuffStat::PowerGuard, effect.x),
+            // Meso Guard
+            4211005 => push(BuffStat::MesoGuard, effect.x),
+            // Dark Sight
+            4001003 => push(BuffStat::DarkSight, effect.x),
+            // Holy Symbol (Priest, GM)
+            2311003 | 9101002 => push(BuffStat::HolySymbol, effect.x),
+            // Meso Up
+            4111001 => push(BuffStat::MesoUp, effect.x),
+            // Shadow Partner
+            4111002 => push(BuffStat::ShadowPartner, effect.x),
+            // Soul Arrow (Hunter, Crossbowman)
+            3101004 | 3201004 => push(BuffStat::SoulArrow, effect.x),
+            // Maple Warrior
+            1121000 | 1221000 | 1321000 | 2121000 | 2221000 | 2321000 | 3121000 | 3221000
+            | 4121000 | 4221000 => push(BuffStat::MapleWarrior, effect.x),
+            // Sharp Eyes (Bowmaster, Marksman)
+            3121002 | 3221002 => push(BuffStat::SharpEyes, (effect.x << 8) | effect.y),
+            // Boosters
+            1101004 | 1101005 | 1201004 | 1201005 | 1301004 | 1301005 | 2111005 | 2211005
+            | 3101002 | 3201002 | 4101003 | 4201002 => push(BuffStat::Booster, effect.x),
+            _ => {}
+        }
+
+        if stats.is_empty() {
+            return None;
+        }
+
+        let duration = effect.time * 1000;
+
+        Some(Self {
+            skill_id,
+            level: effect.level,
+            stats,
+            duration,
+            expires_at: Utc::now().timestamp_millis() + duration as i64,
+        })
+    }
+
+    /// Creates the buff for riding a mount, which lasts until it's cancelled
+    pub fn mount(skill_id: i32) -> Self {
+        Self {
+            skill_id,
+            level: 1,
+            stats: vec![(BuffStat::MonsterRiding, 0)],
+            duration: 0,
+            expires_at: i64::MAX,
+        }
+    }
+
+    /// Gets the combined (first, second) bit masks of the buff's stats
+    pub fn mask(&self) -> (i64, i64) {
+        BuffStat::mask(self.stats.iter().map(|(stat, _)| *stat))
+    }
+
+    /// Gets the value of the given buff stat if this buff provides it
+    pub fn value(&self, stat: BuffStat) -> Option<i16> {
+        self.stats
+            .iter()
+            .find(|(buff_stat, _)| *buff_stat == stat)
+            .map(|(_, value)| *value)
+    }
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at <= now
+    }
+}
+
+/// Buff stats and their bit masks, the client splits these into two 64 bit masks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuffStat {
+    MapleWarrior,
+    SharpEyes,
+    MonsterRiding,
+    WeaponAttack,
+    WeaponDefense,
+    MagicAttack,
+    MagicDefense,
+    Accuracy,
+    Avoid,
+    Hands,
+    Speed,
+    Jump,
+    MagicGuard,
+    DarkSight,
+    Booster,
+    PowerGuard,
+    HyperBodyHp,
+    HyperBodyMp,
+    Invincible,
+    SoulArrow,
+    HolySymbol,
+    MesoUp,
+    ShadowPartner,
+    MesoGuard,
+}
+
+impl BuffStat {
+    /// Gets the stat's bit mask and whether it belongs to the first mask
+    pub fn value(&self) -> (i64, bool) {
+        match self {
+            Self::MapleWarrior => (0x8, true),
+            Self::SharpEyes => (0x20, true),
+            Self::MonsterRiding => (0x40000000, true),
+            Self::WeaponAttack => (0x1, false),
+            Self::WeaponDefense => (0x2, false),
+            Self::MagicAttack => (0x4, false),
+            Self::MagicDefense => (0x8, false),
+            Self::Accuracy => (0x10, false),
+            Self::Avoid => (0x20, false),
+            Self::Hands => (0x40, false),
+            Self::Speed => (0x80, false),
+            Self::Jump => (0x100, false),
+            Self::MagicGuard => (0x200, false),
+            Self::DarkSight => (0x400, false),
+            Self::Booster => (0x800, false),
+            Self::PowerGuard => (0x1000, false),
+            Self::HyperBodyHp => (0x2000, false),
+            Self::HyperBodyMp => (0x4000, false),
+            Self::Invincible => (0x8000, false),
+            Self::SoulArrow => (0x10000, false),
+            Self::HolySymbol => (0x1000000, false),
+            Self::MesoUp => (0x2000000, false),
+            Self::ShadowPartner => (0x4000000, false),
+            Self::MesoGuard => (0x10000000, false),
+        }
+    }
+
+    /// Combines the given stats into (first, second) bit masks
+    pub fn mask(stats: impl Iterator<Item = BuffStat>) -> (i64, i64) {
+        let mut first = 0;
+        let mut second = 0;
+
+        for stat in stats {
+            match stat.value() {
+                (value, true) => first |= value,
+                (value, false) => second |= value,
+            }
+        }
+
+        (first, second)
+    }
+}
@@ -1,9 +1,24 @@
-use crate::{sql, Db};
+use super::{buff::BuffStat, exp, mount, pet, Buff, Pet};
+use crate::{
+    nx,
+    sql::{self, item::InventoryType, quest::QuestStatus},
+    Db,
+};
+use anyhow::anyhow;
+use rand::Rng;
+use sqlx::{types::chrono::Utc, MySqlConnection};
+
+/// The highest max hp/mp a character can have
+const MAX_HP_MP: i32 = 30000;
 
 #[derive(Debug, Clone)]
 pub struct Character {
     pub pos: (i32, i32),
     pub stance: u8,
+    /// The chair the character is sitting on, 0 if they aren't sitting on one
+    pub chair: i32,
+    /// The instance of their map the character is in, the map's id unless it's a private copy (ex. for a party quest)
+    pub field_id: i32,
 
     pub data: sql::Character,
     pub equipment: Vec<sql::Equipment>, // TODO might want to make a map?
@@ -12,24 +27,46 @@ pub struct Character {
     pub skills: Vec<sql::Skill>,
     pub cooldowns: Vec<sql::Cooldown>,
     pub quests: Vec<sql::Quest>,
+    pub quest_progress: Vec<sql::QuestProgress>,
+    pub buffs: Vec<Buff>,
+    pub pets: Vec<Pet>,
+    pub mount: sql::Mount,
+
+    /// The character's guild, shown to other characters in the map
+    pub guild: Option<sql::Guild>,
 }
 
 impl Character {
     /// Loads a character by id
     pub async fn load(id: i32, db: &Db) -> anyhow::Result<Self> {
+        // Equipment that expired while the character was offline is gone
+        let now = Utc::now().timestamp_millis();
+        sql::Equipment::delete_expired(id, now, db).await?;
+
         let character = sql::Character::load(id, db).await?;
         let equipment = sql::Equipment::load_all(id, db).await?;
-        let items = sql::Item::load_all(id, db).await?;
+        let (held_items, items): (Vec<_>, Vec<_>) = sql::Item::load_all(id, db)
+            .await?
+            .into_iter()
+            .partition(|item| item.position == sql::item::HELD_POSITION);
         let keymaps = sql::Keymap::load_all(id, db).await?;
+        let skills = sql::Skill::load_all(id, db).await?;
+        let cooldowns = sql::Cooldown::load_all(id, db).await?;
+        let quests = sql::Quest::load_all(id, db).await?;
+        let quest_progress = sql::QuestProgress::load_all(id, db).await?;
+        let pets = sql::Pet::load_all(id, db).await?;
+        let mount = sql::Mount::load(id, db).await?;
 
-        // TODO load
-        let skills = Vec::new();
-        let cooldowns = Vec::new();
-        let quests = Vec::new();
+        let guild = match character.guild {
+            Some(guild_id) => sql::Guild::load_optional(guild_id, db).await?,
+            None => None,
+        };
 
-        Ok(Self {
+        let mut character = Self {
             pos: (0, 0),
             stance: 0,
+            chair: 0,
+            field_id: character.map,
             data: character,
             equipment,
             items,
@@ -37,6 +74,593 @@ impl Character {
             skills,
             cooldowns,
             quests,
+            quest_progress,
+            buffs: Vec::new(),
+            pets: pets.into_iter().map(Pet::new).collect(),
+            mount,
+            guild,
+        };
+
+        // Items still held by a trade when the character logged out are given back
+        for item in held_items {
+            character.restore_item(item, db).await?;
+        }
+
+        // Items that expired while the character was offline are gone, along with their pets
+        for item in character.take_expired_items(now) {
+            item.delete(db).await?;
+            character.remove_pet(&item, db).await?;
+        }
+
+        // Pets that were never loaded before don't have their data yet
+        for item in character.items.clone().iter() {
+            character.add_pet(item, db).await?;
+        }
+
+        Ok(character)
+    }
+
+    /// Saves the character's stats, cooldowns and pets
+    pub async fn save(&self, db: &Db) -> anyhow::Result<()> {
+        self.data.save(db).await?;
+        sql::Cooldown::save_all(self.data.id, &self.cooldowns, db).await?;
+        sql::Pet::save_all(self.pets.iter().map(|pet| &pet.data), db).await?;
+        self.mount.save(db).await?;
+        Ok(())
+    }
+
+    /// Loads the pet for a pet item that was put into the character's inventory, creating it if it's new
+    pub async fn add_pet(&mut self, item: &sql::Item, db: &Db) -> anyhow::Result<()> {
+        let id = match item.cash_id {
+            Some(id) if nx::Pet::is_pet(item.item_id) => id,
+            _ => return Ok(()),
+        };
+
+        if self.pets.iter().any(|pet| pet.data.id == id) {
+            return Ok(());
+        }
+
+        let pet = match sql::Pet::load_optional(id, db).await? {
+            Some(pet) => pet,
+            None => {
+                let pet = sql::Pet {
+                    id,
+                    item_id: item.item_id,
+                    name: nx::Item::load_name(item.item_id).unwrap_or_default(),
+                    level: 1,
+                    tameness: 0,
+                    fullness: pet::MAX_FULLNESS,
+                    flag: 0,
+                    slot: None,
+                };
+                pet.insert(db).await?;
+                pet
+            }
+        };
+
+        self.pets.push(Pet::new(pet));
+        Ok(())
+    }
+
+    /// Removes the pet for a pet item that was taken out of the character's inventory, unsummoning it
+    pub async fn remove_pet(&mut self, item: &sql::Item, db: &Db) -> anyhow::Result<()> {
+        let index = match self
+            .pets
+            .iter()
+            .position(|pet| Some(pet.data.id) == item.cash_id)
+        {
+            Some(index) => index,
+            None => return Ok(()),
+        };
+
+        let mut pet = self.pets.remove(index);
+        pet.data.slot = None;
+        sql::Pet::save_all([&pet.data].into_iter(), db).await
+    }
+
+    /// Gets the character's summoned pets, in the order of their slots
+    pub fn get_summoned_pets(&self) -> Vec<&Pet> {
+        let mut pets: Vec<&Pet> = self.pets.iter().filter(|pet| pet.is_summoned()).collect();
+        pets.sort_by_key(|pet| pet.data.slot);
+        pets
+    }
+
+    /// Gets one of the character's pets by id
+    pub fn get_pet_mut(&mut self, id: i64) -> Option<&mut Pet> {
+        self.pets.iter_mut().find(|pet| pet.data.id == id)
+    }
+
+    /// Gets the character's level in the given skill, 0 if the skill hasn't been learned
+    pub fn get_skill_level(&self, skill_id: i32) -> i32 {
+        self.skills
+            .iter()
+            .find(|skill| skill.id == skill_id)
+            .map(|skill| skill.level)
+            .unwrap_or(0)
+    }
+
+    /// Checks if the given skill is still on cooldown
+    pub fn is_on_cooldown(&self, skill_id: i32, now: i64) -> bool {
+        self.cooldowns
+            .iter()
+            .any(|cooldown| cooldown.skill_id == skill_id && !cooldown.is_expired(now))
+    }
+
+    /// Forgets the character's cooldowns that have finished
+    pub fn remove_expired_cooldowns(&mut self, now: i64) {
+        self.cooldowns.retain(|cooldown| !cooldown.is_expired(now));
+    }
+
+    /// Checks if the character is facing left, based on their stance
+    pub fn is_facing_left(&self) -> bool {
+        self.stance % 2 == 1
+    }
+
+    /// Applies a buff to the character, replacing any existing buff from the same skill
+    pub fn apply_buff(&mut self, buff: Buff) {
+        self.buffs
+            .retain(|existing| existing.skill_id != buff.skill_id);
+        self.buffs.push(buff);
+    }
+
+    /// Removes and returns all of the character's expired buffs
+    pub fn take_expired_buffs(&mut self, now: i64) -> Vec<Buff> {
+        let (expired, active) = self.buffs.drain(..).partition(|buff| buff.is_expired(now));
+        self.buffs = active;
+        expired
+    }
+
+    /// Removes and returns all of the character's expired items
+    pub fn take_expired_items(&mut self, now: i64) -> Vec<sql::Item> {
+        let (expired, items) = self.items.drain(..).partition(|item| item.is_expired(now));
+        self.items = items;
+        expired
+    }
+
+    /// Removes and returns all of the character's expired equipment
+    pub fn take_expired_equipment(&mut self, now: i64) -> Vec<sql::Equipment> {
+        let (expired, equipment) = self
+            .equipment
+            .drain(..)
+            .partition(|equip| equip.is_expired(now));
+        self.equipment = equipment;
+        expired
+    }
+
+    /// Gets the value of the given buff stat if the character has it
+    pub fn get_buff_value(&self, stat: BuffStat) -> Option<i16> {
+        self.buffs.iter().find_map(|buff| buff.value(stat))
+    }
+
+    /// Removes the buff from the given skill, if any
+    pub fn cancel_buff(&mut self, skill_id: i32) -> Option<Buff> {
+        let index = self
+            .buffs
+            .iter()
+            .position(|buff| buff.skill_id == skill_id)?;
+        Some(self.buffs.remove(index))
+    }
+
+    /// Gets the item id of the character's mount, None if they don't have a mount and saddle equipped
+    pub fn get_mount_item_id(&self) -> Option<i32> {
+        let get = |position: i32| {
+            self.equipment
+                .iter()
+                .find(|equip| equip.position == position)
+                .map(|equip| equip.item_id)
+        };
+
+        get(mount::SADDLE_POSITION)?;
+        get(mount::MOUNT_POSITION)
+    }
+
+    /// Checks if the character is riding their mount
+    pub fn is_riding(&self) -> bool {
+        self.get_buff_value(BuffStat::MonsterRiding).is_some()
+    }
+
+    /// Removes the buff that provides the given buff stat, if any
+    pub fn cancel_buff_stat(&mut self, stat: BuffStat) -> Option<Buff> {
+        let index = self
+            .buffs
+            .iter()
+            .position(|buff| buff.value(stat).is_some())?;
+        Some(self.buffs.remove(index))
+    }
+
+    /// Checks if the character is alive
+    pub fn is_alive(&self) -> bool {
+        self.data.hp > 0
+    }
+
+    /// Checks if the character is a beginner (beginner, noblesse, or legend)
+    pub fn is_beginner(&self) -> bool {
+        self.data.job % 1000 == 0
+    }
+
+    /// Gets the number of slots in one of the character's inventories
+    pub fn get_slots(&self, inventory_type: InventoryType) -> i32 {
+        self.data.get_slots(inventory_type)
+    }
+
+    /// Gets the first empty position in one of the character's inventories
+    pub fn get_free_position(&self, inventory_type: InventoryType) -> Option<i32> {
+        (0..self.get_slots(inventory_type)).find(|position| {
+            !self
+                .items
+                .iter()
+                .any(|item| item.inventory_type == inventory_type && item.position == *position)
         })
     }
+
+    /// Gets every empty position in one of the character's inventories
+    pub fn get_free_positions(&self, inventory_type: InventoryType) -> Vec<i32> {
+        (0..self.get_slots(inventory_type))
+            .filter(|position| {
+                !self
+                    .items
+                    .iter()
+                    .any(|item| item.inventory_type == inventory_type && item.position == *position)
+            })
+            .collect()
+    }
+
+    /// Gets the number of empty positions in one of the character's inventories
+    pub fn get_free_slots(&self, inventory_type: InventoryType) -> i32 {
+        let used = self
+            .items
+            .iter()
+            .filter(|item| item.inventory_type == inventory_type)
+            .count() as i32;
+
+        (self.get_slots(inventory_type) - used).max(0)
+    }
+
+    /// Adds an item to the character's inventory, filling existing stacks before using empty slots
+    /// Equipment doesn't stack, each piece gets its own stats from Character.nx
+    /// Returns None if the character doesn't have enough space for the items
+    pub async fn add_item(
+        &mut self,
+        item_id: i32,
+        amount: i32,
+        db: &Db,
+    ) -> anyhow::Result<Option<Vec<InventoryChange>>> {
+        let mut tx = db.begin().await?;
+        let changes = self.add_item_in(item_id, amount, &mut tx).await?;
+        tx.commit().await?;
+        Ok(changes)
+    }
+
+    /// Adds an item to the character's inventory like `add_item`, as part of a transaction
+    pub async fn add_item_in(
+        &mut self,
+        item_id: i32,
+        amount: i32,
+        db: &mut MySqlConnection,
+    ) -> anyhow::Result<Option<Vec<InventoryChange>>> {
+        let inventory_type = InventoryType::from_item_id(item_id)
+            .ok_or_else(|| anyhow!("Item {} doesn't belong in an inventory", item_id))?;
+
+        let slot_max = match inventory_type {
+            InventoryType::Equip => 1,
+            _ => nx::Item::load(item_id)?.slot_max.max(1),
+        };
+
+        // Make sure everything fits before changing anything
+        let stack_space: i32 = self
+            .items
+            .iter()
+            .filter(|item| item.item_id == item_id && item.owner.is_empty())
+            .map(|item| (slot_max - item.amount).max(0))
+            .sum();
+        let new_stacks = ((amount - stack_space).max(0) + slot_max - 1) / slot_max;
+
+        if new_stacks > self.get_free_slots(inventory_type) {
+            return Ok(None);
+        }
+
+        let mut changes = Vec::new();
+        let mut remaining = amount;
+
+        for item in self
+            .items
+            .iter_mut()
+            .filter(|item| item.item_id == item_id && item.owner.is_empty())
+        {
+            let added = (slot_max - item.amount).min(remaining);
+
+            if added <= 0 {
+                continue;
+            }
+
+            item.amount += added;
+            remaining -= added;
+            item.update_amount(&mut *db).await?;
+            changes.push(InventoryChange::Update(item.clone()));
+
+            if remaining == 0 {
+                break;
+            }
+        }
+
+        while remaining > 0 {
+            let position = self.get_free_position(inventory_type).unwrap();
+
+            let equip = match inventory_type {
+                InventoryType::Equip => Some(sql::Equipment::create(item_id, &mut *db).await?),
+                _ => None,
+            };
+
+            let mut item = sql::Item {
+                id: 0,
+                item_id,
+                character_id: self.data.id,
+                inventory_type,
+                position,
+                amount: remaining.min(slot_max),
+                owner: String::new(),
+                flag: 0,
+                cash_id: None,
+                expires_at: None,
+                equipment_id: equip.as_ref().map(|equip| equip.id),
+                equip,
+            };
+            item.insert(&mut *db).await?;
+
+            remaining -= item.amount;
+            self.items.push(item.clone());
+            changes.push(InventoryChange::Add(item));
+        }
+
+        Ok(Some(changes))
+    }
+
+    /// Gets how many of an item the character has, across all of its stacks
+    pub fn get_item_amount(&self, item_id: i32) -> i32 {
+        self.items
+            .iter()
+            .filter(|item| item.item_id == item_id)
+            .map(|item| item.amount)
+            .sum()
+    }
+
+    /// Removes the given amount of an item from the character's inventory
+    /// Returns the updated item if the character had enough of it
+    pub fn remove_item(&mut self, item_id: i32, amount: i32) -> Option<sql::Item> {
+        let index = self
+            .items
+            .iter()
+            .position(|item| item.item_id == item_id && item.amount >= amount)?;
+
+        let item = self.items.get_mut(index).unwrap();
+        item.amount -= amount;
+        let item = item.clone();
+
+        if item.amount <= 0 {
+            self.items.remove(index);
+        }
+
+        Some(item)
+    }
+
+    /// Takes the given amount of the item at a position out of the character's inventory, without changing the db
+    /// Returns the taken item and whether its whole stack was taken, or None if there isn't enough of the item
+    pub fn take_item(
+        &mut self,
+        inventory_type: InventoryType,
+        position: i32,
+        amount: i32,
+    ) -> Option<(sql::Item, bool)> {
+        let index = self
+            .items
+            .iter()
+            .position(|item| item.inventory_type == inventory_type && item.position == position)?;
+
+        let item = self.items.get_mut(index).unwrap();
+
+        if amount <= 0 || item.amount < amount {
+            return None;
+        }
+
+        item.amount -= amount;
+        let mut taken = item.clone();
+        taken.amount = amount;
+
+        let whole_stack = item.amount == 0;
+
+        if whole_stack {
+            self.items.remove(index);
+        }
+
+        Some((taken, whole_stack))
+    }
+
+    /// Puts an item held by a trade back into the character's inventory, merging it into a stack of the same item
+    /// if it fits there
+    /// Returns None if the inventory is full, the item is then held until the character logs in again
+    pub async fn restore_item(
+        &mut self,
+        mut item: sql::Item,
+        db: &Db,
+    ) -> anyhow::Result<Option<InventoryChange>> {
+        let slot_max = nx::Item::load(item.item_id)?.slot_max;
+        let stackable = |item: &sql::Item| item.owner.is_empty() && item.cash_id.is_none();
+
+        if stackable(&item) {
+            if let Some(stack) = self.items.iter_mut().find(|stack| {
+                stack.item_id == item.item_id
+                    && stackable(stack)
+                    && stack.amount + item.amount <= slot_max
+            }) {
+                item.merge_into(stack, db).await?;
+                stack.amount += item.amount;
+                return Ok(Some(InventoryChange::Update(stack.clone())));
+            }
+        }
+
+        item.position = match self.get_free_position(item.inventory_type) {
+            Some(position) => position,
+            None => return Ok(None),
+        };
+        item.update_position(db).await?;
+
+        self.items.push(item.clone());
+        Ok(Some(InventoryChange::Add(item)))
+    }
+
+    /// Gives exp to the character, leveling them up as many times as the exp allows
+    /// Returns the number of levels gained
+    pub fn gain_exp(&mut self, amount: i32) -> i32 {
+        if self.data.level >= exp::MAX_LEVEL {
+            return 0;
+        }
+
+        let mut levels = 0;
+        self.data.exp = self.data.exp.saturating_add(amount.max(0));
+
+        while self.data.level < exp::MAX_LEVEL && self.data.exp >= exp::exp_needed(self.data.level)
+        {
+            self.data.exp -= exp::exp_needed(self.data.level);
+            self.level_up();
+            levels += 1;
+        }
+
+        if self.data.level >= exp::MAX_LEVEL {
+            self.data.exp = 0;
+        }
+
+        levels
+    }
+
+    /// Advances the character to the next level, giving them ap, sp and max hp/mp
+    fn level_up(&mut self) {
+        self.data.level += 1;
+        self.data.ap += 5;
+
+        if !self.is_beginner() {
+            self.data.add_sp(3);
+        }
+
+        // Max hp/mp gains depend on the character's job branch
+        let (hp, mp) = match self.data.job / 100 % 10 {
+            1 => (24..=28, 4..=6),
+            2 => (10..=14, 22..=24),
+            3 | 4 => (20..=24, 14..=16),
+            5 => (22..=28, 18..=23),
+            _ => (12..=16, 10..=12),
+        };
+
+        let mut rng = rand::thread_rng();
+        self.data.max_hp = (self.data.max_hp + rng.gen_range(hp)).min(MAX_HP_MP);
+        self.data.max_mp =
+            (self.data.max_mp + rng.gen_range(mp) + self.data.int / 10).min(MAX_HP_MP);
+        self.data.hp = self.data.max_hp;
+        self.data.mp = self.data.max_mp;
+    }
+
+    /// Gets the number of the given monster the character has killed for a quest
+    pub fn get_quest_kills(&self, quest_id: i32, mob_id: i32) -> i32 {
+        self.quest_progress
+            .iter()
+            .find(|progress| progress.quest_id == quest_id && progress.mob_id == mob_id)
+            .map(|progress| progress.count)
+            .unwrap_or(0)
+    }
+
+    /// Counts a monster kill towards the character's started quests that need it
+    /// Returns the ids of the quests that were updated
+    pub async fn add_quest_kill(&mut self, mob_id: i32, db: &Db) -> anyhow::Result<Vec<i32>> {
+        let mut updated = Vec::new();
+
+        let started = self
+            .quests
+            .iter()
+            .filter(|quest| matches!(quest.status, QuestStatus::Started))
+            .map(|quest| quest.id)
+            .collect::<Vec<_>>();
+
+        for quest_id in started {
+            let quest = nx::Quest::load(quest_id as i16)?;
+
+            let required = match quest
+                .get_required_mobs()
+                .iter()
+                .find(|(required_id, _)| *required_id == mob_id)
+            {
+                Some((_, count)) => *count,
+                None => continue,
+            };
+
+            let index = match self
+                .quest_progress
+                .iter()
+                .position(|progress| progress.quest_id == quest_id && progress.mob_id == mob_id)
+            {
+                Some(index) => index,
+                None => {
+                    self.quest_progress.push(sql::QuestProgress {
+                        character_id: self.data.id,
+                        quest_id,
+                        mob_id,
+                        count: 0,
+                    });
+                    self.quest_progress.len() - 1
+                }
+            };
+
+            let progress = &mut self.quest_progress[index];
+
+            if progress.count >= required {
+                continue;
+            }
+
+            progress.count += 1;
+            progress.save(db).await?;
+            updated.push(quest_id);
+        }
+
+        Ok(updated)
+    }
+
+    /// Gets a quest's kill progress in the format the client expects, three digits per required monster
+    pub fn get_quest_progress(&self, quest_id: i32) -> anyhow::Result<String> {
+        let quest = nx::Quest::load(quest_id as i16)?;
+
+        Ok(quest
+            .get_required_mobs()
+            .iter()
+            .map(|(mob_id, _)| format!("{:03}", self.get_quest_kills(quest_id, *mob_id)))
+            .collect())
+    }
+
+    /// Removes exp from the character, they can't lose a level from losing exp
+    pub fn lose_exp(&mut self, amount: i32) {
+        self.data.exp = (self.data.exp - amount).max(0);
+    }
+
+    /// Gets the amount of exp the character loses when they die
+    pub fn get_death_exp_loss(&self, in_town: bool) -> i32 {
+        if self.is_beginner() {
+            return 0;
+        }
+
+        // Characters lose 10% of their level's exp when they die, or 1% in towns
+        let percent = if in_town { 1 } else { 10 };
+        let loss = (exp::exp_needed(self.data.level) as i64 * percent / 100) as i32;
+        loss.min(self.data.exp)
+    }
+
+    /// Heals the character by the given amount of hp and mp, capped at their max hp/mp
+    pub fn heal(&mut self, hp: i32, mp: i32) {
+        self.data.hp = (self.data.hp + hp).clamp(0, self.data.max_hp);
+        self.data.mp = (self.data.mp + mp).clamp(0, self.data.max_mp);
+    }
+}
+
+/// A change to one of a character's inventories that needs to be sent to the client
+#[derive(Debug, Clone)]
+pub enum InventoryChange {
+    Add(sql::Item),
+    Update(sql::Item),
+    Remove(sql::Item),
 }
@@ -0,0 +1,13 @@
+/// An item dropped in a map
+// TODO mesos drops
+#[derive(Debug, Clone)]
+pub struct Drop {
+    pub object_id: i32,
+    pub item_id: i32,
+    pub amount: i32,
+    pub pos: (i32, i32),
+    /// Where the drop fell from
+    pub source_pos: (i32, i32),
+    /// The object (monster, reactor, etc.) that dropped it
+    pub source_id: i32,
+}
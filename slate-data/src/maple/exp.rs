@@ -0,0 +1,167 @@
+/// The amount of exp needed to advance from each level to the next, indexed by level
+const EXP_TABLE: [i32; 201] = [
+    0, 15, 34, 57, 92, 135, 372, 560, 840, 1242, 1716, 2360, 3216, 4200, 5460, 7050, 8840, 11040,
+    13716, 16680, 20216, 24402, 28980, 34320, 40512, 47216, 54900, 63666, 73080, 83720, 95700,
+    108480, 122760, 138666, 155540, 174216, 194832, 216600, 240500, 266682, 294216, 324240, 356916,
+    391160, 428280, 468450, 510420, 555680, 604416, 655200, 709716, 748608, 789631, 832902, 878545,
+    926689, 977471, 1031036, 1087536, 1147032, 1209994, 1276301, 1346242, 1420016, 1497832,
+    1579913, 1666492, 1757815, 1854143, 1955750, 2062925, 2175973, 2295216, 2420993, 2553663,
+    2693603, 2841212, 2996910, 3161140, 3334370, 3517093, 3709829, 3913127, 4127566, 4353756,
+    4592341, 4844001, 5109452, 5389449, 5684790, 5996316, 6324914, 6671519, 7037118, 7422752,
+    7829518, 8258575, 8711144, 9188514, 9692044, 10223168, 10783397, 11374327, 11997640, 12655110,
+    13348610, 14080113, 14851703, 15665576, 16524049, 17429566, 18384706, 19392187, 20454878,
+    21575805, 22758159, 24005306, 25320796, 26708375, 28171993, 29715818, 31344244, 33061908,
+    34873700, 36784778, 38800583, 40926854, 43169645, 45535341, 48030677, 50662758, 53439077,
+    56367538, 59456479, 62714694, 66151459, 69776558, 73600313, 77633610, 81887931, 86375389,
+    91108760, 96101520, 101367883, 106922842, 112782213, 118962678, 125481832, 132358236,
+    139611467, 147262175, 155332142, 163844343, 172823012, 182293713, 192283408, 202820538,
+    213935103, 225658746, 238024845, 251068606, 264827165, 279339693, 294647508, 310794191,
+    327825712, 345790561, 364739883, 384727628, 405810702, 428049128, 451506220, 476248760,
+    502347192, 529875818, 558913012, 589541445, 621848316, 655925603, 691870326, 729784819,
+    769777027, 811960808, 856456260, 903390063, 952895838, 1005114529, 1060194805, 1118293480,
+    1179575962, 1244216724, 1312399800, 1384319309, 1460180007, 1540197871, 1624600714, 1713628833,
+    1807535693, 1906588648, 2011069705, 2121276324,
+];
+
+pub const MAX_LEVEL: i32 = 200;
+
+/// Gets the amount of exp needed to advance from the given level to the next
+pub fn exp_needed(level: i32) -> i32 {
+    EXP_TABLE[level.clamp(0, MAX_LEVEL) as usize]
+}
+
+/// Party members can't share exp from monsters more than this many levels above them, unless they attacked it
+const PARTY_LEVEL_RANGE: i32 = 5;
+
+/// Extra exp given for each party member sharing exp beyond the first
+const PARTY_BONUS_PER_MEMBER: f64 = 0.05;
+
+/// A party member present in the map when a monster was killed
+#[derive(Debug, Clone, Copy)]
+pub struct PartyExpMember {
+    pub character_id: i32,
+    pub level: i32,
+    /// The damage the member dealt to the monster
+    pub damage: i64,
+}
+
+/// Splits the exp a party earned from a monster between its members present in the map
+/// 80% of the exp is split by level and 20% by the damage each member dealt, then every member
+/// gets a bonus for each other member sharing the exp
+/// Returns the character id, exp and party bonus exp for each member that shares the exp
+pub fn split_party_exp(
+    exp: f64,
+    monster_level: i32,
+    members: &[PartyExpMember],
+) -> Vec<(i32, i32, i32)> {
+    let eligible: Vec<_> = members
+        .iter()
+        .filter(|member| member.damage > 0 || member.level >= monster_level - PARTY_LEVEL_RANGE)
+        .collect();
+
+    let total_level: i64 = eligible.iter().map(|member| member.level as i64).sum();
+    let total_damage: i64 = eligible.iter().map(|member| member.damage).sum();
+
+    if total_level == 0 || total_damage == 0 {
+        return Vec::new();
+    }
+
+    let bonus_rate = PARTY_BONUS_PER_MEMBER * (eligible.len() - 1) as f64;
+
+    eligible
+        .iter()
+        .map(|member| {
+            let level_share = exp * 0.8 * member.level as f64 / total_level as f64;
+            let damage_share = exp * 0.2 * member.damage as f64 / total_damage as f64;
+            let share = level_share + damage_share;
+            (
+                member.character_id,
+                share as i32,
+                (share * bonus_rate) as i32,
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests that 80% of the exp is split by level and 20% by damage, with the bonus for a party of two
+    #[test]
+    fn split_party_exp_by_level_and_damage() {
+        let members = [
+            PartyExpMember {
+                character_id: 1,
+                level: 40,
+                damage: 300,
+            },
+            PartyExpMember {
+                character_id: 2,
+                level: 20,
+                damage: 100,
+            },
+        ];
+
+        let split = split_party_exp(1000.0, 30, &members);
+        assert_eq!(split, vec![(1, 683, 34), (2, 316, 15)]);
+    }
+
+    /// Tests that the party bonus grows by 5% for each member sharing the exp beyond the first
+    #[test]
+    fn split_party_exp_bonus_by_party_size() {
+        let member = |character_id| PartyExpMember {
+            character_id,
+            level: 30,
+            damage: 10,
+        };
+
+        let split = split_party_exp(1200.0, 30, &[member(1)]);
+        assert_eq!(split, vec![(1, 1200, 0)]);
+
+        let members: Vec<_> = (1..=6).map(member).collect();
+        let split = split_party_exp(1200.0, 30, &members);
+        assert_eq!(split.len(), 6);
+        assert!(split
+            .iter()
+            .all(|(_, exp, bonus)| *exp == 200 && *bonus == 50));
+    }
+
+    /// Tests that members more than 5 levels below the monster only share its exp if they attacked it, so those
+    /// that didn't don't take a share from the rest of the party
+    #[test]
+    fn split_party_exp_level_range() {
+        let members = [
+            PartyExpMember {
+                character_id: 1,
+                level: 50,
+                damage: 100,
+            },
+            PartyExpMember {
+                character_id: 2,
+                level: 40,
+                damage: 0,
+            },
+            PartyExpMember {
+                character_id: 3,
+                level: 45,
+                damage: 0,
+            },
+        ];
+
+        let split = split_party_exp(1000.0, 50, &members);
+        assert_eq!(split, vec![(1, 621, 31), (3, 378, 18)]);
+    }
+
+    /// Tests that nothing is shared when the party didn't damage the monster
+    #[test]
+    fn split_party_exp_without_damage() {
+        let members = [PartyExpMember {
+            character_id: 1,
+            level: 30,
+            damage: 0,
+        }];
+
+        assert!(split_party_exp(1000.0, 30, &members).is_empty());
+    }
+}
@@ -0,0 +1,13 @@
+/// Every job a character can have, explorers, gms, cygnus knights, arans and evans
+const JOBS: [i32; 81] = [
+    0, 100, 110, 111, 112, 120, 121, 122, 130, 131, 132, 200, 210, 211, 212, 220, 221, 222, 230,
+    231, 232, 300, 310, 311, 312, 320, 321, 322, 400, 410, 411, 412, 420, 421, 422, 500, 510, 511,
+    512, 520, 521, 522, 900, 910, 1000, 1100, 1110, 1111, 1112, 1200, 1210, 1211, 1212, 1300, 1310,
+    1311, 1312, 1400, 1410, 1411, 1412, 1500, 1510, 1511, 1512, 2000, 2100, 2110, 2111, 2112, 2001,
+    2200, 2210, 2211, 2212, 2213, 2214, 2215, 2216, 2217, 2218,
+];
+
+/// Checks if a job id belongs to a real job
+pub fn is_valid(job: i32) -> bool {
+    JOBS.contains(&job)
+}
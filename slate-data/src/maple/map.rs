@@ -28,6 +28,24 @@ impl Map {
         })
     }
 
+    /// Gets a portal in the current map by name
+    pub fn get_portal_by_name(&self, name: &str) -> Option<&nx::Portal> {
+        self.data
+            .portals
+            .values()
+            .find(|portal| portal.name == name)
+    }
+
+    /// Gets the map characters are sent to when they die or use a return scroll
+    /// Returns the current map's id if it doesn't have a return map
+    pub fn get_return_map_id(&self) -> i32 {
+        // TODO make const -- MAP_NONE
+        match self.data.return_map_id as i32 {
+            999999999 => self.id,
+            return_map_id => return_map_id,
+        }
+    }
+
     /// Gets the closest spawn point to a position in the current map
     /// TODO looks like spawn points also have the name "sp" need to see if this is always the case
     pub fn get_closest_spawn_point(&self, pos: (i32, i32)) -> Option<&nx::Portal> {
@@ -62,6 +80,8 @@ impl Map {
 pub enum MapBroadcast {
     Packet(PacketBroadcast),
     Joined(mpsc::Sender<super::Character>),
+    PartySkill(PartySkillBroadcast),
+    PartyPacket(PartyPacketBroadcast),
 }
 
 #[derive(Debug, Clone)]
@@ -70,3 +90,32 @@ pub struct PacketBroadcast {
     pub sender_id: i32,
     pub send_to_sender: bool,
 }
+
+/// A packet that should only be sent to the sender's party members in the map (ex. hp bar updates)
+#[derive(Debug, Clone)]
+pub struct PartyPacketBroadcast {
+    pub packet: Packet,
+    pub party_id: i32,
+    pub sender_id: i32,
+}
+
+/// A skill used by a party member that should also be applied to other party members in range
+#[derive(Debug, Clone)]
+pub struct PartySkillBroadcast {
+    pub skill_id: i32,
+    pub level: i32,
+    pub party_id: i32,
+    pub sender_id: i32,
+    /// Amount of hp healed by the skill, if any
+    pub hp: i32,
+    /// Area of effect as (left, top, right, bottom)
+    pub bounds: (i32, i32, i32, i32),
+}
+
+impl PartySkillBroadcast {
+    /// Checks if the given position is inside of the skill's area of effect
+    pub fn in_range(&self, pos: (i32, i32)) -> bool {
+        let (left, top, right, bottom) = self.bounds;
+        pos.0 >= left && pos.0 <= right && pos.1 >= top && pos.1 <= bottom
+    }
+}
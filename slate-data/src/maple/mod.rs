@@ -1,5 +1,18 @@
+pub mod buff;
 pub mod character;
+pub mod drop;
+pub mod exp;
+pub mod job;
 pub mod map;
+pub mod monster;
+pub mod mount;
+pub mod pet;
+pub mod reactor;
 
+pub use self::buff::Buff;
 pub use self::character::Character;
+pub use self::drop::Drop;
 pub use self::map::Map;
+pub use self::monster::Monster;
+pub use self::pet::Pet;
+pub use self::reactor::Reactor;
@@ -0,0 +1,49 @@
+use crate::nx;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone)]
+pub struct Monster {
+    pub object_id: i32,
+    pub data: nx::Mob,
+    pub pos: (i32, i32),
+    pub fh: i32,
+    pub stance: u8,
+    pub hp: i32,
+    pub mp: i32,
+
+    /// The total damage dealt to the monster by each character, by character id
+    pub attackers: HashMap<i32, i64>,
+}
+
+impl Monster {
+    /// Creates a monster with full hp/mp at the given position
+    pub fn new(object_id: i32, data: nx::Mob, pos: (i32, i32), fh: i32) -> Self {
+        Self {
+            object_id,
+            pos,
+            fh,
+            stance: 5,
+            hp: data.max_hp,
+            mp: data.max_mp,
+            data,
+            attackers: HashMap::new(),
+        }
+    }
+
+    /// Damages the monster, damage past the monster's remaining hp isn't counted towards the attacker
+    pub fn damage(&mut self, character_id: i32, damage: i32) {
+        let damage = damage.clamp(0, self.hp);
+        self.hp -= damage;
+        *self.attackers.entry(character_id).or_default() += damage as i64;
+    }
+
+    /// Checks if the monster is alive
+    pub fn is_alive(&self) -> bool {
+        self.hp > 0
+    }
+
+    /// Gets the monster's remaining hp as a percentage
+    pub fn hp_percent(&self) -> u8 {
+        (self.hp as i64 * 100 / self.data.max_hp.max(1) as i64) as u8
+    }
+}
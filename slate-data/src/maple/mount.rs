@@ -0,0 +1,110 @@
+use crate::sql;
+
+/// The equipment positions of the character's mount and its saddle
+pub const MOUNT_POSITION: i32 = 18;
+pub const SADDLE_POSITION: i32 = 19;
+
+/// The highest level a mount can reach
+pub const MAX_LEVEL: i32 = 30;
+
+/// Mounts can't be ridden any more once they're this tired
+pub const MAX_TIREDNESS: i32 = 100;
+
+/// The tiredness a mount is left with after it becomes too tired to ride
+const RESTED_TIREDNESS: i32 = 95;
+
+/// The tiredness a mount loses from being fed
+const FEED_TIREDNESS: i32 = 30;
+
+/// The amount of exp a mount needs to advance from each level to the next, indexed by level
+const EXP_TABLE: [i32; 31] = [
+    0, 6, 25, 50, 105, 134, 196, 254, 263, 315, 367, 430, 543, 587, 679, 725, 897, 1146, 1394,
+    1701, 2247, 2543, 2898, 3156, 3313, 3584, 3923, 4150, 4305, 4550, 0,
+];
+
+/// Feeds a mount, making it less tired and giving it exp
+/// Returns true if the mount leveled up
+pub fn feed(mount: &mut sql::Mount) -> bool {
+    mount.tiredness = (mount.tiredness - FEED_TIREDNESS).max(0);
+
+    if mount.level >= MAX_LEVEL {
+        return false;
+    }
+
+    mount.exp += 2 * mount.level + 6;
+
+    let exp_needed = EXP_TABLE[mount.level as usize];
+
+    if mount.exp < exp_needed {
+        return false;
+    }
+
+    mount.exp -= exp_needed;
+    mount.level += 1;
+    true
+}
+
+/// Makes a mount more tired from being ridden, by the mount's fatigue
+/// Returns true if the mount became too tired to ride
+pub fn tire(mount: &mut sql::Mount, fatigue: i32) -> bool {
+    mount.tiredness += fatigue.max(1);
+
+    if mount.tiredness < MAX_TIREDNESS {
+        return false;
+    }
+
+    mount.tiredness = RESTED_TIREDNESS;
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests riding a mount until it's too tired, then feeding it so it can be ridden again
+    #[test]
+    fn ride_until_tired_then_feed() {
+        let mut mount = sql::Mount {
+            character_id: 1,
+            level: 1,
+            exp: 0,
+            tiredness: 0,
+        };
+
+        let rides = (1..).find(|_| tire(&mut mount, 10)).unwrap();
+        assert_eq!(rides, 10);
+        assert_eq!(mount.tiredness, RESTED_TIREDNESS);
+
+        // Mounts without fatigue still tire a little
+        assert!(!tire(&mut mount, 0));
+        assert_eq!(mount.tiredness, RESTED_TIREDNESS + 1);
+
+        assert!(feed(&mut mount));
+        assert!(mount.tiredness < MAX_TIREDNESS);
+        assert_eq!((mount.level, mount.exp), (2, 2));
+    }
+
+    /// Tests that feeding gives a mount more exp the higher its level, until it reaches the highest level
+    #[test]
+    fn feed_until_max_level() {
+        let mut mount = sql::Mount {
+            character_id: 1,
+            level: 1,
+            exp: 0,
+            tiredness: MAX_TIREDNESS,
+        };
+
+        // 8 exp takes the mount from level 1 to 2, then 10 exp each meal takes 3 meals to reach level 3
+        let meals: Vec<bool> = (0..4).map(|_| feed(&mut mount)).collect();
+        assert_eq!(meals, vec![true, false, false, true]);
+        assert_eq!((mount.level, mount.exp, mount.tiredness), (3, 7, 0));
+
+        while mount.level < MAX_LEVEL {
+            feed(&mut mount);
+        }
+
+        let exp = mount.exp;
+        assert!(!feed(&mut mount));
+        assert_eq!((mount.level, mount.exp), (MAX_LEVEL, exp));
+    }
+}
@@ -0,0 +1,160 @@
+use crate::sql;
+
+/// The highest level a pet can reach
+pub const MAX_LEVEL: i32 = 30;
+
+/// The most tameness a pet can have
+pub const MAX_TAMENESS: i32 = 30000;
+
+/// The most fullness a pet can have
+pub const MAX_FULLNESS: i32 = 100;
+
+/// Pets go back into their owner's inventory when their fullness drops this low
+const HUNGRY_FULLNESS: i32 = 5;
+
+/// The fullness a pet has after going back into its owner's inventory because it was hungry
+const RETURNED_FULLNESS: i32 = 15;
+
+/// The total tameness needed to reach each pet level, starting from level 1
+const TAMENESS_TABLE: [i32; 30] = [
+    0, 1, 3, 6, 14, 31, 60, 108, 181, 287, 434, 632, 891, 1224, 1642, 2161, 2793, 3557, 4467, 5542,
+    6801, 8263, 9950, 11882, 14084, 16578, 19391, 22547, 26074, 30000,
+];
+
+/// A character's pet, along with where it is in the map while it's summoned
+#[derive(Debug, Clone)]
+pub struct Pet {
+    pub pos: (i32, i32),
+    pub stance: u8,
+    pub data: sql::Pet,
+}
+
+impl Pet {
+    pub fn new(data: sql::Pet) -> Self {
+        Self {
+            pos: (0, 0),
+            stance: 0,
+            data,
+        }
+    }
+
+    /// Checks if the pet is summoned
+    pub fn is_summoned(&self) -> bool {
+        self.data.slot.is_some()
+    }
+
+    /// Changes the pet's tameness, updating its level to match
+    /// Returns true if the pet leveled up
+    pub fn gain_tameness(&mut self, amount: i32) -> bool {
+        self.data.tameness = (self.data.tameness + amount).clamp(0, MAX_TAMENESS);
+
+        let level = TAMENESS_TABLE
+            .iter()
+            .take_while(|tameness| self.data.tameness >= **tameness)
+            .count() as i32;
+        let leveled_up = level > self.data.level;
+
+        self.data.level = level.clamp(1, MAX_LEVEL);
+        leveled_up
+    }
+
+    /// Feeds the pet food that fills it by `fullness`, a pet that isn't hungry loses tameness from being overfed
+    /// `tamed` is whether a hungry pet grows tamer from the meal
+    /// Returns whether the pet was hungry, and whether it leveled up
+    pub fn feed(&mut self, fullness: i32, tamed: bool) -> (bool, bool) {
+        if self.data.fullness >= MAX_FULLNESS {
+            self.gain_tameness(-1);
+            return (false, false);
+        }
+
+        self.data.fullness = (self.data.fullness + fullness).min(MAX_FULLNESS);
+        (true, tamed && self.gain_tameness(1))
+    }
+
+    /// Makes the pet hungrier by its hunger
+    /// Returns true if it's too hungry to stay summoned, it's then left with a little fullness
+    pub fn starve(&mut self, hunger: i32) -> bool {
+        self.data.fullness = (self.data.fullness - hunger).max(0);
+
+        if self.data.fullness > HUNGRY_FULLNESS {
+            return false;
+        }
+
+        self.data.fullness = RETURNED_FULLNESS;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tests a summoned pet getting hungrier until it goes back into the inventory, then being fed back to full
+    #[test]
+    fn starve_and_feed() {
+        let mut pet = Pet::new(sql::Pet {
+            id: 1,
+            item_id: 5000000,
+            name: String::from("Brownie"),
+            level: 1,
+            tameness: 0,
+            fullness: MAX_FULLNESS,
+            flag: 0,
+            slot: Some(0),
+        });
+
+        let mut ticks = 0;
+
+        while !pet.starve(10) {
+            ticks += 1;
+        }
+
+        assert_eq!(ticks, 9);
+        assert_eq!(pet.data.fullness, RETURNED_FULLNESS);
+
+        // A meal the pet doesn't grow tamer from still fills it up
+        assert_eq!(pet.feed(30, false), (true, false));
+        assert_eq!((pet.data.fullness, pet.data.tameness), (45, 0));
+
+        // Growing tamer from its first meal takes the pet to level 2
+        assert_eq!(pet.feed(100, true), (true, true));
+        assert_eq!(pet.data.fullness, MAX_FULLNESS);
+        assert_eq!((pet.data.tameness, pet.data.level), (1, 2));
+
+        // Feeding a full pet makes it less tame, dropping it back to level 1
+        assert_eq!(pet.feed(30, true), (false, false));
+        assert_eq!((pet.data.tameness, pet.data.level), (0, 1));
+    }
+
+    /// Tests that tameness raises the pet through as many levels as it reaches, up to the highest level
+    #[test]
+    fn gain_tameness_levels() {
+        let mut pet = Pet::new(sql::Pet {
+            id: 1,
+            item_id: 5000000,
+            name: String::from("Brownie"),
+            level: 1,
+            tameness: 0,
+            fullness: MAX_FULLNESS,
+            flag: 0,
+            slot: None,
+        });
+
+        // A pet command's tameness, 14 tameness reaches level 5
+        assert!(pet.gain_tameness(14));
+        assert_eq!(pet.data.level, 5);
+
+        assert!(!pet.gain_tameness(16));
+        assert_eq!(pet.data.level, 5);
+
+        assert!(pet.gain_tameness(MAX_TAMENESS));
+        assert_eq!(
+            (pet.data.tameness, pet.data.level),
+            (MAX_TAMENESS, MAX_LEVEL)
+        );
+
+        // Losing tameness takes levels away, but never below level 1
+        assert!(!pet.gain_tameness(-MAX_TAMENESS));
+        assert_eq!((pet.data.tameness, pet.data.level), (0, 1));
+    }
+}
@@ -0,0 +1,61 @@
+use crate::nx::{
+    self,
+    map::MapReactor,
+    reactor::{HIT_FROM_RIGHT_EVENT, ITEM_EVENT},
+};
+
+#[derive(Debug, Clone)]
+pub struct Reactor {
+    pub object_id: i32,
+    pub data: nx::Reactor,
+    pub name: String,
+    pub pos: (i32, i32),
+    pub f: u8,
+    /// The seconds it takes the reactor to respawn after it's broken
+    pub reactor_time: i32,
+    pub state: i32,
+}
+
+impl Reactor {
+    /// Creates a reactor in its first state from its placement in a map
+    pub fn new(map_reactor: &MapReactor, data: nx::Reactor) -> Self {
+        Self {
+            object_id: map_reactor.object_id,
+            data,
+            name: map_reactor.name.clone(),
+            pos: map_reactor.position,
+            f: map_reactor.f,
+            reactor_time: map_reactor.reactor_time,
+            state: 0,
+        }
+    }
+
+    /// Hits the reactor with the given skill (0 for regular attacks), moving it to its next state
+    /// `from_left` is set when the character hit the reactor from its left
+    /// Returns false if the hit didn't change the reactor's state
+    pub fn hit(&mut self, skill_id: i32, from_left: bool) -> bool {
+        let event = self.data.get_events(self.state).iter().find(|event| {
+            event.event_type != ITEM_EVENT
+                && (event.skill_ids.is_empty() || event.skill_ids.contains(&skill_id))
+        });
+
+        let next_state = match event {
+            Some(event) if event.event_type == HIT_FROM_RIGHT_EVENT && from_left => return false,
+            Some(event) => event.next_state,
+            None => return false,
+        };
+
+        self.state = next_state;
+        true
+    }
+
+    /// Checks if the reactor is broken, broken reactors can't be hit until they respawn
+    pub fn is_broken(&self) -> bool {
+        self.data.is_broken(self.state)
+    }
+
+    /// Moves the reactor back to its first state after respawning
+    pub fn reset(&mut self) {
+        self.state = 0;
+    }
+}
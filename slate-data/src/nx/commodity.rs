@@ -0,0 +1,111 @@
+use crate::{nx::DATA, sql::cash_shop::DAY};
+use anyhow::anyhow;
+use nx::GenericNode;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+
+/// Every commodity sold in the cash shop, by serial number
+static COMMODITIES: Lazy<HashMap<i32, Commodity>> = Lazy::new(|| {
+    let root = DATA.get("Etc").unwrap().root().get("Commodity.img");
+    let mut commodities = HashMap::new();
+
+    for data in root.iter().flat_map(|root| root.iter()) {
+        let commodity = Commodity {
+            serial_number: data.get("SN").integer().unwrap_or_default() as i32,
+            item_id: data.get("ItemId").integer().unwrap_or_default() as i32,
+            count: data.get("Count").integer().unwrap_or(1) as i32,
+            price: data.get("Price").integer().unwrap_or_default() as i32,
+            period: data.get("Period").integer().unwrap_or_default() as i32,
+            gender: data.get("Gender").integer().unwrap_or(2) as i32,
+            on_sale: data.get("OnSale").integer().unwrap_or_default() == 1,
+        };
+
+        commodities.insert(commodity.serial_number, commodity);
+    }
+
+    commodities
+});
+
+/// The serial numbers of the commodities in every cash shop package, by the package's item id
+static PACKAGES: Lazy<HashMap<i32, Vec<i32>>> = Lazy::new(|| {
+    let root = DATA.get("Etc").unwrap().root().get("CashPackage.img");
+    let mut packages = HashMap::new();
+
+    for data in root.iter().flat_map(|root| root.iter()) {
+        let item_id = match data.name().parse::<i32>() {
+            Ok(item_id) => item_id,
+            Err(_) => continue,
+        };
+
+        let serial_numbers = data
+            .get("SN")
+            .iter()
+            .flat_map(|serial_numbers| serial_numbers.iter())
+            .map(|serial_number| serial_number.integer().unwrap_or_default() as i32)
+            .collect();
+
+        packages.insert(item_id, serial_numbers);
+    }
+
+    packages
+});
+
+/// An item sold in the cash shop
+#[derive(Debug, Clone)]
+pub struct Commodity {
+    pub serial_number: i32,
+    pub item_id: i32,
+    pub count: i32,
+    pub price: i32,
+    /// The number of days the item lasts, 0 if it's permanent
+    pub period: i32,
+    /// 0 for male characters only, 1 for female characters only, or 2 for both
+    pub gender: i32,
+    pub on_sale: bool,
+}
+
+impl Commodity {
+    /// Loads a commodity from Etc.nx by its serial number
+    pub fn load(serial_number: i32) -> anyhow::Result<Self> {
+        COMMODITIES
+            .get(&serial_number)
+            .cloned()
+            .ok_or_else(|| anyhow!("Commodity {} not found", serial_number))
+    }
+
+    /// Finds a commodity that sells the given item, preferring one that's on sale
+    pub fn find_by_item_id(item_id: i32) -> Option<Self> {
+        COMMODITIES
+            .values()
+            .filter(|commodity| commodity.item_id == item_id)
+            .max_by_key(|commodity| (commodity.on_sale, -commodity.serial_number))
+            .cloned()
+    }
+
+    /// Checks if the commodity is a package of other commodities
+    pub fn is_package(&self) -> bool {
+        PACKAGES.contains_key(&self.item_id)
+    }
+
+    /// Gets the commodities bought along with a package, None if the commodity isn't a package
+    pub fn get_package_contents(&self) -> Option<Vec<Self>> {
+        let serial_numbers = PACKAGES.get(&self.item_id)?;
+
+        Some(
+            serial_numbers
+                .iter()
+                .filter_map(|serial_number| COMMODITIES.get(serial_number).cloned())
+                .collect(),
+        )
+    }
+
+    /// Gets when the commodity expires if it's bought at the given time, None if it's permanent
+    pub fn get_expiration(&self, now: i64) -> Option<i64> {
+        (self.period > 0).then(|| now + self.period as i64 * DAY)
+    }
+
+    /// Checks if a character of the given gender can buy the commodity
+    pub fn is_for_gender(&self, gender: i32) -> bool {
+        self.gender == 2 || self.gender == gender
+    }
+}
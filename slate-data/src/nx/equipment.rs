@@ -3,47 +3,93 @@ use nx::GenericNode;
 
 #[derive(Default)]
 pub struct Equipment {
-    pub w_atk: Option<i32>,
-    pub upgrade_slots: Option<i32>,
+    pub upgrade_slots: i32,
+    pub str: i32,
+    pub dex: i32,
+    pub int: i32,
+    pub luk: i32,
+    pub hp: i32,
+    pub mp: i32,
+    pub w_atk: i32,
+    pub m_atk: i32,
+    pub w_def: i32,
+    pub m_def: i32,
+    pub acc: i32,
+    pub avoid: i32,
+    pub hands: i32,
+    pub speed: i32,
+    pub jump: i32,
 }
 
 impl Equipment {
     /// Loads equip data from Character.nx for the given equip id and category
-    /// Returns (key, value) pairs
     // FIXME this panics for handaxe?
-    // TODO should cache this
     pub fn load(id: i32, equip_type: &EquipmentType) -> Option<Self> {
+        Self::load_from(id, equip_type.as_str())
+    }
+
+    /// Loads equip data from Character.nx for the given equip id, finding its category from the id
+    pub fn load_by_id(id: i32) -> Option<Self> {
+        Self::load_from(id, get_category(id)?)
+    }
+
+    // TODO should cache this
+    fn load_from(id: i32, category: &str) -> Option<Self> {
         let id = format!("0{}.img", id);
         let root = DATA.get("Character").unwrap().root();
-        let category = root.get(equip_type.as_str());
-        let equip = category.get(&id);
+        let equip = root.get(category).get(&id);
 
         if equip.is_none() {
-            log::debug!("{} of type {} not found", id, equip_type.as_str());
+            log::debug!("{} of type {} not found", id, category);
             return None;
         }
 
         let info = equip.get("info");
         log::debug!("Loading equip data from {}", id);
 
-        let mut nx_equip = Self::default();
-
-        // Weapon attack
-        let w_atk = info.get("incPAD");
+        let stat = |name: &str| info.get(name).integer().unwrap_or_default() as i32;
 
-        if w_atk.is_some() {
-            nx_equip.w_atk = Some(w_atk.integer().unwrap() as i32);
-        }
-
-        // Upgrade slots
-        let upgrade_slots = info.get("tuc");
+        Some(Self {
+            upgrade_slots: stat("tuc"),
+            str: stat("incSTR"),
+            dex: stat("incDEX"),
+            int: stat("incINT"),
+            luk: stat("incLUK"),
+            hp: stat("incMHP"),
+            mp: stat("incMMP"),
+            w_atk: stat("incPAD"),
+            m_atk: stat("incMAD"),
+            w_def: stat("incPDD"),
+            m_def: stat("incMDD"),
+            acc: stat("incACC"),
+            avoid: stat("incEVA"),
+            hands: stat("incCraft"),
+            speed: stat("incSpeed"),
+            jump: stat("incJump"),
+        })
+    }
+}
 
-        if upgrade_slots.is_some() {
-            nx_equip.upgrade_slots = Some(upgrade_slots.integer().unwrap() as i32);
-        }
+/// Gets the Character.nx folder an equip is found in, based on the first 4 digits of its id
+pub fn get_category(id: i32) -> Option<&'static str> {
+    let category = match id / 10000 {
+        100 => "Cap",
+        101..=103 | 112..=119 => "Accessory",
+        104 => "Coat",
+        105 => "Longcoat",
+        106 => "Pants",
+        107 => "Shoes",
+        108 => "Glove",
+        109 => "Shield",
+        110 => "Cape",
+        111 => "Ring",
+        130..=170 => "Weapon",
+        180..=183 => "PetEquip",
+        190..=199 => "TamingMob",
+        _ => return None,
+    };
 
-        Some(nx_equip)
-    }
+    Some(category)
 }
 
 pub enum EquipmentType {
@@ -0,0 +1,103 @@
+use super::{equipment, DATA};
+use anyhow::anyhow;
+use nx::GenericNode;
+
+#[derive(Debug, Clone)]
+pub struct Item {
+    pub id: i32,
+    pub slot_max: i32,
+    pub price: i32,
+    pub is_cash: bool,
+    pub is_untradeable: bool,
+    /// The price of a single throwing star or bullet when recharging
+    pub unit_price: f64,
+    /// The hp and mp recovered when the item is used
+    pub hp: i32,
+    pub mp: i32,
+    /// The percentage of max hp and mp recovered when the item is used
+    pub hp_rate: i32,
+    pub mp_rate: i32,
+    /// The fullness a pet gains from eating the item
+    pub pet_fullness: i32,
+}
+
+impl Item {
+    /// Loads item data from Item.nx for the given item id, or from Character.nx for equipment
+    // TODO should cache this
+    pub fn load(id: i32) -> anyhow::Result<Self> {
+        // Items are grouped by their first 4 digits, ex. item 2000000 (red potion) is found in Consume/0200.img
+        // Equipment is grouped by its category instead, ex. 1302000 (sword) is found in Weapon/01302000.img
+        let (category, item_data) = match id / 1000000 {
+            1 => {
+                let category = equipment::get_category(id)
+                    .ok_or_else(|| anyhow!("Equipment {} doesn't have a category", id))?;
+                let root = DATA.get("Character").unwrap().root();
+                (category, root.get(category).get(&format!("{:08}.img", id)))
+            }
+            _ => {
+                let category = match id / 1000000 {
+                    2 => "Consume",
+                    3 => "Install",
+                    4 => "Etc",
+                    5 => "Cash",
+                    _ => return Err(anyhow!("Item {} doesn't belong in an inventory", id)),
+                };
+                let root = DATA.get("Item").unwrap().root();
+                let item_data = root
+                    .get(category)
+                    .get(&format!("{:04}.img", id / 10000))
+                    .get(&format!("{:08}", id));
+                (category, item_data)
+            }
+        };
+
+        if item_data.is_none() {
+            return Err(anyhow!("Item data {} not found in {}", id, category));
+        }
+
+        let info = item_data.get("info");
+        let spec = item_data.get("spec");
+
+        Ok(Self {
+            id,
+            slot_max: info
+                .get("slotMax")
+                .integer()
+                .unwrap_or(if id / 1000000 == 1 { 1 } else { 100 }) as i32,
+            price: info.get("price").integer().unwrap_or_default() as i32,
+            is_cash: info.get("cash").integer().unwrap_or_default() == 1,
+            is_untradeable: info.get("tradeBlock").integer().unwrap_or_default() == 1,
+            unit_price: info.get("unitPrice").float().unwrap_or_default(),
+            hp: spec.get("hp").integer().unwrap_or_default() as i32,
+            mp: spec.get("mp").integer().unwrap_or_default() as i32,
+            hp_rate: spec.get("hpR").integer().unwrap_or_default() as i32,
+            mp_rate: spec.get("mpR").integer().unwrap_or_default() as i32,
+            pet_fullness: spec.get("inc").integer().unwrap_or_default() as i32,
+        })
+    }
+
+    /// Loads the name of a (non-equipment) item from String.nx
+    pub fn load_name(id: i32) -> Option<String> {
+        let root = DATA.get("String").unwrap().root();
+
+        let names = match id / 1000000 {
+            2 => root.get("Consume.img"),
+            3 => root.get("Ins.img"),
+            4 => root.get("Etc.img").get("Etc"),
+            5 if id / 10000 == 500 => root.get("Pet.img"),
+            5 => root.get("Cash.img"),
+            _ => return None,
+        };
+
+        names
+            .get(&id.to_string())
+            .get("name")
+            .string()
+            .map(|name| name.to_string())
+    }
+
+    /// Checks if the item is a throwing star or bullet, which can be recharged at shops
+    pub fn is_rechargeable(&self) -> bool {
+        matches!(self.id / 10000, 207 | 233)
+    }
+}
@@ -2,9 +2,14 @@ use super::Portal;
 use crate::nx::DATA;
 use anyhow::anyhow;
 use nx::GenericNode;
-use rand::Rng;
 use std::collections::HashMap;
 
+/// Object ids for the map's npcs and monsters start above the ids given to spawned map objects
+const FIRST_LIFE_OBJECT_ID: i32 = 1000000000;
+
+/// Object ids for the map's reactors start above the ids given to the map's npcs and monsters
+const FIRST_REACTOR_OBJECT_ID: i32 = 1500000000;
+
 pub struct Map {
     pub create_mob_interval: i64,
     pub field_limit: i64,
@@ -14,6 +19,9 @@ pub struct Map {
     pub npcs: HashMap<i32, Life>,
     pub monsters: HashMap<i32, Life>,
     pub portals: HashMap<i32, Portal>,
+    pub reactors: HashMap<i32, MapReactor>,
+    /// Named areas in the map, used by party quests to check where characters are standing
+    pub areas: HashMap<String, Area>,
     pub return_map_id: i64,
     pub bounds: (i64, i64, i64, i64),
     pub footholds: Vec<Foothold>,
@@ -99,7 +107,6 @@ impl Map {
         };
 
         // TODO timeMob
-        // TODO area
         // TODO seat
         // TODO add player npc -> playernpcs
         // TODO add player npc -> developer npcs?
@@ -122,9 +129,20 @@ impl Map {
             None => Vec::new(),
         };
 
+        let reactor_root = map_data.get("reactor");
+        let reactors = match reactor_root {
+            Some(reactor_root) => MapReactor::load(reactor_root),
+            None => HashMap::new(),
+        };
+
+        let area_root = map_data.get("area");
+        let areas = match area_root {
+            Some(area_root) => Area::load(area_root),
+            None => HashMap::new(),
+        };
+
         // TODO load life from db
         // TODO if cpq map load monsterCarnival
-        // TODO reactor
         // TODO load map and street name?
 
         Ok(Self {
@@ -136,6 +154,8 @@ impl Map {
             npcs,
             monsters,
             portals,
+            reactors,
+            areas,
             return_map_id,
             bounds,
             footholds,
@@ -167,7 +187,7 @@ impl Life {
         let mut npcs = HashMap::new();
         let mut monsters = HashMap::new();
 
-        for life in root.iter() {
+        for (index, life) in root.iter().enumerate() {
             let id = life.get("id").string().unwrap_or_default();
             let type_ = life.get("type").string().unwrap_or_default();
 
@@ -187,11 +207,9 @@ impl Life {
                 life_type,
                 name: String::new(),
                 position: (x, y),
-                // FIXME this is terrible
-                // Maple requires object ids be i32s (🤢) so pick some random number above
-                // 10000000 to not collide with character ids. Should probably maintain a set per
-                // map that contains used object ids, keep generating until unused.
-                object_id: rand::thread_rng().gen_range(10000000..=i32::MAX),
+                // Object ids are based on the life's position in the map data, so they're the same
+                // every time the map is loaded (ex. to find the npc a character is talking to)
+                object_id: FIRST_LIFE_OBJECT_ID + index as i32,
                 stance: 0,
                 f: life.get("f").integer().unwrap_or(0) as u8,
                 is_hidden: life.get("hide").integer().unwrap_or(0) == 1,
@@ -227,6 +245,79 @@ pub enum LifeType {
     Monster,
 }
 
+/// A reactor placed in the map
+pub struct MapReactor {
+    pub id: i32,
+    pub object_id: i32,
+    pub name: String,
+    pub position: (i32, i32),
+    pub f: u8,
+    /// The seconds it takes the reactor to respawn after it's broken, it doesn't respawn if this isn't positive
+    pub reactor_time: i32,
+}
+
+impl MapReactor {
+    pub fn load(root: nx::Node) -> HashMap<i32, Self> {
+        let mut reactors = HashMap::new();
+
+        for (index, data) in root.iter().enumerate() {
+            let id = match data.get("id").string().map(|id| id.parse()) {
+                Some(Ok(id)) => id,
+                _ => continue,
+            };
+
+            let reactor = MapReactor {
+                id,
+                // Object ids are based on the reactor's position in the map data, like life
+                object_id: FIRST_REACTOR_OBJECT_ID + index as i32,
+                name: data.get("name").string().unwrap_or_default().to_string(),
+                position: (
+                    data.get("x").integer().unwrap_or_default() as i32,
+                    data.get("y").integer().unwrap_or_default() as i32,
+                ),
+                f: data.get("f").integer().unwrap_or_default() as u8,
+                reactor_time: data.get("reactorTime").integer().unwrap_or_default() as i32,
+            };
+
+            reactors.insert(reactor.object_id, reactor);
+        }
+
+        reactors
+    }
+}
+
+/// A rectangle in the map
+pub struct Area {
+    pub x1: i32,
+    pub y1: i32,
+    pub x2: i32,
+    pub y2: i32,
+}
+
+impl Area {
+    pub fn load(root: nx::Node) -> HashMap<String, Self> {
+        let mut areas = HashMap::new();
+
+        for data in root.iter() {
+            let area = Area {
+                x1: data.get("x1").integer().unwrap_or_default() as i32,
+                y1: data.get("y1").integer().unwrap_or_default() as i32,
+                x2: data.get("x2").integer().unwrap_or_default() as i32,
+                y2: data.get("y2").integer().unwrap_or_default() as i32,
+            };
+
+            areas.insert(data.name().to_string(), area);
+        }
+
+        areas
+    }
+
+    /// Checks if a position is inside the area
+    pub fn contains(&self, pos: (i32, i32)) -> bool {
+        (self.x1..=self.x2).contains(&pos.0) && (self.y1..=self.y2).contains(&pos.1)
+    }
+}
+
 pub struct Foothold {
     pub x1: i64,
     pub y1: i64,
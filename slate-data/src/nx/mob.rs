@@ -0,0 +1,44 @@
+use crate::nx::DATA;
+use anyhow::anyhow;
+use nx::GenericNode;
+
+#[derive(Debug, Clone)]
+pub struct Mob {
+    pub id: i32,
+    pub level: i32,
+    pub max_hp: i32,
+    pub max_mp: i32,
+    pub exp: i32,
+    pub is_boss: bool,
+    pub is_undead: bool,
+    /// The colors of the boss hp bar shown to the map when it's attacked, bosses with no tag color don't show one
+    pub hp_tag_color: u8,
+    pub hp_tag_bg_color: u8,
+}
+
+impl Mob {
+    /// Loads monster data from Mob.nx for the given monster id
+    // TODO should cache this
+    pub fn load(id: i32) -> anyhow::Result<Self> {
+        let root = DATA.get("Mob").unwrap().root();
+        let info = root.get(&format!("{:07}.img", id)).get("info");
+
+        if info.is_none() {
+            return Err(anyhow!("Mob data {} not found", id));
+        }
+
+        let get = |key: &str| info.get(key).integer().unwrap_or_default() as i32;
+
+        Ok(Self {
+            id,
+            level: get("level"),
+            max_hp: get("maxHP"),
+            max_mp: get("maxMP"),
+            exp: get("exp"),
+            is_boss: get("boss") == 1,
+            is_undead: get("undead") == 1,
+            hp_tag_color: get("hpTagColor") as u8,
+            hp_tag_bg_color: get("hpTagBgcolor") as u8,
+        })
+    }
+}
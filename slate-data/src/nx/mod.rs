@@ -1,31 +1,49 @@
 use once_cell::sync::Lazy;
 use std::{collections::HashMap, path::Path};
 
+pub mod commodity;
 pub mod equipment;
+pub mod item;
 pub mod map;
+pub mod mob;
+pub mod mount;
+pub mod npc;
+pub mod pet;
 pub mod portal;
 pub mod quest;
 pub mod quest_action;
 pub mod quest_requirement;
+pub mod reactor;
+pub mod skill;
 
+pub use self::commodity::Commodity;
 pub use self::equipment::Equipment;
+pub use self::item::Item;
 pub use self::map::Map;
+pub use self::mob::Mob;
+pub use self::mount::Mount;
+pub use self::npc::Npc;
+pub use self::pet::{Pet, PetCommand};
 pub use self::portal::Portal;
 pub use self::quest::Quest;
 pub use self::quest_action::QuestActionType;
 pub use self::quest_requirement::QuestRequirementType;
+pub use self::reactor::Reactor;
+pub use self::skill::{Skill, SkillLevel};
 
-const NX_FILES: [&str; 13] = [
+const NX_FILES: [&str; 15] = [
     "Base",
     "Character",
     "Effect",
     "Etc",
     "Item",
     "Map",
+    "Mob",
     "Morph",
     "Npc",
     "Quest",
     "Reactor",
+    "Skill",
     "String",
     "TamingMob",
     "UI",
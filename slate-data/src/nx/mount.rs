@@ -0,0 +1,54 @@
+use crate::nx::DATA;
+use anyhow::anyhow;
+use nx::GenericNode;
+
+#[derive(Debug, Clone)]
+pub struct Mount {
+    /// The mount's item id, ridden when it's equipped along with a saddle
+    pub id: i32,
+    /// The mount's id in TamingMob.nx
+    pub taming_mob_id: i32,
+    pub speed: i32,
+    pub jump: i32,
+    /// How quickly the mount gets tired while it's ridden
+    pub fatigue: i32,
+}
+
+impl Mount {
+    /// Loads mount data from Character.nx and TamingMob.nx for the given mount item id
+    // TODO should cache this
+    pub fn load(id: i32) -> anyhow::Result<Self> {
+        let root = DATA.get("Character").unwrap().root();
+        let item_data = root.get("TamingMob").get(&format!("{:08}.img", id));
+
+        if item_data.is_none() {
+            return Err(anyhow!("Mount item data {} not found", id));
+        }
+
+        let taming_mob_id = item_data
+            .get("info")
+            .get("tamingMob")
+            .integer()
+            .unwrap_or_default() as i32;
+
+        let root = DATA.get("TamingMob").unwrap().root();
+        let info = root.get(&format!("{:04}.img", taming_mob_id)).get("info");
+
+        if info.is_none() {
+            return Err(anyhow!("Taming mob data {} not found", taming_mob_id));
+        }
+
+        Ok(Self {
+            id,
+            taming_mob_id,
+            speed: info.get("speed").integer().unwrap_or_default() as i32,
+            jump: info.get("jump").integer().unwrap_or_default() as i32,
+            fatigue: info.get("fatigue").integer().unwrap_or_default() as i32,
+        })
+    }
+
+    /// Checks if the given item is a mount
+    pub fn is_mount(item_id: i32) -> bool {
+        item_id / 10000 == 190
+    }
+}
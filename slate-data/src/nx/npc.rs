@@ -0,0 +1,29 @@
+use crate::nx::DATA;
+use anyhow::anyhow;
+use nx::GenericNode;
+
+#[derive(Debug, Clone)]
+pub struct Npc {
+    pub id: i32,
+    /// The mesos it costs to store an item, if the npc runs a storage
+    pub storage_fee: Option<i32>,
+}
+
+impl Npc {
+    /// Loads npc data from Npc.nx for the given npc id
+    pub fn load(id: i32) -> anyhow::Result<Self> {
+        let root = DATA.get("Npc").unwrap().root();
+        let npc_data = root.get(&format!("{:07}.img", id));
+
+        if npc_data.is_none() {
+            return Err(anyhow!("Npc data {} not found", id));
+        }
+
+        let info = npc_data.get("info");
+
+        Ok(Self {
+            id,
+            storage_fee: info.get("trunkPut").integer().map(|fee| fee as i32),
+        })
+    }
+}
@@ -0,0 +1,71 @@
+use crate::nx::DATA;
+use anyhow::anyhow;
+use nx::GenericNode;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone)]
+pub struct Pet {
+    pub id: i32,
+    /// The fullness the pet loses every minute it's summoned
+    pub hunger: i32,
+    /// The commands the pet responds to, by command id
+    pub commands: HashMap<i32, PetCommand>,
+}
+
+/// A command a pet can be given by its owner
+#[derive(Debug, Clone)]
+pub struct PetCommand {
+    /// The chance (out of 100) the pet listens to the command
+    pub probability: i32,
+    /// The tameness the pet gains when it listens to the command
+    pub tameness: i32,
+    pub min_level: i32,
+    pub max_level: i32,
+}
+
+impl Pet {
+    /// Loads pet data from Item.nx for the given pet item id
+    // TODO should cache this
+    pub fn load(id: i32) -> anyhow::Result<Self> {
+        let root = DATA.get("Item").unwrap().root();
+        let pet_data = root.get("Pet").get(&format!("{}.img", id));
+
+        if pet_data.is_none() {
+            return Err(anyhow!("Pet data {} not found", id));
+        }
+
+        let mut commands = HashMap::new();
+
+        for data in pet_data
+            .get("interact")
+            .iter()
+            .flat_map(|interact| interact.iter())
+        {
+            let command_id = match data.name().parse::<i32>() {
+                Ok(command_id) => command_id,
+                Err(_) => continue,
+            };
+
+            commands.insert(
+                command_id,
+                PetCommand {
+                    probability: data.get("prob").integer().unwrap_or_default() as i32,
+                    tameness: data.get("inc").integer().unwrap_or_default() as i32,
+                    min_level: data.get("l0").integer().unwrap_or(1) as i32,
+                    max_level: data.get("l1").integer().unwrap_or(30) as i32,
+                },
+            );
+        }
+
+        Ok(Self {
+            id,
+            hunger: pet_data.get("info").get("hungry").integer().unwrap_or(1) as i32,
+            commands,
+        })
+    }
+
+    /// Checks if the given item is a pet
+    pub fn is_pet(item_id: i32) -> bool {
+        item_id / 10000 == 500
+    }
+}
@@ -43,7 +43,7 @@ impl Quest {
 
         let start_requirements = match requirements_root.get("0") {
             Some(start_requirements_root) => {
-                QuestRequirementType::load_all(start_requirements_root)
+                QuestRequirementType::load_all(id as i32, start_requirements_root)
             }
             None => Vec::new(),
         };
@@ -56,7 +56,7 @@ impl Quest {
 
         let complete_requirements = match requirements_root.get("1") {
             Some(complete_requirements_root) => {
-                QuestRequirementType::load_all(complete_requirements_root)
+                QuestRequirementType::load_all(id as i32, complete_requirements_root)
             }
             None => Vec::new(),
         };
@@ -89,8 +89,19 @@ impl Quest {
         })
     }
 
+    /// Gets the monster ids and amounts that need to be killed to complete the quest
+    pub fn get_required_mobs(&self) -> &[(i32, i32)] {
+        self.complete_requirements
+            .iter()
+            .find_map(|req| match req {
+                QuestRequirementType::Mob(req) => Some(req.mobs()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
     // Starts the quest
-    pub fn start(&self, character: &maple::Character, npc_id: i32) -> bool {
+    pub fn start(&self, character: &mut maple::Character, npc_id: i32) -> bool {
         if !self.can_start(character, npc_id) {
             return false;
         }
@@ -114,7 +125,7 @@ impl Quest {
 
     pub fn complete(
         &self,
-        character: &maple::Character,
+        character: &mut maple::Character,
         npc_id: i32,
         selection: Option<i16>,
     ) -> bool {
@@ -44,7 +44,7 @@ impl QuestActionType {
         quest_actions
     }
 
-    pub fn execute(&self, character: &maple::Character, selection: Option<i16>) {
+    pub fn execute(&self, character: &mut maple::Character, selection: Option<i16>) {
         use QuestActionType::*;
 
         match self {
@@ -135,7 +135,7 @@ impl ItemAction {
         Self { items }
     }
 
-    pub fn execute(&self, character: &maple::Character, selection: Option<i16>) {
+    pub fn execute(&self, character: &mut maple::Character, selection: Option<i16>) {
         log::debug!(
             "Executing ItemAction (items = {:?}, selection = {:?})",
             self.items,
@@ -264,8 +264,13 @@ impl PetSkillAction {
         }
     }
 
-    pub fn execute(&self, character: &maple::Character) {
+    /// Teaches the character's first summoned pet the skill
+    pub fn execute(&self, character: &mut maple::Character) {
         log::debug!("Executing PetSkillAction (pet_skill = {})", self.pet_skill);
+
+        if let Some(pet) = get_first_pet(character) {
+            pet.data.flag |= self.pet_skill;
+        }
     }
 }
 
@@ -281,11 +286,24 @@ impl PetTamenessAction {
         }
     }
 
-    pub fn execute(&self, character: &maple::Character) {
+    /// Gives the character's first summoned pet tameness
+    pub fn execute(&self, character: &mut maple::Character) {
         log::debug!("Executing PetTamenessAction (tameness = {})", self.tameness);
+
+        if let Some(pet) = get_first_pet(character) {
+            pet.gain_tameness(self.tameness);
+        }
     }
 }
 
+/// Gets the pet summoned in a character's first pet slot
+fn get_first_pet(character: &mut maple::Character) -> Option<&mut maple::Pet> {
+    character
+        .pets
+        .iter_mut()
+        .find(|pet| pet.data.slot == Some(0))
+}
+
 #[derive(Debug)]
 pub struct PetSpeedAction;
 
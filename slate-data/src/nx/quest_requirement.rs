@@ -29,7 +29,7 @@ pub enum QuestRequirementType {
 
 impl QuestRequirementType {
     /// Loads the quest start/complete requirements from the root node
-    pub fn load_all(root: nx::Node) -> Vec<Self> {
+    pub fn load_all(quest_id: i32, root: nx::Node) -> Vec<Self> {
         use QuestRequirementType::*;
         let mut quest_requirements = Vec::new();
 
@@ -41,7 +41,7 @@ impl QuestRequirementType {
                 "lvmin" => MinLevel(MinLevelRequirement::new(requirement)),
                 "lvmax" => MaxLevel(MaxLevelRequirement::new(requirement)),
                 "end" => EndDate(EndDateRequirement::new(requirement)),
-                "mob" => Mob(MobRequirement::new(requirement)),
+                "mob" => Mob(MobRequirement::new(quest_id, requirement)),
                 "npc" => Npc(NpcRequirement::new(requirement)),
                 "fieldEnter" => FieldEnter(FieldEnterRequirement::new(requirement)),
                 "interval" => Interval(IntervalRequirement::new(requirement)),
@@ -221,24 +221,33 @@ impl EndDateRequirement {
 
 #[derive(Debug)]
 pub struct MobRequirement {
-    mobs: HashMap<i32, i32>,
+    quest_id: i32,
+    /// The monster ids and amounts to kill, in the order the client shows their progress
+    mobs: Vec<(i32, i32)>,
 }
 
 impl MobRequirement {
-    pub fn new(data: nx::Node) -> Self {
-        let mut mobs = HashMap::new();
+    pub fn new(quest_id: i32, data: nx::Node) -> Self {
+        let mut mobs = Vec::new();
 
         for mob in data.iter() {
             let id = mob.get("id").integer().unwrap() as i32;
             let count = mob.get("count").integer().unwrap() as i32;
-            mobs.insert(id, count);
+            mobs.push((id, count));
         }
 
-        Self { mobs }
+        Self { quest_id, mobs }
+    }
+
+    /// Gets the monster ids and amounts to kill
+    pub fn mobs(&self) -> &[(i32, i32)] {
+        &self.mobs
     }
 
     fn has_requirement(&self, character: &maple::Character) -> bool {
-        todo!()
+        self.mobs
+            .iter()
+            .all(|(mob_id, count)| character.get_quest_kills(self.quest_id, *mob_id) >= *count)
     }
 }
 
@@ -329,8 +338,12 @@ impl PetRequirement {
         Self { pet_ids }
     }
 
+    /// Checks if the character has one of the pets summoned
     fn has_requirement(&self, character: &maple::Character) -> bool {
-        todo!()
+        character
+            .get_summoned_pets()
+            .iter()
+            .any(|pet| self.pet_ids.contains(&pet.data.item_id))
     }
 }
 
@@ -346,8 +359,12 @@ impl PetTamenessRequirement {
         }
     }
 
+    /// Checks if the character's first summoned pet is tame enough
     fn has_requirement(&self, character: &maple::Character) -> bool {
-        todo!()
+        character
+            .get_summoned_pets()
+            .first()
+            .is_some_and(|pet| pet.data.tameness >= self.min_tameness)
     }
 }
 
@@ -0,0 +1,128 @@
+use crate::nx::DATA;
+use anyhow::anyhow;
+use nx::GenericNode;
+use std::collections::HashMap;
+
+/// Events that advance a reactor to its next state
+pub const HIT_EVENT: i32 = 0;
+/// Hits that only count from the right of the reactor (ex. the plants in the Kerning City swamp)
+pub const HIT_FROM_RIGHT_EVENT: i32 = 2;
+/// Dropping the event's item onto the reactor
+pub const ITEM_EVENT: i32 = 100;
+
+#[derive(Debug, Clone)]
+pub struct Reactor {
+    pub id: i32,
+    /// Reactors that are activated by characters touching them instead of hitting them
+    pub activate_by_touch: bool,
+    pub states: HashMap<i32, ReactorState>,
+}
+
+/// One of a reactor's states, a reactor is broken once it reaches a state without any events
+#[derive(Debug, Clone, Default)]
+pub struct ReactorState {
+    pub events: Vec<ReactorEvent>,
+    /// The time in milliseconds before the reactor moves on by itself, if it does
+    pub timeout: Option<i32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReactorEvent {
+    pub event_type: i32,
+    pub next_state: i32,
+    /// The (item id, amount) that has to be dropped onto the reactor for item events
+    pub item: Option<(i32, i32)>,
+    /// The only skills that trigger the event, any attack triggers it if this is empty
+    pub skill_ids: Vec<i32>,
+}
+
+impl Reactor {
+    /// Loads reactor data from Reactor.nx for the given reactor id
+    // TODO should cache this
+    pub fn load(id: i32) -> anyhow::Result<Self> {
+        let root = DATA.get("Reactor").unwrap().root();
+        let mut reactor_data = root
+            .get(&format!("{:07}.img", id))
+            .ok_or_else(|| anyhow!("Reactor data {} not found", id))?;
+
+        // Some reactors share the states of another reactor
+        if let Some(link) = reactor_data.get("info").get("link").string() {
+            reactor_data = root
+                .get(&format!("{:0>7}.img", link))
+                .ok_or_else(|| anyhow!("Linked reactor data {} not found", link))?;
+        }
+
+        let activate_by_touch = reactor_data
+            .get("info")
+            .get("activateByTouch")
+            .integer()
+            .unwrap_or_default()
+            == 1;
+
+        let mut states = HashMap::new();
+
+        for state_data in reactor_data.iter() {
+            let state: i32 = match state_data.name().parse() {
+                Ok(state) => state,
+                Err(_) => continue,
+            };
+
+            let mut reactor_state = ReactorState::default();
+
+            let events = state_data.get("event");
+
+            for event_data in events.iter().flat_map(|events| events.iter()) {
+                if event_data.name() == "timeOut" {
+                    reactor_state.timeout = event_data.integer().map(|timeout| timeout as i32);
+                    continue;
+                }
+
+                let event_type = event_data.get("type").integer().unwrap_or_default() as i32;
+
+                let item = match event_type {
+                    ITEM_EVENT => Some((
+                        event_data.get("0").integer().unwrap_or_default() as i32,
+                        event_data.get("1").integer().unwrap_or(1) as i32,
+                    )),
+                    _ => None,
+                };
+
+                let skill_ids = event_data
+                    .get("activeSkillID")
+                    .iter()
+                    .flat_map(|skills| skills.iter())
+                    .filter_map(|skill| skill.integer())
+                    .map(|skill_id| skill_id as i32)
+                    .collect();
+
+                reactor_state.events.push(ReactorEvent {
+                    event_type,
+                    next_state: event_data.get("state").integer().unwrap_or_default() as i32,
+                    item,
+                    skill_ids,
+                });
+            }
+
+            states.insert(state, reactor_state);
+        }
+
+        Ok(Self {
+            id,
+            activate_by_touch,
+            states,
+        })
+    }
+
+    /// Gets the events that advance the reactor out of the given state
+    pub fn get_events(&self, state: i32) -> &[ReactorEvent] {
+        self.states
+            .get(&state)
+            .map(|state| state.events.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Checks if the reactor is broken in the given state
+    pub fn is_broken(&self, state: i32) -> bool {
+        self.get_events(state).is_empty()
+    }
+}
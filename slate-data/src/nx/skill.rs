@@ -0,0 +1,127 @@
+use crate::nx::DATA;
+use anyhow::anyhow;
+use nx::GenericNode;
+use std::collections::HashMap;
+
+#[derive(Debug)]
+pub struct Skill {
+    pub id: i32,
+    pub is_summon: bool,
+    pub levels: HashMap<i32, SkillLevel>,
+}
+
+impl Skill {
+    /// Loads skill data from Skill.nx for the given skill id
+    // TODO should cache this
+    pub fn load(id: i32) -> anyhow::Result<Self> {
+        let root = DATA.get("Skill").unwrap().root();
+
+        // Skills are grouped by job, ex. skill 2301002 (heal) is found in 230.img
+        let job_img = format!("{}.img", id / 10000);
+        let skill_data = root.get(&job_img).get("skill").get(&format!("{:07}", id));
+
+        if skill_data.is_none() {
+            return Err(anyhow!("Skill data {} not found in {}", id, job_img));
+        }
+
+        let is_summon = skill_data.get("summon").is_some();
+        let mut levels = HashMap::new();
+
+        if let Some(levels_root) = skill_data.get("level") {
+            for level_data in levels_root.iter() {
+                let level: i32 = match level_data.name().parse() {
+                    Ok(level) => level,
+                    Err(_) => continue,
+                };
+
+                levels.insert(level, SkillLevel::load(level, level_data));
+            }
+        }
+
+        Ok(Self {
+            id,
+            is_summon,
+            levels,
+        })
+    }
+
+    /// Gets the effect data for the given skill level
+    pub fn level(&self, level: i32) -> Option<&SkillLevel> {
+        self.levels.get(&level)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SkillLevel {
+    pub level: i32,
+    pub mp_con: i32,
+    pub hp_con: i32,
+    pub hp: i32,
+    pub mp: i32,
+    pub time: i32,
+    pub cooltime: i32,
+    pub prop: i32,
+    pub x: i32,
+    pub y: i32,
+    pub pad: i32,
+    pub pdd: i32,
+    pub mad: i32,
+    pub mdd: i32,
+    pub acc: i32,
+    pub eva: i32,
+    pub speed: i32,
+    pub jump: i32,
+    pub item_con: i32,
+    pub item_con_no: i32,
+    pub lt: Option<(i32, i32)>,
+    pub rb: Option<(i32, i32)>,
+}
+
+impl SkillLevel {
+    fn load(level: i32, data: nx::Node) -> Self {
+        let get = |key: &str| data.get(key).integer().unwrap_or_default() as i32;
+
+        Self {
+            level,
+            mp_con: get("mpCon"),
+            hp_con: get("hpCon"),
+            hp: get("hp"),
+            mp: get("mp"),
+            time: get("time"),
+            cooltime: get("cooltime"),
+            prop: data.get("prop").integer().unwrap_or(100) as i32,
+            x: get("x"),
+            y: get("y"),
+            pad: get("pad"),
+            pdd: get("pdd"),
+            mad: get("mad"),
+            mdd: get("mdd"),
+            acc: get("acc"),
+            eva: get("eva"),
+            speed: get("speed"),
+            jump: get("jump"),
+            item_con: get("itemCon"),
+            item_con_no: get("itemConNo"),
+            lt: data.get("lt").vector(),
+            rb: data.get("rb").vector(),
+        }
+    }
+
+    /// Gets the area of effect for the skill relative to the given position, as (left, top, right, bottom)
+    /// Returns None if the skill doesn't have an area of effect
+    pub fn bounds(&self, pos: (i32, i32), facing_left: bool) -> Option<(i32, i32, i32, i32)> {
+        let (lt, rb) = match (self.lt, self.rb) {
+            (Some(lt), Some(rb)) => (lt, rb),
+            _ => return None,
+        };
+
+        // Skill ranges are defined facing left, so they need to be mirrored when facing right
+        let bounds = if facing_left {
+            (pos.0 + lt.0, pos.1 + lt.1, pos.0 + rb.0, pos.1 + rb.1)
+        } else {
+            (pos.0 - rb.0, pos.1 + lt.1, pos.0 - lt.0, pos.1 + rb.1)
+        };
+
+        Some(bounds)
+    }
+}
@@ -1,5 +1,32 @@
-use crate::sql;
+use crate::{
+    maple::{self, buff::BuffStat, character::InventoryChange, Buff},
+    nx,
+    sql::{self, item::InventoryType},
+};
 use slate_net::Packet;
+use sqlx::types::chrono::{Local, Utc};
+
+/// The expiration time the client shows as permanent
+const PERMANENT: i64 = 150842304000000000;
+
+/// Converts a unix timestamp in milliseconds to the FILETIME format the client uses for dates
+pub fn to_filetime(timestamp: i64) -> i64 {
+    timestamp * 10000 + 116444736000000000
+}
+
+/// Writes when an item expires to a packet, `expires_at` is None for permanent items
+pub fn write_expiration(packet: &mut Packet, expires_at: Option<i64>) {
+    packet.write_long(expires_at.map(to_filetime).unwrap_or(PERMANENT));
+}
+
+/// Writes whether an item was bought from the cash shop to a packet, along with its serial number if it was
+pub fn write_cash_id(packet: &mut Packet, cash_id: Option<i64>) {
+    packet.write_byte(cash_id.is_some() as u8);
+
+    if let Some(cash_id) = cash_id {
+        packet.write_long(cash_id);
+    }
+}
 
 /// Writes a character's "style" to a packet (gender, skin colour, face, and hair)
 pub fn write_character_style(packet: &mut Packet, character: &sql::Character) {
@@ -10,8 +37,12 @@ pub fn write_character_style(packet: &mut Packet, character: &sql::Character) {
     packet.write_int(character.hair);
 }
 
-/// Writes a character's equipment to a packet
-pub fn write_character_equipment(packet: &mut Packet, equipment: &[sql::Equipment]) {
+/// Writes a character's equipment to a packet, along with the item ids of their summoned pets
+pub fn write_character_equipment(
+    packet: &mut Packet,
+    equipment: &[sql::Equipment],
+    pet_item_ids: &[i32],
+) {
     for equip in equipment.iter() {
         packet.write_byte(equip.position as u8);
         packet.write_int(equip.item_id);
@@ -23,14 +54,23 @@ pub fn write_character_equipment(packet: &mut Packet, equipment: &[sql::Equipmen
     // TODO write item @ pos -111 (weapon?)
     packet.write_int(0);
 
-    for _ in 0..3 {
-        // TODO write pet item id's
-        packet.write_int(0);
+    for i in 0..3 {
+        packet.write_int(pet_item_ids.get(i).copied().unwrap_or(0));
     }
 }
 
-/// Writes a character's stats to a packet
-pub fn write_character_stats(packet: &mut Packet, character: &sql::Character) {
+/// Writes a character's look to a packet (their style and equipment)
+pub fn write_character_look(
+    packet: &mut Packet,
+    character: &sql::Character,
+    equipment: &[sql::Equipment],
+) {
+    write_character_style(packet, character);
+    write_character_equipment(packet, equipment, &[]);
+}
+
+/// Writes a character's stats to a packet, along with the ids of their summoned pets
+pub fn write_character_stats(packet: &mut Packet, character: &sql::Character, pet_ids: &[i64]) {
     packet.write_int(character.id);
 
     let mut fixed_name = String::from(&character.name);
@@ -46,8 +86,7 @@ pub fn write_character_stats(packet: &mut Packet, character: &sql::Character) {
     packet.write_int(character.hair);
 
     for i in 0..3 {
-        // TODO write pet ids if exists
-        packet.write_long(0);
+        packet.write_long(pet_ids.get(i).copied().unwrap_or(0));
     }
 
     packet.write_byte(character.level as u8);
@@ -62,24 +101,7 @@ pub fn write_character_stats(packet: &mut Packet, character: &sql::Character) {
     packet.write_short(character.max_mp as i16);
     packet.write_short(character.ap as i16);
 
-    let mut sp_index = 0;
-
-    if character.job >= 2210 && character.job <= 2218 {
-        sp_index = character.job - 2209;
-    }
-
-    // SP is stored as a comma seperated array, need to split the string
-    // and get the value for the correct index
-    let sp: i16 = character
-        .sp
-        .split(',')
-        .collect::<Vec<_>>()
-        .get(sp_index as usize)
-        .unwrap()
-        .parse()
-        .unwrap();
-
-    packet.write_short(sp);
+    packet.write_short(character.get_sp() as i16);
     packet.write_int(character.exp);
     packet.write_short(character.fame as i16);
     packet.write_int(character.gacha_exp);
@@ -88,6 +110,276 @@ pub fn write_character_stats(packet: &mut Packet, character: &sql::Character) {
     packet.write_int(0);
 }
 
+/// Spawns a character for everyone else in the map
+pub fn spawn_character(character: &maple::Character, entering: bool) -> Packet {
+    let mut packet = Packet::new(0xA0);
+    packet.write_int(character.data.id);
+    packet.write_byte(character.data.level as u8);
+    packet.write_string(&character.data.name);
+
+    match &character.guild {
+        Some(guild) => {
+            packet.write_string(&guild.name);
+            packet.write_short(guild.logo_bg as i16);
+            packet.write_byte(guild.logo_bg_color as u8);
+            packet.write_short(guild.logo as i16);
+            packet.write_byte(guild.logo_color as u8);
+        }
+        None => {
+            packet.write_string("");
+            packet.write_bytes(&[0, 0, 0, 0, 0, 0]);
+        }
+    };
+
+    write_buffs(&mut packet, character);
+    // TODO need to get the correct job id based on the job, create an enum that maps all jobs to job ids? (see Job class)
+    packet.write_short(0); // FIXME job id
+    let pets = character.get_summoned_pets();
+    let pet_item_ids: Vec<i32> = pets.iter().map(|pet| pet.data.item_id).collect();
+    write_character_style(&mut packet, &character.data);
+    write_character_equipment(&mut packet, &character.equipment, &pet_item_ids);
+    packet.write_int(0); // TODO # of heart shaped chocolate in cash inv??? why
+    packet.write_int(0); // TODO item effect
+    packet.write_int(character.chair);
+
+    // Check if character is already present in the map
+    if entering {
+        packet.write_position((character.pos.0, character.pos.1 - 42));
+        packet.write_byte(6);
+    } else {
+        packet.write_position(character.pos);
+        packet.write_byte(character.stance);
+    }
+
+    packet.write_short(0);
+    packet.write_byte(0);
+
+    for pet in pets.iter() {
+        packet.write_byte(1);
+        write_pet(&mut packet, pet);
+    }
+
+    packet.write_byte(0);
+
+    write_mount(&mut packet, &character.mount);
+
+    // Player shop and minigame boxes are shown separately once the character has spawned
+    packet.write_byte(0);
+
+    // TODO chalkboard
+    packet.write_byte(0);
+
+    // TODO crush ring
+    packet.write_byte(0);
+
+    // TODO friendship ring
+    packet.write_byte(0);
+
+    // TODO marriage ring
+    packet.write_byte(0);
+
+    // TODO new years card info
+    packet.write_byte(0);
+
+    packet.write_byte(0);
+    packet.write_byte(0);
+    packet.write_byte(0); // TODO team
+    packet
+}
+
+/// Writes a character's mount's level, exp, and tiredness to a packet
+fn write_mount(packet: &mut Packet, mount: &sql::Mount) {
+    packet.write_int(mount.level);
+    packet.write_int(mount.exp);
+    packet.write_int(mount.tiredness);
+}
+
+/// Writes a character's foreign buffs to a packet
+fn write_buffs(packet: &mut Packet, character: &maple::Character) {
+    packet.write_int(0);
+    packet.write_short(0);
+    packet.write_byte(0xFC);
+    packet.write_byte(1);
+    packet.write_int(0); // TODO morph
+
+    let buff_mask = 0i64;
+    // TODO compute buff mask
+    packet.write_int(((buff_mask >> 32) & 0xffffffffi64) as i32);
+    // TODO buff value
+    packet.write_int((buff_mask & 0xffffffffi64) as i32);
+
+    // TODO energy
+    packet.write_int(0);
+    packet.write_short(0);
+    packet.write_bytes(&[0u8; 4]);
+
+    // TODO dash buff
+    packet.write_int(0);
+    packet.write_bytes(&[0u8; 11]);
+    packet.write_short(0);
+
+    // TODO dash jump
+    packet.write_bytes(&[0u8; 9]);
+    packet.write_int(0);
+    packet.write_short(0);
+    packet.write_byte(0);
+
+    // Monster riding
+    match (
+        character.get_mount_item_id(),
+        character
+            .buffs
+            .iter()
+            .find(|buff| buff.value(BuffStat::MonsterRiding).is_some()),
+    ) {
+        (Some(item_id), Some(buff)) => {
+            packet.write_int(item_id);
+            packet.write_int(buff.skill_id);
+        }
+        _ => packet.write_long(0),
+    }
+
+    let char_magic_spawn = rand::random::<i32>();
+    packet.write_int(char_magic_spawn);
+
+    // Speed Infusion
+    packet.write_bytes(&[0u8; 8]);
+    packet.write_int(char_magic_spawn);
+    packet.write_byte(0);
+    packet.write_int(char_magic_spawn);
+    packet.write_short(0);
+
+    // Homing Beacon
+    packet.write_bytes(&[0u8; 9]);
+    packet.write_int(char_magic_spawn);
+    packet.write_int(0);
+
+    // Zombify
+    packet.write_bytes(&[0u8; 9]);
+    packet.write_int(char_magic_spawn);
+    packet.write_short(0);
+    packet.write_short(0);
+}
+
+/// Shows the current player another character's info window
+pub fn character_info(character: &maple::Character, alliance_name: &str) -> Packet {
+    let mut packet = Packet::new(0x3D);
+    packet.write_int(character.data.id);
+    packet.write_byte(character.data.level as u8);
+    packet.write_short(character.data.job as i16);
+    packet.write_short(character.data.fame as i16);
+    packet.write_byte(0); // TODO marriage ring
+
+    match &character.guild {
+        Some(guild) => packet.write_string(&guild.name),
+        None => packet.write_string(""),
+    }
+
+    packet.write_string(alliance_name);
+    packet.write_byte(0); // TODO medal info
+
+    for pet in character.get_summoned_pets() {
+        packet.write_byte(1);
+        packet.write_int(pet.data.item_id);
+        packet.write_string(&pet.data.name);
+        packet.write_byte(pet.data.level as u8);
+        packet.write_short(pet.data.tameness as i16);
+        packet.write_byte(pet.data.fullness as u8);
+        packet.write_short(0);
+        packet.write_int(0); // TODO pet equipment
+    }
+
+    packet.write_byte(0);
+
+    match character.get_mount_item_id() {
+        Some(_) => {
+            packet.write_byte(1);
+            write_mount(&mut packet, &character.mount);
+        }
+        None => packet.write_byte(0),
+    }
+
+    packet.write_byte(0); // TODO wishlist
+
+    // TODO monster book
+    packet.write_int(0);
+    packet.write_int(0);
+    packet.write_int(0);
+    packet.write_int(0);
+    packet.write_int(0);
+
+    packet.write_int(0); // TODO medal
+    packet.write_short(0); // TODO medal quests
+    packet
+}
+
+/// Removes a character from the map for everyone else in the map
+pub fn remove_character(character_id: i32) -> Packet {
+    let mut packet = Packet::new(0xA1);
+    packet.write_int(character_id);
+    packet
+}
+
+/// Warps the current player to the given map and spawn point
+pub fn warp_to_map(channel_id: i32, map_id: i32, spawn_point: i32, hp: i32) -> Packet {
+    let mut packet = Packet::new(0x7D);
+    packet.write_int(channel_id);
+    packet.write_int(0);
+    packet.write_byte(0);
+    packet.write_int(map_id);
+    packet.write_byte(spawn_point as u8);
+    packet.write_short(hp as i16);
+    packet.write_byte(0); // chasing
+
+    // FIXME same as character info
+    let current_time = Utc::now().timestamp_millis() * 10000;
+    let offset: i64 =
+        116444736010800000 + (10000000 * i64::from(Local::now().offset().local_minus_utc()));
+    packet.write_long(current_time + offset);
+    packet
+}
+
+/// Spawns an npc in the map
+pub fn spawn_npc(npc: &nx::map::Life) -> Packet {
+    let mut packet = Packet::new(0x101);
+    packet.write_int(npc.object_id);
+    packet.write_int(npc.id);
+    packet.write_short(npc.position.0);
+    packet.write_short(npc.cy);
+    packet.write_byte((npc.f != 1) as u8);
+    packet.write_short(npc.fh);
+    packet.write_short(npc.rx0);
+    packet.write_short(npc.rx1);
+    packet.write_byte(1);
+    packet
+}
+
+/// Spawns an npc in the map and gives the current player control of it
+pub fn spawn_npc_request_controller(npc: &nx::map::Life) -> Packet {
+    let mut packet = Packet::new(0x103);
+    packet.write_byte(1);
+    packet.write_int(npc.object_id);
+    packet.write_int(npc.id);
+    packet.write_short(npc.position.0);
+    packet.write_short(npc.cy);
+    packet.write_byte((npc.f != 1) as u8);
+    packet.write_short(npc.fh);
+    packet.write_short(npc.rx0);
+    packet.write_short(npc.rx1);
+    packet.write_byte(1);
+    packet
+}
+
+// TODO DoorObject.sendSpawnData
+pub fn spawn_portal(map_id: i32, portal: &nx::Portal) -> Packet {
+    let mut packet = Packet::new(0x43);
+    packet.write_int(map_id);
+    packet.write_int(portal.target_map_id);
+    packet.write_short(portal.x as i16);
+    packet.write_short(portal.y as i16);
+    packet
+}
+
 pub enum SpecialEffect {
     LevelUp = 0,
     SafetyCharms = 6,
@@ -105,6 +397,21 @@ pub enum SpecialEffect {
     SpiritStone = 26,
 }
 
+/// Shows a character sitting on a chair to everyone else in the map, or standing up if `item_id` is 0
+pub fn show_chair(character_id: i32, item_id: i32) -> Packet {
+    let mut packet = Packet::new(0xC4);
+    packet.write_int(character_id);
+    packet.write_int(item_id);
+    packet
+}
+
+/// Stands the current player up from their chair
+pub fn cancel_chair() -> Packet {
+    let mut packet = Packet::new(0xCD);
+    packet.write_byte(0);
+    packet
+}
+
 /// Applies a special effect to the current player (shown to themselves)
 pub fn show_special_effect(effect: SpecialEffect) -> Packet {
     let mut packet = Packet::new(0xCE);
@@ -119,3 +426,2455 @@ pub fn show_foreign_effect(character_id: i32, effect: SpecialEffect) -> Packet {
     packet.write_byte(effect as u8);
     packet
 }
+
+/// Character stats that can be updated with the update stats packet
+#[derive(Debug, Clone, Copy)]
+pub enum Stat {
+    Skin = 0x1,
+    Face = 0x2,
+    Hair = 0x4,
+    Level = 0x10,
+    Job = 0x20,
+    Str = 0x40,
+    Dex = 0x80,
+    Int = 0x100,
+    Luk = 0x200,
+    Hp = 0x400,
+    MaxHp = 0x800,
+    Mp = 0x1000,
+    MaxMp = 0x2000,
+    Ap = 0x4000,
+    Sp = 0x8000,
+    Exp = 0x10000,
+    Fame = 0x20000,
+    Mesos = 0x40000,
+    GachaExp = 0x200000,
+}
+
+/// Updates the given stats for the current player
+/// An empty list of stats can be sent to re-enable the client's actions
+pub fn update_stats(stats: &[(Stat, i32)]) -> Packet {
+    let mut packet = Packet::new(0x1F);
+    packet.write_byte(1); // enable actions
+
+    // Stats must be written in the order of their masks
+    let mut stats = stats.to_vec();
+    stats.sort_by_key(|(stat, _)| *stat as i32);
+
+    let mask = stats.iter().fold(0, |mask, (stat, _)| mask | *stat as i32);
+    packet.write_int(mask);
+
+    for (stat, value) in stats.iter() {
+        match stat {
+            Stat::Skin | Stat::Level => packet.write_byte(*value as u8),
+            Stat::Face | Stat::Hair | Stat::Exp | Stat::Mesos | Stat::GachaExp => {
+                packet.write_int(*value)
+            }
+            _ => packet.write_short(*value as i16),
+        }
+    }
+
+    packet
+}
+
+/// Writes a buff's first and second stat masks to a packet
+fn write_buff_mask(packet: &mut Packet, buff: &Buff) {
+    let (first, second) = buff.mask();
+    packet.write_long(first);
+    packet.write_long(second);
+}
+
+/// Gives the current player a buff
+pub fn give_buff(buff: &Buff) -> Packet {
+    let mut packet = Packet::new(0x20);
+    write_buff_mask(&mut packet, buff);
+
+    for (_, value) in buff.stats.iter() {
+        packet.write_short(*value);
+        packet.write_int(buff.skill_id);
+        packet.write_int(buff.duration);
+    }
+
+    packet.write_int(0);
+    packet.write_byte(0);
+    packet.write_int(
+        buff.stats
+            .first()
+            .map(|(_, value)| *value as i32)
+            .unwrap_or(0),
+    );
+    packet
+}
+
+/// Gives the current player the buff for riding their mount
+pub fn give_mount_buff(item_id: i32, buff: &Buff) -> Packet {
+    let mut packet = Packet::new(0x20);
+    write_buff_mask(&mut packet, buff);
+    packet.write_short(0);
+    packet.write_int(item_id);
+    packet.write_int(buff.skill_id);
+    packet.write_int(0);
+    packet.write_byte(0);
+    packet.write_int(0);
+    packet
+}
+
+/// Shows the given character riding their mount for everyone else in the map
+pub fn give_foreign_mount_buff(character_id: i32, item_id: i32, buff: &Buff) -> Packet {
+    let mut packet = Packet::new(0xC7);
+    packet.write_int(character_id);
+    write_buff_mask(&mut packet, buff);
+    packet.write_short(0);
+    packet.write_int(item_id);
+    packet.write_int(buff.skill_id);
+    packet.write_int(0);
+    packet.write_short(0);
+    packet.write_byte(0);
+    packet
+}
+
+/// Updates the given character's mount's level, exp, and tiredness, for everyone in the map
+pub fn update_mount(character_id: i32, mount: &sql::Mount, leveled_up: bool) -> Packet {
+    let mut packet = Packet::new(0x30);
+    packet.write_int(character_id);
+    write_mount(&mut packet, mount);
+    packet.write_byte(leveled_up as u8);
+    packet
+}
+
+/// Cancels a buff for the current player
+pub fn cancel_buff(buff: &Buff) -> Packet {
+    let mut packet = Packet::new(0x21);
+    write_buff_mask(&mut packet, buff);
+    packet.write_byte(1);
+    packet
+}
+
+/// Shows a buff on the given character for everyone else in the map
+pub fn give_foreign_buff(character_id: i32, buff: &Buff) -> Packet {
+    let mut packet = Packet::new(0xC7);
+    packet.write_int(character_id);
+    write_buff_mask(&mut packet, buff);
+
+    for (_, value) in buff.stats.iter() {
+        packet.write_short(*value);
+    }
+
+    packet.write_int(0);
+    packet.write_short(0);
+    packet
+}
+
+/// Cancels a buff on the given character for everyone else in the map
+pub fn cancel_foreign_buff(character_id: i32, buff: &Buff) -> Packet {
+    let mut packet = Packet::new(0xC8);
+    packet.write_int(character_id);
+    write_buff_mask(&mut packet, buff);
+    packet
+}
+
+/// Shows a skill's effect on the current player
+/// `effect` is 1 when the player used the skill, 2 when they were affected by someone else's skill
+pub fn show_skill_effect(skill_id: i32, level: i32, effect: u8) -> Packet {
+    let mut packet = Packet::new(0xCE);
+    packet.write_byte(effect);
+    packet.write_int(skill_id);
+    packet.write_byte(level as u8);
+    packet.write_byte(1);
+    packet
+}
+
+/// Shows a skill's effect on the given character for everyone else in the map
+pub fn show_foreign_skill_effect(
+    character_id: i32,
+    skill_id: i32,
+    level: i32,
+    effect: u8,
+) -> Packet {
+    let mut packet = Packet::new(0xC6);
+    packet.write_int(character_id);
+    packet.write_byte(effect);
+    packet.write_int(skill_id);
+    packet.write_byte(level as u8);
+    packet.write_byte(1);
+    packet
+}
+
+/// Starts a skill's cooldown for the current player, 0 seconds ends the cooldown
+pub fn skill_cooldown(skill_id: i32, seconds: i32) -> Packet {
+    let mut packet = Packet::new(0xEA);
+    packet.write_int(skill_id);
+    packet.write_short(seconds as i16);
+    packet
+}
+
+pub enum NoticeType {
+    Notice = 0,
+    Popup = 1,
+    Megaphone = 2,
+    PinkText = 5,
+    LightBlueText = 6,
+}
+
+/// Shows a server notice to the current player
+pub fn server_notice(notice_type: NoticeType, message: &str) -> Packet {
+    let mut packet = Packet::new(0x44);
+    packet.write_byte(notice_type as u8);
+    packet.write_string(message);
+    packet
+}
+
+/// Updates the amount of an item in the current player's inventory, removing it if the amount is 0
+pub fn update_item_amount(item: &sql::Item) -> Packet {
+    let change = if item.amount > 0 {
+        InventoryChange::Update(item.clone())
+    } else {
+        InventoryChange::Remove(item.clone())
+    };
+
+    update_inventory(&[change])
+}
+
+/// Applies changes to the current player's inventories
+pub fn update_inventory(changes: &[InventoryChange]) -> Packet {
+    let mut packet = Packet::new(0x1D);
+    packet.write_byte(1); // update tick
+    packet.write_byte(changes.len() as u8);
+
+    for change in changes.iter() {
+        match change {
+            InventoryChange::Add(item) => {
+                packet.write_byte(0);
+                packet.write_byte(item.inventory_type as u8 + 1);
+                packet.write_short((item.position + 1) as i16);
+                write_item_data(&mut packet, item);
+            }
+            InventoryChange::Update(item) => {
+                packet.write_byte(1);
+                packet.write_byte(item.inventory_type as u8 + 1);
+                packet.write_short((item.position + 1) as i16);
+                packet.write_short(item.amount as i16);
+            }
+            InventoryChange::Remove(item) => {
+                packet.write_byte(3);
+                packet.write_byte(item.inventory_type as u8 + 1);
+                packet.write_short((item.position + 1) as i16);
+            }
+        }
+    }
+
+    packet
+}
+
+/// Removes a worn piece of equipment from the current player
+pub fn remove_equipment(equip: &sql::Equipment) -> Packet {
+    let mut packet = Packet::new(0x1D);
+    packet.write_byte(1); // update tick
+    packet.write_byte(1);
+    packet.write_byte(3);
+    packet.write_byte(InventoryType::Equip as u8 + 1);
+
+    // Worn equipment is at negative positions in the client
+    packet.write_short(-equip.position.abs() as i16);
+    packet.write_byte(2); // the character's look changed
+    packet
+}
+
+/// Tells the current player that one of their items expired
+pub fn show_item_expired(item_id: i32) -> Packet {
+    let mut packet = Packet::new(0x27);
+    packet.write_byte(2);
+    packet.write_int(item_id);
+    packet
+}
+
+/// Updates a character's look for everyone else in the map
+pub fn update_character_look(character: &sql::Character, equipment: &[sql::Equipment]) -> Packet {
+    let mut packet = Packet::new(0xC5);
+    packet.write_int(character.id);
+    packet.write_byte(1);
+    write_character_look(&mut packet, character, equipment);
+    packet.write_byte(0); // TODO crush ring
+    packet.write_byte(0); // TODO friendship ring
+    packet.write_byte(0); // TODO marriage ring
+    packet.write_int(0);
+    packet
+}
+
+/// Writes an item's data to a packet
+pub fn write_item(packet: &mut Packet, item: &sql::Item) {
+    // Positions are 0-indexed in db, client expects 1-indexed
+    match item.equip {
+        Some(_) => packet.write_short((item.position + 1) as i16),
+        None => packet.write_byte((item.position + 1) as u8),
+    }
+
+    write_item_data(packet, item);
+}
+
+/// Writes a worn piece of equipment's data to a packet
+pub fn write_equip(packet: &mut Packet, equip: &sql::Equipment) {
+    let mut pos = equip.position.abs();
+
+    if pos > 100 {
+        pos -= 100;
+    }
+
+    packet.write_short(pos as i16);
+    write_equip_info(
+        packet,
+        equip.item_id,
+        equip.cash_id,
+        equip.expires_at,
+        equip,
+    );
+}
+
+/// Writes the data of a piece of equipment wherever it's kept, `equip` holds its stats
+fn write_equip_info(
+    packet: &mut Packet,
+    item_id: i32,
+    cash_id: Option<i64>,
+    expires_at: Option<i64>,
+    equip: &sql::Equipment,
+) {
+    packet.write_byte(1); // item type (equip)
+    packet.write_int(item_id);
+    write_cash_id(packet, cash_id);
+    write_expiration(packet, expires_at);
+    packet.write_byte(equip.upgrade_slots as u8);
+    packet.write_byte(equip.level as u8);
+    packet.write_short(equip.str as i16);
+    packet.write_short(equip.dex as i16);
+    packet.write_short(equip.int as i16);
+    packet.write_short(equip.luk as i16);
+    packet.write_short(equip.hp as i16);
+    packet.write_short(equip.mp as i16);
+    packet.write_short(equip.w_atk as i16);
+    packet.write_short(equip.m_atk as i16);
+    packet.write_short(equip.w_def as i16);
+    packet.write_short(equip.m_def as i16);
+    packet.write_short(equip.acc as i16);
+    packet.write_short(equip.avoid as i16);
+    packet.write_short(equip.hands as i16);
+    packet.write_short(equip.speed as i16);
+    packet.write_short(equip.jump as i16);
+    packet.write_string(&equip.owner);
+    packet.write_short(equip.flag as i16);
+    // TODO if iscash write 10 0x40 bytes? and dont wirte item level stuff
+
+    packet.write_byte(0);
+    packet.write_byte(equip.item_level as u8);
+    packet.write_int(0); // TODO exp nibble?
+    packet.write_int(equip.vicious);
+    packet.write_long(0);
+
+    // UTC zero-timestamp
+    packet.write_long(94354848000000000);
+    packet.write_int(-1);
+}
+
+/// Writes an item's data without its position to a packet
+fn write_item_data(packet: &mut Packet, item: &sql::Item) {
+    if let Some(equip) = item.equip.as_ref() {
+        return write_equip_info(packet, item.item_id, item.cash_id, item.expires_at, equip);
+    }
+
+    write_item_info(
+        packet,
+        item.item_id,
+        item.cash_id,
+        item.expires_at,
+        item.amount,
+        &item.owner,
+        item.flag,
+    );
+}
+
+/// Writes the data of an item wherever it's kept (inventory, storage, shop, etc.)
+/// `cash_id` is the serial number of items bought from the cash shop
+fn write_item_info(
+    packet: &mut Packet,
+    item_id: i32,
+    cash_id: Option<i64>,
+    expires_at: Option<i64>,
+    amount: i32,
+    owner: &str,
+    flag: i32,
+) {
+    packet.write_byte(2); // item type (item)
+    packet.write_int(item_id);
+    write_cash_id(packet, cash_id);
+    write_expiration(packet, expires_at);
+    packet.write_short(amount as i16);
+    packet.write_string(owner);
+    packet.write_short(flag as i16);
+    // TODO if item is rechargable, sent int(2), bytes (0x54, 0, 0, 0x34)?
+}
+
+/// Spawns a monster for the current player, `new_spawn` shows the monster's spawn animation
+pub fn spawn_monster(monster: &maple::Monster, new_spawn: bool) -> Packet {
+    let mut packet = Packet::new(0xEC);
+    packet.write_int(monster.object_id);
+    packet.write_byte(5); // TODO 1 once monsters have a controller
+    packet.write_int(monster.data.id);
+    packet.write_bytes(&[0; 15]); // TODO monster status
+    packet.write_byte(0x88);
+    packet.write_bytes(&[0; 6]);
+    packet.write_position(monster.pos);
+    packet.write_byte(monster.stance);
+    packet.write_short(0); // origin foothold
+    packet.write_short(monster.fh as i16);
+    packet.write_byte(if new_spawn { 0xFE } else { 0xFF });
+    packet.write_byte(0xFF); // team
+    packet.write_int(0);
+    packet
+}
+
+/// Removes a monster from the map, `animation` is 1 to show the monster dying or 0 to make it disappear
+pub fn kill_monster(object_id: i32, animation: u8) -> Packet {
+    let mut packet = Packet::new(0xED);
+    packet.write_int(object_id);
+    packet.write_byte(animation);
+    packet
+}
+
+/// Shows a monster's remaining hp bar to the character attacking it
+pub fn show_monster_hp(object_id: i32, hp_percent: u8) -> Packet {
+    let mut packet = Packet::new(0xFA);
+    packet.write_int(object_id);
+    packet.write_byte(hp_percent);
+    packet
+}
+
+/// Shows a boss's hp bar at the top of the screen
+pub fn show_boss_hp(
+    monster_id: i32,
+    hp: i32,
+    max_hp: i32,
+    tag_color: u8,
+    tag_bg_color: u8,
+) -> Packet {
+    let mut packet = Packet::new(0x8A);
+    packet.write_byte(5);
+    packet.write_int(monster_id);
+    packet.write_int(hp);
+    packet.write_int(max_hp);
+    packet.write_byte(tag_color);
+    packet.write_byte(tag_bg_color);
+    packet
+}
+
+/// Shows an item the current player picked up in the bottom right of the screen
+pub fn show_item_gain(item_id: i32, amount: i32) -> Packet {
+    let mut packet = Packet::new(0x27);
+    packet.write_byte(0);
+    packet.write_byte(0);
+    packet.write_int(item_id);
+    packet.write_int(amount);
+    packet.write_int(0);
+    packet.write_int(0);
+    packet
+}
+
+/// Spawns a reactor in the map
+pub fn spawn_reactor(reactor: &maple::Reactor) -> Packet {
+    let mut packet = Packet::new(0x117);
+    packet.write_int(reactor.object_id);
+    packet.write_int(reactor.data.id);
+    packet.write_byte(reactor.state as u8);
+    packet.write_position(reactor.pos);
+    packet.write_byte(reactor.f);
+    packet.write_string(&reactor.name);
+    packet
+}
+
+/// Shows a reactor being hit and moving to its current state
+/// `stance` is the stance of the character that hit it
+pub fn trigger_reactor(reactor: &maple::Reactor, stance: i16) -> Packet {
+    let mut packet = Packet::new(0x115);
+    packet.write_int(reactor.object_id);
+    packet.write_byte(reactor.state as u8);
+    packet.write_position(reactor.pos);
+    packet.write_short(stance);
+    packet.write_byte(0);
+    packet.write_byte(5); // frame delay
+    packet
+}
+
+/// Removes a broken reactor from the map
+pub fn destroy_reactor(reactor: &maple::Reactor) -> Packet {
+    let mut packet = Packet::new(0x118);
+    packet.write_int(reactor.object_id);
+    packet.write_byte(reactor.state as u8);
+    packet.write_position(reactor.pos);
+    packet
+}
+
+/// Spawns a dropped item in the map, `new_drop` shows it falling from whatever dropped it
+pub fn spawn_drop(drop: &maple::Drop, new_drop: bool) -> Packet {
+    let mut packet = Packet::new(0x10C);
+    packet.write_byte(if new_drop { 1 } else { 2 });
+    packet.write_int(drop.object_id);
+    packet.write_byte(0); // mesos
+    packet.write_int(drop.item_id);
+    packet.write_int(0); // owner
+    packet.write_byte(2); // free for all
+    packet.write_position(drop.pos);
+    packet.write_int(drop.source_id);
+
+    if new_drop {
+        packet.write_position(drop.source_pos);
+        packet.write_short(0);
+    }
+
+    write_expiration(&mut packet, None);
+    packet.write_byte(1); // pets can pick it up
+    packet
+}
+
+/// Removes a dropped item from the map, fading it out after it was left on the ground for too long
+pub fn expire_drop(object_id: i32) -> Packet {
+    let mut packet = Packet::new(0x10D);
+    packet.write_byte(0);
+    packet.write_int(object_id);
+    packet
+}
+
+/// Removes a dropped item from the map, showing it being picked up by the given character
+/// `pet_slot` is the slot of the character's pet that picked it up, if it wasn't the character
+pub fn pick_up_drop(object_id: i32, character_id: i32, pet_slot: Option<i32>) -> Packet {
+    let mut packet = Packet::new(0x10D);
+    packet.write_byte(if pet_slot.is_some() { 5 } else { 2 });
+    packet.write_int(object_id);
+    packet.write_int(character_id);
+
+    if let Some(slot) = pet_slot {
+        packet.write_byte(slot as u8);
+    }
+
+    packet
+}
+
+/// Shows a clock at the top of the screen counting down from the given number of seconds
+pub fn clock(seconds: i32) -> Packet {
+    let mut packet = Packet::new(0x93);
+    packet.write_byte(2);
+    packet.write_int(seconds);
+    packet
+}
+
+/// Shows a screen effect to everyone in the map (ex. "quest/party/clear")
+pub fn show_map_effect(path: &str) -> Packet {
+    let mut packet = Packet::new(0x8A);
+    packet.write_byte(3);
+    packet.write_string(path);
+    packet
+}
+
+/// Plays a sound for everyone in the map (ex. "Party1/Clear")
+pub fn play_map_sound(path: &str) -> Packet {
+    let mut packet = Packet::new(0x8A);
+    packet.write_byte(4);
+    packet.write_string(path);
+    packet
+}
+
+/// Triggers a named object in the map for everyone in it (ex. opening a party quest's "gate")
+pub fn trigger_map_object(name: &str) -> Packet {
+    let mut packet = Packet::new(0x8A);
+    packet.write_byte(2);
+    packet.write_string(name);
+    packet
+}
+
+/// Changes the background music for everyone in the map (ex. "Bgm04/ArabPirate")
+pub fn change_map_music(path: &str) -> Packet {
+    let mut packet = Packet::new(0x8A);
+    packet.write_byte(6);
+    packet.write_string(path);
+    packet
+}
+
+/// Shows a ship sailing into a station, or out of it once it departs
+pub fn move_ship(arriving: bool) -> Packet {
+    let mut packet = Packet::new(0x94);
+    packet.write_byte(8);
+    packet.write_byte(if arriving { 2 } else { 3 });
+    packet
+}
+
+/// Shows the Balrog ship pulling up alongside a ship to invade it, or leaving again
+pub fn move_balrog_ship(arriving: bool) -> Packet {
+    let mut packet = Packet::new(0x94);
+    packet.write_byte(10);
+    packet.write_byte(if arriving { 4 } else { 5 });
+    packet
+}
+
+/// Shows the exp the character gained in the bottom right of the screen, or in the chat box
+/// `party_bonus` is the extra exp gained from sharing exp with a party and buffs like holy symbol
+pub fn show_exp_gain(exp: i32, party_bonus: i32, white: bool, in_chat: bool) -> Packet {
+    let mut packet = Packet::new(0x27);
+    packet.write_byte(3);
+    packet.write_byte(white as u8);
+    packet.write_int(exp);
+    packet.write_byte(in_chat as u8);
+    packet.write_int(0); // event bonus
+    packet.write_byte(0);
+    packet.write_byte(0);
+    packet.write_int(0); // wedding bonus
+
+    if in_chat {
+        packet.write_byte(0);
+    }
+
+    packet.write_byte(0);
+    packet.write_int(party_bonus);
+    packet.write_int(0); // equipment bonus
+    packet.write_int(0); // internet cafe bonus
+    packet.write_int(0); // rainbow week bonus
+    packet
+}
+
+/// Updates a started quest's kill progress
+pub fn update_quest_progress(quest_id: i32, progress: &str) -> Packet {
+    let mut packet = Packet::new(0x27);
+    packet.write_byte(1);
+    packet.write_short(quest_id as i16);
+    packet.write_byte(1);
+    packet.write_string(progress);
+    packet.write_bytes(&[0, 0, 0, 0, 0]);
+    packet
+}
+
+/// Party operations that update the party window
+#[derive(Clone, Copy)]
+pub enum PartyOperation<'a> {
+    /// A character joined the party, by name
+    Join(&'a str),
+    /// A character left or was expelled from the party
+    Leave {
+        character_id: i32,
+        name: &'a str,
+        expelled: bool,
+    },
+    /// The party was disbanded by its leader
+    Disband { leader_id: i32 },
+    /// A member logged in/out or changed maps
+    Update,
+    /// The party leader changed, by character id
+    ChangeLeader(i32),
+}
+
+/// Tells the current player that their party was created
+pub fn party_created(party_id: i32) -> Packet {
+    let mut packet = Packet::new(0x3E);
+    packet.write_byte(8);
+    packet.write_int(party_id);
+    // TODO mystic door info
+    packet.write_int(999999999);
+    packet.write_int(999999999);
+    packet.write_int(0);
+    packet.write_int(0);
+    packet
+}
+
+/// Invites the current player to a party
+pub fn party_invite(party_id: i32, inviter_name: &str) -> Packet {
+    let mut packet = Packet::new(0x3E);
+    packet.write_byte(4);
+    packet.write_int(party_id);
+    packet.write_string(inviter_name);
+    packet.write_byte(0);
+    packet
+}
+
+pub enum PartyMessage {
+    BeginnerCantCreate = 10,
+    NotInParty = 12,
+    AlreadyInParty = 16,
+    PartyFull = 17,
+    CharacterNotFound = 19,
+    InviteDenied = 23,
+}
+
+/// Shows a party status message to the current player
+/// `name` is only shown for messages that refer to another character (ex. a denied invite)
+pub fn party_message(message: PartyMessage, name: Option<&str>) -> Packet {
+    let mut packet = Packet::new(0x3E);
+    packet.write_byte(message as u8);
+
+    if let Some(name) = name {
+        packet.write_string(name);
+    }
+
+    packet
+}
+
+/// Updates the party window for a party member on the given channel
+pub fn update_party(
+    party: &sql::Party,
+    members: &[sql::PartyMember],
+    channel_id: i32,
+    operation: PartyOperation,
+) -> Packet {
+    let mut packet = Packet::new(0x3E);
+
+    match operation {
+        PartyOperation::Join(name) => {
+            packet.write_byte(0x0F);
+            packet.write_int(party.id);
+            packet.write_string(name);
+            write_party_members(&mut packet, party, members, channel_id);
+        }
+        PartyOperation::Leave {
+            character_id,
+            name,
+            expelled,
+        } => {
+            packet.write_byte(0x0C);
+            packet.write_int(party.id);
+            packet.write_int(character_id);
+            packet.write_byte(1);
+            packet.write_byte(expelled as u8);
+            packet.write_string(name);
+            write_party_members(&mut packet, party, members, channel_id);
+        }
+        PartyOperation::Disband { leader_id } => {
+            packet.write_byte(0x0C);
+            packet.write_int(party.id);
+            packet.write_int(leader_id);
+            packet.write_byte(0);
+            packet.write_int(party.id);
+        }
+        PartyOperation::Update => {
+            packet.write_byte(0x07);
+            packet.write_int(party.id);
+            write_party_members(&mut packet, party, members, channel_id);
+        }
+        PartyOperation::ChangeLeader(leader_id) => {
+            packet.write_byte(0x1B);
+            packet.write_int(leader_id);
+            packet.write_byte(0);
+        }
+    }
+
+    packet
+}
+
+/// Writes a party's members to a packet, the client always expects 6 members
+fn write_party_members(
+    packet: &mut Packet,
+    party: &sql::Party,
+    members: &[sql::PartyMember],
+    channel_id: i32,
+) {
+    let members: Vec<Option<&sql::PartyMember>> = members
+        .iter()
+        .map(Some)
+        .chain(std::iter::repeat(None))
+        .take(sql::party::MAX_PARTY_SIZE)
+        .collect();
+
+    for member in members.iter() {
+        packet.write_int(member.map(|member| member.id).unwrap_or(0));
+    }
+
+    for member in members.iter() {
+        let mut name = member.map(|member| member.name.clone()).unwrap_or_default();
+
+        for _ in name.len()..13 {
+            name.push('\0');
+        }
+
+        packet.write_fixed_string(&name);
+    }
+
+    for member in members.iter() {
+        packet.write_int(member.map(|member| member.job).unwrap_or(0));
+    }
+
+    for member in members.iter() {
+        packet.write_int(member.map(|member| member.level).unwrap_or(0));
+    }
+
+    // -2 means the member is offline
+    for member in members.iter() {
+        packet.write_int(member.and_then(|member| member.channel_id).unwrap_or(-2));
+    }
+
+    packet.write_int(party.leader_id);
+
+    // Maps are only shown for members on the same channel
+    for member in members.iter() {
+        match member {
+            Some(member) if member.channel_id == Some(channel_id) => {
+                packet.write_int(member.map_id.unwrap_or(0))
+            }
+            _ => packet.write_int(0),
+        }
+    }
+
+    for _ in members.iter() {
+        // TODO mystic door info
+        packet.write_int(999999999);
+        packet.write_int(999999999);
+        packet.write_int(0);
+        packet.write_int(-1);
+        packet.write_int(-1);
+    }
+}
+
+/// Updates a party member's hp bar for the current player
+pub fn update_party_member_hp(character_id: i32, hp: i32, max_hp: i32) -> Packet {
+    let mut packet = Packet::new(0xC9);
+    packet.write_int(character_id);
+    packet.write_int(hp);
+    packet.write_int(max_hp);
+    packet
+}
+
+/// Writes a string padded with null characters to a fixed length
+fn write_padded_string(packet: &mut Packet, string: &str, len: usize) {
+    let mut padded = String::from(string);
+
+    for _ in padded.len()..len {
+        padded.push('\0');
+    }
+
+    packet.write_fixed_string(&padded);
+}
+
+/// Updates the current player's buddy list
+pub fn update_buddy_list(buddies: &[sql::BuddyEntry]) -> Packet {
+    let mut packet = Packet::new(0x3F);
+    packet.write_byte(7);
+    packet.write_byte(buddies.len() as u8);
+
+    for buddy in buddies.iter() {
+        packet.write_int(buddy.id);
+        write_padded_string(&mut packet, &buddy.name, 13);
+        packet.write_byte(0);
+        packet.write_int(buddy.channel_id.unwrap_or(-1));
+        write_padded_string(&mut packet, &buddy.group_name, 17);
+    }
+
+    for _ in buddies.iter() {
+        packet.write_int(0);
+    }
+
+    packet
+}
+
+/// Asks the current player to accept a buddy request
+pub fn buddy_request(from_id: i32, from_name: &str, character_id: i32) -> Packet {
+    let mut packet = Packet::new(0x3F);
+    packet.write_byte(9);
+    packet.write_int(from_id);
+    packet.write_string(from_name);
+    packet.write_int(from_id);
+    write_padded_string(&mut packet, from_name, 13);
+    packet.write_byte(1);
+    packet.write_int(0x0F);
+    write_padded_string(&mut packet, sql::buddy::DEFAULT_GROUP, 17);
+    packet.write_int(character_id);
+    packet
+}
+
+/// Updates the channel a buddy is shown on in the current player's buddy list, -1 if they're offline
+pub fn update_buddy_channel(character_id: i32, channel_id: i32) -> Packet {
+    let mut packet = Packet::new(0x3F);
+    packet.write_byte(0x14);
+    packet.write_int(character_id);
+    packet.write_byte(0);
+    packet.write_int(channel_id);
+    packet
+}
+
+pub enum BuddyMessage {
+    ListFull = 11,
+    OtherListFull = 12,
+    AlreadyRegistered = 13,
+    CharacterNotFound = 15,
+}
+
+/// Shows a buddy list status message to the current player
+pub fn buddy_message(message: BuddyMessage) -> Packet {
+    let mut packet = Packet::new(0x3F);
+    packet.write_byte(message as u8);
+    packet
+}
+
+pub enum GuildMessage {
+    /// Asks the current player to enter a name for their new guild
+    ShowCreate = 0x01,
+    /// Opens the emblem editor for the current player
+    ShowEmblem = 0x11,
+    NameInUse = 0x1C,
+    CreateFailed = 0x23,
+    AlreadyInGuild = 0x28,
+    NotInChannel = 0x2A,
+    NotInGuild = 0x2D,
+}
+
+/// Shows a guild status message to the current player
+pub fn guild_message(message: GuildMessage) -> Packet {
+    let mut packet = Packet::new(0x41);
+    packet.write_byte(message as u8);
+    packet
+}
+
+/// Shows another character's declined guild invite to the current player
+pub fn guild_invite_denied(name: &str) -> Packet {
+    let mut packet = Packet::new(0x41);
+    packet.write_byte(0x37);
+    packet.write_string(name);
+    packet
+}
+
+/// Sends the current player their guild's info, or no guild if `guild` is None
+pub fn guild_info(guild: Option<&sql::Guild>, members: &[sql::GuildMember]) -> Packet {
+    let mut packet = Packet::new(0x41);
+    packet.write_byte(0x1A);
+
+    let guild = match guild {
+        Some(guild) => guild,
+        None => {
+            packet.write_byte(0);
+            return packet;
+        }
+    };
+
+    packet.write_byte(1);
+    write_guild_info(&mut packet, guild, members);
+    packet
+}
+
+/// Writes a guild's info and members to a packet
+fn write_guild_info(packet: &mut Packet, guild: &sql::Guild, members: &[sql::GuildMember]) {
+    packet.write_int(guild.id);
+    packet.write_string(&guild.name);
+
+    for title in guild.rank_titles() {
+        packet.write_string(title);
+    }
+
+    packet.write_byte(members.len() as u8);
+
+    for member in members.iter() {
+        packet.write_int(member.id);
+    }
+
+    for member in members.iter() {
+        write_guild_member(packet, member);
+    }
+
+    packet.write_int(guild.capacity);
+    packet.write_short(guild.logo_bg as i16);
+    packet.write_byte(guild.logo_bg_color as u8);
+    packet.write_short(guild.logo as i16);
+    packet.write_byte(guild.logo_color as u8);
+    packet.write_string(&guild.notice);
+    packet.write_int(guild.points);
+    packet.write_int(guild.alliance_id.unwrap_or(0));
+}
+
+/// Writes a guild member's info to a packet
+fn write_guild_member(packet: &mut Packet, member: &sql::GuildMember) {
+    write_padded_string(packet, &member.name, 13);
+    packet.write_int(member.job);
+    packet.write_int(member.level);
+    packet.write_int(member.guild_rank);
+    packet.write_int(member.channel_id.is_some() as i32);
+    packet.write_int(1); // signature
+    packet.write_int(member.alliance_rank);
+}
+
+/// Invites the current player to a guild
+pub fn guild_invite(guild_id: i32, inviter_name: &str) -> Packet {
+    let mut packet = Packet::new(0x41);
+    packet.write_byte(0x05);
+    packet.write_int(guild_id);
+    packet.write_string(inviter_name);
+    packet
+}
+
+/// Adds a new member to the current player's guild window
+pub fn guild_member_joined(guild_id: i32, member: &sql::GuildMember) -> Packet {
+    let mut packet = Packet::new(0x41);
+    packet.write_byte(0x27);
+    packet.write_int(guild_id);
+    packet.write_int(member.id);
+    write_guild_member(&mut packet, member);
+    packet
+}
+
+/// Removes a member who left or was expelled from the current player's guild window
+pub fn guild_member_left(guild_id: i32, character_id: i32, name: &str, expelled: bool) -> Packet {
+    let mut packet = Packet::new(0x41);
+    packet.write_byte(if expelled { 0x2F } else { 0x2C });
+    packet.write_int(guild_id);
+    packet.write_int(character_id);
+    packet.write_string(name);
+    packet
+}
+
+/// Shows the current player that their guild was disbanded
+pub fn guild_disbanded(guild_id: i32) -> Packet {
+    let mut packet = Packet::new(0x41);
+    packet.write_byte(0x32);
+    packet.write_int(guild_id);
+    packet.write_byte(1);
+    packet
+}
+
+/// Updates a guild's capacity for the current player
+pub fn update_guild_capacity(guild_id: i32, capacity: i32) -> Packet {
+    let mut packet = Packet::new(0x41);
+    packet.write_byte(0x3A);
+    packet.write_int(guild_id);
+    packet.write_byte(capacity as u8);
+    packet
+}
+
+/// Updates a guild member's level and job for the current player
+pub fn update_guild_member_level(guild_id: i32, character_id: i32, level: i32, job: i32) -> Packet {
+    let mut packet = Packet::new(0x41);
+    packet.write_byte(0x3C);
+    packet.write_int(guild_id);
+    packet.write_int(character_id);
+    packet.write_int(level);
+    packet.write_int(job);
+    packet
+}
+
+/// Shows a guild member as online or offline for the current player
+pub fn update_guild_member_online(guild_id: i32, character_id: i32, online: bool) -> Packet {
+    let mut packet = Packet::new(0x41);
+    packet.write_byte(0x3D);
+    packet.write_int(guild_id);
+    packet.write_int(character_id);
+    packet.write_byte(online as u8);
+    packet
+}
+
+/// Updates a guild's rank titles for the current player
+pub fn update_guild_rank_titles(guild: &sql::Guild) -> Packet {
+    let mut packet = Packet::new(0x41);
+    packet.write_byte(0x3E);
+    packet.write_int(guild.id);
+
+    for title in guild.rank_titles() {
+        packet.write_string(title);
+    }
+
+    packet
+}
+
+/// Updates a guild member's rank for the current player
+pub fn update_guild_member_rank(guild_id: i32, character_id: i32, rank: i32) -> Packet {
+    let mut packet = Packet::new(0x41);
+    packet.write_byte(0x40);
+    packet.write_int(guild_id);
+    packet.write_int(character_id);
+    packet.write_byte(rank as u8);
+    packet
+}
+
+/// Updates a guild's emblem for the current player
+pub fn update_guild_emblem(guild: &sql::Guild) -> Packet {
+    let mut packet = Packet::new(0x41);
+    packet.write_byte(0x42);
+    packet.write_int(guild.id);
+    packet.write_short(guild.logo_bg as i16);
+    packet.write_byte(guild.logo_bg_color as u8);
+    packet.write_short(guild.logo as i16);
+    packet.write_byte(guild.logo_color as u8);
+    packet
+}
+
+/// Updates a guild's notice for the current player
+pub fn update_guild_notice(guild_id: i32, notice: &str) -> Packet {
+    let mut packet = Packet::new(0x41);
+    packet.write_byte(0x44);
+    packet.write_int(guild_id);
+    packet.write_string(notice);
+    packet
+}
+
+/// Updates a guild's points for the current player
+pub fn update_guild_points(guild_id: i32, points: i32) -> Packet {
+    let mut packet = Packet::new(0x41);
+    packet.write_byte(0x48);
+    packet.write_int(guild_id);
+    packet.write_int(points);
+    packet
+}
+
+/// Updates the guild name shown above a character for everyone else in the map
+pub fn update_foreign_guild_name(character_id: i32, guild: Option<&sql::Guild>) -> Packet {
+    let mut packet = Packet::new(0xAF);
+    packet.write_int(character_id);
+    packet.write_string(guild.map(|guild| guild.name.as_str()).unwrap_or(""));
+    packet
+}
+
+/// Updates the guild emblem shown above a character for everyone else in the map
+pub fn update_foreign_guild_emblem(character_id: i32, guild: Option<&sql::Guild>) -> Packet {
+    let mut packet = Packet::new(0xB0);
+    packet.write_int(character_id);
+
+    match guild {
+        Some(guild) => {
+            packet.write_short(guild.logo_bg as i16);
+            packet.write_byte(guild.logo_bg_color as u8);
+            packet.write_short(guild.logo as i16);
+            packet.write_byte(guild.logo_color as u8);
+        }
+        None => packet.write_bytes(&[0, 0, 0, 0, 0, 0]),
+    }
+
+    packet
+}
+
+/// Sends the current player their alliance's info, or no alliance if `alliance` is None
+pub fn alliance_info(alliance: Option<&sql::Alliance>, guilds: &[sql::Guild]) -> Packet {
+    let mut packet = Packet::new(0x42);
+    packet.write_byte(0x0C);
+
+    let alliance = match alliance {
+        Some(alliance) => alliance,
+        None => {
+            packet.write_byte(0);
+            return packet;
+        }
+    };
+
+    packet.write_byte(1);
+    packet.write_int(alliance.id);
+    packet.write_string(&alliance.name);
+
+    for title in alliance.rank_titles() {
+        packet.write_string(title);
+    }
+
+    packet.write_byte(guilds.len() as u8);
+
+    for guild in guilds.iter() {
+        packet.write_int(guild.id);
+    }
+
+    packet.write_int(alliance.capacity);
+    packet.write_string(&alliance.notice);
+    packet
+}
+
+/// Sends the current player the info of each guild in their alliance
+pub fn alliance_guilds(guilds: &[(sql::Guild, Vec<sql::GuildMember>)]) -> Packet {
+    let mut packet = Packet::new(0x42);
+    packet.write_byte(0x0D);
+    packet.write_int(guilds.len() as i32);
+
+    for (guild, members) in guilds.iter() {
+        write_guild_info(&mut packet, guild, members);
+    }
+
+    packet
+}
+
+/// Invites the current player's guild to an alliance
+pub fn alliance_invite(alliance_id: i32, inviter_name: &str) -> Packet {
+    let mut packet = Packet::new(0x42);
+    packet.write_byte(0x03);
+    packet.write_int(alliance_id);
+    packet.write_string(inviter_name);
+    packet.write_short(0);
+    packet
+}
+
+/// Shows an alliance member as online or offline for the current player
+pub fn update_alliance_member_online(
+    alliance_id: i32,
+    guild_id: i32,
+    character_id: i32,
+    online: bool,
+) -> Packet {
+    let mut packet = Packet::new(0x42);
+    packet.write_byte(0x0E);
+    packet.write_int(alliance_id);
+    packet.write_int(guild_id);
+    packet.write_int(character_id);
+    packet.write_byte(online as u8);
+    packet
+}
+
+/// Updates an alliance's rank titles for the current player
+pub fn update_alliance_rank_titles(alliance: &sql::Alliance) -> Packet {
+    let mut packet = Packet::new(0x42);
+    packet.write_byte(0x1A);
+    packet.write_int(alliance.id);
+
+    for title in alliance.rank_titles() {
+        packet.write_string(title);
+    }
+
+    packet
+}
+
+/// Updates an alliance member's rank for the current player
+pub fn update_alliance_member_rank(alliance_id: i32, character_id: i32, rank: i32) -> Packet {
+    let mut packet = Packet::new(0x42);
+    packet.write_byte(0x1B);
+    packet.write_int(alliance_id);
+    packet.write_int(character_id);
+    packet.write_int(rank);
+    packet
+}
+
+/// Updates an alliance's notice for the current player
+pub fn update_alliance_notice(alliance_id: i32, notice: &str) -> Packet {
+    let mut packet = Packet::new(0x42);
+    packet.write_byte(0x1C);
+    packet.write_int(alliance_id);
+    packet.write_string(notice);
+    packet
+}
+
+/// Removes the alliance from the current player's guild window
+/// Sent when the alliance is disbanded, or when the player's guild leaves it
+pub fn alliance_disbanded(alliance_id: i32) -> Packet {
+    let mut packet = Packet::new(0x42);
+    packet.write_byte(0x1D);
+    packet.write_int(alliance_id);
+    packet
+}
+
+/// Npc dialog types
+#[derive(Clone, Copy)]
+pub enum NpcDialog {
+    /// A message with an ok button
+    Ok,
+    /// A message with yes and no buttons
+    YesNo,
+    /// A message with a list of options (#L<selection>#<text>#l)
+    Selection,
+    /// A message with a text box for the player to type in
+    GetText,
+}
+
+/// Shows an npc dialog to the current player
+pub fn npc_talk(npc_id: i32, dialog: NpcDialog, text: &str) -> Packet {
+    let mut packet = Packet::new(0x130);
+    packet.write_byte(4);
+    packet.write_int(npc_id);
+
+    let dialog_type = match dialog {
+        NpcDialog::Ok => 0,
+        NpcDialog::YesNo => 1,
+        NpcDialog::GetText => 2,
+        NpcDialog::Selection => 4,
+    };
+
+    packet.write_byte(dialog_type);
+    packet.write_byte(0); // speaker
+    packet.write_string(text);
+
+    match dialog {
+        NpcDialog::Ok => packet.write_bytes(&[0, 0]), // no back or next buttons
+        NpcDialog::GetText => {
+            packet.write_string(""); // default text
+            packet.write_int(0);
+        }
+        _ => {}
+    }
+
+    packet
+}
+
+/// Opens an npc's shop for the current player
+pub fn open_shop(npc_id: i32, items: &[(sql::ShopItem, nx::Item)]) -> Packet {
+    let mut packet = Packet::new(0x131);
+    packet.write_int(npc_id);
+    packet.write_short(items.len() as i16);
+
+    for (shop_item, data) in items.iter() {
+        packet.write_int(shop_item.item_id);
+        packet.write_int(shop_item.price);
+        packet.write_int(0); // perfect pitch price
+        packet.write_int(0); // minutes the item can be used for
+        packet.write_int(0);
+
+        if data.is_rechargeable() {
+            packet.write_short(0);
+            packet.write_int(0);
+            // The client only reads the top 16 bits of the unit price
+            packet.write_short((data.unit_price.to_bits() >> 48) as i16);
+        } else {
+            packet.write_short(1); // amount per purchase
+        }
+
+        packet.write_short(data.slot_max as i16);
+    }
+
+    packet
+}
+
+/// The result of buying, selling, or recharging an item at a shop
+#[derive(Debug, Clone, Copy)]
+pub enum ShopResult {
+    Bought = 0,
+    NotEnoughMesos = 2,
+    InventoryFull = 3,
+    Sold = 8,
+    Recharged = 0x0C,
+}
+
+/// Tells the current player the result of their shop transaction
+pub fn shop_result(result: ShopResult) -> Packet {
+    let mut packet = Packet::new(0x132);
+    packet.write_byte(result as u8);
+    packet
+}
+
+/// Opens an account's storage for the current player, `items` are sorted by inventory type
+pub fn open_storage(npc_id: i32, storage: &sql::Storage, items: &[&sql::StorageItem]) -> Packet {
+    let mut packet = Packet::new(0x135);
+    packet.write_byte(0x16);
+    packet.write_int(npc_id);
+    packet.write_byte(storage.slots as u8);
+    packet.write_short(0x7E); // shows every inventory tab
+    packet.write_short(0);
+    packet.write_int(0);
+    packet.write_int(storage.mesos);
+    packet.write_short(0);
+    write_storage_items(&mut packet, items);
+    packet.write_short(0);
+    packet.write_byte(0);
+    packet
+}
+
+/// Shows the items left in one of the storage's tabs after an item was taken out of it
+pub fn storage_item_taken(
+    slots: i32,
+    inventory_type: InventoryType,
+    items: &[&sql::StorageItem],
+) -> Packet {
+    storage_tab_changed(0x09, slots, inventory_type, items)
+}
+
+/// Shows the items in one of the storage's tabs after an item was stored in it
+pub fn storage_item_stored(
+    slots: i32,
+    inventory_type: InventoryType,
+    items: &[&sql::StorageItem],
+) -> Packet {
+    storage_tab_changed(0x0D, slots, inventory_type, items)
+}
+
+fn storage_tab_changed(
+    operation: u8,
+    slots: i32,
+    inventory_type: InventoryType,
+    items: &[&sql::StorageItem],
+) -> Packet {
+    let mut packet = Packet::new(0x135);
+    packet.write_byte(operation);
+    packet.write_byte(slots as u8);
+    packet.write_short(2 << (inventory_type as u8 + 1)); // the changed tab
+    packet.write_short(0);
+    packet.write_int(0);
+    write_storage_items(&mut packet, items);
+    packet
+}
+
+/// Shows the storage's items after they were arranged, `items` are sorted by inventory type
+pub fn storage_arranged(slots: i32, items: &[&sql::StorageItem]) -> Packet {
+    let mut packet = Packet::new(0x135);
+    packet.write_byte(0x0F);
+    packet.write_byte(slots as u8);
+    packet.write_byte(0x7C); // every item tab
+    packet.write_bytes(&[0; 10]);
+    write_storage_items(&mut packet, items);
+    packet.write_byte(0);
+    packet
+}
+
+/// Updates the mesos in the storage
+pub fn storage_mesos(slots: i32, mesos: i32) -> Packet {
+    let mut packet = Packet::new(0x135);
+    packet.write_byte(0x13);
+    packet.write_byte(slots as u8);
+    packet.write_short(2); // the mesos
+    packet.write_short(0);
+    packet.write_int(0);
+    packet.write_int(mesos);
+    packet
+}
+
+/// Why an item couldn't be stored or taken out of storage
+#[derive(Debug, Clone, Copy)]
+pub enum StorageError {
+    InventoryFull = 0x0A,
+    NotEnoughMesos = 0x0B,
+    StorageFull = 0x11,
+}
+
+/// Tells the current player why their storage action failed
+pub fn storage_error(error: StorageError) -> Packet {
+    let mut packet = Packet::new(0x135);
+    packet.write_byte(error as u8);
+    packet
+}
+
+fn write_storage_items(packet: &mut Packet, items: &[&sql::StorageItem]) {
+    packet.write_byte(items.len() as u8);
+
+    for item in items.iter() {
+        match item.equip.as_ref() {
+            Some(equip) => {
+                write_equip_info(packet, item.item_id, item.cash_id, item.expires_at, equip)
+            }
+            None => write_item_info(
+                packet,
+                item.item_id,
+                item.cash_id,
+                item.expires_at,
+                item.amount,
+                &item.owner,
+                item.flag,
+            ),
+        }
+    }
+}
+
+/// Why a trade closed
+#[derive(Debug, Clone, Copy)]
+pub enum TradeResult {
+    PartnerCancelled = 2,
+    Successful = 7,
+    Unsuccessful = 8,
+}
+
+/// Opens the trade window for the current player
+/// `number` is the player's side of the trade (0 if they opened it, 1 if they were invited)
+/// `partner` is the other trader's info if they're already in the trade
+pub fn trade_room(
+    number: u8,
+    character: (&sql::Character, &[sql::Equipment]),
+    partner: Option<(&sql::Character, &[sql::Equipment])>,
+) -> Packet {
+    let mut packet = Packet::new(0x13A);
+    packet.write_byte(0x05);
+    packet.write_byte(3); // room type (trade)
+    packet.write_byte(2); // max traders
+    packet.write_byte(number);
+
+    if let Some((data, equipment)) = partner {
+        packet.write_byte(0);
+        write_character_look(&mut packet, data, equipment);
+        packet.write_string(&data.name);
+    }
+
+    let (data, equipment) = character;
+    packet.write_byte(number);
+    write_character_look(&mut packet, data, equipment);
+    packet.write_string(&data.name);
+    packet.write_byte(0xFF);
+    packet
+}
+
+/// Invites the current player to a trade
+pub fn trade_invite(trade_id: i32, inviter_name: &str) -> Packet {
+    let mut packet = Packet::new(0x13A);
+    packet.write_byte(0x02);
+    packet.write_byte(3); // room type (trade)
+    packet.write_string(inviter_name);
+    packet.write_int(trade_id);
+    packet
+}
+
+/// Shows the current player's trade partner joining the trade
+pub fn trade_partner_joined(character: &sql::Character, equipment: &[sql::Equipment]) -> Packet {
+    let mut packet = Packet::new(0x13A);
+    packet.write_byte(0x04);
+    packet.write_byte(1);
+    write_character_look(&mut packet, character, equipment);
+    packet.write_string(&character.name);
+    packet
+}
+
+/// Shows a chat message in the current player's trade, player shop, or minigame window
+/// `number` is the side of the trade or the room slot the message is shown for
+pub fn room_chat(number: u8, sender_name: &str, message: &str) -> Packet {
+    let mut packet = Packet::new(0x13A);
+    packet.write_byte(0x06);
+    packet.write_byte(0x08);
+    packet.write_byte(number);
+    packet.write_string(&format!("{} : {}", sender_name, message));
+    packet
+}
+
+/// Shows an item put into the trade, `number` is the side of the trade it was put on
+pub fn trade_item_added(number: u8, slot: u8, item: &sql::Item) -> Packet {
+    let mut packet = Packet::new(0x13A);
+    packet.write_byte(0x0F);
+    packet.write_byte(number);
+    packet.write_byte(slot);
+    write_item_data(&mut packet, item);
+    packet
+}
+
+/// Shows the mesos put into the trade, `number` is the side of the trade they were put on
+pub fn trade_mesos_set(number: u8, mesos: i32) -> Packet {
+    let mut packet = Packet::new(0x13A);
+    packet.write_byte(0x10);
+    packet.write_byte(number);
+    packet.write_int(mesos);
+    packet
+}
+
+/// Shows the current player that their trade partner confirmed the trade
+pub fn trade_confirmed() -> Packet {
+    let mut packet = Packet::new(0x13A);
+    packet.write_byte(0x11);
+    packet
+}
+
+/// Closes the current player's trade window, `number` is the player's own side of the trade
+pub fn trade_closed(number: u8, result: TradeResult) -> Packet {
+    let mut packet = Packet::new(0x13A);
+    packet.write_byte(0x0A);
+    packet.write_byte(number);
+    packet.write_byte(result as u8);
+    packet
+}
+
+/// A character in a player shop or hired merchant room, along with their slot in the room
+pub type RoomVisitor<'a> = (u8, &'a sql::Character, &'a [sql::Equipment]);
+
+/// Opens a player shop for the current player, `slot` is their slot in the shop (0 for the owner)
+pub fn player_shop_room(
+    slot: u8,
+    owner: (&sql::Character, &[sql::Equipment]),
+    visitors: &[RoomVisitor],
+    title: &str,
+    items: &[sql::PlayerShopItem],
+) -> Packet {
+    let mut packet = Packet::new(0x13A);
+    packet.write_byte(0x05);
+    packet.write_byte(4); // room type (player shop)
+    packet.write_byte(4); // max characters
+    packet.write_byte(slot);
+    packet.write_byte(0); // TODO sold items
+
+    let (data, equipment) = owner;
+    packet.write_byte(0);
+    write_character_look(&mut packet, data, equipment);
+    packet.write_string(&data.name);
+    write_room_visitors(&mut packet, visitors);
+
+    packet.write_string(title);
+    packet.write_byte(0x10); // item slots
+    write_player_shop_items(&mut packet, items);
+    packet
+}
+
+/// Opens a hired merchant for the current player, `slot` is their slot in the merchant (0 for the owner)
+/// `mesos` is the merchant's uncollected mesos for the owner, or the current player's own mesos for visitors
+pub fn hired_merchant_room(
+    slot: u8,
+    owner_name: &str,
+    visitors: &[RoomVisitor],
+    shop: ShopBox,
+    first_time: bool,
+    mesos: i32,
+    items: &[sql::PlayerShopItem],
+) -> Packet {
+    let mut packet = Packet::new(0x13A);
+    packet.write_byte(0x05);
+    packet.write_byte(5); // room type (hired merchant)
+    packet.write_byte(4); // max characters
+    packet.write_short(slot as i16);
+    packet.write_int(shop.permit_id);
+    packet.write_string("Hired Merchant");
+    write_room_visitors(&mut packet, visitors);
+
+    packet.write_short(0); // TODO messages left for the owner
+    packet.write_string(owner_name);
+
+    if slot == 0 {
+        packet.write_short(0);
+        packet.write_short(0); // TODO time the merchant has been open
+        packet.write_byte(first_time as u8);
+        packet.write_byte(0); // TODO sold items
+        packet.write_int(mesos);
+    }
+
+    packet.write_string(shop.title);
+    packet.write_byte(0x10); // item slots
+    packet.write_int(mesos);
+    write_player_shop_items(&mut packet, items);
+    packet
+}
+
+/// Shows a character visiting the current player's player shop or hired merchant
+pub fn room_visitor_joined(
+    slot: u8,
+    character: &sql::Character,
+    equipment: &[sql::Equipment],
+) -> Packet {
+    let mut packet = Packet::new(0x13A);
+    packet.write_byte(0x04);
+    packet.write_byte(slot);
+    write_character_look(&mut packet, character, equipment);
+    packet.write_string(&character.name);
+    packet
+}
+
+/// Shows a visitor leaving the current player's player shop or hired merchant
+pub fn room_visitor_left(slot: u8) -> Packet {
+    let mut packet = Packet::new(0x13A);
+    packet.write_byte(0x0A);
+    packet.write_byte(slot);
+    packet
+}
+
+/// Why a character had to leave a player shop or hired merchant
+#[derive(Debug, Clone, Copy)]
+pub enum RoomLeaveReason {
+    Banned = 5,
+    Closed = 10,
+}
+
+/// Closes the player shop or hired merchant window of the current player, `slot` is their slot in the room
+pub fn room_closed(slot: u8, reason: RoomLeaveReason) -> Packet {
+    let mut packet = Packet::new(0x13A);
+    packet.write_byte(0x0A);
+    packet.write_byte(slot);
+    packet.write_byte(reason as u8);
+    packet
+}
+
+/// Updates the items shown in the current player's player shop
+pub fn update_player_shop_items(items: &[sql::PlayerShopItem]) -> Packet {
+    let mut packet = Packet::new(0x13A);
+    packet.write_byte(0x19);
+    write_player_shop_items(&mut packet, items);
+    packet
+}
+
+/// Updates the items shown in the current player's hired merchant
+/// `mesos` is the merchant's uncollected mesos for the owner, or the current player's own mesos for visitors
+pub fn update_hired_merchant_items(mesos: i32, items: &[sql::PlayerShopItem]) -> Packet {
+    let mut packet = Packet::new(0x13A);
+    packet.write_byte(0x19);
+    packet.write_int(mesos);
+    write_player_shop_items(&mut packet, items);
+    packet
+}
+
+/// Shows the owner of a hired merchant the characters that visited it, with how long they stayed in milliseconds
+pub fn hired_merchant_visit_list(visits: &[(String, i32)]) -> Packet {
+    let mut packet = Packet::new(0x13A);
+    packet.write_byte(0x2E);
+    packet.write_short(visits.len() as i16);
+
+    for (name, duration) in visits.iter() {
+        packet.write_string(name);
+        packet.write_int(*duration);
+    }
+
+    packet
+}
+
+/// Shows the owner of a hired merchant the characters that aren't allowed to visit it
+pub fn hired_merchant_blacklist(names: &[String]) -> Packet {
+    let mut packet = Packet::new(0x13A);
+    packet.write_byte(0x2F);
+    packet.write_short(names.len() as i16);
+
+    for name in names.iter() {
+        packet.write_string(name);
+    }
+
+    packet
+}
+
+/// A player shop's or hired merchant's box shown in the map
+#[derive(Debug, Clone, Copy)]
+pub struct ShopBox<'a> {
+    pub object_id: i32,
+    pub title: &'a str,
+    pub permit_id: i32,
+    /// The number of characters in the shop, 0 if it isn't open to visitors
+    pub characters: u8,
+}
+
+/// Shows or removes the player shop box above a character for everyone in the map
+pub fn update_character_box(character_id: i32, shop: Option<ShopBox>) -> Packet {
+    let mut packet = Packet::new(0xA5);
+    packet.write_int(character_id);
+
+    match shop {
+        Some(shop) => {
+            packet.write_byte(4); // room type (player shop)
+            packet.write_int(shop.object_id);
+            packet.write_string(shop.title);
+            packet.write_byte(0); // no password
+            packet.write_byte((shop.permit_id % 100) as u8);
+            packet.write_byte(shop.characters);
+            packet.write_byte(4); // max characters
+            packet.write_byte(0);
+        }
+        None => packet.write_byte(0),
+    }
+
+    packet
+}
+
+/// Spawns a hired merchant in the map
+pub fn spawn_hired_merchant(
+    owner_id: i32,
+    owner_name: &str,
+    pos: (i32, i32),
+    shop: ShopBox,
+) -> Packet {
+    let mut packet = Packet::new(0x109);
+    packet.write_int(owner_id);
+    packet.write_int(shop.permit_id);
+    packet.write_position(pos);
+    packet.write_short(0); // foothold
+    packet.write_string(owner_name);
+    write_hired_merchant_box(&mut packet, shop);
+    packet
+}
+
+/// Removes a hired merchant from the map
+pub fn remove_hired_merchant(owner_id: i32) -> Packet {
+    let mut packet = Packet::new(0x10A);
+    packet.write_int(owner_id);
+    packet
+}
+
+/// Updates the box shown above a hired merchant
+pub fn update_hired_merchant(owner_id: i32, shop: ShopBox) -> Packet {
+    let mut packet = Packet::new(0x10B);
+    packet.write_int(owner_id);
+    write_hired_merchant_box(&mut packet, shop);
+    packet
+}
+
+/// Opens the window for setting up a hired merchant
+pub fn show_hired_merchant_setup() -> Packet {
+    let mut packet = Packet::new(0x32);
+    packet.write_byte(0x07);
+    packet
+}
+
+/// Shows the current player the items and mesos their closed player shops left with Fredrick
+pub fn fredrick_items(npc_id: i32, mesos: i32, items: &[sql::PlayerShopItem]) -> Packet {
+    let mut packet = Packet::new(0x137);
+    packet.write_byte(0x23);
+    packet.write_int(npc_id);
+    packet.write_int(32272);
+    packet.write_long(0);
+    packet.write_byte(0);
+    packet.write_int(mesos);
+    packet.write_byte(0);
+    packet.write_byte(items.len() as u8);
+
+    for item in items.iter() {
+        write_player_shop_item(&mut packet, item, item.amount * item.bundles);
+    }
+
+    packet.write_bytes(&[0; 3]);
+    packet
+}
+
+/// The result of retrieving items and mesos from Fredrick
+#[derive(Debug, Clone, Copy)]
+pub enum FredrickMessage {
+    Retrieved = 0x1E,
+    InventoryFull = 0x21,
+}
+
+/// Tells the current player the result of retrieving their items and mesos from Fredrick
+pub fn fredrick_message(message: FredrickMessage) -> Packet {
+    let mut packet = Packet::new(0x136);
+    packet.write_byte(message as u8);
+    packet
+}
+
+fn write_room_visitors(packet: &mut Packet, visitors: &[RoomVisitor]) {
+    for (slot, data, equipment) in visitors.iter() {
+        packet.write_byte(*slot);
+        write_character_look(packet, data, equipment);
+        packet.write_string(&data.name);
+    }
+
+    packet.write_byte(0xFF);
+}
+
+fn write_player_shop_items(packet: &mut Packet, items: &[sql::PlayerShopItem]) {
+    packet.write_byte(items.len() as u8);
+
+    for item in items.iter() {
+        packet.write_short(item.bundles as i16);
+        packet.write_short(item.amount as i16);
+        packet.write_int(item.price);
+        write_player_shop_item(packet, item, item.amount);
+    }
+}
+
+/// Writes the data of an item listed in a player shop, with the given amount
+fn write_player_shop_item(packet: &mut Packet, item: &sql::PlayerShopItem, amount: i32) {
+    match item.equip.as_ref() {
+        Some(equip) => write_equip_info(packet, item.item_id, None, None, equip),
+        None => write_item_info(
+            packet,
+            item.item_id,
+            None,
+            None,
+            amount,
+            &item.owner,
+            item.flag,
+        ),
+    }
+}
+
+fn write_hired_merchant_box(packet: &mut Packet, shop: ShopBox) {
+    packet.write_byte(5); // room type (hired merchant)
+    packet.write_int(shop.object_id);
+    packet.write_string(shop.title);
+    packet.write_byte((shop.permit_id % 100) as u8);
+    packet.write_byte(shop.characters);
+    packet.write_byte(4); // max characters
+}
+
+/// A character in a minigame room along with their record in the room's minigame
+pub type MinigamePlayer<'a> = (
+    &'a sql::Character,
+    &'a [sql::Equipment],
+    &'a sql::MinigameRecord,
+);
+
+/// Opens a minigame room for the current player, `slot` is their slot in the room (0 for the owner)
+/// `piece` is the omok piece set or the match cards board size
+pub fn minigame_room(
+    game_type: sql::MinigameType,
+    slot: u8,
+    players: &[MinigamePlayer],
+    title: &str,
+    piece: u8,
+) -> Packet {
+    let mut packet = Packet::new(0x13A);
+    packet.write_byte(0x05);
+    packet.write_byte(game_type as u8);
+    packet.write_byte(0);
+    packet.write_byte(slot);
+
+    for (slot, (data, equipment, _)) in players.iter().enumerate() {
+        packet.write_byte(slot as u8);
+        write_character_look(&mut packet, data, equipment);
+        packet.write_string(&data.name);
+    }
+
+    packet.write_byte(0xFF);
+
+    for (slot, (_, _, record)) in players.iter().enumerate() {
+        packet.write_byte(slot as u8);
+        write_minigame_record(&mut packet, record);
+    }
+
+    packet.write_byte(0xFF);
+    packet.write_string(title);
+    packet.write_byte(piece);
+    packet.write_byte(0);
+    packet
+}
+
+/// Shows a character joining the current player's minigame room
+pub fn minigame_visitor_joined(
+    character: &sql::Character,
+    equipment: &[sql::Equipment],
+    record: &sql::MinigameRecord,
+) -> Packet {
+    let mut packet = Packet::new(0x13A);
+    packet.write_byte(0x04);
+    packet.write_byte(1);
+    write_character_look(&mut packet, character, equipment);
+    packet.write_string(&character.name);
+    write_minigame_record(&mut packet, record);
+    packet
+}
+
+/// Shows whether the visitor of the current player's minigame room is ready to play
+pub fn minigame_ready(ready: bool) -> Packet {
+    let mut packet = Packet::new(0x13A);
+    packet.write_byte(if ready { 0x3A } else { 0x3B });
+    packet
+}
+
+/// Starts a game in the current player's minigame room, `first` is the slot of the player that moves first
+/// `cards` is the shuffled board of a match cards game, each pair of cards shares a number
+pub fn minigame_started(first: u8, cards: Option<&[i32]>) -> Packet {
+    let mut packet = Packet::new(0x13A);
+    packet.write_byte(0x3D);
+    packet.write_byte(first);
+
+    if let Some(cards) = cards {
+        packet.write_byte(cards.len() as u8);
+
+        for card in cards.iter() {
+            packet.write_int(*card);
+        }
+    }
+
+    packet
+}
+
+/// Asks the current player if they want to end their minigame in a tie
+pub fn minigame_tie_requested() -> Packet {
+    let mut packet = Packet::new(0x13A);
+    packet.write_byte(0x32);
+    packet
+}
+
+/// Tells the current player that their opponent didn't accept a tie
+pub fn minigame_tie_denied() -> Packet {
+    let mut packet = Packet::new(0x13A);
+    packet.write_byte(0x33);
+    packet
+}
+
+/// Shows the player in `slot` passing their turn in an omok game
+pub fn minigame_turn_skipped(slot: u8) -> Packet {
+    let mut packet = Packet::new(0x13A);
+    packet.write_byte(0x3F);
+    packet.write_byte(slot);
+    packet
+}
+
+/// Shows a piece placed on the omok board
+pub fn omok_piece_placed(x: i32, y: i32, piece: u8) -> Packet {
+    let mut packet = Packet::new(0x13A);
+    packet.write_byte(0x40);
+    packet.write_int(x);
+    packet.write_int(y);
+    packet.write_byte(piece);
+    packet
+}
+
+/// Shows the first card of a turn flipped in a match cards game
+pub fn match_card_flipped(card: u8) -> Packet {
+    let mut packet = Packet::new(0x13A);
+    packet.write_byte(0x44);
+    packet.write_byte(1);
+    packet.write_byte(card);
+    packet
+}
+
+/// Shows the second card of a turn flipped in a match cards game, and whether the two cards matched
+/// `slot` is the slot of the player that flipped the cards
+pub fn match_cards_compared(card: u8, first_card: u8, slot: u8, matched: bool) -> Packet {
+    let mut packet = Packet::new(0x13A);
+    packet.write_byte(0x44);
+    packet.write_byte(0);
+    packet.write_byte(card);
+    packet.write_byte(first_card);
+    packet.write_byte(if matched { slot + 2 } else { slot });
+    packet
+}
+
+/// How a minigame ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MinigameResult {
+    Win = 0,
+    Tie = 1,
+    Forfeit = 2,
+}
+
+/// Shows the result of the game in the current player's minigame room, along with both players' new records
+/// `winner` is the slot of the player that won, it's ignored for ties
+pub fn minigame_result(
+    result: MinigameResult,
+    winner: u8,
+    records: [&sql::MinigameRecord; 2],
+) -> Packet {
+    let mut packet = Packet::new(0x13A);
+    packet.write_byte(0x3E);
+    packet.write_byte(result as u8);
+
+    if result != MinigameResult::Tie {
+        packet.write_byte(winner);
+    }
+
+    for record in records.iter() {
+        write_minigame_record(&mut packet, record);
+    }
+
+    packet
+}
+
+/// A minigame room's box shown in the map
+#[derive(Debug, Clone, Copy)]
+pub struct MinigameBox<'a> {
+    pub object_id: i32,
+    pub game_type: sql::MinigameType,
+    pub title: &'a str,
+    pub has_password: bool,
+    pub piece: u8,
+    pub characters: u8,
+    pub in_progress: bool,
+}
+
+/// Shows or removes the minigame room box above a character for everyone in the map
+pub fn update_minigame_box(character_id: i32, minigame: Option<MinigameBox>) -> Packet {
+    let mut packet = Packet::new(0xA5);
+    packet.write_int(character_id);
+
+    match minigame {
+        Some(minigame) => {
+            packet.write_byte(minigame.game_type as u8);
+            packet.write_int(minigame.object_id);
+            packet.write_string(minigame.title);
+            packet.write_byte(minigame.has_password as u8);
+            packet.write_byte(minigame.piece);
+            packet.write_byte(minigame.characters);
+            packet.write_byte(2); // max characters
+            packet.write_byte(minigame.in_progress as u8);
+        }
+        None => packet.write_byte(0),
+    }
+
+    packet
+}
+
+/// Writes a character's record in a minigame to a packet
+pub fn write_minigame_record(packet: &mut Packet, record: &sql::MinigameRecord) {
+    packet.write_int(record.game_type as i32);
+    packet.write_int(record.wins);
+    packet.write_int(record.ties);
+    packet.write_int(record.losses);
+    packet.write_int(2000); // TODO minigame points
+}
+
+/// Tells the current player's client to reconnect to a channel at the given address
+pub fn change_channel(ip: [u8; 4], port: i32) -> Packet {
+    let mut packet = Packet::new(0x10);
+    packet.write_byte(1);
+    packet.write_bytes(&ip);
+    packet.write_short(port as i16);
+    packet
+}
+
+/// Shows the current player their account's NX Credit and Maple Points
+pub fn cash_balance(nx_credit: i32, maple_points: i32) -> Packet {
+    let mut packet = Packet::new(0x144);
+    packet.write_int(nx_credit);
+    packet.write_int(maple_points);
+    packet.write_int(0); // TODO nx prepaid
+    packet
+}
+
+/// Writes an item in a cash shop locker to a packet
+fn write_cash_item(packet: &mut Packet, item: &sql::CashItem) {
+    packet.write_long(item.id);
+    packet.write_int(item.account_id);
+    packet.write_int(0);
+    packet.write_int(item.item_id);
+    packet.write_int(item.commodity_id);
+    packet.write_short(item.amount as i16);
+    write_padded_string(packet, &item.gift_from, 13);
+    write_expiration(packet, item.expires_at);
+    packet.write_long(0);
+}
+
+/// Shows the current player the items in their cash shop locker
+pub fn cash_inventory(items: &[sql::CashItem], storage_slots: i32) -> Packet {
+    let mut packet = Packet::new(0x145);
+    packet.write_byte(0x4B);
+    packet.write_short(items.len() as i16);
+
+    for item in items.iter() {
+        write_cash_item(&mut packet, item);
+    }
+
+    packet.write_short(storage_slots as i16);
+    packet.write_short(3); // character slots
+    packet
+}
+
+/// Shows the current player the gifts that were put in their cash shop locker
+pub fn cash_gifts(gifts: &[(sql::CashGift, sql::CashItem)]) -> Packet {
+    let mut packet = Packet::new(0x145);
+    packet.write_byte(0x4D);
+    packet.write_short(gifts.len() as i16);
+
+    for (gift, item) in gifts.iter() {
+        packet.write_long(item.id);
+        packet.write_int(item.item_id);
+        write_padded_string(&mut packet, &gift.sender, 13);
+        write_padded_string(&mut packet, &gift.message, 73);
+    }
+
+    packet
+}
+
+/// Shows the current player their wishlist, `updated` is set after they change it
+pub fn wishlist(wishlist: &sql::Wishlist, updated: bool) -> Packet {
+    let mut packet = Packet::new(0x145);
+    packet.write_byte(if updated { 0x55 } else { 0x4F });
+
+    for i in 0..10 {
+        packet.write_int(wishlist.commodity_ids.get(i).copied().unwrap_or(0));
+    }
+
+    packet
+}
+
+/// Shows the current player an item they bought, now in their cash shop locker
+pub fn cash_item_bought(item: &sql::CashItem) -> Packet {
+    let mut packet = Packet::new(0x145);
+    packet.write_byte(0x57);
+    write_cash_item(&mut packet, item);
+    packet
+}
+
+/// Shows the current player the contents of a package they bought, now in their cash shop locker
+pub fn cash_package_bought(items: &[sql::CashItem]) -> Packet {
+    let mut packet = Packet::new(0x145);
+    packet.write_byte(0x89);
+    packet.write_byte(items.len() as u8);
+
+    for item in items.iter() {
+        write_cash_item(&mut packet, item);
+    }
+
+    packet.write_short(0);
+    packet
+}
+
+/// Tells the current player their gift was sent
+pub fn cash_gift_sent(recipient: &str, commodity: &nx::Commodity) -> Packet {
+    let mut packet = Packet::new(0x145);
+    packet.write_byte(0x5E);
+    packet.write_string(recipient);
+    packet.write_int(commodity.item_id);
+    packet.write_short(commodity.count as i16);
+    packet.write_int(commodity.price);
+    packet
+}
+
+/// Reasons a cash shop operation can fail
+pub enum CashShopError {
+    Unknown = 0x00,
+    NotEnoughCash = 0xA5,
+    GiftToOwnAccount = 0xA8,
+    WrongCharacterName = 0xA9,
+    WrongGender = 0xAA,
+    InventoryFull = 0xBB,
+}
+
+/// Shows the current player why their cash shop operation failed
+pub fn cash_shop_error(error: CashShopError) -> Packet {
+    let mut packet = Packet::new(0x145);
+    packet.write_byte(0x5C);
+    packet.write_byte(error as u8);
+    packet
+}
+
+/// Moves an item from the current player's cash shop locker into their inventory
+/// `pet` is the pet's data if the item is a pet
+pub fn cash_item_taken(item: &sql::Item, pet: Option<&sql::Pet>) -> Packet {
+    let mut packet = Packet::new(0x145);
+    packet.write_byte(0x68);
+    packet.write_short((item.position + 1) as i16);
+
+    match pet {
+        Some(pet) => write_pet_data(&mut packet, item, pet),
+        None => write_item_data(&mut packet, item),
+    }
+
+    packet
+}
+
+/// Moves an item from the current player's inventory into their cash shop locker
+pub fn cash_item_stored(item: &sql::CashItem) -> Packet {
+    let mut packet = Packet::new(0x145);
+    packet.write_byte(0x6A);
+    write_cash_item(&mut packet, item);
+    packet
+}
+
+/// Shows the current player their account's NX Credit and Maple Points in the MTS
+pub fn mts_balance(nx_credit: i32, maple_points: i32) -> Packet {
+    let mut packet = Packet::new(0x15B);
+    packet.write_int(nx_credit);
+    packet.write_int(maple_points);
+    packet
+}
+
+/// Writes an item listed in the MTS to a packet
+fn write_mts_listing(packet: &mut Packet, listing: &sql::MtsListing) {
+    match listing.equip.as_ref() {
+        Some(equip) => write_equip_info(
+            packet,
+            listing.item_id,
+            listing.cash_id,
+            listing.expires_at,
+            equip,
+        ),
+        None => write_item_info(
+            packet,
+            listing.item_id,
+            listing.cash_id,
+            listing.expires_at,
+            listing.amount,
+            &listing.owner,
+            listing.flag,
+        ),
+    }
+    packet.write_int(listing.id);
+    packet.write_int(listing.get_commission());
+    packet.write_int(listing.price);
+    packet.write_int(0);
+    packet.write_long(to_filetime(listing.ends_at));
+    packet.write_string(&listing.seller_name); // account name, but the client shows it as the seller
+    packet.write_string(&listing.seller_name);
+    packet.write_bytes(&[0; 28]);
+}
+
+/// Shows the current player a page of MTS listings
+/// `total` is the number of listings across every page
+pub fn mts_listings(
+    listings: &[sql::MtsListing],
+    total: i32,
+    tab: i32,
+    category: i32,
+    page: i32,
+) -> Packet {
+    let mut packet = Packet::new(0x15C);
+    packet.write_byte(0x15);
+    packet.write_int(total);
+    packet.write_int(listings.len() as i32);
+    packet.write_int(tab);
+    packet.write_int(category);
+    packet.write_int(page);
+    packet.write_byte(1);
+    packet.write_byte(1);
+
+    for listing in listings {
+        write_mts_listing(&mut packet, listing);
+    }
+
+    packet.write_byte(1);
+    packet
+}
+
+/// Shows the current player the items they're selling in the MTS that haven't been sold yet
+pub fn mts_not_yet_sold(listings: &[sql::MtsListing]) -> Packet {
+    let mut packet = Packet::new(0x15C);
+    packet.write_byte(0x23);
+    packet.write_int(listings.len() as i32);
+
+    for listing in listings {
+        write_mts_listing(&mut packet, listing);
+    }
+
+    packet
+}
+
+/// Shows the current player the items in their MTS transfer inventory
+pub fn mts_transfer_inventory(listings: &[sql::MtsListing]) -> Packet {
+    let mut packet = Packet::new(0x15C);
+    packet.write_byte(0x21);
+    packet.write_int(listings.len() as i32);
+
+    for listing in listings {
+        write_mts_listing(&mut packet, listing);
+    }
+
+    packet
+}
+
+/// MTS operations that succeeded
+pub enum MtsResult {
+    Listed = 0x1D,
+    WantedAdded = 0x29,
+    WantedRemoved = 0x2B,
+    Bought = 0x33,
+    Cancelled = 0x3F,
+}
+
+/// Tells the current player their MTS operation succeeded
+pub fn mts_result(result: MtsResult) -> Packet {
+    let mut packet = Packet::new(0x15C);
+    packet.write_byte(result as u8);
+    packet
+}
+
+/// Tells the current player they couldn't buy an item from the MTS
+pub fn mts_buy_failed() -> Packet {
+    let mut packet = Packet::new(0x15C);
+    packet.write_byte(0x34);
+    packet.write_byte(0x42);
+    packet
+}
+
+/// Tells the current player an item was moved from their MTS transfer inventory into their inventory
+pub fn mts_item_transferred(item: &sql::Item) -> Packet {
+    let mut packet = Packet::new(0x15C);
+    packet.write_byte(0x27);
+    packet.write_int(item.amount);
+    packet.write_int(item.position + 1);
+    packet
+}
+
+/// Writes a summoned pet to a packet
+fn write_pet(packet: &mut Packet, pet: &maple::Pet) {
+    packet.write_int(pet.data.item_id);
+    packet.write_string(&pet.data.name);
+    packet.write_long(pet.data.id);
+    packet.write_position(pet.pos);
+    packet.write_byte(pet.stance);
+    packet.write_short(0); // TODO foothold
+}
+
+/// Writes a pet item's data to a packet
+pub fn write_pet_item(packet: &mut Packet, item: &sql::Item, pet: &sql::Pet) {
+    // Positions are 0-indexed in db, client expects 1-indexed
+    packet.write_byte((item.position + 1) as u8);
+    write_pet_data(packet, item, pet);
+}
+
+/// Writes a pet item's data without its position to a packet
+fn write_pet_data(packet: &mut Packet, item: &sql::Item, pet: &sql::Pet) {
+    packet.write_byte(3); // item type (pet)
+    packet.write_int(item.item_id);
+    write_cash_id(packet, Some(pet.id));
+    write_expiration(packet, item.expires_at);
+
+    let mut fixed_name = pet.name.clone();
+
+    for _ in fixed_name.len()..13 {
+        fixed_name.push('\0');
+    }
+
+    packet.write_fixed_string(&fixed_name);
+    packet.write_byte(pet.level as u8);
+    packet.write_short(pet.tameness as i16);
+    packet.write_byte(pet.fullness as u8);
+    write_expiration(packet, item.expires_at);
+    packet.write_short(0);
+    packet.write_short(pet.flag as i16);
+    packet.write_int(18000); // remaining life
+    packet.write_short(0);
+}
+
+/// Updates a pet item in the current player's inventory, showing the pet's new level, tameness and fullness
+pub fn update_pet_item(item: &sql::Item, pet: &sql::Pet) -> Packet {
+    let mut packet = Packet::new(0x1D);
+    packet.write_byte(0);
+    packet.write_byte(1);
+    packet.write_byte(0);
+    packet.write_byte(InventoryType::Cash as u8 + 1);
+    packet.write_short((item.position + 1) as i16);
+    write_pet_data(&mut packet, item, pet);
+    packet
+}
+
+/// Updates the pets the current player has summoned
+pub fn update_pet_stats(pet_ids: &[i64]) -> Packet {
+    let mut packet = Packet::new(0x1F);
+    packet.write_byte(0);
+    packet.write_int(0x180008); // pet stat mask
+
+    for i in 0..3 {
+        packet.write_long(pet_ids.get(i).copied().unwrap_or(0));
+    }
+
+    packet.write_byte(0);
+    packet
+}
+
+/// Spawns one of a character's pets for everyone in the map
+/// `slot` is the pet's slot (0 to 2)
+pub fn spawn_pet(character_id: i32, slot: u8, pet: &maple::Pet) -> Packet {
+    let mut packet = Packet::new(0xA8);
+    packet.write_int(character_id);
+    packet.write_byte(slot);
+    packet.write_byte(1);
+    packet.write_byte(0);
+    write_pet(&mut packet, pet);
+    packet
+}
+
+/// Removes one of a character's pets for everyone in the map, `hungry` shows it left because it was hungry
+pub fn remove_pet(character_id: i32, slot: u8, hungry: bool) -> Packet {
+    let mut packet = Packet::new(0xA8);
+    packet.write_int(character_id);
+    packet.write_byte(slot);
+    packet.write_byte(0);
+    packet.write_byte(hungry as u8);
+    packet
+}
+
+/// Moves one of a character's pets for everyone else in the map
+pub fn move_pet(character_id: i32, slot: u8, movement_data: &Packet) -> Packet {
+    let mut packet = Packet::new(0xAA);
+    packet.write_int(character_id);
+    packet.write_byte(slot);
+    packet.write_bytes(&movement_data.bytes);
+    packet
+}
+
+/// Shows one of a character's pets talking for everyone in the map
+pub fn pet_chat(character_id: i32, slot: u8, action: u8, text: &str) -> Packet {
+    let mut packet = Packet::new(0xAB);
+    packet.write_int(character_id);
+    packet.write_byte(slot);
+    packet.write_byte(0);
+    packet.write_byte(action);
+    packet.write_string(text);
+    packet.write_byte(0);
+    packet
+}
+
+/// Shows how one of a character's pets responded to a command or being fed, for everyone in the map
+pub fn pet_command_response(
+    character_id: i32,
+    slot: u8,
+    fed: bool,
+    command: u8,
+    success: bool,
+) -> Packet {
+    let mut packet = Packet::new(0xAE);
+    packet.write_int(character_id);
+    packet.write_byte(slot);
+    packet.write_byte(fed as u8);
+    packet.write_byte(command);
+    packet.write_byte(success as u8);
+    packet.write_byte(0);
+    packet
+}
+
+/// Shows the current player's pet leveling up
+pub fn show_pet_level_up(slot: u8) -> Packet {
+    let mut packet = Packet::new(0xCE);
+    packet.write_byte(4);
+    packet.write_byte(0);
+    packet.write_byte(slot);
+    packet
+}
+
+/// Shows a character's pet leveling up for everyone else in the map
+pub fn show_foreign_pet_level_up(character_id: i32, slot: u8) -> Packet {
+    let mut packet = Packet::new(0xC6);
+    packet.write_int(character_id);
+    packet.write_byte(4);
+    packet.write_byte(0);
+    packet.write_byte(slot);
+    packet
+}
@@ -16,6 +16,8 @@ pub struct Account {
     pub accepted_tos: bool,
     pub last_login: Option<DateTime<Utc>>,
     pub gender: i32,
+    pub nx_credit: i32,
+    pub maple_points: i32,
 }
 
 impl Account {
@@ -54,6 +56,17 @@ impl Account {
         Ok(())
     }
 
+    /// Bans or unbans an account
+    pub async fn update_banned(account_id: i32, banned: bool, db: &Db) -> anyhow::Result<()> {
+        sqlx::query("UPDATE accounts SET banned = ? WHERE id = ?")
+            .bind(banned)
+            .bind(account_id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+
     /// Updates an account's PIC
     pub async fn update_pic(account_id: i32, pic: &String, db: &Db) -> anyhow::Result<()> {
         sqlx::query("UPDATE accounts SET pic = ? WHERE id = ?")
@@ -0,0 +1,228 @@
+use crate::Db;
+use sqlx::FromRow;
+
+/// The alliance rank of the alliance's leader
+pub const ALLIANCE_LEADER_RANK: i32 = 1;
+
+/// The alliance rank of the guild masters in an alliance
+pub const GUILD_MASTER_ALLIANCE_RANK: i32 = 2;
+
+/// The lowest alliance rank, given to guild members and characters outside of alliances
+pub const LOWEST_ALLIANCE_RANK: i32 = 5;
+
+/// The most guilds an alliance's capacity can be increased to
+pub const MAX_ALLIANCE_CAPACITY: i32 = 5;
+
+#[derive(FromRow, Debug, Clone)]
+pub struct Alliance {
+    pub id: i32,
+    pub world_id: i32,
+    pub name: String,
+    pub rank1_title: String,
+    pub rank2_title: String,
+    pub rank3_title: String,
+    pub rank4_title: String,
+    pub rank5_title: String,
+    pub capacity: i32,
+    pub notice: String,
+}
+
+impl Alliance {
+    /// Loads an alliance by id if it exists
+    pub async fn load_optional(id: i32, db: &Db) -> anyhow::Result<Option<Self>> {
+        let alliance = sqlx::query_as::<_, Self>("SELECT * FROM alliances WHERE id = ?")
+            .bind(id)
+            .fetch_optional(db)
+            .await?;
+
+        Ok(alliance)
+    }
+
+    /// Checks if an alliance with the given name already exists in the world
+    pub async fn name_exists(name: &str, world_id: i32, db: &Db) -> anyhow::Result<bool> {
+        let exists: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM alliances WHERE name = ? AND world_id = ?)",
+        )
+        .bind(name)
+        .bind(world_id)
+        .fetch_one(db)
+        .await?;
+
+        Ok(exists)
+    }
+
+    /// Creates an alliance of the given guilds, led by the given character
+    /// Returns the new alliance's id
+    pub async fn create(
+        world_id: i32,
+        name: &str,
+        leader_id: i32,
+        guild_ids: &[i32],
+        db: &Db,
+    ) -> anyhow::Result<i32> {
+        let mut tx = db.begin().await?;
+        let capacity = (guild_ids.len() as i32).max(2);
+
+        let id = sqlx::query("INSERT INTO alliances (world_id, name, capacity) VALUES (?, ?, ?)")
+            .bind(world_id)
+            .bind(name)
+            .bind(capacity)
+            .execute(&mut *tx)
+            .await?
+            .last_insert_id() as i32;
+
+        for guild_id in guild_ids.iter() {
+            sqlx::query("UPDATE guilds SET alliance_id = ? WHERE id = ?")
+                .bind(id)
+                .bind(guild_id)
+                .execute(&mut *tx)
+                .await?;
+
+            sqlx::query(
+                "UPDATE characters SET alliance_rank = ? WHERE guild = ? AND guild_rank = 1",
+            )
+            .bind(GUILD_MASTER_ALLIANCE_RANK)
+            .bind(guild_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query("UPDATE characters SET alliance_rank = ? WHERE id = ?")
+            .bind(ALLIANCE_LEADER_RANK)
+            .bind(leader_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(id)
+    }
+
+    /// Gets the alliance's rank titles, from the leader's rank to the lowest rank
+    pub fn rank_titles(&self) -> [&str; 5] {
+        [
+            &self.rank1_title,
+            &self.rank2_title,
+            &self.rank3_title,
+            &self.rank4_title,
+            &self.rank5_title,
+        ]
+    }
+
+    /// Updates the alliance's rank titles
+    pub async fn update_rank_titles(&self, db: &Db) -> anyhow::Result<()> {
+        sqlx::query(
+            "UPDATE alliances SET rank1_title = ?, rank2_title = ?, rank3_title = ?,
+            rank4_title = ?, rank5_title = ? WHERE id = ?",
+        )
+        .bind(&self.rank1_title)
+        .bind(&self.rank2_title)
+        .bind(&self.rank3_title)
+        .bind(&self.rank4_title)
+        .bind(&self.rank5_title)
+        .bind(self.id)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Updates the alliance's notice
+    pub async fn update_notice(&self, db: &Db) -> anyhow::Result<()> {
+        sqlx::query("UPDATE alliances SET notice = ? WHERE id = ?")
+            .bind(&self.notice)
+            .bind(self.id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Updates the alliance's capacity
+    pub async fn update_capacity(&self, db: &Db) -> anyhow::Result<()> {
+        sqlx::query("UPDATE alliances SET capacity = ? WHERE id = ?")
+            .bind(self.capacity)
+            .bind(self.id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Adds a guild to the alliance, its master joins at the guild master rank
+    pub async fn add_guild(&self, guild_id: i32, db: &Db) -> anyhow::Result<()> {
+        let mut tx = db.begin().await?;
+
+        sqlx::query("UPDATE guilds SET alliance_id = ? WHERE id = ?")
+            .bind(self.id)
+            .bind(guild_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("UPDATE characters SET alliance_rank = ? WHERE guild = ? AND guild_rank = 1")
+            .bind(GUILD_MASTER_ALLIANCE_RANK)
+            .bind(guild_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Removes a guild from the alliance, resetting its members' alliance ranks
+    pub async fn remove_guild(&self, guild_id: i32, db: &Db) -> anyhow::Result<()> {
+        let mut tx = db.begin().await?;
+
+        sqlx::query("UPDATE guilds SET alliance_id = NULL WHERE id = ? AND alliance_id = ?")
+            .bind(guild_id)
+            .bind(self.id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("UPDATE characters SET alliance_rank = ? WHERE guild = ?")
+            .bind(LOWEST_ALLIANCE_RANK)
+            .bind(guild_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Changes a character's alliance rank
+    pub async fn update_member_rank(character_id: i32, rank: i32, db: &Db) -> anyhow::Result<()> {
+        sqlx::query("UPDATE characters SET alliance_rank = ? WHERE id = ?")
+            .bind(rank)
+            .bind(character_id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Removes every guild from the alliance and deletes it
+    pub async fn disband(&self, db: &Db) -> anyhow::Result<()> {
+        let mut tx = db.begin().await?;
+
+        sqlx::query(
+            "UPDATE characters c JOIN guilds g ON g.id = c.guild SET c.alliance_rank = ?
+            WHERE g.alliance_id = ?",
+        )
+        .bind(LOWEST_ALLIANCE_RANK)
+        .bind(self.id)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE guilds SET alliance_id = NULL WHERE alliance_id = ?")
+            .bind(self.id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM alliances WHERE id = ?")
+            .bind(self.id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+use crate::Db;
+use sqlx::Row;
+
+/// A record of a character entering a boss's map with an expedition, used to limit how often they can fight it
+pub struct BossEntry;
+
+impl BossEntry {
+    /// Gets the number of times a character has entered a boss's map today
+    pub async fn get_count_today(character_id: i32, boss: &str, db: &Db) -> anyhow::Result<i64> {
+        let count: i64 = sqlx::query(
+            "SELECT COUNT(*) AS count FROM boss_entries
+            WHERE character_id = ? AND boss = ? AND entered_at >= CURDATE()",
+        )
+        .bind(character_id)
+        .bind(boss)
+        .fetch_one(db)
+        .await?
+        .get("count");
+
+        Ok(count)
+    }
+
+    /// Records a character entering a boss's map
+    pub async fn insert(character_id: i32, boss: &str, db: &Db) -> anyhow::Result<()> {
+        sqlx::query("INSERT INTO boss_entries (character_id, boss) VALUES (?, ?)")
+            .bind(character_id)
+            .bind(boss)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,153 @@
+use crate::Db;
+use sqlx::FromRow;
+
+/// The group buddies are added to when they accept a buddy request
+pub const DEFAULT_GROUP: &str = "Default Group";
+
+/// A character on another character's buddy list
+/// Pending buddies sent the character a buddy request that they haven't accepted yet
+#[derive(FromRow, Debug, Clone)]
+pub struct Buddy {
+    pub character_id: i32,
+    pub buddy_id: i32,
+    pub group_name: String,
+    pub pending: bool,
+}
+
+impl Buddy {
+    /// Loads a character's buddy entry for another character if it exists
+    pub async fn load_optional(
+        character_id: i32,
+        buddy_id: i32,
+        db: &Db,
+    ) -> anyhow::Result<Option<Self>> {
+        let buddy = sqlx::query_as::<_, Self>(
+            "SELECT * FROM buddies WHERE character_id = ? AND buddy_id = ?",
+        )
+        .bind(character_id)
+        .bind(buddy_id)
+        .fetch_optional(db)
+        .await?;
+
+        Ok(buddy)
+    }
+
+    /// Loads the ids of the characters on a character's buddy list that also have them as a buddy
+    pub async fn load_mutual_ids(character_id: i32, db: &Db) -> anyhow::Result<Vec<i32>> {
+        let ids = sqlx::query_scalar(
+            "SELECT b.buddy_id FROM buddies b
+            JOIN buddies m ON m.character_id = b.buddy_id AND m.buddy_id = b.character_id
+            WHERE b.character_id = ? AND b.pending = 0 AND m.pending = 0",
+        )
+        .bind(character_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(ids)
+    }
+
+    /// Gets the number of accepted buddies on a character's buddy list
+    pub async fn get_count(character_id: i32, db: &Db) -> anyhow::Result<i32> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM buddies WHERE character_id = ? AND pending = 0",
+        )
+        .bind(character_id)
+        .fetch_one(db)
+        .await?;
+
+        Ok(count as i32)
+    }
+
+    /// Inserts the buddy, keeping the existing entry if there already is one
+    pub async fn insert(&self, db: &Db) -> anyhow::Result<()> {
+        sqlx::query(
+            "INSERT IGNORE INTO buddies (character_id, buddy_id, group_name, pending)
+            VALUES (?, ?, ?, ?)",
+        )
+        .bind(self.character_id)
+        .bind(self.buddy_id)
+        .bind(&self.group_name)
+        .bind(self.pending)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Updates the buddy's group and pending status
+    pub async fn update(&self, db: &Db) -> anyhow::Result<()> {
+        sqlx::query(
+            "UPDATE buddies SET group_name = ?, pending = ? WHERE character_id = ? AND buddy_id = ?",
+        )
+        .bind(&self.group_name)
+        .bind(self.pending)
+        .bind(self.character_id)
+        .bind(self.buddy_id)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Removes the buddy from the character's buddy list
+    pub async fn delete(&self, db: &Db) -> anyhow::Result<()> {
+        sqlx::query("DELETE FROM buddies WHERE character_id = ? AND buddy_id = ?")
+            .bind(self.character_id)
+            .bind(self.buddy_id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// A buddy's info shown in the buddy list
+#[derive(FromRow, Debug, Clone)]
+pub struct BuddyEntry {
+    pub id: i32,
+    pub name: String,
+    pub group_name: String,
+    /// None if the buddy is offline, or hasn't added the character back
+    pub channel_id: Option<i32>,
+}
+
+impl BuddyEntry {
+    /// Loads a character's accepted buddies, along with their channel if they're online
+    pub async fn load_all(character_id: i32, db: &Db) -> anyhow::Result<Vec<Self>> {
+        let buddies = sqlx::query_as::<_, Self>(
+            "SELECT c.id, c.name, b.group_name, IF(m.pending = 0, o.channel_id, NULL) AS channel_id
+            FROM buddies b
+            JOIN characters c ON c.id = b.buddy_id
+            LEFT JOIN buddies m ON m.character_id = b.buddy_id AND m.buddy_id = b.character_id
+            LEFT JOIN online_characters o ON o.character_id = b.buddy_id
+            WHERE b.character_id = ? AND b.pending = 0 ORDER BY c.name",
+        )
+        .bind(character_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(buddies)
+    }
+}
+
+/// A buddy request a character hasn't answered yet
+#[derive(FromRow, Debug, Clone)]
+pub struct BuddyRequest {
+    pub id: i32,
+    pub name: String,
+}
+
+impl BuddyRequest {
+    /// Loads the next buddy request sent to a character if there is one
+    pub async fn load_next(character_id: i32, db: &Db) -> anyhow::Result<Option<Self>> {
+        let request = sqlx::query_as::<_, Self>(
+            "SELECT c.id, c.name FROM buddies b JOIN characters c ON c.id = b.buddy_id
+            WHERE b.character_id = ? AND b.pending = 1 ORDER BY c.id LIMIT 1",
+        )
+        .bind(character_id)
+        .fetch_optional(db)
+        .await?;
+
+        Ok(request)
+    }
+}
@@ -0,0 +1,427 @@
+use super::{item::InventoryType, Equipment, Item};
+use crate::Db;
+use anyhow::anyhow;
+use sqlx::FromRow;
+
+/// A day in milliseconds, cash shop items last for a number of days
+pub const DAY: i64 = 24 * 60 * 60 * 1000;
+
+/// The balances cash shop items can be paid with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CashCurrency {
+    NxCredit = 1,
+    MaplePoints = 2,
+}
+
+impl CashCurrency {
+    pub fn from_i32(value: i32) -> Option<Self> {
+        match value {
+            1 => Some(Self::NxCredit),
+            2 => Some(Self::MaplePoints),
+            _ => None,
+        }
+    }
+
+    /// Gets the accounts column holding the balance
+    fn column(&self) -> &'static str {
+        match self {
+            Self::NxCredit => "nx_credit",
+            Self::MaplePoints => "maple_points",
+        }
+    }
+}
+
+/// Takes the price of a cash shop purchase from an account's balance
+/// Returns false (changing nothing) if the account can't afford it
+pub(crate) async fn pay(
+    account_id: i32,
+    currency: CashCurrency,
+    price: i32,
+    tx: &mut sqlx::Transaction<'_, sqlx::MySql>,
+) -> anyhow::Result<bool> {
+    let column = currency.column();
+    let query = format!(
+        "UPDATE accounts SET {} = {} - ? WHERE id = ? AND {} >= ?",
+        column, column, column
+    );
+
+    let updated = sqlx::query(&query)
+        .bind(price)
+        .bind(account_id)
+        .bind(price)
+        .execute(&mut **tx)
+        .await?
+        .rows_affected();
+
+    Ok(updated == 1)
+}
+
+/// An item in an account's cash shop locker in a world, shared by all of the account's characters in that world
+/// The item's id is its unique serial number, which it keeps while it's in a character's inventory
+#[derive(FromRow, Debug, Clone)]
+pub struct CashItem {
+    pub id: i64,
+    pub account_id: i32,
+    pub world_id: i32,
+    pub item_id: i32,
+    /// The serial number of the commodity the item was bought as
+    pub commodity_id: i32,
+    pub amount: i32,
+    /// The name of the character who gifted the item, empty if it was bought by the account
+    pub gift_from: String,
+    /// When the item expires, as a unix timestamp in milliseconds, None if it's permanent
+    pub expires_at: Option<i64>,
+    /// The id of the equipment holding the item's stats, None if it isn't equipment
+    pub equipment_id: Option<i32>,
+    /// The equipment holding the item's stats, loaded along with the item
+    #[sqlx(skip)]
+    pub equip: Option<Equipment>,
+}
+
+/// Inserts an item into an account's locker, creating its stats first if it's equipment
+/// Returns the item with its serial number
+async fn insert_cash_item(
+    mut item: CashItem,
+    tx: &mut sqlx::Transaction<'_, sqlx::MySql>,
+) -> anyhow::Result<CashItem> {
+    if InventoryType::from_item_id(item.item_id) == Some(InventoryType::Equip) {
+        let equip = Equipment::create(item.item_id, &mut **tx).await?;
+        item.equipment_id = Some(equip.id);
+        item.equip = Some(equip);
+    }
+
+    item.id = sqlx::query(
+        "INSERT INTO cash_items (account_id, world_id, item_id, commodity_id, amount, gift_from, expires_at, equipment_id)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(item.account_id)
+    .bind(item.world_id)
+    .bind(item.item_id)
+    .bind(item.commodity_id)
+    .bind(item.amount)
+    .bind(&item.gift_from)
+    .bind(item.expires_at)
+    .bind(item.equipment_id)
+    .execute(&mut **tx)
+    .await?
+    .last_insert_id() as i64;
+
+    Ok(item)
+}
+
+impl CashItem {
+    /// Loads all of the items in an account's locker in a world, in the order they were added
+    pub async fn load_all(account_id: i32, world_id: i32, db: &Db) -> anyhow::Result<Vec<Self>> {
+        let mut items = sqlx::query_as::<_, Self>(
+            "SELECT * FROM cash_items WHERE account_id = ? AND world_id = ? ORDER BY id",
+        )
+        .bind(account_id)
+        .bind(world_id)
+        .fetch_all(db)
+        .await?;
+
+        for item in items.iter_mut() {
+            item.equip = Equipment::load_optional(item.equipment_id, db).await?;
+        }
+
+        Ok(items)
+    }
+
+    /// Deletes all of the items in an account's locker in a world that expired before the given time, along with their stats
+    pub async fn delete_expired(
+        account_id: i32,
+        world_id: i32,
+        now: i64,
+        db: &Db,
+    ) -> anyhow::Result<()> {
+        sqlx::query(
+            "DELETE cash_items, equipment FROM cash_items LEFT JOIN equipment ON equipment.id = cash_items.equipment_id
+            WHERE cash_items.account_id = ? AND cash_items.world_id = ? AND cash_items.expires_at <= ?",
+        )
+        .bind(account_id)
+        .bind(world_id)
+        .bind(now)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Buys the items (a single commodity, or the contents of a package) into their account's locker,
+    /// paying for them in one transaction
+    /// Returns the bought items with their serial numbers, or None (changing nothing) if the account can't afford them
+    pub async fn buy(
+        items: Vec<Self>,
+        account_id: i32,
+        currency: CashCurrency,
+        price: i32,
+        db: &Db,
+    ) -> anyhow::Result<Option<Vec<Self>>> {
+        let mut tx = db.begin().await?;
+
+        if !pay(account_id, currency, price, &mut tx).await? {
+            return Ok(None);
+        }
+
+        let mut bought = Vec::new();
+
+        for item in items {
+            bought.push(insert_cash_item(item, &mut tx).await?);
+        }
+
+        tx.commit().await?;
+        Ok(Some(bought))
+    }
+
+    /// Moves the item out of the locker into a character's inventory in one transaction
+    /// Returns the item as it's now stored in the inventory, or None (changing nothing) if it was already taken out
+    pub async fn take(
+        &self,
+        character_id: i32,
+        position: i32,
+        db: &Db,
+    ) -> anyhow::Result<Option<Item>> {
+        let inventory_type = InventoryType::from_item_id(self.item_id)
+            .ok_or_else(|| anyhow!("Item {} has no inventory", self.item_id))?;
+
+        let mut tx = db.begin().await?;
+
+        let deleted = sqlx::query("DELETE FROM cash_items WHERE id = ?")
+            .bind(self.id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+        if deleted != 1 {
+            return Ok(None);
+        }
+
+        let mut item = Item {
+            id: 0,
+            item_id: self.item_id,
+            character_id,
+            inventory_type,
+            position,
+            amount: self.amount,
+            owner: String::new(),
+            flag: 0,
+            cash_id: Some(self.id),
+            expires_at: self.expires_at,
+            equipment_id: self.equipment_id,
+            equip: self.equip.clone(),
+        };
+
+        item.insert(&mut *tx).await?;
+
+        tx.commit().await?;
+        Ok(Some(item))
+    }
+
+    /// Moves an item bought from the cash shop out of a character's inventory into a locker in one transaction
+    /// The item keeps its serial number
+    /// Returns the stored item, or None (changing nothing) if the character no longer has the item
+    pub async fn store(
+        item: &Item,
+        account_id: i32,
+        world_id: i32,
+        commodity_id: i32,
+        db: &Db,
+    ) -> anyhow::Result<Option<Self>> {
+        let cash_id = item
+            .cash_id
+            .ok_or_else(|| anyhow!("Item {} wasn't bought from the cash shop", item.id))?;
+
+        let mut tx = db.begin().await?;
+
+        let deleted = sqlx::query("DELETE FROM items WHERE id = ? AND character_id = ?")
+            .bind(item.id)
+            .bind(item.character_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+        if deleted != 1 {
+            return Ok(None);
+        }
+
+        let stored = Self {
+            id: cash_id,
+            account_id,
+            world_id,
+            item_id: item.item_id,
+            commodity_id,
+            amount: item.amount,
+            gift_from: String::new(),
+            expires_at: item.expires_at,
+            equipment_id: item.equipment_id,
+            equip: item.equip.clone(),
+        };
+
+        sqlx::query(
+            "INSERT INTO cash_items
+            (id, account_id, world_id, item_id, commodity_id, amount, gift_from, expires_at, equipment_id)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(stored.id)
+        .bind(stored.account_id)
+        .bind(stored.world_id)
+        .bind(stored.item_id)
+        .bind(stored.commodity_id)
+        .bind(stored.amount)
+        .bind(&stored.gift_from)
+        .bind(stored.expires_at)
+        .bind(stored.equipment_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(Some(stored))
+    }
+}
+
+/// A cash shop item gifted to a character, waiting to be put in their locker the next time they enter the cash shop
+#[derive(FromRow, Debug, Clone)]
+pub struct CashGift {
+    pub id: i32,
+    pub character_id: i32,
+    pub item_id: i32,
+    pub commodity_id: i32,
+    pub amount: i32,
+    pub sender: String,
+    pub message: String,
+    /// The number of days the item lasts once it's claimed, 0 if it's permanent
+    pub period: i32,
+}
+
+impl CashGift {
+    /// Sends the gifts (a single commodity, or the contents of a package),
+    /// paying for them with the sending account's NX Credit in one transaction
+    /// Returns false (changing nothing) if the account can't afford them
+    pub async fn send(
+        gifts: &[Self],
+        account_id: i32,
+        price: i32,
+        db: &Db,
+    ) -> anyhow::Result<bool> {
+        let mut tx = db.begin().await?;
+
+        if !pay(account_id, CashCurrency::NxCredit, price, &mut tx).await? {
+            return Ok(false);
+        }
+
+        for gift in gifts.iter() {
+            sqlx::query(
+                "INSERT INTO cash_gifts (character_id, item_id, commodity_id, amount, sender, message, period)
+                VALUES (?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(gift.character_id)
+            .bind(gift.item_id)
+            .bind(gift.commodity_id)
+            .bind(gift.amount)
+            .bind(&gift.sender)
+            .bind(&gift.message)
+            .bind(gift.period)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Moves every gift sent to a character into their account's locker in one transaction
+    /// Timed gifts start expiring from `now`
+    /// Returns the claimed gifts along with the locker items they became
+    pub async fn claim_all(
+        character_id: i32,
+        account_id: i32,
+        world_id: i32,
+        now: i64,
+        db: &Db,
+    ) -> anyhow::Result<Vec<(Self, CashItem)>> {
+        let mut tx = db.begin().await?;
+
+        let gifts = sqlx::query_as::<_, Self>(
+            "SELECT * FROM cash_gifts WHERE character_id = ? ORDER BY id FOR UPDATE",
+        )
+        .bind(character_id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let mut claimed = Vec::new();
+
+        for gift in gifts {
+            let item = CashItem {
+                id: 0,
+                account_id,
+                world_id,
+                item_id: gift.item_id,
+                commodity_id: gift.commodity_id,
+                amount: gift.amount,
+                gift_from: gift.sender.clone(),
+                expires_at: (gift.period > 0).then(|| now + gift.period as i64 * DAY),
+                equipment_id: None,
+                equip: None,
+            };
+            let item = insert_cash_item(item, &mut tx).await?;
+
+            sqlx::query("DELETE FROM cash_gifts WHERE id = ?")
+                .bind(gift.id)
+                .execute(&mut *tx)
+                .await?;
+
+            claimed.push((gift, item));
+        }
+
+        tx.commit().await?;
+        Ok(claimed)
+    }
+}
+
+/// The cash shop commodities a character has added to their wishlist, by serial number
+#[derive(Debug, Clone)]
+pub struct Wishlist {
+    pub character_id: i32,
+    pub commodity_ids: Vec<i32>,
+}
+
+impl Wishlist {
+    /// Loads a character's wishlist
+    pub async fn load(character_id: i32, db: &Db) -> anyhow::Result<Self> {
+        let commodity_ids = sqlx::query_scalar(
+            "SELECT commodity_id FROM wishlists WHERE character_id = ? ORDER BY position",
+        )
+        .bind(character_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(Self {
+            character_id,
+            commodity_ids,
+        })
+    }
+
+    /// Replaces the character's saved wishlist with this one in one transaction
+    pub async fn save(&self, db: &Db) -> anyhow::Result<()> {
+        let mut tx = db.begin().await?;
+
+        sqlx::query("DELETE FROM wishlists WHERE character_id = ?")
+            .bind(self.character_id)
+            .execute(&mut *tx)
+            .await?;
+
+        for (position, commodity_id) in self.commodity_ids.iter().enumerate() {
+            sqlx::query(
+                "INSERT INTO wishlists (character_id, position, commodity_id) VALUES (?, ?, ?)",
+            )
+            .bind(self.character_id)
+            .bind(position as i32)
+            .bind(commodity_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
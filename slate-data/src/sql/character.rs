@@ -1,7 +1,8 @@
+use super::item::InventoryType;
 use crate::Db;
 use sqlx::{
     types::chrono::{DateTime, Utc},
-    FromRow, Row,
+    FromRow, MySqlExecutor, Row,
 };
 
 #[derive(FromRow, Debug, Clone)]
@@ -42,6 +43,7 @@ pub struct Character {
     pub job_rank_move: i32,
     pub guild: Option<i32>,
     pub guild_rank: Option<i32>,
+    pub alliance_rank: i32,
     pub equip_slots: i32,
     pub use_slots: i32,
     pub setup_slots: i32,
@@ -60,6 +62,68 @@ impl Character {
         Ok(character)
     }
 
+    /// Loads a character by name in the given world if it exists
+    pub async fn load_optional_by_name(
+        name: &str,
+        world_id: i32,
+        db: &Db,
+    ) -> anyhow::Result<Option<Self>> {
+        let character =
+            sqlx::query_as::<_, Self>("SELECT * FROM characters WHERE name = ? AND world_id = ?")
+                .bind(name)
+                .bind(world_id)
+                .fetch_optional(db)
+                .await?;
+
+        Ok(character)
+    }
+
+    /// Saves a character's stats and location
+    pub async fn save(&self, db: &Db) -> anyhow::Result<()> {
+        sqlx::query(
+            "UPDATE characters SET level = ?, exp = ?, str = ?, dex = ?, luk = ?, `int` = ?, hp = ?,
+            mp = ?, max_hp = ?, max_mp = ?, mesos = ?, job = ?, fame = ?, ap = ?, sp = ?, map = ?,
+            spawn_point = ? WHERE id = ?",
+        )
+        .bind(self.level)
+        .bind(self.exp)
+        .bind(self.str)
+        .bind(self.dex)
+        .bind(self.luk)
+        .bind(self.int)
+        .bind(self.hp)
+        .bind(self.mp)
+        .bind(self.max_hp)
+        .bind(self.max_mp)
+        .bind(self.mesos)
+        .bind(self.job)
+        .bind(self.fame)
+        .bind(self.ap)
+        .bind(&self.sp)
+        .bind(self.map)
+        .bind(self.spawn_point)
+        .bind(self.id)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Updates a character's mesos
+    pub async fn update_mesos(
+        id: i32,
+        mesos: i32,
+        db: impl MySqlExecutor<'_>,
+    ) -> anyhow::Result<()> {
+        sqlx::query("UPDATE characters SET mesos = ? WHERE id = ?")
+            .bind(mesos)
+            .bind(id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+
     /// Loads alls characters by account id in the selected world
     pub async fn load_all(account_id: i32, world_id: i32, db: &Db) -> anyhow::Result<Vec<Self>> {
         let characters = sqlx::query_as::<_, Self>(
@@ -73,6 +137,73 @@ impl Character {
         Ok(characters)
     }
 
+    /// Loads the ids of every member of the given party
+    pub async fn load_party_member_ids(party_id: i32, db: &Db) -> anyhow::Result<Vec<i32>> {
+        let ids = sqlx::query_scalar("SELECT id FROM characters WHERE party = ?")
+            .bind(party_id)
+            .fetch_all(db)
+            .await?;
+
+        Ok(ids)
+    }
+
+    /// Loads the ids of every member of the given guild
+    pub async fn load_guild_member_ids(guild_id: i32, db: &Db) -> anyhow::Result<Vec<i32>> {
+        let ids = sqlx::query_scalar("SELECT id FROM characters WHERE guild = ?")
+            .bind(guild_id)
+            .fetch_all(db)
+            .await?;
+
+        Ok(ids)
+    }
+
+    /// Loads the ids of every member of every guild in the given alliance
+    pub async fn load_alliance_member_ids(alliance_id: i32, db: &Db) -> anyhow::Result<Vec<i32>> {
+        let ids = sqlx::query_scalar(
+            "SELECT c.id FROM characters c JOIN guilds g ON g.id = c.guild WHERE g.alliance_id = ?",
+        )
+        .bind(alliance_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(ids)
+    }
+
+    /// Loads the mesos the character's player shops earned that they haven't collected yet
+    pub async fn load_merchant_mesos(id: i32, db: &Db) -> anyhow::Result<i32> {
+        let mesos = sqlx::query_scalar("SELECT merchant_mesos FROM characters WHERE id = ?")
+            .bind(id)
+            .fetch_one(db)
+            .await?;
+
+        Ok(mesos)
+    }
+
+    /// Moves up to `max` of the mesos the character's player shops earned into their mesos
+    /// Returns the amount of mesos moved
+    pub async fn take_merchant_mesos(id: i32, max: i32, db: &Db) -> anyhow::Result<i32> {
+        let mut tx = db.begin().await?;
+
+        let mesos: i32 =
+            sqlx::query_scalar("SELECT merchant_mesos FROM characters WHERE id = ? FOR UPDATE")
+                .bind(id)
+                .fetch_one(&mut *tx)
+                .await?;
+        let taken = mesos.min(max.max(0));
+
+        sqlx::query(
+            "UPDATE characters SET merchant_mesos = merchant_mesos - ?, mesos = mesos + ? WHERE id = ?",
+        )
+        .bind(taken)
+        .bind(taken)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(taken)
+    }
+
     /// Get the number of characters an account has in the selected world
     pub async fn get_count(account_id: i32, world_id: i32, db: &Db) -> anyhow::Result<i32> {
         let num_characters: i32 = sqlx::query(
@@ -86,4 +217,55 @@ impl Character {
 
         Ok(num_characters)
     }
+
+    /// Gets the index of the character's current sp in the comma separated sp list
+    /// Evans keep separate sp for each of their job advancements
+    fn sp_index(&self) -> usize {
+        if self.job >= 2210 && self.job <= 2218 {
+            (self.job - 2209) as usize
+        } else {
+            0
+        }
+    }
+
+    /// Gets the number of slots in one of the character's inventories
+    pub fn get_slots(&self, inventory_type: InventoryType) -> i32 {
+        match inventory_type {
+            InventoryType::Equip => self.equip_slots,
+            InventoryType::Use => self.use_slots,
+            InventoryType::Setup => self.setup_slots,
+            InventoryType::Etc => self.etc_slots,
+            InventoryType::Cash => self.cash_slots,
+        }
+    }
+
+    /// Gets the character's sp for their current job
+    pub fn get_sp(&self) -> i32 {
+        self.sp
+            .split(',')
+            .nth(self.sp_index())
+            .and_then(|sp| sp.parse().ok())
+            .unwrap_or(0)
+    }
+
+    /// Adds sp for the character's current job
+    pub fn add_sp(&mut self, amount: i32) {
+        let index = self.sp_index();
+        let mut sp: Vec<i32> = self
+            .sp
+            .split(',')
+            .map(|sp| sp.parse().unwrap_or(0))
+            .collect();
+
+        if sp.len() <= index {
+            sp.resize(index + 1, 0);
+        }
+
+        sp[index] += amount;
+        self.sp = sp
+            .iter()
+            .map(|sp| sp.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+    }
 }
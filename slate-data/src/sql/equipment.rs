@@ -1,11 +1,14 @@
-use crate::Db;
-use sqlx::FromRow;
+use crate::{nx, Db};
+use anyhow::anyhow;
+use sqlx::{FromRow, MySqlExecutor};
 
+/// A piece of equipment's stats, `character_id` is set while it's worn
+/// Equipment that isn't worn belongs to the item (in an inventory, storage, shop, etc.) that refers to it
 #[derive(FromRow, Debug, Clone)]
 pub struct Equipment {
     pub id: i32,
     pub item_id: i32,
-    pub character_id: i32,
+    pub character_id: Option<i32>,
     pub position: i32,
     pub amount: i32,
     pub upgrade_slots: i32,
@@ -31,6 +34,10 @@ pub struct Equipment {
     pub vicious: i32,
     pub owner: String,
     pub flag: i32,
+    /// The unique serial number of equipment bought from the cash shop
+    pub cash_id: Option<i64>,
+    /// When the equipment expires, as a unix timestamp in milliseconds, None if it's permanent
+    pub expires_at: Option<i64>,
 }
 
 impl Equipment {
@@ -43,4 +50,112 @@ impl Equipment {
 
         Ok(equipment)
     }
+
+    /// Loads the stats of the equipment an item refers to, None if the item isn't equipment
+    pub async fn load_optional(id: Option<i32>, db: &Db) -> anyhow::Result<Option<Self>> {
+        let id = match id {
+            Some(id) => id,
+            None => return Ok(None),
+        };
+
+        let equipment = sqlx::query_as::<_, Self>("SELECT * FROM equipment WHERE id = ?")
+            .bind(id)
+            .fetch_optional(db)
+            .await?;
+
+        Ok(equipment)
+    }
+
+    /// Creates a new piece of equipment with its stats from Character.nx, it isn't worn by anyone
+    pub async fn create(item_id: i32, db: impl MySqlExecutor<'_>) -> anyhow::Result<Self> {
+        let nx_equip = nx::Equipment::load_by_id(item_id)
+            .ok_or_else(|| anyhow!("Equipment {} doesn't exist", item_id))?;
+
+        let mut equipment = Self {
+            id: 0,
+            item_id,
+            character_id: None,
+            position: 0,
+            amount: 1,
+            upgrade_slots: nx_equip.upgrade_slots,
+            level: 0,
+            item_level: 1,
+            exp: 0,
+            str: nx_equip.str,
+            dex: nx_equip.dex,
+            int: nx_equip.int,
+            luk: nx_equip.luk,
+            hp: nx_equip.hp,
+            mp: nx_equip.mp,
+            w_atk: nx_equip.w_atk,
+            m_atk: nx_equip.m_atk,
+            w_def: nx_equip.w_def,
+            m_def: nx_equip.m_def,
+            acc: nx_equip.acc,
+            avoid: nx_equip.avoid,
+            hands: nx_equip.hands,
+            speed: nx_equip.speed,
+            jump: nx_equip.jump,
+            locked: 0,
+            vicious: 0,
+            owner: String::new(),
+            flag: 0,
+            cash_id: None,
+            expires_at: None,
+        };
+
+        equipment.id = sqlx::query(
+            "INSERT INTO equipment (item_id, position, upgrade_slots, str, dex, `int`, luk, hp, mp, w_atk, m_atk, w_def,
+            m_def, acc, avoid, hands, speed, jump) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(equipment.item_id)
+        .bind(equipment.position)
+        .bind(equipment.upgrade_slots)
+        .bind(equipment.str)
+        .bind(equipment.dex)
+        .bind(equipment.int)
+        .bind(equipment.luk)
+        .bind(equipment.hp)
+        .bind(equipment.mp)
+        .bind(equipment.w_atk)
+        .bind(equipment.m_atk)
+        .bind(equipment.w_def)
+        .bind(equipment.m_def)
+        .bind(equipment.acc)
+        .bind(equipment.avoid)
+        .bind(equipment.hands)
+        .bind(equipment.speed)
+        .bind(equipment.jump)
+        .execute(db)
+        .await?
+        .last_insert_id() as i32;
+
+        Ok(equipment)
+    }
+
+    /// Deletes all of a character's equipment that expired before the given time
+    pub async fn delete_expired(character_id: i32, now: i64, db: &Db) -> anyhow::Result<()> {
+        sqlx::query("DELETE FROM equipment WHERE character_id = ? AND expires_at <= ?")
+            .bind(character_id)
+            .bind(now)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Checks if the equipment has expired
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// Deletes the equipment
+    pub async fn delete(&self, db: &Db) -> anyhow::Result<()> {
+        sqlx::query("DELETE FROM equipment WHERE id = ?")
+            .bind(self.id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
 }
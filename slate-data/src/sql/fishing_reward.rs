@@ -0,0 +1,31 @@
+use crate::Db;
+use sqlx::FromRow;
+
+/// Something a character can catch while fishing, a reward can give any of an item, mesos and exp
+/// A reward without any of them means nothing was caught
+#[derive(FromRow, Debug, Clone)]
+pub struct FishingReward {
+    pub id: i32,
+    pub item_id: Option<i32>,
+    pub amount: i32,
+    pub mesos: i32,
+    pub exp: i32,
+    /// How likely the reward is to be caught compared to the others
+    pub weight: i32,
+}
+
+impl FishingReward {
+    /// Loads everything that can be caught while fishing
+    pub async fn load_all(db: &Db) -> anyhow::Result<Vec<Self>> {
+        let rewards = sqlx::query_as::<_, Self>("SELECT * FROM fishing_rewards")
+            .fetch_all(db)
+            .await?;
+
+        Ok(rewards)
+    }
+
+    /// Checks if the reward gives anything, otherwise nothing was caught
+    pub fn is_catch(&self) -> bool {
+        self.item_id.is_some() || self.mesos > 0 || self.exp > 0
+    }
+}
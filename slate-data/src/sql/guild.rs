@@ -0,0 +1,294 @@
+use super::alliance::LOWEST_ALLIANCE_RANK;
+use crate::Db;
+use sqlx::FromRow;
+
+/// The lowest guild rank, given to new members
+pub const LOWEST_GUILD_RANK: i32 = 5;
+
+/// The most members a guild's capacity can be increased to
+pub const MAX_GUILD_CAPACITY: i32 = 100;
+
+#[derive(FromRow, Debug, Clone)]
+pub struct Guild {
+    pub id: i32,
+    pub world_id: i32,
+    pub name: String,
+    pub leader_id: i32,
+    pub rank1_title: String,
+    pub rank2_title: String,
+    pub rank3_title: String,
+    pub rank4_title: String,
+    pub rank5_title: String,
+    pub capacity: i32,
+    pub logo: i32,
+    pub logo_color: i32,
+    pub logo_bg: i32,
+    pub logo_bg_color: i32,
+    pub notice: String,
+    pub points: i32,
+    pub alliance_id: Option<i32>,
+}
+
+impl Guild {
+    /// Loads a guild by id if it exists
+    pub async fn load_optional(id: i32, db: &Db) -> anyhow::Result<Option<Self>> {
+        let guild = sqlx::query_as::<_, Self>("SELECT * FROM guilds WHERE id = ?")
+            .bind(id)
+            .fetch_optional(db)
+            .await?;
+
+        Ok(guild)
+    }
+
+    /// Loads a guild by name in the given world if it exists
+    pub async fn load_optional_by_name(
+        name: &str,
+        world_id: i32,
+        db: &Db,
+    ) -> anyhow::Result<Option<Self>> {
+        let guild =
+            sqlx::query_as::<_, Self>("SELECT * FROM guilds WHERE name = ? AND world_id = ?")
+                .bind(name)
+                .bind(world_id)
+                .fetch_optional(db)
+                .await?;
+
+        Ok(guild)
+    }
+
+    /// Loads every guild in an alliance
+    pub async fn load_all_by_alliance(alliance_id: i32, db: &Db) -> anyhow::Result<Vec<Self>> {
+        let guilds =
+            sqlx::query_as::<_, Self>("SELECT * FROM guilds WHERE alliance_id = ? ORDER BY id")
+                .bind(alliance_id)
+                .fetch_all(db)
+                .await?;
+
+        Ok(guilds)
+    }
+
+    /// Checks if a guild with the given name already exists in the world
+    pub async fn name_exists(name: &str, world_id: i32, db: &Db) -> anyhow::Result<bool> {
+        let exists: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM guilds WHERE name = ? AND world_id = ?)",
+        )
+        .bind(name)
+        .bind(world_id)
+        .fetch_one(db)
+        .await?;
+
+        Ok(exists)
+    }
+
+    /// Creates a guild led by the given character, the other characters join it as members
+    /// Returns the new guild's id
+    pub async fn create(
+        world_id: i32,
+        name: &str,
+        leader_id: i32,
+        member_ids: &[i32],
+        db: &Db,
+    ) -> anyhow::Result<i32> {
+        let mut tx = db.begin().await?;
+
+        let id = sqlx::query("INSERT INTO guilds (world_id, name, leader_id) VALUES (?, ?, ?)")
+            .bind(world_id)
+            .bind(name)
+            .bind(leader_id)
+            .execute(&mut *tx)
+            .await?
+            .last_insert_id() as i32;
+
+        sqlx::query("UPDATE characters SET guild = ?, guild_rank = 1 WHERE id = ?")
+            .bind(id)
+            .bind(leader_id)
+            .execute(&mut *tx)
+            .await?;
+
+        for member_id in member_ids.iter() {
+            sqlx::query("UPDATE characters SET guild = ?, guild_rank = ? WHERE id = ?")
+                .bind(id)
+                .bind(LOWEST_GUILD_RANK)
+                .bind(member_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(id)
+    }
+
+    /// Gets the guild's rank titles, from the master's rank to the lowest rank
+    pub fn rank_titles(&self) -> [&str; 5] {
+        [
+            &self.rank1_title,
+            &self.rank2_title,
+            &self.rank3_title,
+            &self.rank4_title,
+            &self.rank5_title,
+        ]
+    }
+
+    /// Updates the guild's rank titles
+    pub async fn update_rank_titles(&self, db: &Db) -> anyhow::Result<()> {
+        sqlx::query(
+            "UPDATE guilds SET rank1_title = ?, rank2_title = ?, rank3_title = ?, rank4_title = ?,
+            rank5_title = ? WHERE id = ?",
+        )
+        .bind(&self.rank1_title)
+        .bind(&self.rank2_title)
+        .bind(&self.rank3_title)
+        .bind(&self.rank4_title)
+        .bind(&self.rank5_title)
+        .bind(self.id)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Updates the guild's emblem
+    pub async fn update_emblem(&self, db: &Db) -> anyhow::Result<()> {
+        sqlx::query(
+            "UPDATE guilds SET logo = ?, logo_color = ?, logo_bg = ?, logo_bg_color = ? WHERE id = ?",
+        )
+        .bind(self.logo)
+        .bind(self.logo_color)
+        .bind(self.logo_bg)
+        .bind(self.logo_bg_color)
+        .bind(self.id)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Updates the guild's notice
+    pub async fn update_notice(&self, db: &Db) -> anyhow::Result<()> {
+        sqlx::query("UPDATE guilds SET notice = ? WHERE id = ?")
+            .bind(&self.notice)
+            .bind(self.id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Updates the guild's capacity
+    pub async fn update_capacity(&self, db: &Db) -> anyhow::Result<()> {
+        sqlx::query("UPDATE guilds SET capacity = ? WHERE id = ?")
+            .bind(self.capacity)
+            .bind(self.id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Updates the guild's points
+    pub async fn update_points(&self, db: &Db) -> anyhow::Result<()> {
+        sqlx::query("UPDATE guilds SET points = ? WHERE id = ?")
+            .bind(self.points)
+            .bind(self.id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Adds a character to the guild at the lowest rank
+    pub async fn add_member(&self, character_id: i32, db: &Db) -> anyhow::Result<()> {
+        sqlx::query("UPDATE characters SET guild = ?, guild_rank = ? WHERE id = ?")
+            .bind(self.id)
+            .bind(LOWEST_GUILD_RANK)
+            .bind(character_id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Removes a character from the guild
+    pub async fn remove_member(&self, character_id: i32, db: &Db) -> anyhow::Result<()> {
+        sqlx::query(
+            "UPDATE characters SET guild = NULL, guild_rank = NULL, alliance_rank = ?
+            WHERE id = ? AND guild = ?",
+        )
+        .bind(LOWEST_ALLIANCE_RANK)
+        .bind(character_id)
+        .bind(self.id)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Changes a guild member's rank
+    pub async fn update_member_rank(
+        &self,
+        character_id: i32,
+        rank: i32,
+        db: &Db,
+    ) -> anyhow::Result<()> {
+        sqlx::query("UPDATE characters SET guild_rank = ? WHERE id = ? AND guild = ?")
+            .bind(rank)
+            .bind(character_id)
+            .bind(self.id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Removes every member from the guild and deletes it
+    pub async fn disband(&self, db: &Db) -> anyhow::Result<()> {
+        let mut tx = db.begin().await?;
+
+        sqlx::query(
+            "UPDATE characters SET guild = NULL, guild_rank = NULL, alliance_rank = ?
+            WHERE guild = ?",
+        )
+        .bind(LOWEST_ALLIANCE_RANK)
+        .bind(self.id)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("DELETE FROM guilds WHERE id = ?")
+            .bind(self.id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+/// A guild member's info shown in the guild window
+#[derive(FromRow, Debug, Clone)]
+pub struct GuildMember {
+    pub id: i32,
+    pub name: String,
+    pub job: i32,
+    pub level: i32,
+    pub guild_rank: i32,
+    pub alliance_rank: i32,
+    /// None if the member is offline
+    pub channel_id: Option<i32>,
+}
+
+impl GuildMember {
+    /// Loads all of a guild's members, along with whether they're online
+    pub async fn load_all(guild_id: i32, db: &Db) -> anyhow::Result<Vec<Self>> {
+        let members = sqlx::query_as::<_, Self>(
+            "SELECT c.id, c.name, c.job, c.level, c.guild_rank, c.alliance_rank, o.channel_id
+            FROM characters c
+            LEFT JOIN online_characters o ON o.character_id = c.id
+            WHERE c.guild = ? ORDER BY c.guild_rank, c.id",
+        )
+        .bind(guild_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(members)
+    }
+}
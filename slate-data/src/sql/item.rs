@@ -1,7 +1,11 @@
-use sqlx::{Decode, Encode, FromRow};
+use sqlx::{Decode, Encode, FromRow, MySqlExecutor};
 
+use super::Equipment;
 use crate::Db;
 
+/// The position of items held by a trade, outside of their character's inventory
+pub const HELD_POSITION: i32 = -1;
+
 #[derive(FromRow, Debug, Clone)]
 pub struct Item {
     pub id: i32,
@@ -12,21 +16,282 @@ pub struct Item {
     pub amount: i32,
     pub owner: String,
     pub flag: i32,
+    /// The unique serial number of an item bought from the cash shop
+    pub cash_id: Option<i64>,
+    /// When the item expires, as a unix timestamp in milliseconds, None if it's permanent
+    pub expires_at: Option<i64>,
+    /// The id of the equipment holding the item's stats, None if it isn't equipment
+    pub equipment_id: Option<i32>,
+    /// The equipment holding the item's stats, loaded along with the item
+    #[sqlx(skip)]
+    pub equip: Option<Equipment>,
 }
 
 impl Item {
     /// Loads all of a character's items
     pub async fn load_all(character_id: i32, db: &Db) -> anyhow::Result<Vec<Self>> {
-        let items = sqlx::query_as::<_, Item>("SELECT * FROM items WHERE character_id = ?")
+        let mut items = sqlx::query_as::<_, Item>("SELECT * FROM items WHERE character_id = ?")
             .bind(character_id)
             .fetch_all(db)
             .await?;
 
+        for item in items.iter_mut() {
+            item.equip = Equipment::load_optional(item.equipment_id, db).await?;
+        }
+
         Ok(items)
     }
+
+    /// Checks if the item has expired
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// Inserts the item into the db, setting its id
+    pub async fn insert(&mut self, db: impl MySqlExecutor<'_>) -> anyhow::Result<()> {
+        self.id = sqlx::query(
+            "INSERT INTO items
+            (item_id, character_id, inventory_type, position, amount, owner, flag, cash_id, expires_at, equipment_id)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(self.item_id)
+        .bind(self.character_id)
+        .bind(self.inventory_type)
+        .bind(self.position)
+        .bind(self.amount)
+        .bind(&self.owner)
+        .bind(self.flag)
+        .bind(self.cash_id)
+        .bind(self.expires_at)
+        .bind(self.equipment_id)
+        .execute(db)
+        .await?
+        .last_insert_id() as i32;
+
+        Ok(())
+    }
+
+    /// Updates the item's amount, deleting it if the amount is 0
+    pub async fn update_amount(&self, db: impl MySqlExecutor<'_>) -> anyhow::Result<()> {
+        if self.amount <= 0 {
+            return self.delete(db).await;
+        }
+
+        sqlx::query("UPDATE items SET amount = ? WHERE id = ?")
+            .bind(self.amount)
+            .bind(self.id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Updates the item's position
+    pub async fn update_position(&self, db: &Db) -> anyhow::Result<()> {
+        sqlx::query("UPDATE items SET position = ? WHERE id = ?")
+            .bind(self.position)
+            .bind(self.id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Deletes the item, along with its stats if it's equipment
+    pub async fn delete(&self, db: impl MySqlExecutor<'_>) -> anyhow::Result<()> {
+        sqlx::query(
+            "DELETE items, equipment FROM items LEFT JOIN equipment ON equipment.id = items.equipment_id
+            WHERE items.id = ?",
+        )
+        .bind(self.id)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Moves an item out of its character's inventory to be held by a trade, in one transaction
+    /// `item` holds the amount being traded, the rest of its stack stays in the inventory unless `whole_stack` is set
+    /// Returns the held item, or None (changing nothing) if the character no longer has the item
+    pub async fn hold(item: &Self, whole_stack: bool, db: &Db) -> anyhow::Result<Option<Self>> {
+        let mut tx = db.begin().await?;
+        let mut held = Self {
+            position: HELD_POSITION,
+            ..item.clone()
+        };
+
+        if whole_stack {
+            let updated = sqlx::query(
+                "UPDATE items SET position = ? WHERE id = ? AND character_id = ? AND amount = ?",
+            )
+            .bind(HELD_POSITION)
+            .bind(item.id)
+            .bind(item.character_id)
+            .bind(item.amount)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+            if updated != 1 {
+                return Ok(None);
+            }
+        } else {
+            let updated = sqlx::query(
+                "UPDATE items SET amount = amount - ? WHERE id = ? AND character_id = ? AND amount > ?",
+            )
+            .bind(item.amount)
+            .bind(item.id)
+            .bind(item.character_id)
+            .bind(item.amount)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+            if updated != 1 {
+                return Ok(None);
+            }
+
+            held.id = sqlx::query(
+                "INSERT INTO items
+                (item_id, character_id, inventory_type, position, amount, owner, flag, cash_id, expires_at, equipment_id)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(held.item_id)
+            .bind(held.character_id)
+            .bind(held.inventory_type)
+            .bind(held.position)
+            .bind(held.amount)
+            .bind(&held.owner)
+            .bind(held.flag)
+            .bind(held.cash_id)
+            .bind(held.expires_at)
+            .bind(held.equipment_id)
+            .execute(&mut *tx)
+            .await?
+            .last_insert_id() as i32;
+        }
+
+        tx.commit().await?;
+        Ok(Some(held))
+    }
+
+    /// Merges the item into another stack of it in the same inventory, in one transaction
+    pub async fn merge_into(&self, stack: &Self, db: &Db) -> anyhow::Result<()> {
+        let mut tx = db.begin().await?;
+
+        sqlx::query("UPDATE items SET amount = amount + ? WHERE id = ?")
+            .bind(self.amount)
+            .bind(stack.id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM items WHERE id = ?")
+            .bind(self.id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Gets the empty positions in one of a character's inventories, as it's stored in the db
+    pub async fn get_free_positions(
+        character_id: i32,
+        inventory_type: InventoryType,
+        slots: i32,
+        db: &Db,
+    ) -> anyhow::Result<Vec<i32>> {
+        let used: Vec<i32> = sqlx::query_scalar(
+            "SELECT position FROM items WHERE character_id = ? AND inventory_type = ?",
+        )
+        .bind(character_id)
+        .bind(inventory_type)
+        .fetch_all(db)
+        .await?;
+
+        Ok((0..slots)
+            .filter(|position| !used.contains(position))
+            .collect())
+    }
+
+    /// Moves items held by a trade into the inventories of the characters receiving them, and changes the
+    /// traders' mesos, in one transaction
+    /// `mesos` holds the change to each character's mesos, by character id
+    /// Returns the moved items as they're now stored, or None (changing nothing) if any of the items is no longer
+    /// held by the trade or its new position was filled in the meantime
+    pub async fn trade(
+        transfers: &[ItemTransfer],
+        mesos: &[(i32, i32)],
+        db: &Db,
+    ) -> anyhow::Result<Option<Vec<Self>>> {
+        let mut tx = db.begin().await?;
+        let mut moved = Vec::new();
+
+        for transfer in transfers.iter() {
+            let item = &transfer.item;
+
+            let used = sqlx::query(
+                "SELECT id FROM items WHERE character_id = ? AND inventory_type = ? AND position = ? FOR UPDATE",
+            )
+            .bind(transfer.character_id)
+            .bind(item.inventory_type)
+            .bind(transfer.position)
+            .fetch_optional(&mut *tx)
+            .await?
+            .is_some();
+
+            if used {
+                return Ok(None);
+            }
+
+            let updated = sqlx::query(
+                "UPDATE items SET character_id = ?, position = ? WHERE id = ? AND character_id = ? AND position = ?",
+            )
+            .bind(transfer.character_id)
+            .bind(transfer.position)
+            .bind(item.id)
+            .bind(item.character_id)
+            .bind(HELD_POSITION)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+            if updated != 1 {
+                return Ok(None);
+            }
+
+            moved.push(Self {
+                character_id: transfer.character_id,
+                position: transfer.position,
+                ..item.clone()
+            });
+        }
+
+        for (character_id, amount) in mesos.iter() {
+            sqlx::query("UPDATE characters SET mesos = mesos + ? WHERE id = ?")
+                .bind(amount)
+                .bind(character_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(Some(moved))
+    }
 }
 
-#[derive(Decode, Encode, Clone, Copy, Debug)]
+/// An item held by a trade, moving from one character's inventory to another's
+#[derive(Debug, Clone)]
+pub struct ItemTransfer {
+    /// The item as the trade holds it
+    pub item: Item,
+    /// The character receiving the item
+    pub character_id: i32,
+    /// The position in the receiving character's inventory
+    pub position: i32,
+}
+
+#[derive(Decode, Encode, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum InventoryType {
     Equip,
     Use,
@@ -35,6 +300,20 @@ pub enum InventoryType {
     Cash,
 }
 
+impl InventoryType {
+    /// Gets the inventory an item belongs in based on its id, ex. 2000000 (red potion) is a use item
+    pub fn from_item_id(item_id: i32) -> Option<Self> {
+        match item_id / 1000000 {
+            1 => Some(Self::Equip),
+            2 => Some(Self::Use),
+            3 => Some(Self::Setup),
+            4 => Some(Self::Etc),
+            5 => Some(Self::Cash),
+            _ => None,
+        }
+    }
+}
+
 impl sqlx::Type<sqlx::MySql> for InventoryType {
     fn type_info() -> <sqlx::MySql as sqlx::Database>::TypeInfo {
         <str as sqlx::Type<sqlx::MySql>>::type_info()
@@ -0,0 +1,79 @@
+use crate::Db;
+use sqlx::{Decode, Encode, FromRow};
+
+/// A character's wins, ties, and losses in one of the minigames
+#[derive(FromRow, Debug, Clone)]
+pub struct MinigameRecord {
+    pub character_id: i32,
+    pub game_type: MinigameType,
+    pub wins: i32,
+    pub ties: i32,
+    pub losses: i32,
+}
+
+impl MinigameRecord {
+    /// Loads all of a character's minigame records, characters only have records for games they've finished
+    pub async fn load_all(character_id: i32, db: &Db) -> anyhow::Result<Vec<Self>> {
+        let records = sqlx::query_as::<_, Self>(
+            "SELECT * FROM minigame_records WHERE character_id = ? ORDER BY game_type",
+        )
+        .bind(character_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(records)
+    }
+
+    /// Loads a character's record in a minigame, with no wins, ties, or losses if they haven't played it yet
+    pub async fn load(character_id: i32, game_type: MinigameType, db: &Db) -> anyhow::Result<Self> {
+        let record = sqlx::query_as::<_, Self>(
+            "SELECT * FROM minigame_records WHERE character_id = ? AND game_type = ?",
+        )
+        .bind(character_id)
+        .bind(game_type)
+        .fetch_optional(db)
+        .await?;
+
+        Ok(record.unwrap_or(Self {
+            character_id,
+            game_type,
+            wins: 0,
+            ties: 0,
+            losses: 0,
+        }))
+    }
+
+    /// Saves the record after a finished game
+    pub async fn save(&self, db: &Db) -> anyhow::Result<()> {
+        sqlx::query(
+            "INSERT INTO minigame_records (character_id, game_type, wins, ties, losses)
+            VALUES (?, ?, ?, ?, ?)
+            ON DUPLICATE KEY UPDATE wins = VALUES(wins), ties = VALUES(ties), losses = VALUES(losses)",
+        )
+        .bind(self.character_id)
+        .bind(self.game_type)
+        .bind(self.wins)
+        .bind(self.ties)
+        .bind(self.losses)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Decode, Encode, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MinigameType {
+    Omok = 1,
+    MatchCards = 2,
+}
+
+impl sqlx::Type<sqlx::MySql> for MinigameType {
+    fn type_info() -> <sqlx::MySql as sqlx::Database>::TypeInfo {
+        <str as sqlx::Type<sqlx::MySql>>::type_info()
+    }
+
+    fn compatible(ty: &<sqlx::MySql as sqlx::Database>::TypeInfo) -> bool {
+        <str as sqlx::Type<sqlx::MySql>>::compatible(ty)
+    }
+}
@@ -1,20 +1,54 @@
 pub mod account;
+pub mod alliance;
+pub mod boss_entry;
+pub mod buddy;
+pub mod cash_shop;
 pub mod channel;
 pub mod character;
 pub mod equipment;
+pub mod fishing_reward;
+pub mod guild;
 pub mod item;
 pub mod keymap;
 pub mod login_session;
+pub mod minigame;
+pub mod mount;
+pub mod mts;
+pub mod online_character;
+pub mod party;
+pub mod pet;
+pub mod player_shop;
 pub mod quest;
+pub mod reactor_drop;
+pub mod shop;
 pub mod skill;
+pub mod storage;
+pub mod world_message;
 
 pub use self::account::Account;
+pub use self::alliance::Alliance;
+pub use self::boss_entry::BossEntry;
+pub use self::buddy::{Buddy, BuddyEntry, BuddyRequest};
+pub use self::cash_shop::{CashGift, CashItem, Wishlist};
 pub use self::channel::Channel;
 pub use self::character::Character;
 pub use self::equipment::Equipment;
+pub use self::fishing_reward::FishingReward;
+pub use self::guild::{Guild, GuildMember};
 pub use self::item::Item;
 pub use self::keymap::Keymap;
 pub use self::login_session::LoginSession;
-pub use self::quest::Quest;
+pub use self::minigame::{MinigameRecord, MinigameType};
+pub use self::mount::Mount;
+pub use self::mts::{MtsListing, MtsWanted};
+pub use self::online_character::OnlineCharacter;
+pub use self::party::{Party, PartyMember};
+pub use self::pet::Pet;
+pub use self::player_shop::{HiredMerchant, PlayerShopItem};
+pub use self::quest::{Quest, QuestProgress};
+pub use self::reactor_drop::ReactorDrop;
+pub use self::shop::{Shop, ShopItem};
 pub use self::skill::Cooldown;
 pub use self::skill::Skill;
+pub use self::storage::{Storage, StorageItem};
+pub use self::world_message::WorldMessage;
@@ -0,0 +1,45 @@
+use crate::Db;
+use sqlx::FromRow;
+
+/// A character's mount, shared by all of the mounts they ride
+#[derive(FromRow, Debug, Clone)]
+pub struct Mount {
+    pub character_id: i32,
+    pub level: i32,
+    pub exp: i32,
+    /// Mounts get tired while they're ridden, and have to be fed once they're too tired
+    pub tiredness: i32,
+}
+
+impl Mount {
+    /// Loads a character's mount, at level 1 if they haven't ridden one yet
+    pub async fn load(character_id: i32, db: &Db) -> anyhow::Result<Self> {
+        let mount = sqlx::query_as::<_, Self>("SELECT * FROM mounts WHERE character_id = ?")
+            .bind(character_id)
+            .fetch_optional(db)
+            .await?;
+
+        Ok(mount.unwrap_or(Self {
+            character_id,
+            level: 1,
+            exp: 0,
+            tiredness: 0,
+        }))
+    }
+
+    /// Saves the mount's level, exp, and tiredness
+    pub async fn save(&self, db: &Db) -> anyhow::Result<()> {
+        sqlx::query(
+            "INSERT INTO mounts (character_id, level, exp, tiredness) VALUES (?, ?, ?, ?)
+            ON DUPLICATE KEY UPDATE level = VALUES(level), exp = VALUES(exp), tiredness = VALUES(tiredness)",
+        )
+        .bind(self.character_id)
+        .bind(self.level)
+        .bind(self.exp)
+        .bind(self.tiredness)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+}
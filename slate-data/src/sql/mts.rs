@@ -0,0 +1,384 @@
+use super::{
+    cash_shop::{pay, CashCurrency, DAY},
+    item::InventoryType,
+    Equipment, Item,
+};
+use crate::Db;
+use sqlx::FromRow;
+
+/// The mesos it costs to list an item in the MTS
+pub const LISTING_FEE: i32 = 5000;
+
+/// How long an item stays listed before it's returned to its seller, in milliseconds
+pub const LISTING_DURATION: i64 = 7 * DAY;
+
+/// An item listed in a world's MTS (Maple Trading System) for NX Credit
+/// Once it's sold or its listing is cancelled, it waits in `character_id`'s transfer inventory
+#[derive(FromRow, Debug, Clone)]
+pub struct MtsListing {
+    pub id: i32,
+    pub world_id: i32,
+    /// The character selling the item, or the character the item is waiting for once it's in transfer
+    pub character_id: i32,
+    pub seller_name: String,
+    pub item_id: i32,
+    pub inventory_type: InventoryType,
+    pub amount: i32,
+    pub owner: String,
+    pub flag: i32,
+    pub cash_id: Option<i64>,
+    pub expires_at: Option<i64>,
+    /// The NX Credit the seller receives when the item is sold
+    pub price: i32,
+    /// When the listing ends, as a unix timestamp in milliseconds
+    pub ends_at: i64,
+    pub in_transfer: bool,
+    /// The id of the equipment holding the item's stats, None if it isn't equipment
+    pub equipment_id: Option<i32>,
+    /// The equipment holding the item's stats, loaded along with the listing
+    #[sqlx(skip)]
+    pub equip: Option<Equipment>,
+}
+
+impl MtsListing {
+    /// Loads a listing by its id
+    pub async fn load_optional(id: i32, db: &Db) -> anyhow::Result<Option<Self>> {
+        let mut listing = sqlx::query_as::<_, Self>("SELECT * FROM mts_listings WHERE id = ?")
+            .bind(id)
+            .fetch_optional(db)
+            .await?;
+
+        if let Some(listing) = listing.as_mut() {
+            listing.equip = Equipment::load_optional(listing.equipment_id, db).await?;
+        }
+
+        Ok(listing)
+    }
+
+    /// Loads every item for sale in a world's MTS, newest first
+    /// Only items of `inventory_type` are loaded if it's given
+    pub async fn load_for_sale(
+        world_id: i32,
+        inventory_type: Option<InventoryType>,
+        now: i64,
+        db: &Db,
+    ) -> anyhow::Result<Vec<Self>> {
+        let mut listings = sqlx::query_as::<_, Self>(
+            "SELECT * FROM mts_listings
+            WHERE world_id = ? AND in_transfer = 0 AND ends_at > ? AND (? IS NULL OR inventory_type = ?)
+            ORDER BY id DESC",
+        )
+        .bind(world_id)
+        .bind(now)
+        .bind(inventory_type)
+        .bind(inventory_type)
+        .fetch_all(db)
+        .await?;
+
+        Self::load_equipment(&mut listings, db).await?;
+        Ok(listings)
+    }
+
+    /// Loads the items a character is selling that haven't been sold yet
+    pub async fn load_selling(character_id: i32, db: &Db) -> anyhow::Result<Vec<Self>> {
+        let mut listings = sqlx::query_as::<_, Self>(
+            "SELECT * FROM mts_listings WHERE character_id = ? AND in_transfer = 0 ORDER BY id",
+        )
+        .bind(character_id)
+        .fetch_all(db)
+        .await?;
+
+        Self::load_equipment(&mut listings, db).await?;
+        Ok(listings)
+    }
+
+    /// Loads the items waiting in a character's transfer inventory
+    pub async fn load_transfer(character_id: i32, db: &Db) -> anyhow::Result<Vec<Self>> {
+        let mut listings = sqlx::query_as::<_, Self>(
+            "SELECT * FROM mts_listings WHERE character_id = ? AND in_transfer = 1 ORDER BY id",
+        )
+        .bind(character_id)
+        .fetch_all(db)
+        .await?;
+
+        Self::load_equipment(&mut listings, db).await?;
+        Ok(listings)
+    }
+
+    /// Loads the items still for sale on a character's wanted list
+    pub async fn load_wanted(character_id: i32, now: i64, db: &Db) -> anyhow::Result<Vec<Self>> {
+        let mut listings = sqlx::query_as::<_, Self>(
+            "SELECT l.* FROM mts_listings l JOIN mts_wanted w ON w.listing_id = l.id
+            WHERE w.character_id = ? AND l.in_transfer = 0 AND l.ends_at > ?
+            ORDER BY l.id DESC",
+        )
+        .bind(character_id)
+        .bind(now)
+        .fetch_all(db)
+        .await?;
+
+        Self::load_equipment(&mut listings, db).await?;
+        Ok(listings)
+    }
+
+    /// Loads the stats of every listed piece of equipment
+    async fn load_equipment(listings: &mut [Self], db: &Db) -> anyhow::Result<()> {
+        for listing in listings.iter_mut() {
+            listing.equip = Equipment::load_optional(listing.equipment_id, db).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Moves a character's listings that ended before the given time into their transfer inventory
+    pub async fn return_expired(character_id: i32, now: i64, db: &Db) -> anyhow::Result<()> {
+        sqlx::query(
+            "UPDATE mts_listings SET in_transfer = 1 WHERE character_id = ? AND in_transfer = 0 AND ends_at <= ?",
+        )
+        .bind(character_id)
+        .bind(now)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Gets the commission the buyer pays on top of the item's price
+    pub fn get_commission(&self) -> i32 {
+        self.price / 10
+    }
+
+    /// Moves an item from a character's inventory into the MTS in one transaction
+    /// `item` holds the amount being listed, the rest of its stack stays in the inventory unless `whole_stack` is set
+    /// Returns the listing, or None (changing nothing) if the character no longer has the item
+    pub async fn list(
+        item: &Item,
+        whole_stack: bool,
+        world_id: i32,
+        seller_name: &str,
+        price: i32,
+        ends_at: i64,
+        db: &Db,
+    ) -> anyhow::Result<Option<Self>> {
+        let mut tx = db.begin().await?;
+
+        let updated = if whole_stack {
+            sqlx::query("DELETE FROM items WHERE id = ? AND character_id = ? AND amount = ?")
+                .bind(item.id)
+                .bind(item.character_id)
+                .bind(item.amount)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected()
+        } else {
+            sqlx::query(
+                "UPDATE items SET amount = amount - ? WHERE id = ? AND character_id = ? AND amount > ?",
+            )
+            .bind(item.amount)
+            .bind(item.id)
+            .bind(item.character_id)
+            .bind(item.amount)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected()
+        };
+
+        if updated != 1 {
+            return Ok(None);
+        }
+
+        let mut listing = Self {
+            id: 0,
+            world_id,
+            character_id: item.character_id,
+            seller_name: seller_name.to_string(),
+            item_id: item.item_id,
+            inventory_type: item.inventory_type,
+            amount: item.amount,
+            owner: item.owner.clone(),
+            flag: item.flag,
+            cash_id: item.cash_id,
+            expires_at: item.expires_at,
+            price,
+            ends_at,
+            in_transfer: false,
+            equipment_id: item.equipment_id,
+            equip: item.equip.clone(),
+        };
+
+        listing.id = sqlx::query(
+            "INSERT INTO mts_listings
+            (world_id, character_id, seller_name, item_id, inventory_type, amount, owner, flag, cash_id, expires_at, price,
+            ends_at, equipment_id)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(listing.world_id)
+        .bind(listing.character_id)
+        .bind(&listing.seller_name)
+        .bind(listing.item_id)
+        .bind(listing.inventory_type)
+        .bind(listing.amount)
+        .bind(&listing.owner)
+        .bind(listing.flag)
+        .bind(listing.cash_id)
+        .bind(listing.expires_at)
+        .bind(listing.price)
+        .bind(listing.ends_at)
+        .bind(listing.equipment_id)
+        .execute(&mut *tx)
+        .await?
+        .last_insert_id() as i32;
+
+        tx.commit().await?;
+        Ok(Some(listing))
+    }
+
+    /// Cancels the listing, moving the item into its seller's transfer inventory in one transaction
+    /// Returns false (changing nothing) if the item was already sold or returned
+    pub async fn cancel(&self, db: &Db) -> anyhow::Result<bool> {
+        let mut tx = db.begin().await?;
+
+        let updated = sqlx::query(
+            "UPDATE mts_listings SET in_transfer = 1 WHERE id = ? AND character_id = ? AND in_transfer = 0",
+        )
+        .bind(self.id)
+        .bind(self.character_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        if updated != 1 {
+            return Ok(false);
+        }
+
+        sqlx::query("DELETE FROM mts_wanted WHERE listing_id = ?")
+            .bind(self.id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Buys the item into a character's transfer inventory in one transaction
+    /// The buyer's account pays the price and commission in NX Credit, and the seller's account receives the price
+    /// Returns false (changing nothing) if the item is no longer for sale or the buyer can't afford it
+    pub async fn buy(
+        &self,
+        buyer_id: i32,
+        buyer_account_id: i32,
+        now: i64,
+        db: &Db,
+    ) -> anyhow::Result<bool> {
+        let mut tx = db.begin().await?;
+
+        let updated = sqlx::query(
+            "UPDATE mts_listings SET character_id = ?, in_transfer = 1
+            WHERE id = ? AND character_id = ? AND in_transfer = 0 AND ends_at > ?",
+        )
+        .bind(buyer_id)
+        .bind(self.id)
+        .bind(self.character_id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        if updated != 1 {
+            return Ok(false);
+        }
+
+        let total = self.price + self.get_commission();
+
+        if !pay(buyer_account_id, CashCurrency::NxCredit, total, &mut tx).await? {
+            return Ok(false);
+        }
+
+        sqlx::query(
+            "UPDATE accounts SET nx_credit = nx_credit + ?
+            WHERE id = (SELECT account_id FROM characters WHERE id = ?)",
+        )
+        .bind(self.price)
+        .bind(self.character_id)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("DELETE FROM mts_wanted WHERE listing_id = ?")
+            .bind(self.id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Moves the item out of its character's transfer inventory into their inventory in one transaction
+    /// Returns the item as it's now stored in the inventory, or None (changing nothing) if it was already taken out
+    pub async fn take(&self, position: i32, db: &Db) -> anyhow::Result<Option<Item>> {
+        let mut tx = db.begin().await?;
+
+        let deleted = sqlx::query(
+            "DELETE FROM mts_listings WHERE id = ? AND character_id = ? AND in_transfer = 1",
+        )
+        .bind(self.id)
+        .bind(self.character_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        if deleted != 1 {
+            return Ok(None);
+        }
+
+        let mut item = Item {
+            id: 0,
+            item_id: self.item_id,
+            character_id: self.character_id,
+            inventory_type: self.inventory_type,
+            position,
+            amount: self.amount,
+            owner: self.owner.clone(),
+            flag: self.flag,
+            cash_id: self.cash_id,
+            expires_at: self.expires_at,
+            equipment_id: self.equipment_id,
+            equip: self.equip.clone(),
+        };
+
+        item.insert(&mut *tx).await?;
+
+        tx.commit().await?;
+        Ok(Some(item))
+    }
+}
+
+/// An MTS listing a character added to their wanted list
+#[derive(FromRow, Debug, Clone)]
+pub struct MtsWanted {
+    pub character_id: i32,
+    pub listing_id: i32,
+}
+
+impl MtsWanted {
+    /// Adds the listing to the character's wanted list, if it isn't on it already
+    pub async fn insert(&self, db: &Db) -> anyhow::Result<()> {
+        sqlx::query("INSERT IGNORE INTO mts_wanted (character_id, listing_id) VALUES (?, ?)")
+            .bind(self.character_id)
+            .bind(self.listing_id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Removes the listing from the character's wanted list
+    pub async fn delete(&self, db: &Db) -> anyhow::Result<()> {
+        sqlx::query("DELETE FROM mts_wanted WHERE character_id = ? AND listing_id = ?")
+            .bind(self.character_id)
+            .bind(self.listing_id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+}
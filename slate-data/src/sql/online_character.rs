@@ -0,0 +1,124 @@
+use crate::Db;
+use sqlx::{FromRow, MySql, QueryBuilder};
+
+/// A character that is currently logged in to one of the world's channels
+#[derive(FromRow, Debug, Clone)]
+pub struct OnlineCharacter {
+    pub character_id: i32,
+    pub account_id: i32,
+    pub name: String,
+    pub world_id: i32,
+    pub channel_id: i32,
+    pub map_id: i32,
+}
+
+impl OnlineCharacter {
+    /// Loads an online character by id if they are online
+    pub async fn load_optional(character_id: i32, db: &Db) -> anyhow::Result<Option<Self>> {
+        let character =
+            sqlx::query_as::<_, Self>("SELECT * FROM online_characters WHERE character_id = ?")
+                .bind(character_id)
+                .fetch_optional(db)
+                .await?;
+
+        Ok(character)
+    }
+
+    /// Loads an online character by name in the given world if they are online
+    pub async fn load_optional_by_name(
+        name: &str,
+        world_id: i32,
+        db: &Db,
+    ) -> anyhow::Result<Option<Self>> {
+        let character = sqlx::query_as::<_, Self>(
+            "SELECT * FROM online_characters WHERE name = ? AND world_id = ?",
+        )
+        .bind(name)
+        .bind(world_id)
+        .fetch_optional(db)
+        .await?;
+
+        Ok(character)
+    }
+
+    /// Loads every online character out of the given character ids
+    pub async fn load_all_by_ids(character_ids: &[i32], db: &Db) -> anyhow::Result<Vec<Self>> {
+        if character_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut query_builder =
+            QueryBuilder::<MySql>::new("SELECT * FROM online_characters WHERE character_id IN (");
+        let mut separated = query_builder.separated(", ");
+
+        for character_id in character_ids.iter() {
+            separated.push_bind(character_id);
+        }
+
+        separated.push_unseparated(")");
+
+        let characters = query_builder.build_query_as::<Self>().fetch_all(db).await?;
+        Ok(characters)
+    }
+
+    /// Loads every online character in the given world
+    pub async fn load_all(world_id: i32, db: &Db) -> anyhow::Result<Vec<Self>> {
+        let characters =
+            sqlx::query_as::<_, Self>("SELECT * FROM online_characters WHERE world_id = ?")
+                .bind(world_id)
+                .fetch_all(db)
+                .await?;
+
+        Ok(characters)
+    }
+
+    /// Marks a character as online, replacing any previous entry for the character
+    pub async fn insert(&self, db: &Db) -> anyhow::Result<()> {
+        sqlx::query(
+            "REPLACE INTO online_characters (character_id, account_id, name, world_id, channel_id, map_id)
+            VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(self.character_id)
+        .bind(self.account_id)
+        .bind(&self.name)
+        .bind(self.world_id)
+        .bind(self.channel_id)
+        .bind(self.map_id)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Updates the map an online character is in
+    pub async fn update_map(character_id: i32, map_id: i32, db: &Db) -> anyhow::Result<()> {
+        sqlx::query("UPDATE online_characters SET map_id = ? WHERE character_id = ?")
+            .bind(map_id)
+            .bind(character_id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Marks a character as offline
+    pub async fn delete(character_id: i32, db: &Db) -> anyhow::Result<()> {
+        sqlx::query("DELETE FROM online_characters WHERE character_id = ?")
+            .bind(character_id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Marks every character on the given channel as offline
+    pub async fn delete_all(world_id: i32, channel_id: i32, db: &Db) -> anyhow::Result<()> {
+        sqlx::query("DELETE FROM online_characters WHERE world_id = ? AND channel_id = ?")
+            .bind(world_id)
+            .bind(channel_id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+use crate::Db;
+use sqlx::FromRow;
+
+/// The most characters that can be in a party
+pub const MAX_PARTY_SIZE: usize = 6;
+
+#[derive(FromRow, Debug, Clone)]
+pub struct Party {
+    pub id: i32,
+    pub world_id: i32,
+    pub leader_id: i32,
+}
+
+impl Party {
+    /// Loads a party by id if it exists
+    pub async fn load_optional(id: i32, db: &Db) -> anyhow::Result<Option<Self>> {
+        let party = sqlx::query_as::<_, Self>("SELECT * FROM parties WHERE id = ?")
+            .bind(id)
+            .fetch_optional(db)
+            .await?;
+
+        Ok(party)
+    }
+
+    /// Creates a party led by the given character and adds them to it
+    pub async fn create(world_id: i32, leader_id: i32, db: &Db) -> anyhow::Result<Self> {
+        let mut tx = db.begin().await?;
+
+        let id = sqlx::query("INSERT INTO parties (world_id, leader_id) VALUES (?, ?)")
+            .bind(world_id)
+            .bind(leader_id)
+            .execute(&mut *tx)
+            .await?
+            .last_insert_id() as i32;
+
+        sqlx::query("UPDATE characters SET party = ? WHERE id = ?")
+            .bind(id)
+            .bind(leader_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(Self {
+            id,
+            world_id,
+            leader_id,
+        })
+    }
+
+    /// Updates the party's leader
+    pub async fn update_leader(&self, db: &Db) -> anyhow::Result<()> {
+        sqlx::query("UPDATE parties SET leader_id = ? WHERE id = ?")
+            .bind(self.leader_id)
+            .bind(self.id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Adds a character to the party
+    pub async fn add_member(&self, character_id: i32, db: &Db) -> anyhow::Result<()> {
+        sqlx::query("UPDATE characters SET party = ? WHERE id = ?")
+            .bind(self.id)
+            .bind(character_id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Removes a character from the party
+    pub async fn remove_member(&self, character_id: i32, db: &Db) -> anyhow::Result<()> {
+        sqlx::query("UPDATE characters SET party = NULL WHERE id = ? AND party = ?")
+            .bind(character_id)
+            .bind(self.id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Removes every member from the party and deletes it
+    pub async fn disband(&self, db: &Db) -> anyhow::Result<()> {
+        let mut tx = db.begin().await?;
+
+        sqlx::query("UPDATE characters SET party = NULL WHERE party = ?")
+            .bind(self.id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM parties WHERE id = ?")
+            .bind(self.id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+/// A party member's info shown in the party window
+#[derive(FromRow, Debug, Clone)]
+pub struct PartyMember {
+    pub id: i32,
+    pub name: String,
+    pub job: i32,
+    pub level: i32,
+    /// None if the member is offline
+    pub channel_id: Option<i32>,
+    pub map_id: Option<i32>,
+}
+
+impl PartyMember {
+    /// Loads all of a party's members, along with where they are if they're online
+    pub async fn load_all(party_id: i32, db: &Db) -> anyhow::Result<Vec<Self>> {
+        let members = sqlx::query_as::<_, Self>(
+            "SELECT c.id, c.name, c.job, c.level, o.channel_id, o.map_id FROM characters c
+            LEFT JOIN online_characters o ON o.character_id = c.id
+            WHERE c.party = ? ORDER BY c.id",
+        )
+        .bind(party_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(members)
+    }
+}
@@ -0,0 +1,85 @@
+use crate::Db;
+use sqlx::FromRow;
+
+/// A pet, kept in the cash inventory as the item with the pet's id as its serial number
+#[derive(FromRow, Debug, Clone)]
+pub struct Pet {
+    pub id: i64,
+    pub item_id: i32,
+    pub name: String,
+    pub level: i32,
+    pub tameness: i32,
+    pub fullness: i32,
+    /// The pet skills (item pickup, etc.) the pet has learned
+    pub flag: i32,
+    /// The slot (0 to 2) the pet is summoned in, None if it isn't summoned
+    pub slot: Option<i32>,
+}
+
+impl Pet {
+    /// Loads all of the pets in a character's inventory
+    pub async fn load_all(character_id: i32, db: &Db) -> anyhow::Result<Vec<Self>> {
+        let pets = sqlx::query_as::<_, Self>(
+            "SELECT p.* FROM pets p JOIN items i ON i.cash_id = p.id WHERE i.character_id = ?",
+        )
+        .bind(character_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(pets)
+    }
+
+    /// Loads a pet by id
+    pub async fn load_optional(id: i64, db: &Db) -> anyhow::Result<Option<Self>> {
+        let pet = sqlx::query_as::<_, Self>("SELECT * FROM pets WHERE id = ?")
+            .bind(id)
+            .fetch_optional(db)
+            .await?;
+
+        Ok(pet)
+    }
+
+    /// Inserts the pet into the db
+    pub async fn insert(&self, db: &Db) -> anyhow::Result<()> {
+        sqlx::query(
+            "INSERT INTO pets (id, item_id, name, level, tameness, fullness, flag, slot)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(self.id)
+        .bind(self.item_id)
+        .bind(&self.name)
+        .bind(self.level)
+        .bind(self.tameness)
+        .bind(self.fullness)
+        .bind(self.flag)
+        .bind(self.slot)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Saves the given pets in one transaction
+    pub async fn save_all<'a>(pets: impl Iterator<Item = &'a Self>, db: &Db) -> anyhow::Result<()> {
+        let mut tx = db.begin().await?;
+
+        for pet in pets {
+            sqlx::query(
+                "UPDATE pets SET name = ?, level = ?, tameness = ?, fullness = ?, flag = ?, slot = ?
+                WHERE id = ?",
+            )
+            .bind(&pet.name)
+            .bind(pet.level)
+            .bind(pet.tameness)
+            .bind(pet.fullness)
+            .bind(pet.flag)
+            .bind(pet.slot)
+            .bind(pet.id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,300 @@
+use super::{item::InventoryType, Equipment, Item};
+use crate::Db;
+use sqlx::FromRow;
+
+/// An item listed in a character's player shop or hired merchant, sold in bundles of `amount` for `price` each
+/// Items stay listed after their shop closes until the character retrieves them from Fredrick
+#[derive(FromRow, Debug, Clone)]
+pub struct PlayerShopItem {
+    pub id: i32,
+    pub character_id: i32,
+    pub item_id: i32,
+    pub inventory_type: InventoryType,
+    pub position: i32,
+    pub amount: i32,
+    pub bundles: i32,
+    pub price: i32,
+    pub owner: String,
+    pub flag: i32,
+    /// The id of the equipment holding the item's stats, None if it isn't equipment
+    pub equipment_id: Option<i32>,
+    #[sqlx(skip)]
+    pub equip: Option<Equipment>,
+}
+
+impl PlayerShopItem {
+    /// Loads all of the items a character has listed, in the order they were listed
+    pub async fn load_all(character_id: i32, db: &Db) -> anyhow::Result<Vec<Self>> {
+        let mut items = sqlx::query_as::<_, Self>(
+            "SELECT * FROM player_shop_items WHERE character_id = ? ORDER BY position",
+        )
+        .bind(character_id)
+        .fetch_all(db)
+        .await?;
+
+        for item in items.iter_mut() {
+            item.equip = Equipment::load_optional(item.equipment_id, db).await?;
+        }
+
+        Ok(items)
+    }
+
+    /// Moves an item from a character's inventory into their shop in one transaction
+    /// `item` holds the amount being listed, the rest of its stack stays in the inventory unless `whole_stack` is set
+    /// Returns the listed item, or None (changing nothing) if the character no longer has the item
+    pub async fn list(
+        item: &Item,
+        whole_stack: bool,
+        bundles: i32,
+        price: i32,
+        position: i32,
+        db: &Db,
+    ) -> anyhow::Result<Option<Self>> {
+        let mut tx = db.begin().await?;
+
+        let updated = if whole_stack {
+            sqlx::query("DELETE FROM items WHERE id = ? AND character_id = ? AND amount = ?")
+                .bind(item.id)
+                .bind(item.character_id)
+                .bind(item.amount)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected()
+        } else {
+            sqlx::query(
+                "UPDATE items SET amount = amount - ? WHERE id = ? AND character_id = ? AND amount > ?",
+            )
+            .bind(item.amount)
+            .bind(item.id)
+            .bind(item.character_id)
+            .bind(item.amount)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected()
+        };
+
+        if updated != 1 {
+            return Ok(None);
+        }
+
+        let mut listed = Self {
+            id: 0,
+            character_id: item.character_id,
+            item_id: item.item_id,
+            inventory_type: item.inventory_type,
+            position,
+            amount: item.amount / bundles,
+            bundles,
+            price,
+            owner: item.owner.clone(),
+            flag: item.flag,
+            equipment_id: item.equipment_id,
+            equip: item.equip.clone(),
+        };
+
+        listed.id = sqlx::query(
+            "INSERT INTO player_shop_items
+            (character_id, item_id, inventory_type, position, amount, bundles, price, owner, flag, equipment_id)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(listed.character_id)
+        .bind(listed.item_id)
+        .bind(listed.inventory_type)
+        .bind(listed.position)
+        .bind(listed.amount)
+        .bind(listed.bundles)
+        .bind(listed.price)
+        .bind(&listed.owner)
+        .bind(listed.flag)
+        .bind(listed.equipment_id)
+        .execute(&mut *tx)
+        .await?
+        .last_insert_id() as i32;
+
+        tx.commit().await?;
+        Ok(Some(listed))
+    }
+
+    /// Sells bundles of the item to a character in one transaction, the price is added to the seller's merchant mesos
+    /// The buyer's mesos are changed by the price rather than set, their character is expected to pay it too
+    /// Returns the bought item as it's now stored in the buyer's inventory, or None (changing nothing) if there
+    /// aren't enough bundles left
+    pub async fn buy(
+        &self,
+        bundles: i32,
+        buyer_id: i32,
+        position: i32,
+        db: &Db,
+    ) -> anyhow::Result<Option<Item>> {
+        let mut tx = db.begin().await?;
+        let price = self.price * bundles;
+
+        let updated = sqlx::query(
+            "UPDATE player_shop_items SET bundles = bundles - ? WHERE id = ? AND bundles >= ?",
+        )
+        .bind(bundles)
+        .bind(self.id)
+        .bind(bundles)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        if updated != 1 {
+            return Ok(None);
+        }
+
+        sqlx::query("DELETE FROM player_shop_items WHERE id = ? AND bundles = 0")
+            .bind(self.id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("UPDATE characters SET mesos = mesos - ? WHERE id = ?")
+            .bind(price)
+            .bind(buyer_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("UPDATE characters SET merchant_mesos = merchant_mesos + ? WHERE id = ?")
+            .bind(price)
+            .bind(self.character_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let mut item = Item {
+            id: 0,
+            item_id: self.item_id,
+            character_id: buyer_id,
+            inventory_type: self.inventory_type,
+            position,
+            amount: self.amount * bundles,
+            owner: self.owner.clone(),
+            flag: self.flag,
+            cash_id: None,
+            expires_at: None,
+            equipment_id: self.equipment_id,
+            equip: self.equip.clone(),
+        };
+        item.id = insert_item(&item, &mut tx).await?;
+
+        tx.commit().await?;
+        Ok(Some(item))
+    }
+
+    /// Moves the item's remaining bundles back into its seller's inventory in one transaction
+    /// Returns the item as it's now stored in the inventory, or None (changing nothing) if it was already sold out
+    pub async fn take_back(&self, position: i32, db: &Db) -> anyhow::Result<Option<Item>> {
+        let mut tx = db.begin().await?;
+
+        let deleted = sqlx::query("DELETE FROM player_shop_items WHERE id = ? AND bundles = ?")
+            .bind(self.id)
+            .bind(self.bundles)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+        if deleted != 1 {
+            return Ok(None);
+        }
+
+        let mut item = Item {
+            id: 0,
+            item_id: self.item_id,
+            character_id: self.character_id,
+            inventory_type: self.inventory_type,
+            position,
+            amount: self.amount * self.bundles,
+            owner: self.owner.clone(),
+            flag: self.flag,
+            cash_id: None,
+            expires_at: None,
+            equipment_id: self.equipment_id,
+            equip: self.equip.clone(),
+        };
+        item.id = insert_item(&item, &mut tx).await?;
+
+        tx.commit().await?;
+        Ok(Some(item))
+    }
+}
+
+/// Inserts an item as part of a transaction, returning its id
+async fn insert_item(
+    item: &Item,
+    tx: &mut sqlx::Transaction<'_, sqlx::MySql>,
+) -> anyhow::Result<i32> {
+    let id = sqlx::query(
+        "INSERT INTO items (item_id, character_id, inventory_type, position, amount, owner, flag, equipment_id)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(item.item_id)
+    .bind(item.character_id)
+    .bind(item.inventory_type)
+    .bind(item.position)
+    .bind(item.amount)
+    .bind(&item.owner)
+    .bind(item.flag)
+    .bind(item.equipment_id)
+    .execute(&mut **tx)
+    .await?
+    .last_insert_id() as i32;
+
+    Ok(id)
+}
+
+/// A hired merchant that's open in one of the world's channels, it stays open while its owner is offline
+#[derive(FromRow, Debug, Clone)]
+pub struct HiredMerchant {
+    pub character_id: i32,
+    pub world_id: i32,
+    pub channel_id: i32,
+    pub map_id: i32,
+}
+
+impl HiredMerchant {
+    /// Loads a character's open hired merchant if they have one
+    pub async fn load_optional(character_id: i32, db: &Db) -> anyhow::Result<Option<Self>> {
+        let merchant =
+            sqlx::query_as::<_, Self>("SELECT * FROM hired_merchants WHERE character_id = ?")
+                .bind(character_id)
+                .fetch_optional(db)
+                .await?;
+
+        Ok(merchant)
+    }
+
+    /// Marks the hired merchant as open
+    pub async fn insert(&self, db: &Db) -> anyhow::Result<()> {
+        sqlx::query(
+            "INSERT INTO hired_merchants (character_id, world_id, channel_id, map_id) VALUES (?, ?, ?, ?)",
+        )
+        .bind(self.character_id)
+        .bind(self.world_id)
+        .bind(self.channel_id)
+        .bind(self.map_id)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Marks a character's hired merchant as closed
+    pub async fn delete(character_id: i32, db: &Db) -> anyhow::Result<()> {
+        sqlx::query("DELETE FROM hired_merchants WHERE character_id = ?")
+            .bind(character_id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Marks every hired merchant on the given channel as closed
+    pub async fn delete_all(world_id: i32, channel_id: i32, db: &Db) -> anyhow::Result<()> {
+        sqlx::query("DELETE FROM hired_merchants WHERE world_id = ? AND channel_id = ?")
+            .bind(world_id)
+            .bind(channel_id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
+use crate::Db;
 use sqlx::{Decode, Encode, FromRow};
 
 #[derive(FromRow, Debug, Clone)]
@@ -6,12 +7,62 @@ pub struct Quest {
     pub character_id: i32,
     pub status: QuestStatus,
     pub time: i32,
-    pub expires: i32,
+    pub expires: i64,
     pub forfeited: i32,
     pub completed: i32,
     pub info: i32,
 }
 
+impl Quest {
+    /// Loads all of a character's quests
+    pub async fn load_all(character_id: i32, db: &Db) -> anyhow::Result<Vec<Self>> {
+        let quests = sqlx::query_as::<_, Self>("SELECT * FROM quests WHERE character_id = ?")
+            .bind(character_id)
+            .fetch_all(db)
+            .await?;
+
+        Ok(quests)
+    }
+}
+
+/// The number of a monster a character has killed for a started quest
+#[derive(FromRow, Debug, Clone)]
+pub struct QuestProgress {
+    pub character_id: i32,
+    pub quest_id: i32,
+    pub mob_id: i32,
+    pub count: i32,
+}
+
+impl QuestProgress {
+    /// Loads all of a character's quest progress
+    pub async fn load_all(character_id: i32, db: &Db) -> anyhow::Result<Vec<Self>> {
+        let progress =
+            sqlx::query_as::<_, Self>("SELECT * FROM quest_progress WHERE character_id = ?")
+                .bind(character_id)
+                .fetch_all(db)
+                .await?;
+
+        Ok(progress)
+    }
+
+    /// Saves the progress, replacing the previously saved count
+    pub async fn save(&self, db: &Db) -> anyhow::Result<()> {
+        sqlx::query(
+            "INSERT INTO quest_progress (character_id, quest_id, mob_id, count) VALUES (?, ?, ?, ?)
+            ON DUPLICATE KEY UPDATE count = VALUES(count)",
+        )
+        .bind(self.character_id)
+        .bind(self.quest_id)
+        .bind(self.mob_id)
+        .bind(self.count)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+}
+
 #[derive(Decode, Encode, Debug, Copy, Clone)]
 pub enum QuestStatus {
     NotStarted,
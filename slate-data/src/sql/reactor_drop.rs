@@ -0,0 +1,26 @@
+use crate::Db;
+use sqlx::FromRow;
+
+/// An item dropped by a reactor when it breaks
+#[derive(FromRow, Debug, Clone)]
+pub struct ReactorDrop {
+    pub id: i32,
+    pub reactor_id: i32,
+    pub item_id: i32,
+    /// The item drops 1 in `chance` times
+    pub chance: i32,
+    /// The quest the item is for, only characters who have started the quest get it
+    pub quest_id: Option<i32>,
+}
+
+impl ReactorDrop {
+    /// Loads all of the items a reactor can drop
+    pub async fn load_all(reactor_id: i32, db: &Db) -> anyhow::Result<Vec<Self>> {
+        let drops = sqlx::query_as::<_, Self>("SELECT * FROM reactor_drops WHERE reactor_id = ?")
+            .bind(reactor_id)
+            .fetch_all(db)
+            .await?;
+
+        Ok(drops)
+    }
+}
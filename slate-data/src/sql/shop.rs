@@ -0,0 +1,44 @@
+use crate::Db;
+use sqlx::FromRow;
+
+#[derive(FromRow, Debug, Clone)]
+pub struct Shop {
+    pub id: i32,
+    pub npc_id: i32,
+}
+
+impl Shop {
+    /// Loads the shop an npc runs if it has one
+    pub async fn load_optional_by_npc(npc_id: i32, db: &Db) -> anyhow::Result<Option<Self>> {
+        let shop = sqlx::query_as::<_, Self>("SELECT * FROM shops WHERE npc_id = ?")
+            .bind(npc_id)
+            .fetch_optional(db)
+            .await?;
+
+        Ok(shop)
+    }
+}
+
+/// An item sold in a shop, for `price` mesos each
+#[derive(FromRow, Debug, Clone)]
+pub struct ShopItem {
+    pub id: i32,
+    pub shop_id: i32,
+    pub item_id: i32,
+    pub price: i32,
+    pub position: i32,
+}
+
+impl ShopItem {
+    /// Loads all of the items sold in a shop, in the order they're shown
+    pub async fn load_all(shop_id: i32, db: &Db) -> anyhow::Result<Vec<Self>> {
+        let items = sqlx::query_as::<_, Self>(
+            "SELECT * FROM shop_items WHERE shop_id = ? ORDER BY position",
+        )
+        .bind(shop_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(items)
+    }
+}
@@ -1,3 +1,4 @@
+use crate::Db;
 use sqlx::FromRow;
 
 #[derive(FromRow, Debug, Clone)]
@@ -9,7 +10,17 @@ pub struct Skill {
     pub expiration: i64,
 }
 
-impl Skill {}
+impl Skill {
+    /// Loads all of a character's skills
+    pub async fn load_all(character_id: i32, db: &Db) -> anyhow::Result<Vec<Self>> {
+        let skills = sqlx::query_as::<_, Self>("SELECT * FROM skills WHERE character_id = ?")
+            .bind(character_id)
+            .fetch_all(db)
+            .await?;
+
+        Ok(skills)
+    }
+}
 
 #[derive(FromRow, Debug, Clone)]
 pub struct Cooldown {
@@ -19,4 +30,44 @@ pub struct Cooldown {
     pub length: i64,
 }
 
-impl Cooldown {}
+impl Cooldown {
+    /// Loads all of a character's cooldowns
+    pub async fn load_all(character_id: i32, db: &Db) -> anyhow::Result<Vec<Self>> {
+        let cooldowns = sqlx::query_as::<_, Self>("SELECT * FROM cooldowns WHERE character_id = ?")
+            .bind(character_id)
+            .fetch_all(db)
+            .await?;
+
+        Ok(cooldowns)
+    }
+
+    /// Saves a character's cooldowns, replacing any previously saved cooldowns
+    pub async fn save_all(character_id: i32, cooldowns: &[Self], db: &Db) -> anyhow::Result<()> {
+        let mut tx = db.begin().await?;
+
+        sqlx::query("DELETE FROM cooldowns WHERE character_id = ?")
+            .bind(character_id)
+            .execute(&mut *tx)
+            .await?;
+
+        for cooldown in cooldowns.iter() {
+            sqlx::query(
+                "INSERT INTO cooldowns (character_id, skill_id, start, length) VALUES (?, ?, ?, ?)",
+            )
+            .bind(cooldown.character_id)
+            .bind(cooldown.skill_id)
+            .bind(cooldown.start)
+            .bind(cooldown.length)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Checks if the cooldown has finished
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.start + self.length <= now
+    }
+}
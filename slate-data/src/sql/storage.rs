@@ -0,0 +1,259 @@
+use super::{item::InventoryType, Equipment, Item};
+use crate::Db;
+use sqlx::FromRow;
+
+/// The most slots a storage can be expanded to
+pub const MAX_STORAGE_SLOTS: i32 = 48;
+
+/// An account's storage in a world, shared by all of the account's characters in that world
+#[derive(FromRow, Debug, Clone)]
+pub struct Storage {
+    pub id: i32,
+    pub account_id: i32,
+    pub world_id: i32,
+    pub slots: i32,
+    pub mesos: i32,
+}
+
+impl Storage {
+    /// Loads an account's storage in a world, creating it if the account hasn't used storage before
+    pub async fn load_or_create(account_id: i32, world_id: i32, db: &Db) -> anyhow::Result<Self> {
+        sqlx::query("INSERT IGNORE INTO storages (account_id, world_id) VALUES (?, ?)")
+            .bind(account_id)
+            .bind(world_id)
+            .execute(db)
+            .await?;
+
+        let storage = sqlx::query_as::<_, Self>(
+            "SELECT * FROM storages WHERE account_id = ? AND world_id = ?",
+        )
+        .bind(account_id)
+        .bind(world_id)
+        .fetch_one(db)
+        .await?;
+
+        Ok(storage)
+    }
+
+    /// Updates the storage's number of slots
+    pub async fn update_slots(&self, db: &Db) -> anyhow::Result<()> {
+        sqlx::query("UPDATE storages SET slots = ? WHERE id = ?")
+            .bind(self.slots)
+            .bind(self.id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Updates the storage's mesos along with the mesos of the character moving them, in one transaction
+    pub async fn update_mesos(
+        &self,
+        character_id: i32,
+        character_mesos: i32,
+        db: &Db,
+    ) -> anyhow::Result<()> {
+        let mut tx = db.begin().await?;
+
+        sqlx::query("UPDATE storages SET mesos = ? WHERE id = ?")
+            .bind(self.mesos)
+            .bind(self.id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("UPDATE characters SET mesos = ? WHERE id = ?")
+            .bind(character_mesos)
+            .bind(character_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+/// An item kept in a storage
+#[derive(FromRow, Debug, Clone)]
+pub struct StorageItem {
+    pub id: i32,
+    pub storage_id: i32,
+    pub item_id: i32,
+    pub inventory_type: InventoryType,
+    pub position: i32,
+    pub amount: i32,
+    pub owner: String,
+    pub flag: i32,
+    pub cash_id: Option<i64>,
+    pub expires_at: Option<i64>,
+    /// The id of the equipment holding the item's stats, None if it isn't equipment
+    pub equipment_id: Option<i32>,
+    #[sqlx(skip)]
+    pub equip: Option<Equipment>,
+}
+
+impl StorageItem {
+    /// Loads all of the items in a storage, in the order they were stored
+    pub async fn load_all(storage_id: i32, db: &Db) -> anyhow::Result<Vec<Self>> {
+        let mut items = sqlx::query_as::<_, Self>(
+            "SELECT * FROM storage_items WHERE storage_id = ? ORDER BY position",
+        )
+        .bind(storage_id)
+        .fetch_all(db)
+        .await?;
+
+        for item in items.iter_mut() {
+            item.equip = Equipment::load_optional(item.equipment_id, db).await?;
+        }
+
+        Ok(items)
+    }
+
+    /// Moves an item from a character's inventory into a storage in one transaction
+    /// `item` holds the amount being stored, the rest of its stack stays in the inventory unless `whole_stack` is set
+    /// Returns the stored item, or None (changing nothing) if the character no longer has the item
+    pub async fn store(
+        item: &Item,
+        whole_stack: bool,
+        storage_id: i32,
+        position: i32,
+        db: &Db,
+    ) -> anyhow::Result<Option<Self>> {
+        let mut tx = db.begin().await?;
+
+        let updated = if whole_stack {
+            sqlx::query("DELETE FROM items WHERE id = ? AND character_id = ? AND amount = ?")
+                .bind(item.id)
+                .bind(item.character_id)
+                .bind(item.amount)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected()
+        } else {
+            sqlx::query(
+                "UPDATE items SET amount = amount - ? WHERE id = ? AND character_id = ? AND amount > ?",
+            )
+            .bind(item.amount)
+            .bind(item.id)
+            .bind(item.character_id)
+            .bind(item.amount)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected()
+        };
+
+        if updated != 1 {
+            return Ok(None);
+        }
+
+        let mut stored = Self {
+            id: 0,
+            storage_id,
+            item_id: item.item_id,
+            inventory_type: item.inventory_type,
+            position,
+            amount: item.amount,
+            owner: item.owner.clone(),
+            flag: item.flag,
+            cash_id: item.cash_id,
+            expires_at: item.expires_at,
+            equipment_id: item.equipment_id,
+            equip: item.equip.clone(),
+        };
+
+        stored.id = sqlx::query(
+            "INSERT INTO storage_items
+            (storage_id, item_id, inventory_type, position, amount, owner, flag, cash_id, expires_at, equipment_id)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(stored.storage_id)
+        .bind(stored.item_id)
+        .bind(stored.inventory_type)
+        .bind(stored.position)
+        .bind(stored.amount)
+        .bind(&stored.owner)
+        .bind(stored.flag)
+        .bind(stored.cash_id)
+        .bind(stored.expires_at)
+        .bind(stored.equipment_id)
+        .execute(&mut *tx)
+        .await?
+        .last_insert_id() as i32;
+
+        tx.commit().await?;
+        Ok(Some(stored))
+    }
+
+    /// Moves the item out of its storage into a character's inventory in one transaction
+    /// Returns the item as it's now stored in the inventory, or None (changing nothing) if it was already taken out
+    pub async fn take(
+        &self,
+        character_id: i32,
+        position: i32,
+        db: &Db,
+    ) -> anyhow::Result<Option<Item>> {
+        let mut tx = db.begin().await?;
+
+        let deleted = sqlx::query("DELETE FROM storage_items WHERE id = ?")
+            .bind(self.id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+        if deleted != 1 {
+            return Ok(None);
+        }
+
+        let mut item = Item {
+            id: 0,
+            item_id: self.item_id,
+            character_id,
+            inventory_type: self.inventory_type,
+            position,
+            amount: self.amount,
+            owner: self.owner.clone(),
+            flag: self.flag,
+            cash_id: self.cash_id,
+            expires_at: self.expires_at,
+            equipment_id: self.equipment_id,
+            equip: self.equip.clone(),
+        };
+
+        item.id = sqlx::query(
+            "INSERT INTO items
+            (item_id, character_id, inventory_type, position, amount, owner, flag, cash_id, expires_at, equipment_id)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(item.item_id)
+        .bind(item.character_id)
+        .bind(item.inventory_type)
+        .bind(item.position)
+        .bind(item.amount)
+        .bind(&item.owner)
+        .bind(item.flag)
+        .bind(item.cash_id)
+        .bind(item.expires_at)
+        .bind(item.equipment_id)
+        .execute(&mut *tx)
+        .await?
+        .last_insert_id() as i32;
+
+        tx.commit().await?;
+        Ok(Some(item))
+    }
+
+    /// Updates the positions of a storage's items after they were arranged
+    pub async fn update_positions(items: &[Self], db: &Db) -> anyhow::Result<()> {
+        let mut tx = db.begin().await?;
+
+        for item in items.iter() {
+            sqlx::query("UPDATE storage_items SET position = ? WHERE id = ?")
+                .bind(item.position)
+                .bind(item.id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+use crate::Db;
+use sqlx::{Decode, Encode, FromRow};
+
+/// A message sent from one channel server to a character on another channel server in the same world
+#[derive(FromRow, Debug, Clone)]
+pub struct WorldMessage {
+    pub id: i32,
+    pub world_id: i32,
+    pub channel_id: i32,
+    pub recipient_id: i32,
+    pub kind: WorldMessageKind,
+    pub value: Option<i32>,
+    pub packet: Vec<u8>,
+}
+
+impl WorldMessage {
+    /// Loads all of the messages waiting to be delivered to the given channel
+    pub async fn load_all(world_id: i32, channel_id: i32, db: &Db) -> anyhow::Result<Vec<Self>> {
+        let messages = sqlx::query_as::<_, Self>(
+            "SELECT * FROM world_messages WHERE world_id = ? AND channel_id = ? ORDER BY id",
+        )
+        .bind(world_id)
+        .bind(channel_id)
+        .fetch_all(db)
+        .await?;
+
+        Ok(messages)
+    }
+
+    /// Queues a message to be delivered by the recipient's channel
+    pub async fn insert(
+        world_id: i32,
+        channel_id: i32,
+        recipient_id: i32,
+        kind: WorldMessageKind,
+        value: Option<i32>,
+        packet: &[u8],
+        db: &Db,
+    ) -> anyhow::Result<()> {
+        sqlx::query(
+            "INSERT INTO world_messages (world_id, channel_id, recipient_id, kind, value, packet)
+            VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(world_id)
+        .bind(channel_id)
+        .bind(recipient_id)
+        .bind(kind)
+        .bind(value)
+        .bind(packet)
+        .execute(db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Deletes all of the delivered messages up to and including the given id
+    pub async fn delete_up_to(
+        world_id: i32,
+        channel_id: i32,
+        id: i32,
+        db: &Db,
+    ) -> anyhow::Result<()> {
+        sqlx::query("DELETE FROM world_messages WHERE world_id = ? AND channel_id = ? AND id <= ?")
+            .bind(world_id)
+            .bind(channel_id)
+            .bind(id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Decode, Encode, Debug, Clone, Copy)]
+pub enum WorldMessageKind {
+    /// A packet to write to the recipient's stream
+    Packet,
+    /// Disconnects the recipient
+    Disconnect,
+    /// An invite to the party in `value`
+    PartyInvite,
+    /// The recipient joined the party in `value`, or left their party if it's null
+    PartyChanged,
+    /// Exp from a monster kill in `value`, the party bonus exp is stored in `packet` as a little endian int
+    GainExp,
+    /// Quest credit for killing the monster in `value`
+    KillCredit,
+    /// An invite to the guild in `value`
+    GuildInvite,
+    /// The recipient's guild changed and needs to be reloaded
+    GuildChanged,
+    /// An invite to the alliance in `value`
+    AllianceInvite,
+    /// An invite to the trade in `value`
+    TradeInvite,
+    /// The recipient's trade was closed by their trade partner
+    TradeClosed,
+    /// The items of the recipient's player shop or hired merchant changed
+    PlayerShopChanged,
+    /// The character in `value` opened the recipient's info window
+    CharacterInfo,
+    /// Moves the recipient to the map in `value`, the portal id is stored in `packet` as a little endian int
+    ChangeMap,
+}
+
+impl sqlx::Type<sqlx::MySql> for WorldMessageKind {
+    fn type_info() -> <sqlx::MySql as sqlx::Database>::TypeInfo {
+        <str as sqlx::Type<sqlx::MySql>>::type_info()
+    }
+
+    fn compatible(ty: &<sqlx::MySql as sqlx::Database>::TypeInfo) -> bool {
+        <str as sqlx::Type<sqlx::MySql>>::compatible(ty)
+    }
+}
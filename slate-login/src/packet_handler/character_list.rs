@@ -93,9 +93,9 @@ pub fn write_character(
     equipment: &[sql::Equipment],
     view_all: bool,
 ) {
-    packet::write_character_stats(packet, character);
+    packet::write_character_stats(packet, character, &[]);
     packet::write_character_style(packet, character);
-    packet::write_character_equipment(packet, equipment);
+    packet::write_character_equipment(packet, equipment, &[]);
 
     if !view_all {
         packet.write_byte(0);
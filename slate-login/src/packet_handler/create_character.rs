@@ -135,8 +135,8 @@ async fn create_equips(
             .push_bind(id)
             .push_bind(character_id)
             .push_bind(equip_type.get_position())
-            .push_bind(nx_equip.w_atk.unwrap_or(0))
-            .push_bind(nx_equip.upgrade_slots.unwrap_or(0));
+            .push_bind(nx_equip.w_atk)
+            .push_bind(nx_equip.upgrade_slots);
     });
 
     query_builder.build().execute(&session.db).await?;
@@ -81,6 +81,10 @@ impl Packet {
         self.bytes.get_i32_le()
     }
 
+    pub fn read_long(&mut self) -> i64 {
+        self.bytes.get_i64_le()
+    }
+
     pub fn read_string(&mut self) -> String {
         let len = self.read_short() as usize;
         let bytes = self.bytes.split_to(len);